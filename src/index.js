@@ -3,66 +3,317 @@ import wasmModule from './wasm-pkg/cf_wasm_lib_bg.wasm';
 
 let wasmInstance = null;
 
-async function initWasm() {
+// Stands in for the KV namespace when none is bound; lives as long as the isolate
+const localKv = new Map();
+// Reads and writes of the request currently inside handle_request
+let kvPass = null;
+// Replays allowed for KV reads before giving up
+const MAX_KV_PASSES = 4;
+// Outbound fetches taking longer count as failed
+const FETCH_TIMEOUT_MS = 5000;
+
+async function initWasm(env) {
   if (!wasmInstance) {
-    wasmInstance = await WebAssembly.instantiate(wasmModule);
+    wasmInstance = await WebAssembly.instantiate(wasmModule, {
+      env: {
+        host_now_ms: () => Date.now(),
+        // Only called while handling requests, after wasmInstance is set
+        host_random_fill: (ptr, len) => {
+          crypto.getRandomValues(new Uint8Array(wasmInstance.exports.memory.buffer, ptr, len));
+        },
+        host_log: (ptr, len) => console.warn('[wasm]', readTextFromWasm(wasmInstance, ptr, len)),
+        // Answers only from values loaded before this pass; a miss is loaded
+        // after the handler returns and the request is replayed
+        host_kv_get: (keyPtr, keyLen, valuePtrOut, valueLenOut) => {
+          const key = readTextFromWasm(wasmInstance, keyPtr, keyLen);
+          let value;
+          if (kvPass.writes.has(key)) {
+            value = kvPass.writes.get(key).value;
+          } else if (kvPass.loaded.has(key)) {
+            value = kvPass.loaded.get(key);
+          } else {
+            kvPass.misses.add(key);
+            return -1;
+          }
+          if (value === null) return 0;
+          const bytes = new TextEncoder().encode(value);
+          const valuePtr = writeBytesToWasm(wasmInstance, bytes);
+          const view = new DataView(wasmInstance.exports.memory.buffer);
+          view.setUint32(valuePtrOut, valuePtr, true);
+          view.setUint32(valueLenOut, bytes.length, true);
+          return 1;
+        },
+        host_kv_put: (keyPtr, keyLen, valuePtr, valueLen, ttlSeconds) => {
+          const key = readTextFromWasm(wasmInstance, keyPtr, keyLen);
+          const value = readTextFromWasm(wasmInstance, valuePtr, valueLen);
+          kvPass.writes.set(key, { value, ttlSeconds });
+        },
+        // Works like host_kv_get: a URL not fetched yet is fetched after the
+        // handler returns and the request is replayed
+        host_fetch: (urlPtr, urlLen, maxBytes, outPtrOut, outLenOut) => {
+          const url = readTextFromWasm(wasmInstance, urlPtr, urlLen);
+          if (!kvPass.fetched.has(url)) {
+            kvPass.fetchMisses.set(url, maxBytes >>> 0);
+            return -1;
+          }
+          const { ok, bytes } = kvPass.fetched.get(url);
+          const view = new DataView(wasmInstance.exports.memory.buffer);
+          view.setUint32(outPtrOut, writeBytesToWasm(wasmInstance, bytes), true);
+          view.setUint32(outLenOut, bytes.length, true);
+          return ok ? 1 : 0;
+        },
+      },
+    });
+    // Optional JSON settings, e.g. {"max_constant_digits": 5000}
+    if (env && env.CONFIG) {
+      const config = new TextEncoder().encode(env.CONFIG);
+      const configPtr = writeBytesToWasm(wasmInstance, config);
+      if (!wasmInstance.exports.init_config(configPtr, config.length)) {
+        console.warn('Ignoring invalid CONFIG');
+      }
+      if (configPtr) wasmInstance.exports.dealloc(configPtr, config.length);
+    }
+    // Load a custom autocomplete word list once; it stays in WASM memory for the isolate's lifetime
+    if (env && env.DICTIONARY) {
+      const words = new TextEncoder().encode(env.DICTIONARY);
+      const wordsPtr = writeBytesToWasm(wasmInstance, words);
+      wasmInstance.exports.init_dictionary(wordsPtr, words.length);
+      if (wordsPtr) wasmInstance.exports.dealloc(wordsPtr, words.length);
+    }
+    // Build the lazily initialised tables now rather than inside the first
+    // request that needs them; the status it returns is also in /status
+    takeOutputFromWasm(wasmInstance, wasmInstance.exports.warmup());
   }
   return wasmInstance;
 }
 
-// Helper functions to work with WASM memory and strings
-function writeStringToWasm(instance, str, offset) {
-  const bytes = new TextEncoder().encode(str + '\0');
-  const mem = new Uint8Array(instance.exports.memory.buffer);
-  mem.set(bytes, offset);
-  return offset; // Return the offset as the pointer
+// Helper functions to work with WASM memory and strings. Everything crosses
+// the boundary as (pointer, length); nothing is NUL-terminated.
+function writeBytesToWasm(instance, bytes) {
+  if (bytes.length === 0) return 0;
+  const ptr = instance.exports.alloc(bytes.length);
+  new Uint8Array(instance.exports.memory.buffer).set(bytes, ptr);
+  return ptr;
 }
 
-function readStringFromWasm(instance, ptr) {
-  const mem = new Uint8Array(instance.exports.memory.buffer);
-  let len = 0;
-  while (mem[ptr + len] !== 0) len++; // Find null terminator
-  return new TextDecoder().decode(mem.slice(ptr, ptr + len));
+// Request buffers come from the module's per-request arena and are all
+// released by end_request, so they are never freed one by one
+function writeBytesToArena(instance, bytes) {
+  if (bytes.length === 0) return 0;
+  const ptr = instance.exports.arena_alloc(bytes.length);
+  new Uint8Array(instance.exports.memory.buffer).set(bytes, ptr);
+  return ptr;
+}
+
+function readTextFromWasm(instance, ptr, len) {
+  return new TextDecoder().decode(new Uint8Array(instance.exports.memory.buffer, ptr, len));
+}
+
+// Repeats a host call that threw or, through `statusOf`, answered with a
+// failing HTTP status, waiting as long as WASM's retry_delay_ms says
+async function withRetry(instance, call, statusOf = () => 200) {
+  for (let attempt = 1; ; attempt++) {
+    let outcome;
+    try {
+      const value = await call();
+      outcome = { value, status: statusOf(value) };
+    } catch (error) {
+      outcome = { error, status: 0 };
+    }
+    const delay = instance.exports.retry_delay_ms(attempt, outcome.status);
+    if (delay < 0) {
+      if (outcome.error) throw outcome.error;
+      return outcome.value;
+    }
+    await new Promise((resolve) => setTimeout(resolve, delay));
+  }
+}
+
+async function kvLoad(instance, env, key) {
+  if (env && env.KV) return withRetry(instance, () => env.KV.get(key));
+  const entry = localKv.get(key);
+  if (!entry) return null;
+  if (entry.expiresAt && entry.expiresAt <= Date.now()) {
+    localKv.delete(key);
+    return null;
+  }
+  return entry.value;
+}
+
+// The body, or null as soon as it grows past maxBytes; a Content-Length over
+// the limit is refused before any of it is read
+async function readCapped(response, maxBytes) {
+  if (Number(response.headers.get('content-length')) > maxBytes || !response.body) {
+    if (response.body) await response.body.cancel();
+    return response.body ? null : new Uint8Array(0);
+  }
+  const chunks = [];
+  let length = 0;
+  const reader = response.body.getReader();
+  for (;;) {
+    const { done, value } = await reader.read();
+    if (done) break;
+    length += value.length;
+    if (length > maxBytes) {
+      await reader.cancel();
+      return null;
+    }
+    chunks.push(value);
+  }
+  const body = new Uint8Array(length);
+  let offset = 0;
+  for (const chunk of chunks) {
+    body.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return body;
+}
+
+// A response as "<status>\n<content type>\n<body>", or the reason there is none
+async function fetchLoad(instance, url, maxBytes) {
+  const encoder = new TextEncoder();
+  try {
+    // Redirects go back to WASM, which decides whether to follow them
+    const { response, body } = await withRetry(
+      instance,
+      async () => {
+        const response = await fetch(url, { redirect: 'manual', signal: AbortSignal.timeout(FETCH_TIMEOUT_MS) });
+        return { response, body: await readCapped(response, maxBytes) };
+      },
+      ({ response }) => response.status,
+    );
+    if (!body) return { ok: false, bytes: encoder.encode(`Response is larger than ${maxBytes} bytes`) };
+    const head = encoder.encode(`${response.status}\n${response.headers.get('content-type') || ''}\n`);
+    const bytes = new Uint8Array(head.length + body.length);
+    bytes.set(head);
+    bytes.set(body, head.length);
+    return { ok: true, bytes };
+  } catch (error) {
+    return { ok: false, bytes: encoder.encode(error.message || String(error)) };
+  }
+}
+
+function kvCommit(instance, env, ctx, writes) {
+  for (const [key, { value, ttlSeconds }] of writes) {
+    if (env && env.KV) {
+      // Workers KV rejects TTLs under 60 seconds
+      const options = ttlSeconds ? { expirationTtl: Math.max(60, ttlSeconds) } : undefined;
+      const write = withRetry(instance, () => env.KV.put(key, value, options));
+      if (ctx) ctx.waitUntil(write);
+    } else {
+      localKv.set(key, { value, expiresAt: ttlSeconds ? Date.now() + ttlSeconds * 1000 : 0 });
+    }
+  }
+}
+
+// Outputs start with their byte length as a little-endian u32; this decodes
+// one and releases it
+function takeOutputFromWasm(instance, ptr) {
+  const len = new DataView(instance.exports.memory.buffer).getUint32(ptr, true);
+  const text = readTextFromWasm(instance, ptr + 4, len);
+  instance.exports.free_bytes(ptr);
+  return text;
+}
+
+// Runs one pass of the WASM handler; KV imports see `pass` while it runs
+function callHandler(instance, method, url, requestHeaders, requestBody, pass) {
+  const encoder = new TextEncoder();
+  // One byte past the limit is enough for WASM to refuse it with a 414
+  const query = url.search.slice(1, (instance.exports.max_query_length() >>> 0) + 2);
+  const parts = [encoder.encode(method), encoder.encode(url.pathname), encoder.encode(query), requestHeaders];
+  // Bodies over the limit are never copied in; WASM sees the length and answers 413
+  const bodyTooLarge = requestBody.length > instance.exports.max_body_bytes() >>> 0;
+  
+  // Every part goes into its own arena buffer
+  const ptrs = parts.map((bytes) => writeBytesToArena(instance, bytes));
+  const bodyPtr = bodyTooLarge ? 0 : writeBytesToArena(instance, requestBody);
+  
+  // Call WASM handler
+  kvPass = pass;
+  try {
+    const responsePtr = instance.exports.handle_request(
+      ...parts.flatMap((bytes, i) => [ptrs[i], bytes.length]),
+      bodyPtr, requestBody.length
+    );
+    if (!responsePtr) {
+      throw new Error('WASM handle_request returned null');
+    }
+    // The response lives in the arena too: copy it out before end_request
+    const len = new DataView(instance.exports.memory.buffer).getUint32(responsePtr, true);
+    return readTextFromWasm(instance, responsePtr + 4, len);
+  } finally {
+    kvPass = null;
+    instance.exports.end_request();
+  }
+}
+
+// Parse WASM response format: "status|content-type|body", where the
+// content-type segment may carry extra "Name: value" header lines
+function parseResponse(responseStr) {
+  const [status, headerBlock, ...bodyParts] = responseStr.split('|');
+  const body = bodyParts.join('|'); // In case body contains pipes
+  const [contentType, ...extraHeaders] = headerBlock.split('\n');
+  const headers = {
+    'Content-Type': contentType,
+    'Access-Control-Allow-Origin': '*',
+  };
+  for (const line of extraHeaders) {
+    const colon = line.indexOf(':');
+    if (colon > 0) headers[line.slice(0, colon).trim()] = line.slice(colon + 1).trim();
+  }
+  // Binary bodies arrive base64-encoded and flagged by this header
+  if (headers['X-Wasm-Body-Encoding'] === 'base64') {
+    delete headers['X-Wasm-Body-Encoding'];
+    return { status: parseInt(status), headers, body: Uint8Array.from(atob(body), (c) => c.charCodeAt(0)) };
+  }
+  return { status: parseInt(status), headers, body };
 }
 
 export default {
   async fetch(request, env, ctx) {
     try {
-      const instance = await initWasm();
+      const instance = await initWasm(env);
       const url = new URL(request.url);
       
-      // Use fixed memory offsets for string passing - ensure they don't overlap
-      const METHOD_OFFSET = 1000;   // Start at 1000 to avoid low memory
-      const URL_OFFSET = 1100;      // Give 100 bytes for method
-      const QUERY_OFFSET = 1200;    // Give 100 bytes for URL path
-      
-      // Pass request data to WASM for processing
-      const methodPtr = writeStringToWasm(instance, request.method, METHOD_OFFSET);
-      const urlPtr = writeStringToWasm(instance, url.pathname, URL_OFFSET);
-      const queryPtr = writeStringToWasm(instance, url.search.slice(1), QUERY_OFFSET);
-      
-      // Call WASM handler
-      const responsePtr = instance.exports.handle_request(methodPtr, urlPtr, queryPtr);
-      
-      if (!responsePtr) {
-        throw new Error('WASM handle_request returned null');
+      const requestBody = new Uint8Array(await request.arrayBuffer());
+      const headerPairs = [...request.headers];
+      const encodeHeaders = (pairs) => new TextEncoder().encode(pairs.map(([name, value]) => `${name}: ${value}`).join('\n'));
+      let requestHeaders = encodeHeaders(headerPairs);
+
+      // Handlers that read KV keys not loaded yet, or fetch URLs not fetched
+      // yet, are replayed once those are in
+      const loaded = new Map();
+      const fetched = new Map();
+      let pass;
+      let responseStr;
+      for (let attempt = 1; ; attempt++) {
+        pass = { loaded, misses: new Set(), writes: new Map(), fetched, fetchMisses: new Map() };
+        responseStr = callHandler(instance, request.method, url, requestHeaders, requestBody, pass);
+        if (pass.misses.size === 0 && pass.fetchMisses.size === 0) break;
+        if (attempt === MAX_KV_PASSES) throw new Error('KV reads did not settle');
+        // Replays keep the request ID the first pass settled on, which is
+        // the client's only if it was acceptable
+        if (attempt === 1) {
+          const requestId = parseResponse(responseStr).headers['X-Request-Id'];
+          requestHeaders = encodeHeaders([...headerPairs.filter(([name]) => name !== 'x-request-id'), ['x-request-id', requestId]]);
+        }
+        const keys = [...pass.misses];
+        const urls = [...pass.fetchMisses];
+        const [values, responses] = await Promise.all([
+          Promise.all(keys.map((key) => kvLoad(instance, env, key))),
+          Promise.all(urls.map(([target, maxBytes]) => fetchLoad(instance, target, maxBytes))),
+        ]);
+        keys.forEach((key, i) => loaded.set(key, values[i]));
+        urls.forEach(([target], i) => fetched.set(target, responses[i]));
       }
+      kvCommit(instance, env, ctx, pass.writes);
       
-      const responseStr = readStringFromWasm(instance, responsePtr);
-      
-      // Parse WASM response format: "status|content-type|body"
-      const [status, contentType, ...bodyParts] = responseStr.split('|');
-      const body = bodyParts.join('|'); // In case body contains pipes
-      
-      // Clean up WASM memory
-      instance.exports.free_string(responsePtr);
-      
-      return new Response(body, {
-        status: parseInt(status),
-        headers: {
-          'Content-Type': contentType,
-          'Access-Control-Allow-Origin': '*',
-        }
+      const { status, headers, body } = parseResponse(responseStr);
+
+      // HEAD, 204 and 304 responses must not carry a body
+      return new Response(request.method === 'HEAD' || status === 204 || status === 304 ? null : body, {
+        status,
+        headers,
       });
       
     } catch (error) {
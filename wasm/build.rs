@@ -0,0 +1,15 @@
+// Records when the module was built, for the sitemap's lastmod dates
+// SOURCE_DATE_EPOCH (seconds) takes precedence, so reproducible builds stay
+// reproducible.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=src");
+    let seconds = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()));
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", seconds);
+}
@@ -0,0 +1,299 @@
+// Global allocator selection
+// The default build keeps Rust's own allocator (dlmalloc on wasm32). Two
+// mutually exclusive features swap it out at compile time:
+// - small-alloc: an address-ordered first-fit free list that takes pages
+//   straight from linear memory, so dlmalloc is not linked at all. Smallest
+//   module, but every allocation walks the free list.
+// - fast-alloc: power-of-two size classes up to 4 KiB, each with its own free
+//   list, in front of the default allocator. Small allocations are a couple
+//   of pointer swaps; freed blocks stay in their class instead of being
+//   handed back, so memory use is higher.
+// Both are written here rather than pulled in as crates because wee_alloc is
+// unmaintained and the module is single threaded anyway.
+//
+// Builds with std put the request arena (arena.rs) in front as the global
+// allocator, and the allocator chosen here becomes its BACKING one.
+
+#[cfg(all(feature = "small-alloc", feature = "fast-alloc"))]
+compile_error!("Enable at most one of the small-alloc and fast-alloc features");
+
+// fast-alloc refills from the standard allocator, and without std small-alloc
+// can only take pages from wasm32 linear memory
+#[cfg(all(feature = "fast-alloc", not(feature = "std")))]
+compile_error!("The fast-alloc feature needs std");
+#[cfg(all(feature = "small-alloc", not(feature = "std"), not(target_arch = "wasm32")))]
+compile_error!("Without std the small-alloc feature only supports wasm32");
+
+#[cfg(feature = "small-alloc")]
+pub const NAME: &str = "small-alloc";
+#[cfg(all(feature = "fast-alloc", not(feature = "small-alloc")))]
+pub const NAME: &str = "fast-alloc";
+#[cfg(all(not(any(feature = "small-alloc", feature = "fast-alloc")), target_arch = "wasm32"))]
+pub const NAME: &str = "dlmalloc";
+#[cfg(all(not(any(feature = "small-alloc", feature = "fast-alloc")), not(target_arch = "wasm32")))]
+pub const NAME: &str = "system";
+
+// Size of linear memory, which never shrinks; native builds have no such figure
+pub fn memory_bytes() -> Option<u64> {
+    #[cfg(target_arch = "wasm32")]
+    return Some(core::arch::wasm32::memory_size(0) as u64 * 65536);
+    #[cfg(not(target_arch = "wasm32"))]
+    None
+}
+
+#[cfg(any(feature = "small-alloc", feature = "fast-alloc", feature = "std"))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+// WASM has one thread, but native builds still have to be safe to share
+#[cfg(any(feature = "small-alloc", feature = "fast-alloc", feature = "std"))]
+pub(crate) struct Lock(AtomicBool);
+
+#[cfg(any(feature = "small-alloc", feature = "fast-alloc", feature = "std"))]
+impl Lock {
+    pub(crate) const fn new() -> Self {
+        Lock(AtomicBool::new(false))
+    }
+
+    pub(crate) fn with<R>(&self, f: impl FnOnce() -> R) -> R {
+        while self.0.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            core::hint::spin_loop();
+        }
+        let result = f();
+        self.0.store(false, Ordering::Release);
+        result
+    }
+}
+
+#[cfg(any(feature = "small-alloc", feature = "fast-alloc", feature = "std"))]
+pub(crate) fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+#[cfg(feature = "small-alloc")]
+mod small {
+    use super::{align_up, Lock};
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::cell::UnsafeCell;
+    #[cfg(not(target_arch = "wasm32"))]
+    use std::alloc::System;
+
+    const PAGE_BYTES: usize = 64 * 1024;
+    // Every block is a multiple of this and starts on it, which leaves room for
+    // a free block's header
+    const UNIT: usize = 16;
+
+    struct FreeBlock {
+        size: usize,
+        next: *mut FreeBlock,
+    }
+
+    pub(crate) struct SmallAlloc {
+        lock: Lock,
+        // Sorted by address so neighbours can be merged when freed
+        head: UnsafeCell<*mut FreeBlock>,
+    }
+
+    unsafe impl Sync for SmallAlloc {}
+
+    // Whole pages that are never given back, as (start, length)
+    #[cfg(target_arch = "wasm32")]
+    unsafe fn grow(bytes: usize) -> Option<(*mut u8, usize)> {
+        let pages = bytes.div_ceil(PAGE_BYTES);
+        match core::arch::wasm32::memory_grow(0, pages) {
+            usize::MAX => None,
+            previous => Some(((previous * PAGE_BYTES) as *mut u8, pages * PAGE_BYTES)),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn grow(bytes: usize) -> Option<(*mut u8, usize)> {
+        let len = bytes.div_ceil(PAGE_BYTES) * PAGE_BYTES;
+        let ptr = System.alloc(Layout::from_size_align_unchecked(len, PAGE_BYTES));
+        (!ptr.is_null()).then_some((ptr, len))
+    }
+
+    impl SmallAlloc {
+        const fn new() -> Self {
+            SmallAlloc { lock: Lock::new(), head: UnsafeCell::new(core::ptr::null_mut()) }
+        }
+
+        // First block that fits once aligned; the gaps before and after stay free
+        unsafe fn take(head: &mut *mut FreeBlock, size: usize, align: usize) -> Option<*mut u8> {
+            let mut link: *mut *mut FreeBlock = head;
+            while !(*link).is_null() {
+                let block = *link;
+                let start = block as usize;
+                let end = start + (*block).size;
+                let aligned = align_up(start, align);
+                if aligned + size <= end {
+                    let next = (*block).next;
+                    if aligned > start {
+                        (*block).size = aligned - start;
+                        link = &mut (*block).next;
+                    }
+                    if aligned + size < end {
+                        let rest = (aligned + size) as *mut FreeBlock;
+                        (*rest).size = end - aligned - size;
+                        (*rest).next = next;
+                        *link = rest;
+                    } else {
+                        *link = next;
+                    }
+                    return Some(aligned as *mut u8);
+                }
+                link = &mut (*block).next;
+            }
+            None
+        }
+
+        unsafe fn release(head: &mut *mut FreeBlock, ptr: *mut u8, size: usize) {
+            let addr = ptr as usize;
+            let mut prev: *mut FreeBlock = core::ptr::null_mut();
+            let mut next = *head;
+            while !next.is_null() && (next as usize) < addr {
+                prev = next;
+                next = (*next).next;
+            }
+            let block = ptr as *mut FreeBlock;
+            (*block).size = size;
+            (*block).next = next;
+            if !next.is_null() && addr + size == next as usize {
+                (*block).size += (*next).size;
+                (*block).next = (*next).next;
+            }
+            if prev.is_null() {
+                *head = block;
+            } else if prev as usize + (*prev).size == addr {
+                (*prev).size += (*block).size;
+                (*prev).next = (*block).next;
+            } else {
+                (*prev).next = block;
+            }
+        }
+    }
+
+    fn block_size(layout: Layout) -> usize {
+        layout.size().max(1).div_ceil(UNIT) * UNIT
+    }
+
+    unsafe impl GlobalAlloc for SmallAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let size = block_size(layout);
+            let align = layout.align().max(UNIT);
+            self.lock.with(|| {
+                let head = &mut *self.head.get();
+                if let Some(ptr) = Self::take(head, size, align) {
+                    return ptr;
+                }
+                let Some((region, len)) = grow(size + align) else {
+                    return core::ptr::null_mut();
+                };
+                Self::release(head, region, len);
+                Self::take(head, size, align).unwrap_or(core::ptr::null_mut())
+            })
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.lock.with(|| Self::release(&mut *self.head.get(), ptr, block_size(layout)))
+        }
+    }
+
+    #[cfg_attr(not(feature = "std"), global_allocator)]
+    pub(crate) static BACKING: SmallAlloc = SmallAlloc::new();
+}
+
+#[cfg(all(feature = "fast-alloc", not(feature = "small-alloc")))]
+mod fast {
+    use super::{align_up, Lock};
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::cell::UnsafeCell;
+    use std::alloc::System;
+
+    // 16, 32, ... 4096 bytes
+    const CLASSES: usize = 9;
+    const MAX_CLASS_BYTES: usize = 16 << (CLASSES - 1);
+    const REFILL_BYTES: usize = 64 * 1024;
+
+    struct FreeSlot {
+        next: *mut FreeSlot,
+    }
+
+    struct Classes {
+        free: [*mut FreeSlot; CLASSES],
+        // Untouched space in the chunk new slots are carved from
+        bump: usize,
+        bump_end: usize,
+    }
+
+    pub(crate) struct FastAlloc {
+        lock: Lock,
+        classes: UnsafeCell<Classes>,
+    }
+
+    unsafe impl Sync for FastAlloc {}
+
+    // Slots are aligned to their own size, so alignment only ever bumps the class
+    fn class(layout: Layout) -> Option<usize> {
+        let size = layout.size().max(layout.align()).max(16).next_power_of_two();
+        (size <= MAX_CLASS_BYTES).then(|| size.trailing_zeros() as usize - 4)
+    }
+
+    impl FastAlloc {
+        const fn new() -> Self {
+            FastAlloc {
+                lock: Lock::new(),
+                classes: UnsafeCell::new(Classes { free: [core::ptr::null_mut(); CLASSES], bump: 0, bump_end: 0 }),
+            }
+        }
+    }
+
+    unsafe impl GlobalAlloc for FastAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let Some(index) = class(layout) else {
+                return System.alloc(layout);
+            };
+            self.lock.with(|| {
+                let classes = &mut *self.classes.get();
+                let slot = classes.free[index];
+                if !slot.is_null() {
+                    classes.free[index] = (*slot).next;
+                    return slot as *mut u8;
+                }
+                let size = 16 << index;
+                let mut start = align_up(classes.bump, size);
+                if classes.bump == 0 || start + size > classes.bump_end {
+                    // The rest of the old chunk is abandoned; it is under 4 KiB
+                    let chunk = System.alloc(Layout::from_size_align_unchecked(REFILL_BYTES, MAX_CLASS_BYTES));
+                    if chunk.is_null() {
+                        return chunk;
+                    }
+                    start = chunk as usize;
+                    classes.bump_end = start + REFILL_BYTES;
+                }
+                classes.bump = start + size;
+                start as *mut u8
+            })
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let Some(index) = class(layout) else {
+                return System.dealloc(ptr, layout);
+            };
+            self.lock.with(|| {
+                let classes = &mut *self.classes.get();
+                let slot = ptr as *mut FreeSlot;
+                (*slot).next = classes.free[index];
+                classes.free[index] = slot;
+            })
+        }
+    }
+
+    pub(crate) static BACKING: FastAlloc = FastAlloc::new();
+}
+
+#[cfg(all(feature = "small-alloc", feature = "std"))]
+pub(crate) use small::BACKING;
+#[cfg(all(feature = "fast-alloc", not(feature = "small-alloc")))]
+pub(crate) use fast::BACKING;
+#[cfg(all(not(any(feature = "small-alloc", feature = "fast-alloc")), feature = "std"))]
+pub(crate) static BACKING: std::alloc::System = std::alloc::System;
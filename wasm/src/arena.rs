@@ -0,0 +1,459 @@
+// Per-request bump arena
+// This is the global allocator of std builds, in front of the one
+// allocator.rs picks. While handle_request runs, allocations of up to
+// MAX_BUMP_BYTES are bumped out of 64 KiB chunks and freeing them costs
+// nothing; end_request then releases everything the request left behind at
+// once. Larger allocations, and everything allocated between requests, go to
+// the backing allocator as before.
+//
+// The request parts from arena_alloc and the response handed back come from
+// the same chunks. State that outlives a request (the response cache, the
+// search index, the breakers and so on) must be built and changed inside
+// `outside`: an arena allocation still in use after end_request is
+// overwritten by the next request.
+
+use crate::allocator::{align_up, Lock, BACKING};
+use crate::context;
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+use core::ptr;
+
+const CHUNK_BYTES: usize = 64 * 1024;
+// Bigger allocations would strand most of a chunk behind them
+const MAX_BUMP_BYTES: usize = 16 * 1024;
+// Coarser alignment is left to the backing allocator
+const MAX_ALIGN: usize = 16;
+// Room for the link at the start of every chunk and large buffer
+const HEADER_BYTES: usize = 16;
+
+// Chunks are never handed back: linear memory does not shrink anyway, and
+// keeping them means a stray free of an old arena pointer is always ignored
+struct Chunk {
+    next: *mut Chunk,
+}
+
+// A buffer from `alloc` too big for a chunk, freed by reset
+struct Large {
+    next: *mut Large,
+    len: usize,
+}
+
+struct State {
+    active: bool,
+    // Every chunk, in the order they were made; `current` is null until the
+    // first allocation after a reset
+    first: *mut Chunk,
+    current: *mut Chunk,
+    // Next free byte in `current`, and the latest allocation, which can grow
+    // or be freed in place
+    offset: usize,
+    last: usize,
+    // Address range spanning every chunk, so most frees skip the walk
+    low: usize,
+    high: usize,
+    large: *mut Large,
+    chunks: usize,
+    used: usize,
+    peak: usize,
+}
+
+struct Arena {
+    lock: Lock,
+    state: UnsafeCell<State>,
+}
+
+unsafe impl Sync for Arena {}
+
+#[global_allocator]
+static ARENA: Arena = Arena::new();
+
+fn chunk_layout() -> Layout {
+    unsafe { Layout::from_size_align_unchecked(CHUNK_BYTES, MAX_ALIGN) }
+}
+
+fn large_layout(len: usize) -> Layout {
+    unsafe { Layout::from_size_align_unchecked(HEADER_BYTES + len, MAX_ALIGN) }
+}
+
+impl State {
+    fn contains(&self, ptr: *mut u8) -> bool {
+        let addr = ptr as usize;
+        if addr < self.low || addr >= self.high {
+            return false;
+        }
+        let mut chunk = self.first;
+        while !chunk.is_null() {
+            if (chunk as usize..chunk as usize + CHUNK_BYTES).contains(&addr) {
+                return true;
+            }
+            chunk = unsafe { (*chunk).next };
+        }
+        false
+    }
+
+    // Whether `ptr` is the newest allocation and still ends at the offset; a
+    // pointer kept from before a reset can share its address but not both
+    fn is_last(&self, ptr: *mut u8, size: usize) -> bool {
+        self.last != 0 && ptr as usize == self.last && ptr as usize + size.max(1) == self.current as usize + self.offset
+    }
+
+    // Appends a chunk from the backing allocator after `current`
+    unsafe fn grow(&mut self) -> *mut Chunk {
+        let chunk = BACKING.alloc(chunk_layout()) as *mut Chunk;
+        if chunk.is_null() {
+            return chunk;
+        }
+        (*chunk).next = ptr::null_mut();
+        if self.current.is_null() {
+            self.first = chunk;
+        } else {
+            (*self.current).next = chunk;
+        }
+        self.low = self.low.min(chunk as usize);
+        self.high = self.high.max(chunk as usize + CHUNK_BYTES);
+        self.chunks += 1;
+        chunk
+    }
+
+    // `size` bytes at `align` (at most MAX_ALIGN) from the current chunk, or
+    // from the next one once it is full; null if memory runs out
+    unsafe fn bump(&mut self, size: usize, align: usize) -> *mut u8 {
+        let size = size.max(1);
+        let fits = |chunk: *mut Chunk, offset: usize| align_up(chunk as usize + offset, align) + size <= chunk as usize + CHUNK_BYTES;
+        if self.current.is_null() || !fits(self.current, self.offset) {
+            let next = if self.current.is_null() { self.first } else { (*self.current).next };
+            let next = if next.is_null() { self.grow() } else { next };
+            if next.is_null() {
+                return ptr::null_mut();
+            }
+            self.current = next;
+            self.offset = HEADER_BYTES;
+        }
+        let start = align_up(self.current as usize + self.offset, align);
+        self.offset = start + size - self.current as usize;
+        self.last = start;
+        self.used += size;
+        self.peak = self.peak.max(self.used);
+        start as *mut u8
+    }
+}
+
+impl Arena {
+    const fn new() -> Self {
+        Arena {
+            lock: Lock::new(),
+            state: UnsafeCell::new(State {
+                active: false,
+                first: ptr::null_mut(),
+                current: ptr::null_mut(),
+                offset: 0,
+                last: 0,
+                low: usize::MAX,
+                high: 0,
+                large: ptr::null_mut(),
+                chunks: 0,
+                used: 0,
+                peak: 0,
+            }),
+        }
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        self.lock.with(|| f(unsafe { &mut *self.state.get() }))
+    }
+
+    fn set_active(&self, active: bool) -> bool {
+        self.with(|state| core::mem::replace(&mut state.active, active))
+    }
+
+    fn buffer(&self, len: usize) -> *mut u8 {
+        self.with(|state| unsafe {
+            if len <= CHUNK_BYTES - HEADER_BYTES {
+                return state.bump(len, 8);
+            }
+            let large = BACKING.alloc(large_layout(len)) as *mut Large;
+            if large.is_null() {
+                return ptr::null_mut();
+            }
+            (*large).next = state.large;
+            (*large).len = len;
+            state.large = large;
+            state.used += len;
+            state.peak = state.peak.max(state.used);
+            (large as *mut u8).add(HEADER_BYTES)
+        })
+    }
+
+    fn reset(&self) {
+        self.with(|state| unsafe {
+            while !state.large.is_null() {
+                let large = state.large;
+                state.large = (*large).next;
+                BACKING.dealloc(large as *mut u8, large_layout((*large).len));
+            }
+            state.active = false;
+            state.current = ptr::null_mut();
+            state.offset = 0;
+            state.last = 0;
+            state.used = 0;
+        })
+    }
+}
+
+unsafe impl GlobalAlloc for Arena {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let bumped = self.with(|state| {
+            let small = layout.size() <= MAX_BUMP_BYTES && layout.align() <= MAX_ALIGN;
+            if state.active && small {
+                state.bump(layout.size(), layout.align())
+            } else {
+                ptr::null_mut()
+            }
+        });
+        if bumped.is_null() {
+            BACKING.alloc(layout)
+        } else {
+            bumped
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let bumped = self.with(|state| {
+            let bumped = state.contains(ptr);
+            if bumped && state.is_last(ptr, layout.size()) {
+                state.offset = state.last - state.current as usize;
+                state.used -= layout.size().max(1);
+                state.last = 0;
+            }
+            bumped
+        });
+        if !bumped {
+            BACKING.dealloc(ptr, layout);
+        }
+    }
+
+    // Memory stays where it came from: the latest arena allocation grows in
+    // place while its chunk has room, other arena allocations are copied
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let resized = self.with(|state| {
+            if !state.contains(ptr) {
+                return None;
+            }
+            let end = ptr as usize + new_size;
+            if state.is_last(ptr, layout.size()) && new_size <= MAX_BUMP_BYTES && end <= state.current as usize + CHUNK_BYTES {
+                state.offset = end - state.current as usize;
+                state.used = state.used + new_size.max(1) - layout.size().max(1);
+                state.peak = state.peak.max(state.used);
+                return Some(ptr);
+            }
+            Some(ptr::null_mut())
+        });
+        match resized {
+            None => BACKING.realloc(ptr, layout, new_size),
+            Some(resized) if !resized.is_null() => resized,
+            Some(_) => {
+                let moved = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+                if !moved.is_null() {
+                    ptr::copy_nonoverlapping(ptr, moved, layout.size().min(new_size));
+                }
+                moved
+            }
+        }
+    }
+}
+
+// Runs one request with the arena serving its allocations
+pub fn during_request<R>(f: impl FnOnce() -> R) -> R {
+    ARENA.set_active(true);
+    let result = f();
+    ARENA.set_active(false);
+    result
+}
+
+// Runs `f` with regular allocations, for anything that has to outlive the
+// request; nests, and does nothing between requests
+pub fn outside<R>(f: impl FnOnce() -> R) -> R {
+    let active = ARENA.set_active(false);
+    let result = f();
+    ARENA.set_active(active);
+    result
+}
+
+// Uninitialised, 8-byte aligned space for `len` bytes, valid until the next
+// reset; null if memory runs out
+pub fn alloc(len: usize) -> *mut u8 {
+    ARENA.buffer(len)
+}
+
+pub fn reset() {
+    ARENA.reset()
+}
+
+// {"chunks":n,"retained_bytes":n,"used_bytes":n,"peak_bytes":n}
+pub fn stats_json() -> String {
+    // Formatting allocates, so nothing may be held while it runs
+    let (chunks, used, peak) = ARENA.with(|state| (state.chunks, state.used, state.peak));
+    format!(
+        r#"{{"chunks":{},"retained_bytes":{},"used_bytes":{},"peak_bytes":{}}}"#,
+        chunks,
+        chunks * CHUNK_BYTES,
+        used,
+        peak
+    )
+}
+
+// Request buffers for the host: valid until end_request, never freed individually
+#[no_mangle]
+pub extern "C" fn arena_alloc(len: usize) -> *mut u8 {
+    if len == 0 {
+        return std::ptr::null_mut();
+    }
+    alloc(len)
+}
+
+// Called once the host has copied the response out
+#[no_mangle]
+pub extern "C" fn end_request() {
+    context::end();
+    reset();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A private arena, so the tests never switch the global one on
+    fn arena() -> Arena {
+        let arena = Arena::new();
+        arena.set_active(true);
+        arena
+    }
+
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, 8).unwrap()
+    }
+
+    fn used(arena: &Arena) -> usize {
+        arena.with(|state| state.used)
+    }
+
+    #[test]
+    fn latest_allocation_grows_in_place() {
+        let arena = arena();
+        unsafe {
+            let ptr = arena.alloc(layout(64));
+            ptr.write_bytes(7, 64);
+            assert_eq!(arena.realloc(ptr, layout(64), 256), ptr);
+            assert_eq!(used(&arena), 256);
+            assert_eq!(*ptr.add(63), 7);
+        }
+    }
+
+    #[test]
+    fn earlier_allocation_is_copied() {
+        let arena = arena();
+        unsafe {
+            let first = arena.alloc(layout(32));
+            first.write_bytes(3, 32);
+            let second = arena.alloc(layout(32));
+            let moved = arena.realloc(first, layout(32), 64);
+            assert!(moved != first && moved != second);
+            assert!(arena.with(|state| state.contains(moved)));
+            assert_eq!(core::slice::from_raw_parts(moved, 32), &[3; 32]);
+            // The copy is now the latest allocation, so it can grow in place
+            assert!(arena.with(|state| state.is_last(moved, 64)));
+        }
+    }
+
+    #[test]
+    fn freeing_the_latest_allocation_rolls_back() {
+        let arena = arena();
+        unsafe {
+            let first = arena.alloc(layout(48));
+            let second = arena.alloc(layout(48));
+            arena.dealloc(second, layout(48));
+            assert_eq!(arena.alloc(layout(48)), second);
+            // Only the latest one can be taken back
+            arena.dealloc(first, layout(48));
+            assert_eq!(used(&arena), 96);
+        }
+    }
+
+    #[test]
+    fn frees_after_a_reset_are_ignored() {
+        let arena = arena();
+        unsafe {
+            let old = arena.alloc(layout(40));
+            arena.reset();
+            arena.set_active(true);
+            arena.dealloc(old, layout(40));
+            assert_eq!(used(&arena), 0);
+            // The chunk is reused from the start, so the new allocation shares
+            // the old address but is bigger; freeing the old one leaves it alone
+            let new = arena.alloc(layout(100));
+            assert_eq!(new, old);
+            arena.dealloc(old, layout(40));
+            assert_eq!(used(&arena), 100);
+            let next = arena.alloc(layout(8));
+            assert!(next as usize >= new as usize + 100);
+        }
+    }
+
+    #[test]
+    fn big_allocations_go_to_the_backing_allocator() {
+        let arena = arena();
+        unsafe {
+            let big = arena.alloc(layout(MAX_BUMP_BYTES + 1));
+            assert!(!big.is_null());
+            assert!(arena.with(|state| !state.contains(big)));
+            assert_eq!(used(&arena), 0);
+            arena.dealloc(big, layout(MAX_BUMP_BYTES + 1));
+
+            // Growing a bumped allocation past the limit moves it out too
+            let small = arena.alloc(layout(16));
+            small.write_bytes(9, 16);
+            let grown = arena.realloc(small, layout(16), MAX_BUMP_BYTES * 2);
+            assert!(arena.with(|state| !state.contains(grown)));
+            assert_eq!(core::slice::from_raw_parts(grown, 16), &[9; 16]);
+            arena.dealloc(grown, layout(MAX_BUMP_BYTES * 2));
+        }
+    }
+
+    #[test]
+    fn inactive_arena_uses_the_backing_allocator() {
+        let arena = Arena::new();
+        unsafe {
+            let ptr = arena.alloc(layout(16));
+            assert!(arena.with(|state| !state.contains(ptr)));
+            arena.dealloc(ptr, layout(16));
+        }
+        assert_eq!(arena.with(|state| state.chunks), 0);
+    }
+
+    #[test]
+    fn large_buffers_are_freed_by_reset() {
+        let arena = arena();
+        let len = CHUNK_BYTES * 2;
+        let buffer = arena.buffer(len);
+        assert!(!buffer.is_null());
+        assert_eq!(buffer as usize % 8, 0);
+        unsafe { buffer.write_bytes(1, len) };
+        assert_eq!(used(&arena), len);
+        assert!(arena.with(|state| !state.large.is_null()));
+        arena.reset();
+        assert!(arena.with(|state| state.large.is_null() && state.used == 0));
+    }
+
+    #[test]
+    fn chunks_are_reused_after_a_reset() {
+        let arena = arena();
+        for _ in 0..3 {
+            for _ in 0..8 {
+                assert!(!arena.buffer(CHUNK_BYTES / 4).is_null());
+            }
+            arena.reset();
+        }
+        assert_eq!(arena.with(|state| state.chunks), 3);
+        assert_eq!(arena.with(|state| state.peak), 8 * (CHUNK_BYTES / 4));
+    }
+}
@@ -0,0 +1,50 @@
+// Well-known files crawlers, browsers and security researchers ask every site
+// for: /robots.txt, /favicon.ico, /.well-known/security.txt and /sitemap.xml
+// Each but the sitemap has a default that CONFIG can replace (see
+// config.rs).
+
+use crate::dates;
+use crate::feeds;
+
+// Pastes and short links belong to whoever created them, so crawlers are
+// kept out of both
+pub const DEFAULT_ROBOTS_TXT: &str = "User-agent: *\nDisallow: /paste/\nDisallow: /s/\n";
+
+// Responses are text, so the favicon is an SVG; browsers go by the content
+// type rather than the .ico in the path
+pub const DEFAULT_FAVICON_SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100"><text y=".9em" font-size="90">🦀</text></svg>"#;
+
+// How far ahead security.txt's Expires field is set
+const SECURITY_TXT_VALIDITY_DAYS: u64 = 365;
+
+// Contact schemes RFC 9116 allows
+pub fn is_valid_contact(contact: &str) -> bool {
+    ["mailto:", "https://", "tel:"].iter().any(|scheme| contact.len() > scheme.len() && contact.starts_with(scheme))
+        && !contact.contains(['\r', '\n'])
+}
+
+// RFC 9116 requires a Contact and an Expires field, so without contacts there
+// is no file to serve. Expires rolls forward with the clock, staying a year
+// out for as long as the contacts are configured.
+pub fn security_txt(contacts: &[String], now_ms: f64) -> Option<String> {
+    if contacts.is_empty() {
+        return None;
+    }
+    let expires_ms = (now_ms as u64 / dates::MS_PER_DAY + SECURITY_TXT_VALIDITY_DAYS) * dates::MS_PER_DAY;
+    let mut text: String = contacts.iter().map(|contact| format!("Contact: {}\n", contact)).collect();
+    text.push_str(&format!("Expires: {}\n", dates::rfc3339(expires_ms)));
+    Some(text)
+}
+
+// Every URL shares one lastmod, since pages only change with a new build
+pub fn sitemap(urls: &[String], lastmod_ms: u64) -> String {
+    let lastmod = dates::rfc3339(lastmod_ms);
+    let entries: String = urls
+        .iter()
+        .map(|url| format!("\n  <url>\n    <loc>{}</loc>\n    <lastmod>{}</lastmod>\n  </url>", feeds::escape(url), lastmod))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">{}\n</urlset>\n",
+        entries
+    )
+}
@@ -0,0 +1,63 @@
+// FIGlet-style text banners from bitmap fonts embedded in the module
+// A font file is "BNR1", the glyph height and the glyph count, then per
+// glyph its character, its width (at most 8) and one byte per row with the
+// leftmost pixel in the top bit. The fonts cover printable ASCII without
+// lowercase, which is drawn as uppercase.
+
+pub const STANDARD: &[u8] = include_bytes!("data/banner-standard.bin");
+const SMALL: &[u8] = include_bytes!("data/banner-small.bin");
+
+pub const MAX_TEXT: usize = 40;
+
+// Name, font data and the character drawn for set pixels
+pub const FONTS: [(&str, &[u8], char); 3] = [("standard", STANDARD, '#'), ("small", SMALL, '#'), ("block", STANDARD, '█')];
+
+struct Font<'a> {
+    height: usize,
+    data: &'a [u8],
+}
+
+impl<'a> Font<'a> {
+    fn parse(data: &'a [u8]) -> Font<'a> {
+        assert!(data.starts_with(b"BNR1"), "banner font data is corrupt");
+        Font { height: data[4] as usize, data: &data[6..] }
+    }
+
+    // Width and rows of a glyph, if the font has one for `c`
+    fn glyph(&self, c: char) -> Option<(usize, &'a [u8])> {
+        let record = 2 + self.height;
+        self.data
+            .chunks_exact(record)
+            .find(|glyph| glyph[0] as char == c)
+            .map(|glyph| (glyph[1] as usize, &glyph[2..]))
+    }
+}
+
+// Width of a character's glyph and its rows, leftmost pixel in the top bit
+pub fn glyph(font: &[u8], c: char) -> Option<(usize, &[u8])> {
+    Font::parse(font).glyph(c)
+}
+
+// The rows of the banner, glyphs one blank column apart, unknown
+// characters drawn as '?'
+pub fn render(text: &str, font: &[u8], pixel: char) -> String {
+    let font = Font::parse(font);
+    let mut rows = vec![String::new(); font.height];
+    for (index, c) in text.chars().enumerate() {
+        let Some((width, bits)) = font.glyph(c.to_ascii_uppercase()).or_else(|| font.glyph('?')) else {
+            continue;
+        };
+        for (row, byte) in rows.iter_mut().zip(bits) {
+            if index > 0 {
+                row.push(' ');
+            }
+            row.extend((0..width).map(|x| if byte & (0x80 >> x) != 0 { pixel } else { ' ' }));
+        }
+    }
+    let mut out = String::new();
+    for row in rows {
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    out
+}
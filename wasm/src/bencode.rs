@@ -0,0 +1,223 @@
+// Bencode, the serialization format of BitTorrent metainfo files
+// Decoding is strict, so each value has exactly one encoding: integers
+// without leading zeros or "-0", dictionary keys sorted and unique, and
+// nothing after the top-level value. Integers are limited to 64 bits.
+//
+// In JSON, byte strings that are valid UTF-8 become strings and others
+// (such as a torrent's "pieces") the object {"$binary": "<base64>"}, which
+// from_json turns back into bytes. A dictionary that would itself read as one
+// of these objects, its only key being "$binary" or "$dict", is wrapped as
+// {"$dict": {...}}.
+
+use crate::hashing;
+use crate::json;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Deeply nested input is rejected rather than risking the WASM stack
+const MAX_DEPTH: usize = 128;
+
+const BINARY_KEY: &str = "$binary";
+const DICT_KEY: &str = "$dict";
+
+pub enum Value<'a> {
+    Integer(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Value<'a>>),
+    Dict(Vec<(&'a [u8], Value<'a>)>),
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at byte {}", message, self.pos)
+    }
+
+    // Digits up to `end`, returning the text between and moving past `end`
+    fn until(&mut self, end: u8) -> Result<&'a str, String> {
+        let rest = &self.input[self.pos..];
+        let Some(length) = rest.iter().position(|&b| b == end) else {
+            return Err(self.error(&format!("Missing '{}'", end as char)));
+        };
+        let text = core::str::from_utf8(&rest[..length]).map_err(|_| self.error("Invalid number"))?;
+        self.pos += length + 1;
+        Ok(text)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value<'a>, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("Nesting is too deep"));
+        }
+        match self.input.get(self.pos) {
+            Some(b'i') => {
+                self.pos += 1;
+                let start = self.pos;
+                let text = self.until(b'e')?;
+                let digits = text.strip_prefix('-').unwrap_or(text);
+                let canonical = !digits.is_empty()
+                    && digits.bytes().all(|b| b.is_ascii_digit())
+                    && (digits == "0" || !digits.starts_with('0'))
+                    && text != "-0";
+                match text.parse() {
+                    Ok(value) if canonical => Ok(Value::Integer(value)),
+                    _ if canonical => Err(format!("Integer at byte {} does not fit in 64 bits", start)),
+                    _ => Err(format!("Invalid integer '{}' at byte {}", text, start)),
+                }
+            }
+            Some(b'l') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while self.input.get(self.pos) != Some(&b'e') {
+                    items.push(self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Value::List(items))
+            }
+            Some(b'd') => {
+                self.pos += 1;
+                let mut members: Vec<(&[u8], Value)> = Vec::new();
+                while self.input.get(self.pos) != Some(&b'e') {
+                    let start = self.pos;
+                    let key = self.bytes()?;
+                    if members.last().is_some_and(|(previous, _)| *previous >= key) {
+                        return Err(format!("Dictionary key at byte {} is not sorted or repeats a key", start));
+                    }
+                    let value = self.value(depth + 1)?;
+                    members.push((key, value));
+                }
+                self.pos += 1;
+                Ok(Value::Dict(members))
+            }
+            Some(b'0'..=b'9') => self.bytes().map(Value::Bytes),
+            Some(&other) => Err(self.error(&format!("Unexpected byte {:#04x}", other))),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    // <length>:<bytes>
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let start = self.pos;
+        if !self.input.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            return Err(self.error("Expected a byte string"));
+        }
+        let text = self.until(b':')?;
+        let length: usize = match text.parse() {
+            Ok(length) if text.bytes().all(|b| b.is_ascii_digit()) && (text == "0" || !text.starts_with('0')) => length,
+            _ => return Err(format!("Invalid string length '{}' at byte {}", text, start)),
+        };
+        let Some(bytes) = self.input.get(self.pos..).and_then(|rest| rest.get(..length)) else {
+            return Err(format!("String at byte {} runs past the end", start));
+        };
+        self.pos += length;
+        Ok(bytes)
+    }
+}
+
+pub fn decode(input: &[u8]) -> Result<Value<'_>, String> {
+    let mut decoder = Decoder { input, pos: 0 };
+    let value = decoder.value(0)?;
+    if decoder.pos != input.len() {
+        return Err(decoder.error("Unexpected data after the value"));
+    }
+    Ok(value)
+}
+
+fn bytes_json(bytes: &[u8]) -> String {
+    match core::str::from_utf8(bytes) {
+        Ok(text) => format!("\"{}\"", json::escape(text)),
+        Err(_) => format!("{{\"{}\":\"{}\"}}", BINARY_KEY, hashing::to_base64(bytes)),
+    }
+}
+
+impl Value<'_> {
+    // Integers are written exactly, even beyond the 2^53 that JSON parsers
+    // commonly read exactly
+    pub fn to_json(&self) -> Result<String, String> {
+        Ok(match self {
+            Value::Integer(value) => value.to_string(),
+            Value::Bytes(bytes) => bytes_json(bytes),
+            Value::List(items) => {
+                let items = items.iter().map(Value::to_json).collect::<Result<Vec<_>, _>>()?;
+                format!("[{}]", items.join(","))
+            }
+            Value::Dict(members) => {
+                let mut rendered = Vec::with_capacity(members.len());
+                for (key, value) in members {
+                    let Ok(key) = core::str::from_utf8(key) else {
+                        return Err(format!("Dictionary key {} is not UTF-8, so it cannot be a JSON key", hashing::to_hex(key)));
+                    };
+                    rendered.push(format!("\"{}\":{}", json::escape(key), value.to_json()?));
+                }
+                let reserved = matches!(members.as_slice(), [(key, _)] if *key == BINARY_KEY.as_bytes() || *key == DICT_KEY.as_bytes());
+                if reserved {
+                    format!("{{\"{}\":{{{}}}}}", DICT_KEY, rendered.join(","))
+                } else {
+                    format!("{{{}}}", rendered.join(","))
+                }
+            }
+        })
+    }
+}
+
+// The inverse of to_json; dictionary keys are sorted as bencode requires
+pub fn from_json(value: &json::Value) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    encode_json(value, &mut out, 0)?;
+    Ok(out)
+}
+
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn encode_json(value: &json::Value, out: &mut Vec<u8>, depth: usize) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err("Nesting is too deep".to_string());
+    }
+    match value {
+        // No fract() or abs() without std
+        json::Value::Number(number) if (*number as i64) as f64 == *number && (*number as i64).unsigned_abs() <= 1 << 53 => {
+            out.extend_from_slice(format!("i{}e", *number as i64).as_bytes());
+        }
+        json::Value::Number(number) => return Err(format!("{} is not an integer bencode can hold exactly", number)),
+        json::Value::String(text) => encode_bytes(text.as_bytes(), out),
+        json::Value::Array(items) => {
+            out.push(b'l');
+            for item in items {
+                encode_json(item, out, depth + 1)?;
+            }
+            out.push(b'e');
+        }
+        json::Value::Object(members) => match members.as_slice() {
+            [(key, json::Value::String(encoded))] if key == BINARY_KEY => {
+                let bytes = hashing::from_base64(encoded).map_err(|reason| format!("Invalid {} value: {}", BINARY_KEY, reason))?;
+                encode_bytes(&bytes, out);
+            }
+            [(key, json::Value::Object(members))] if key == DICT_KEY => encode_dict(members, out, depth)?,
+            _ => encode_dict(members, out, depth)?,
+        },
+        json::Value::Bool(_) | json::Value::Null => return Err("Bencode has no booleans or null".to_string()),
+    }
+    Ok(())
+}
+
+fn encode_dict(members: &[(String, json::Value)], out: &mut Vec<u8>, depth: usize) -> Result<(), String> {
+    let mut sorted: Vec<&(String, json::Value)> = members.iter().collect();
+    sorted.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+    if let Some(pair) = sorted.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(format!("Key '{}' appears more than once", pair[0].0));
+    }
+    out.push(b'd');
+    for (key, value) in sorted {
+        encode_bytes(key.as_bytes(), out);
+        encode_json(value, out, depth + 1)?;
+    }
+    out.push(b'e');
+    Ok(())
+}
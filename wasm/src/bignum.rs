@@ -0,0 +1,287 @@
+// Arbitrary precision unsigned integers
+// Limbs are base 10^9 and little-endian, which keeps decimal printing cheap
+// and every limb product within a u64.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::budget;
+use core::cmp::Ordering;
+use core::fmt;
+
+const BASE: u64 = 1_000_000_000;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct BigUint {
+    // No trailing zero limbs; zero is the empty vector
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn from_u64(mut value: u64) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push((value % BASE) as u32);
+            value /= BASE;
+        }
+        BigUint { limbs }
+    }
+
+    // Big-endian bytes, e.g. a hash digest
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        bytes
+            .iter()
+            .fold(BigUint::zero(), |value, &byte| value.mul_small(256).add(&BigUint::from_u64(byte as u64)))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // The base is even, so parity is that of the lowest limb
+    pub fn is_even(&self) -> bool {
+        self.limbs.first().is_none_or(|limb| limb % 2 == 0)
+    }
+
+    // 10^exponent
+    pub fn pow10(exponent: usize) -> BigUint {
+        let mut limbs = vec![0u32; exponent / 9];
+        limbs.push(10u32.pow((exponent % 9) as u32));
+        BigUint { limbs }
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    // Arithmetic charges the computation budget one step per limb operation
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        budget::charge(self.limbs.len().max(other.limbs.len()));
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64 + *other.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push((sum % BASE) as u32);
+            carry = sum / BASE;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }
+    }
+
+    // self - other; None if other is larger
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {
+            return None;
+        }
+        budget::charge(self.limbs.len());
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+        for i in 0..self.limbs.len() {
+            let mut difference = self.limbs[i] as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += BASE as i64;
+                borrow = 1;
+            }
+            limbs.push(difference as u32);
+        }
+        let mut result = BigUint { limbs };
+        result.trim();
+        Some(result)
+    }
+
+    pub fn mul_small(&self, factor: u32) -> BigUint {
+        if factor == 0 {
+            return BigUint::zero();
+        }
+        budget::charge(self.limbs.len());
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = 0;
+        for &limb in &self.limbs {
+            let product = limb as u64 * factor as u64 + carry;
+            limbs.push((product % BASE) as u32);
+            carry = product / BASE;
+        }
+        while carry > 0 {
+            limbs.push((carry % BASE) as u32);
+            carry /= BASE;
+        }
+        BigUint { limbs }
+    }
+
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        budget::charge(self.limbs.len() * other.limbs.len());
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            // (BASE - 1)^2 + 2 * (BASE - 1) < BASE^2, so this never overflows
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let value = limbs[i + j] as u64 + a as u64 * b as u64 + carry;
+                limbs[i + j] = (value % BASE) as u32;
+                carry = value / BASE;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        let mut product = BigUint { limbs };
+        product.trim();
+        product
+    }
+
+    // Quotient and remainder of division by a small nonzero divisor
+    pub fn div_small(&self, divisor: u32) -> (BigUint, u32) {
+        assert!(divisor != 0, "division by zero");
+        budget::charge(self.limbs.len());
+        let mut limbs = vec![0u32; self.limbs.len()];
+        let mut remainder = 0u64;
+        for i in (0..self.limbs.len()).rev() {
+            let value = remainder * BASE + self.limbs[i] as u64;
+            limbs[i] = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        let mut quotient = BigUint { limbs };
+        quotient.trim();
+        (quotient, remainder as u32)
+    }
+
+    // Quotient and remainder of division by a nonzero divisor, using Knuth's
+    // algorithm D (TAOCP vol. 2, 4.3.1) on base 10^9 digits
+    pub fn div_rem(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        assert!(!divisor.is_zero(), "division by zero");
+        if self < divisor {
+            return (BigUint::zero(), self.clone());
+        }
+        if let [limb] = divisor.limbs[..] {
+            let (quotient, remainder) = self.div_small(limb);
+            return (quotient, BigUint::from_u64(remainder as u64));
+        }
+
+        // Scaling so the divisor's top limb is at least BASE / 2 makes each
+        // estimated quotient digit at most two too large
+        let scale = (BASE / (*divisor.limbs.last().unwrap() as u64 + 1)) as u32;
+        let v = divisor.mul_small(scale).limbs;
+        let mut u = self.mul_small(scale).limbs;
+        u.resize(self.limbs.len() + 1, 0);
+        let n = v.len();
+        let mut quotient = vec![0u32; u.len() - n];
+        budget::charge(quotient.len() * n);
+
+        for j in (0..quotient.len()).rev() {
+            let numerator = u[j + n] as u64 * BASE + u[j + n - 1] as u64;
+            let mut q = numerator / v[n - 1] as u64;
+            let mut r = numerator % v[n - 1] as u64;
+            while q >= BASE || q * v[n - 2] as u64 > r * BASE + u[j + n - 2] as u64 {
+                q -= 1;
+                r += v[n - 1] as u64;
+                if r >= BASE {
+                    break;
+                }
+            }
+
+            // Subtract q * v from the current window of u
+            let mut borrow = 0i64;
+            let mut carry = 0u64;
+            for (i, &digit) in v.iter().enumerate() {
+                let product = q * digit as u64 + carry;
+                carry = product / BASE;
+                let mut difference = u[i + j] as i64 - (product % BASE) as i64 - borrow;
+                borrow = 0;
+                if difference < 0 {
+                    difference += BASE as i64;
+                    borrow = 1;
+                }
+                u[i + j] = difference as u32;
+            }
+            let mut top = u[j + n] as i64 - carry as i64 - borrow;
+
+            // Rarely q is still one too large: add the divisor back
+            if top < 0 {
+                q -= 1;
+                let mut carry = 0u64;
+                for (i, &digit) in v.iter().enumerate() {
+                    let sum = u[i + j] as u64 + digit as u64 + carry;
+                    u[i + j] = (sum % BASE) as u32;
+                    carry = sum / BASE;
+                }
+                top += carry as i64;
+            }
+            u[j + n] = top as u32;
+            quotient[j] = q as u32;
+        }
+
+        let mut quotient = BigUint { limbs: quotient };
+        quotient.trim();
+        u.truncate(n);
+        let mut remainder = BigUint { limbs: u };
+        remainder.trim();
+        (quotient, remainder.div_small(scale).0)
+    }
+
+    pub fn rem(&self, modulus: &BigUint) -> BigUint {
+        self.div_rem(modulus).1
+    }
+
+    // self^exponent mod modulus by left-to-right square-and-multiply
+    pub fn modpow(&self, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+        // Exponent bits, least significant first, peeled off 30 at a time
+        let mut bits = Vec::new();
+        let mut rest = exponent.clone();
+        while !rest.is_zero() {
+            let (quotient, chunk) = rest.div_small(1 << 30);
+            bits.extend((0..30).map(|k| (chunk >> k) & 1 == 1));
+            rest = quotient;
+        }
+
+        let base = self.rem(modulus);
+        let mut result = BigUint::from_u64(1).rem(modulus);
+        for &bit in bits.iter().rev() {
+            if budget::exhausted() {
+                break;
+            }
+            result = result.mul(&result).rem(modulus);
+            if bit {
+                result = result.mul(&base).rem(modulus);
+            }
+        }
+        result
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.limbs.split_last() {
+            None => write!(f, "0"),
+            Some((top, rest)) => {
+                write!(f, "{}", top)?;
+                for limb in rest.iter().rev() {
+                    write!(f, "{:09}", limb)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
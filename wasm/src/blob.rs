@@ -0,0 +1,58 @@
+// Content-addressed blobs on top of the KV bindings
+// A blob's id is the SHA-256 of its bytes in hex, so uploading the same
+// content again finds the stored copy instead of writing a second one. KV
+// values are text, so a blob is stored under blob:<id> as
+// "<created_ms> <content type>\n<base64 bytes>". Blobs never expire.
+
+use crate::hashing::{self, Sha256};
+use crate::host;
+use crate::kv::{self, Pending};
+
+pub struct Blob {
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+    pub created_ms: u64,
+}
+
+fn key(id: &str) -> String {
+    format!("blob:{}", id)
+}
+
+pub fn is_valid_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+pub fn id(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    hashing::to_hex(&hasher.finalize())
+}
+
+// Stores the bytes unless a blob with the same id exists, returning the id
+// and whether it was new. An existing blob keeps its original content type.
+pub fn store(bytes: &[u8], content_type: &str) -> Result<(String, bool), Pending> {
+    let id = id(bytes);
+    if kv::get(&key(&id))?.is_some() {
+        return Ok((id, false));
+    }
+    let value = format!("{} {}\n{}", host::now_ms() as u64, content_type, hashing::to_base64(bytes));
+    kv::put(&key(&id), &value, 0);
+    Ok((id, true))
+}
+
+// Ok(None) for unknown or unreadable blobs
+pub fn load(id: &str) -> Result<Option<Blob>, Pending> {
+    let Some(stored) = kv::get(&key(id))? else {
+        return Ok(None);
+    };
+    let Some((header, encoded)) = stored.split_once('\n') else {
+        return Ok(None);
+    };
+    let Some((created_ms, content_type)) = header.split_once(' ') else {
+        return Ok(None);
+    };
+    let (Ok(created_ms), Ok(bytes)) = (created_ms.parse(), hashing::from_base64(encoded)) else {
+        return Ok(None);
+    };
+    Ok(Some(Blob { content_type: content_type.to_string(), bytes, created_ms }))
+}
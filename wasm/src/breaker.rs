@@ -0,0 +1,128 @@
+// Circuit breakers around outbound fetches, one per origin
+// A closed breaker lets requests through and counts consecutive failures,
+// which are network errors and 5xx answers. At the threshold it opens and
+// refuses requests without contacting the origin for a cool-down, so a
+// failing origin is not waited on, and the request replayed, every time.
+// After the cool-down it is half-open: one request goes through as a trial,
+// closing the breaker if it succeeds and opening it again if not. Like the
+// counters, breakers belong to one isolate.
+//
+// A request the host replays sees the same fetch result on every pass, so
+// outcomes are only applied by finish() once the final pass is known.
+
+use crate::arena;
+use crate::config::Config;
+use crate::context;
+use crate::fetch;
+use crate::host;
+use crate::json;
+use crate::kv::Pending;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+enum State {
+    Closed { failures: u32 },
+    Open { until_ms: f64 },
+    // The trial request, by ID, and when it started
+    HalfOpen { trial: String, since_ms: f64 },
+}
+
+pub enum Error {
+    Pending,
+    // Refused without a request; retry after this many milliseconds
+    Open { retry_after_ms: u64 },
+    // The origin could not be reached
+    Failed(String),
+}
+
+impl From<Pending> for Error {
+    fn from(_: Pending) -> Self {
+        Error::Pending
+    }
+}
+
+thread_local! {
+    static BREAKERS: RefCell<BTreeMap<String, State>> = const { RefCell::new(BTreeMap::new()) };
+    // Outcomes seen in the current pass, as origin and success
+    static OUTCOMES: RefCell<Vec<(String, bool)>> = const { RefCell::new(Vec::new()) };
+}
+
+// Whether a request to the origin may go out now. An open breaker turns
+// half-open once its cool-down is over, and a trial that never reported
+// back is given up on after another cool-down.
+fn allow(origin: &str, config: &Config) -> Result<(), Error> {
+    let now = host::now_ms();
+    arena::outside(|| BREAKERS.with(|breakers| {
+        let request = context::request_id().unwrap_or_default();
+        let mut breakers = breakers.borrow_mut();
+        let state = breakers.entry(origin.to_string()).or_insert(State::Closed { failures: 0 });
+        match state {
+            State::Closed { .. } => Ok(()),
+            State::HalfOpen { trial, .. } if *trial == request => Ok(()),
+            State::HalfOpen { since_ms, .. } if now - *since_ms < f64::from(config.breaker_cooldown_ms) => {
+                Err(Error::Open { retry_after_ms: u64::from(config.breaker_cooldown_ms) })
+            }
+            State::Open { until_ms } if now < *until_ms => Err(Error::Open { retry_after_ms: (*until_ms - now) as u64 }),
+            _ => {
+                *state = State::HalfOpen { trial: request, since_ms: now };
+                Ok(())
+            }
+        }
+    }))
+}
+
+// GETs the URL unless the breaker for its origin is open
+pub fn fetch(origin: &str, url: &str, config: &Config) -> Result<fetch::Response, Error> {
+    allow(origin, config)?;
+    let result = fetch::get(url)?;
+    let success = matches!(&result, Ok(response) if response.status < 500);
+    arena::outside(|| OUTCOMES.with(|outcomes| outcomes.borrow_mut().push((origin.to_string(), success))));
+    result.map_err(Error::Failed)
+}
+
+// Applies the outcomes of the pass that just ended, unless the host is
+// going to replay it
+pub fn finish(replayed: bool, config: &Config) {
+    let outcomes = OUTCOMES.with(|outcomes| std::mem::take(&mut *outcomes.borrow_mut()));
+    if replayed {
+        return;
+    }
+    let now = host::now_ms();
+    arena::outside(|| BREAKERS.with(|breakers| {
+        let mut breakers = breakers.borrow_mut();
+        for (origin, success) in outcomes {
+            let state = breakers.entry(origin).or_insert(State::Closed { failures: 0 });
+            *state = match (&*state, success) {
+                (_, true) => State::Closed { failures: 0 },
+                (State::Closed { failures }, false) if failures + 1 < config.breaker_failure_threshold => {
+                    State::Closed { failures: failures + 1 }
+                }
+                (_, false) => State::Open { until_ms: now + f64::from(config.breaker_cooldown_ms) },
+            };
+        }
+    }));
+}
+
+// {"https://example.com":{"state":"open","retry_after_ms":1200}, ...}
+pub fn to_json() -> String {
+    let now = host::now_ms();
+    let entries: Vec<String> = BREAKERS.with(|breakers| {
+        breakers
+            .borrow()
+            .iter()
+            .map(|(origin, state)| {
+                let details = match state {
+                    State::Closed { failures } => format!(r#""state":"closed","failures":{}"#, failures),
+                    State::Open { until_ms } => {
+                        format!(r#""state":"open","retry_after_ms":{}"#, (until_ms - now).max(0.0) as u64)
+                    }
+                    State::HalfOpen { since_ms, .. } => {
+                        format!(r#""state":"half-open","trial_age_ms":{}"#, (now - since_ms).max(0.0) as u64)
+                    }
+                };
+                format!(r#""{}":{{{}}}"#, json::escape(origin), details)
+            })
+            .collect()
+    });
+    format!("{{{}}}", entries.join(","))
+}
@@ -0,0 +1,32 @@
+// Per-request computation budget
+// Workers kill an isolate that overruns its CPU limit, taking every request
+// it is serving with it. Expensive code charges the budget for the work it
+// does (roughly one step per inner-loop iteration) and long-running loops
+// bail out once it is spent; the request then answers 503 instead, whatever
+// partial result the handler produced.
+
+// Relaxed atomics rather than thread locals so this also builds without std;
+// the module serves one request at a time, so they are never contended.
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+static REMAINING: AtomicU64 = AtomicU64::new(u64::MAX);
+static EXHAUSTED: AtomicBool = AtomicBool::new(false);
+
+pub fn reset(steps: u64) {
+    REMAINING.store(steps, Ordering::Relaxed);
+    EXHAUSTED.store(false, Ordering::Relaxed);
+}
+
+pub fn charge(steps: usize) {
+    match REMAINING.load(Ordering::Relaxed).checked_sub(steps as u64) {
+        Some(left) => REMAINING.store(left, Ordering::Relaxed),
+        None => {
+            REMAINING.store(0, Ordering::Relaxed);
+            EXHAUSTED.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn exhausted() -> bool {
+    EXHAUSTED.load(Ordering::Relaxed)
+}
@@ -0,0 +1,91 @@
+// In-memory TTL cache the JS host can use between requests
+// Entries live in module memory, so they survive for as long as the isolate
+// keeps this instance alive and are never shared across isolates.
+
+use crate::ffi;
+use crate::host;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+const MAX_ENTRIES: usize = 1024;
+const MAX_VALUE_BYTES: usize = 64 * 1024;
+
+struct Entry {
+    value: String,
+    // None for entries stored without a TTL
+    expires_at: Option<f64>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: f64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
+
+thread_local! {
+    static CACHE: RefCell<HashMap<String, Entry>> = RefCell::new(HashMap::new());
+}
+
+fn read_str<'a>(ptr: *const u8, len: usize) -> Option<&'a str> {
+    unsafe { ffi::text(ptr, len) }.ok()
+}
+
+// Store a UTF-8 value under a key; ttl_ms of 0 keeps it until deleted.
+// Returns 1 when stored, 0 when the input is invalid or the cache is full.
+#[no_mangle]
+pub extern "C" fn cache_set(key_ptr: *const u8, key_len: usize, value_ptr: *const u8, value_len: usize, ttl_ms: u32) -> i32 {
+    let (Some(key), Some(value)) = (read_str(key_ptr, key_len), read_str(value_ptr, value_len)) else {
+        return 0;
+    };
+    if key.is_empty() || value.len() > MAX_VALUE_BYTES || value.contains('\0') {
+        return 0;
+    }
+
+    let now = host::now_ms();
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= MAX_ENTRIES && !cache.contains_key(key) {
+            cache.retain(|_, entry| !entry.is_expired(now));
+            if cache.len() >= MAX_ENTRIES {
+                return 0;
+            }
+        }
+        let expires_at = (ttl_ms > 0).then_some(now + ttl_ms as f64);
+        cache.insert(key.to_string(), Entry { value: value.to_string(), expires_at });
+        1
+    })
+}
+
+// Look up a key; returns null when missing or expired, otherwise a buffer to
+// release with free_bytes
+#[no_mangle]
+pub extern "C" fn cache_get(key_ptr: *const u8, key_len: usize) -> *mut u8 {
+    let Some(key) = read_str(key_ptr, key_len) else {
+        return std::ptr::null_mut();
+    };
+    let now = host::now_ms();
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        match cache.get(key) {
+            Some(entry) if entry.is_expired(now) => {
+                cache.remove(key);
+                std::ptr::null_mut()
+            }
+            Some(entry) => ffi::output(entry.value.as_bytes()),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+// Remove a key; returns 1 if a live entry was removed
+#[no_mangle]
+pub extern "C" fn cache_delete(key_ptr: *const u8, key_len: usize) -> i32 {
+    let Some(key) = read_str(key_ptr, key_len) else {
+        return 0;
+    };
+    let now = host::now_ms();
+    CACHE.with(|cache| match cache.borrow_mut().remove(key) {
+        Some(entry) if !entry.is_expired(now) => 1,
+        _ => 0,
+    })
+}
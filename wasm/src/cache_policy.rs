@@ -0,0 +1,199 @@
+// How a cache treats a response, after RFC 9111
+// A shared cache (a CDN) prefers s-maxage, then max-age, then Expires minus
+// Date; a browser's private cache skips s-maxage. Without any of them a
+// response with Last-Modified gets a tenth of its age at the time as
+// heuristic freshness, if its status allows that or it is public. The age
+// is the larger of the Age header and the time since Date, taking the
+// response to have just arrived. Once the lifetime is used up the copy is
+// stale: stale-while-revalidate and stale-if-error allow serving it a while
+// longer, unless must-revalidate (or, in a shared cache, proxy-revalidate or
+// s-maxage) forbids it, and no-cache asks for revalidation on every use.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::dates;
+use crate::headers::Headers;
+
+// Statuses RFC 9110 lets a cache give heuristic freshness
+const HEURISTIC_STATUSES: [u16; 12] = [200, 203, 204, 206, 300, 301, 308, 404, 405, 410, 414, 501];
+// Larger delta-seconds are read as this, as RFC 9111 allows
+const MAX_DELTA_SECONDS: u64 = 1 << 31;
+
+#[derive(Default)]
+pub struct Directives {
+    pub max_age: Option<u64>,
+    pub s_maxage: Option<u64>,
+    pub stale_while_revalidate: Option<u64>,
+    pub stale_if_error: Option<u64>,
+    pub no_store: bool,
+    pub no_cache: bool,
+    pub private: bool,
+    pub public: bool,
+    pub must_revalidate: bool,
+    pub proxy_revalidate: bool,
+    pub immutable: bool,
+    pub unknown: Vec<String>,
+}
+
+pub struct Analysis {
+    pub directives: Directives,
+    // Why no cache of this kind may store or reuse the response
+    pub not_storable: Option<String>,
+    // Seconds, and what set them: "s-maxage", "max-age", "expires",
+    // "heuristic" or "none"
+    pub lifetime: u64,
+    pub lifetime_source: &'static str,
+    pub age: u64,
+    // Seconds left before the copy goes stale; negative once it has
+    pub ttl: i64,
+    // "not-stored", "revalidate" (no-cache), "fresh",
+    // "stale-while-revalidate", "stale-if-error", "must-revalidate" or "stale"
+    pub state: &'static str,
+    // Whether a stale copy may ever be served without revalidating
+    pub may_serve_stale: bool,
+    // The conditional request headers revalidation would use
+    pub validators: Vec<&'static str>,
+    pub vary: Vec<String>,
+}
+
+fn delta_seconds(value: &str) -> Option<u64> {
+    let value = value.trim().trim_matches('"');
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(value.parse().unwrap_or(MAX_DELTA_SECONDS).min(MAX_DELTA_SECONDS))
+}
+
+// Splits on commas outside quotes, as in no-cache="Set-Cookie, Foo"
+fn list(value: &str) -> Vec<&str> {
+    let (mut items, mut start, mut quoted) = (Vec::new(), 0, false);
+    for (index, c) in value.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                items.push(value[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(value[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+// Names are compared without case and the first of a repeated directive
+// counts. An invalid lifetime is read as 0, making the response stale, and
+// no-cache or private with field names only restrict those fields, so they
+// leave the response itself alone.
+pub fn directives(cache_control: &str) -> Directives {
+    let mut parsed = Directives::default();
+    for item in list(cache_control) {
+        let (name, argument) = match item.split_once('=') {
+            Some((name, argument)) => (name.trim().to_ascii_lowercase(), Some(argument.trim())),
+            None => (item.to_ascii_lowercase(), None),
+        };
+        let seconds = || Some(argument.and_then(delta_seconds).unwrap_or(0));
+        match (name.as_str(), argument) {
+            ("max-age", _) => parsed.max_age = parsed.max_age.or_else(seconds),
+            ("s-maxage", _) => parsed.s_maxage = parsed.s_maxage.or_else(seconds),
+            ("stale-while-revalidate", _) => parsed.stale_while_revalidate = parsed.stale_while_revalidate.or_else(seconds),
+            ("stale-if-error", _) => parsed.stale_if_error = parsed.stale_if_error.or_else(seconds),
+            ("no-store", _) => parsed.no_store = true,
+            ("no-cache", None) => parsed.no_cache = true,
+            ("private", None) => parsed.private = true,
+            ("no-cache" | "private", Some(_)) => {}
+            ("public", _) => parsed.public = true,
+            ("must-revalidate", _) => parsed.must_revalidate = true,
+            ("proxy-revalidate", _) => parsed.proxy_revalidate = true,
+            ("immutable", _) => parsed.immutable = true,
+            _ => parsed.unknown.push(item.to_string()),
+        }
+    }
+    parsed
+}
+
+// `now_ms` is when the response arrived
+pub fn analyze(headers: &Headers, status: u16, shared: bool, now_ms: u64) -> Analysis {
+    let directives = directives(headers.get("cache-control").unwrap_or(""));
+    let date = headers.get("date").and_then(dates::parse_http_date).unwrap_or(now_ms);
+    let seconds_between = |from: u64, to: u64| to.saturating_sub(from) / 1000;
+
+    let explicit = match (shared.then_some(directives.s_maxage).flatten(), directives.max_age, headers.get("expires")) {
+        (Some(s_maxage), _, _) => Some((s_maxage, "s-maxage")),
+        (None, Some(max_age), _) => Some((max_age, "max-age")),
+        // An invalid Expires, such as "0", means already expired
+        (None, None, Some(expires)) => Some((dates::parse_http_date(expires).map_or(0, |expires| seconds_between(date, expires)), "expires")),
+        (None, None, None) => None,
+    };
+    let heuristic_allowed = HEURISTIC_STATUSES.contains(&status) || directives.public;
+    let (lifetime, lifetime_source) = explicit.unwrap_or_else(|| {
+        match headers.get("last-modified").and_then(dates::parse_http_date).filter(|_| heuristic_allowed) {
+            Some(modified) => (seconds_between(modified, date) / 10, "heuristic"),
+            None => (0, "none"),
+        }
+    });
+    let age = headers.get("age").and_then(delta_seconds).unwrap_or(0).max(seconds_between(date, now_ms));
+    let ttl = lifetime as i64 - age as i64;
+
+    let vary: Vec<String> = headers.get("vary").map(|vary| list(vary).into_iter().map(|name| name.to_ascii_lowercase()).collect()).unwrap_or_default();
+    let not_storable = if directives.no_store {
+        Some("no-store forbids storing it".to_string())
+    } else if shared && directives.private {
+        Some("private leaves it to the browser's own cache".to_string())
+    } else if status < 200 || status == 304 {
+        Some(format!("{} is not a response a cache stores", status))
+    } else if vary.iter().any(|name| name == "*") {
+        Some("Vary: * never matches a later request".to_string())
+    } else if explicit.is_none() && !heuristic_allowed {
+        Some(format!("{} is not cacheable by default and no lifetime is given", status))
+    } else {
+        None
+    };
+
+    let forbids_stale = directives.must_revalidate || shared && (directives.proxy_revalidate || directives.s_maxage.is_some());
+    let may_serve_stale = !forbids_stale && directives.stale_while_revalidate.or(directives.stale_if_error).is_some_and(|seconds| seconds > 0);
+    let stale_for = ttl.unsigned_abs();
+    let state = match () {
+        _ if not_storable.is_some() => "not-stored",
+        _ if directives.no_cache => "revalidate",
+        _ if ttl > 0 => "fresh",
+        _ if forbids_stale => "must-revalidate",
+        _ if directives.stale_while_revalidate.is_some_and(|seconds| stale_for < seconds) => "stale-while-revalidate",
+        _ if directives.stale_if_error.is_some_and(|seconds| stale_for < seconds) => "stale-if-error",
+        _ => "stale",
+    };
+    let mut validators = Vec::new();
+    if headers.get("etag").is_some() {
+        validators.push("If-None-Match");
+    }
+    if headers.get("last-modified").is_some() {
+        validators.push("If-Modified-Since");
+    }
+    Analysis { directives, not_storable, lifetime, lifetime_source, age, ttl, state, may_serve_stale, validators, vary }
+}
+
+impl Analysis {
+    // One sentence on what the cache does now and later
+    pub fn summary(&self) -> String {
+        let fetch = if self.validators.is_empty() { "fetched again in full" } else { "revalidated with a conditional request" };
+        match self.state {
+            "not-stored" => format!("Not stored: {}; every request goes to the origin", self.not_storable.as_deref().unwrap_or("")),
+            "revalidate" => format!("Stored, but no-cache has it {} before every use", fetch),
+            "fresh" if self.directives.immutable => format!("Fresh for another {} s and, being immutable, not revalidated on reload", self.ttl),
+            "fresh" => {
+                let then = match (self.may_serve_stale, self.directives.stale_while_revalidate) {
+                    (true, Some(seconds)) if seconds > 0 => format!("then served stale for up to {} s while it is {} in the background", seconds, fetch),
+                    _ => format!("then {}", fetch),
+                };
+                format!("Fresh for another {} s, {}", self.ttl, then)
+            }
+            "must-revalidate" => format!("Stale for {} s and must be {} before use, even if the origin is down", self.ttl.unsigned_abs(), fetch),
+            "stale-while-revalidate" => format!("Stale for {} s; served as is while being {} in the background", self.ttl.unsigned_abs(), fetch),
+            "stale-if-error" => format!("Stale for {} s; {} before use, but served as is if the origin errors", self.ttl.unsigned_abs(), fetch),
+            _ => format!("Stale for {} s; {} before use", self.ttl.unsigned_abs(), fetch),
+        }
+    }
+}
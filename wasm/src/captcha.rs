@@ -0,0 +1,230 @@
+// Captcha challenges whose answers are never stored
+// A challenge is a sum to work out or characters drawn, rotated and skewed,
+// among noise lines in an SVG. Its token is "<kind>.<issued seconds>.<nonce>.
+// <mac>.<seal>", the mac an HMAC-SHA256 under captcha_secret of the first
+// three parts and the answer, so the answer never leaves the Worker and
+// checking a submission means recomputing the mac with it. The seal, an HMAC
+// of the rest, shows a token is genuine whatever the answer, so every attempt
+// on one is remembered in KV until it expires: each token gets a single try,
+// rather than one per guess at its hundred or so possible sums.
+
+use crate::banner;
+use crate::hashing::{self, HmacSha256};
+use crate::host;
+use crate::kv::{self, Pending};
+use crate::rand::Rng;
+use crate::svg::{self, Svg};
+
+pub const MAX_AGE_SECONDS: u64 = 300;
+// Letters and digits without look-alikes such as 0 and O, or 1 and I
+const TEXT_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const TEXT_LENGTH: usize = 6;
+const PIXEL: f64 = 4.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Math,
+    Text,
+}
+
+impl Kind {
+    pub fn parse(name: &str) -> Option<Kind> {
+        match name {
+            "math" => Some(Kind::Math),
+            "text" => Some(Kind::Text),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Math => "math",
+            Kind::Text => "text",
+        }
+    }
+}
+
+pub struct Challenge {
+    // "What is 7 + 5?", or an SVG document for text challenges
+    pub question: String,
+    pub token: String,
+}
+
+pub enum Outcome {
+    Valid,
+    WrongAnswer,
+    Expired,
+    AlreadyUsed,
+}
+
+impl Outcome {
+    pub fn reason(&self) -> Option<&'static str> {
+        match self {
+            Outcome::Valid => None,
+            Outcome::WrongAnswer => Some("wrong_answer"),
+            Outcome::Expired => Some("expired"),
+            Outcome::AlreadyUsed => Some("already_used"),
+        }
+    }
+}
+
+// Answers are compared without case or spaces
+fn normalize(answer: &str) -> String {
+    answer.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+fn mac(secret: &str, signed: &str, answer: &str) -> [u8; 32] {
+    let mut hmac = HmacSha256::new(secret.as_bytes());
+    hmac.update(signed.as_bytes());
+    hmac.update(b"\n");
+    hmac.update(normalize(answer).as_bytes());
+    hmac.finalize()
+}
+
+fn seal(secret: &str, sealed: &str) -> [u8; 32] {
+    let mut hmac = HmacSha256::new(secret.as_bytes());
+    hmac.update(b"seal\n");
+    hmac.update(sealed.as_bytes());
+    hmac.finalize()
+}
+
+fn math_question(rng: &mut Rng) -> (String, String) {
+    let (a, b) = (rng.range(1, 20), rng.range(1, 20));
+    match rng.below(3) {
+        0 => (format!("What is {} + {}?", a, b), (a + b).to_string()),
+        1 => (format!("What is {} - {}?", a.max(b), a.min(b)), (a.max(b) - a.min(b)).to_string()),
+        _ => {
+            let (a, b) = (a % 10 + 1, b % 10 + 1);
+            (format!("What is {} × {}?", a, b), (a * b).to_string())
+        }
+    }
+}
+
+// Each character is a path of its glyph's pixels, given its own colour,
+// size, rotation and skew
+fn text_image(rng: &mut Rng, text: &str) -> String {
+    let cell = 9.0 * PIXEL;
+    let (width, height) = (text.len() as f64 * cell + 2.0 * PIXEL, 16.0 * PIXEL);
+    let mut image = Svg::new(width as u32, height as u32);
+    image.title("Captcha");
+    let colour = |rng: &mut Rng| format!("hsl({},70%,{}%)", rng.below(360), rng.range(20, 40));
+    for (index, c) in text.chars().enumerate() {
+        let Some((glyph_width, rows)) = banner::glyph(banner::STANDARD, c) else {
+            continue;
+        };
+        let scale = PIXEL * (0.85 + rng.below(30) as f64 / 100.0);
+        let x = 2.0 * PIXEL + index as f64 * cell;
+        let y = (height - rows.len() as f64 * scale) / 2.0 + rng.range(-6, 6) as f64;
+        let mut path = String::new();
+        for (row, bits) in rows.iter().enumerate() {
+            for column in (0..glyph_width).filter(|column| bits & (0x80 >> column) != 0) {
+                path.push_str(&format!(
+                    "M{} {}h{}v{}h-{}z",
+                    svg::number(x + column as f64 * scale),
+                    svg::number(y + row as f64 * scale),
+                    svg::number(scale),
+                    svg::number(scale),
+                    svg::number(scale)
+                ));
+            }
+        }
+        let (centre_x, centre_y) = (x + glyph_width as f64 * scale / 2.0, height / 2.0);
+        // Rotated and skewed about the glyph's centre
+        let transform = format!(
+            "translate({0} {1}) rotate({2}) skewX({3}) translate({4} {5})",
+            svg::number(centre_x),
+            svg::number(centre_y),
+            rng.range(-25, 25),
+            rng.range(-12, 12),
+            svg::number(-centre_x),
+            svg::number(-centre_y)
+        );
+        image.element("path", &[("d", path), ("fill", colour(rng)), ("transform", transform)], None);
+    }
+    for _ in 0..8 {
+        let from = (rng.below(width as u64) as f64, rng.below(height as u64) as f64);
+        let to = (rng.below(width as u64) as f64, rng.below(height as u64) as f64);
+        image.element(
+            "line",
+            &[
+                ("x1", svg::number(from.0)),
+                ("y1", svg::number(from.1)),
+                ("x2", svg::number(to.0)),
+                ("y2", svg::number(to.1)),
+                ("stroke", colour(rng)),
+                ("stroke-width", (1 + rng.below(2)).to_string()),
+            ],
+            None,
+        );
+    }
+    for _ in 0..60 {
+        image.rect(rng.below(width as u64) as f64, rng.below(height as u64) as f64, 2.0, 2.0, &colour(rng));
+    }
+    image.finish()
+}
+
+pub fn new(kind: Kind, secret: &str, now_seconds: u64) -> Challenge {
+    let mut seed = [0u8; 8];
+    host::random_bytes(&mut seed);
+    let mut rng = Rng::new(u64::from_le_bytes(seed));
+    let (question, answer) = match kind {
+        Kind::Math => math_question(&mut rng),
+        Kind::Text => {
+            let text: String = (0..TEXT_LENGTH).map(|_| char::from(*rng.choose(TEXT_ALPHABET))).collect();
+            (text_image(&mut rng, &text), text)
+        }
+    };
+    let mut nonce = [0u8; 16];
+    host::random_bytes(&mut nonce);
+    let signed = format!("{}.{}.{}", kind.name(), now_seconds, hashing::to_base64url(&nonce));
+    let sealed = format!("{}.{}", signed, hashing::to_base64url(&mac(secret, &signed, &answer)));
+    let token = format!("{}.{}", sealed, hashing::to_base64url(&seal(secret, &sealed)));
+    Challenge { question, token }
+}
+
+fn used_key(nonce: &str) -> String {
+    format!("captcha:{}", nonce)
+}
+
+// The kind of challenge and the outcome; Err when the token is not shaped
+// like one this module issues, or not sealed by it
+pub fn verify(secret: &str, token: &str, answer: &str, now_seconds: u64) -> Result<Result<(Kind, Outcome), String>, Pending> {
+    let malformed = || Ok(Err("Captcha token must have five dot-separated parts".to_string()));
+    let Some((sealed, seal_text)) = token.rsplit_once('.') else {
+        return malformed();
+    };
+    let Some((signed, mac_text)) = sealed.rsplit_once('.') else {
+        return malformed();
+    };
+    let parts: Vec<&str> = signed.split('.').collect();
+    let [kind, issued, nonce] = parts[..] else {
+        return malformed();
+    };
+    let Some(kind) = Kind::parse(kind) else {
+        return Ok(Err(format!("Unknown captcha kind '{}'", kind)));
+    };
+    let Ok(issued) = issued.parse::<u64>() else {
+        return Ok(Err("Captcha token has an invalid timestamp".to_string()));
+    };
+    let (given_seal, given_mac) = match (hashing::from_base64url(seal_text), hashing::from_base64url(mac_text)) {
+        (Ok(given_seal), Ok(given_mac)) => (given_seal, given_mac),
+        (Err(reason), _) | (_, Err(reason)) => return Ok(Err(reason)),
+    };
+    if !hashing::constant_time_eq(&given_seal, &seal(secret, sealed)) {
+        return Ok(Err("Captcha token was not issued by this Worker".to_string()));
+    }
+
+    let age = now_seconds.saturating_sub(issued);
+    if age > MAX_AGE_SECONDS || issued > now_seconds + 60 {
+        return Ok(Ok((kind, Outcome::Expired)));
+    }
+    if kv::get(&used_key(nonce))?.is_some() {
+        return Ok(Ok((kind, Outcome::AlreadyUsed)));
+    }
+    // Right or wrong, this was the token's one attempt
+    kv::put(&used_key(nonce), "1", (MAX_AGE_SECONDS - age).max(60) as u32);
+    if !hashing::constant_time_eq(&given_mac, &mac(secret, signed, answer)) {
+        return Ok(Ok((kind, Outcome::WrongAnswer)));
+    }
+    Ok(Ok((kind, Outcome::Valid)))
+}
@@ -0,0 +1,324 @@
+// Text charts and tables for terminals, e.g. `curl .../chart?data=1,5,3`
+// Bars and sparklines use Unicode block elements, or plain ASCII when asked;
+// widths are counted in characters, so wide (CJK) text may misalign.
+// The same data also renders as an SVG line, bar or pie chart.
+
+use crate::json;
+use crate::svg::{self, Svg};
+
+pub const MAX_VALUES: usize = 500;
+pub const MAX_WIDTH: usize = 200;
+pub const MAX_TABLE_ROWS: usize = 1000;
+
+// Eighths of a block, so bar ends land between whole characters
+const BAR_EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_SPARK_LEVELS: [char; 8] = ['_', '.', ',', '-', '~', '*', '^', '\''];
+
+// "1,5,3.5" with optional spaces; every value must be a finite number
+pub fn parse_values(text: &str) -> Result<Vec<f64>, String> {
+    let values = text
+        .split(',')
+        .map(|part| {
+            let part = part.trim();
+            part.parse::<f64>().ok().filter(|value| value.is_finite()).ok_or_else(|| format!("'{}' is not a number", part))
+        })
+        .collect::<Result<Vec<f64>, String>>()?;
+    if values.len() > MAX_VALUES {
+        return Err(format!("At most {} values are supported", MAX_VALUES));
+    }
+    Ok(values)
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 { format!("{}", value as i64) } else { format!("{}", value) }
+}
+
+fn pad_right(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.chars().count())))
+}
+
+fn pad_left(text: &str, width: usize) -> String {
+    format!("{}{}", " ".repeat(width.saturating_sub(text.chars().count())), text)
+}
+
+// One row per value: label, a bar scaled so the largest value fills `width`,
+// then the value. Bars grow from zero, so values must not be negative.
+pub fn bar(values: &[f64], labels: &[&str], width: usize, ascii: bool) -> Result<String, String> {
+    if let Some(value) = values.iter().find(|value| **value < 0.0) {
+        return Err(format!("Bar charts need values of at least 0, not {}", format_value(*value)));
+    }
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    let label_width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+    let mut out = String::new();
+    for (index, value) in values.iter().enumerate() {
+        let eighths = if max > 0.0 { (value / max * (width * 8) as f64).round() as usize } else { 0 };
+        let mut bar = if ascii { "#".repeat(eighths / 8) } else { "█".repeat(eighths / 8) };
+        if !ascii && eighths % 8 != 0 {
+            bar.push(BAR_EIGHTHS[eighths % 8]);
+        }
+        let label = labels.get(index).copied().unwrap_or("");
+        if label_width > 0 {
+            out.push_str(&pad_right(label, label_width));
+            out.push_str(" | ");
+        }
+        out.push_str(&pad_right(&bar, width));
+        out.push(' ');
+        out.push_str(&format_value(*value));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// One character per value, from lowest to highest of the series
+pub fn sparkline(values: &[f64], ascii: bool) -> String {
+    let levels = if ascii { &ASCII_SPARK_LEVELS } else { &SPARK_LEVELS };
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let mut out: String = values
+        .iter()
+        .map(|value| {
+            // A flat series sits in the middle rather than on the floor
+            let level = if max > min { ((value - min) / (max - min) * 7.0).round() as usize } else { 3 };
+            levels[level.min(7)]
+        })
+        .collect();
+    out.push('\n');
+    out
+}
+
+// Cell text: strings as they are, numbers right-aligned, null empty and
+// anything else as compact JSON. Line breaks and tabs would break the grid.
+fn cell(value: &json::Value) -> (String, bool) {
+    let text = match value {
+        json::Value::Null => String::new(),
+        json::Value::String(text) => text.clone(),
+        json::Value::Number(number) => return (format_value(*number), true),
+        other => other.to_json(),
+    };
+    (text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect(), false)
+}
+
+// An array of objects becomes a table with a header of their keys, in the
+// order first seen; an array of arrays becomes rows without a header
+pub fn table(rows: &[json::Value]) -> Result<String, String> {
+    if rows.len() > MAX_TABLE_ROWS {
+        return Err(format!("At most {} rows are supported", MAX_TABLE_ROWS));
+    }
+    let mut header: Vec<String> = Vec::new();
+    let mut cells: Vec<Vec<(String, bool)>> = Vec::with_capacity(rows.len());
+    if rows.iter().all(|row| matches!(row, json::Value::Object(_))) {
+        for row in rows {
+            if let json::Value::Object(members) = row {
+                for (key, _) in members {
+                    if !header.contains(key) {
+                        header.push(key.clone());
+                    }
+                }
+            }
+        }
+        for row in rows {
+            if let json::Value::Object(members) = row {
+                let lookup = |key: &String| members.iter().rev().find(|(name, _)| name == key).map(|(_, value)| value);
+                cells.push(header.iter().map(|key| lookup(key).map_or((String::new(), false), cell)).collect());
+            }
+        }
+        header = header.iter().map(|key| cell(&json::Value::String(key.clone())).0).collect();
+    } else if rows.iter().all(|row| matches!(row, json::Value::Array(_))) {
+        for row in rows {
+            if let json::Value::Array(items) = row {
+                cells.push(items.iter().map(cell).collect());
+            }
+        }
+    } else {
+        return Err("Rows must be all objects or all arrays".to_string());
+    }
+
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0).max(header.len());
+    if columns == 0 {
+        return Err("The table has no columns".to_string());
+    }
+    let mut widths = vec![0; columns];
+    for (column, name) in header.iter().enumerate() {
+        widths[column] = name.chars().count();
+    }
+    for row in &cells {
+        for (column, (text, _)) in row.iter().enumerate() {
+            widths[column] = widths[column].max(text.chars().count());
+        }
+    }
+
+    let rule: String = widths.iter().map(|width| format!("+{}", "-".repeat(width + 2))).collect::<String>() + "+\n";
+    let line = |row: &[(String, bool)]| -> String {
+        let mut out = String::new();
+        for (column, width) in widths.iter().enumerate() {
+            let (text, numeric) = row.get(column).map_or(("", false), |(text, numeric)| (text.as_str(), *numeric));
+            let padded = if numeric { pad_left(text, *width) } else { pad_right(text, *width) };
+            out.push_str(&format!("| {} ", padded));
+        }
+        out + "|\n"
+    };
+    let mut out = rule.clone();
+    if !header.is_empty() {
+        let names: Vec<(String, bool)> = header.into_iter().map(|name| (name, false)).collect();
+        out.push_str(&line(&names));
+        out.push_str(&rule);
+    }
+    for row in &cells {
+        out.push_str(&line(row));
+    }
+    if !cells.is_empty() {
+        out.push_str(&rule);
+    }
+    Ok(out)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Line,
+    Bar,
+    Pie,
+}
+
+impl Kind {
+    pub fn parse(name: &str) -> Option<Kind> {
+        match name {
+            "line" => Some(Kind::Line),
+            "bar" => Some(Kind::Bar),
+            "pie" => Some(Kind::Pie),
+            _ => None,
+        }
+    }
+}
+
+const SVG_WIDTH: f64 = 640.0;
+const SVG_HEIGHT: f64 = 400.0;
+// Room for the title above the plot, value ticks to its left and labels below
+const MARGIN_TOP: f64 = 48.0;
+const MARGIN_LEFT: f64 = 64.0;
+const MARGIN_RIGHT: f64 = 24.0;
+const MARGIN_BOTTOM: f64 = 48.0;
+const TICKS: usize = 5;
+
+const PALETTE: [&str; 8] = ["#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#9c755f"];
+
+// An SVG chart; pie charts need values of at least 0 with a positive total
+pub fn svg(kind: Kind, values: &[f64], labels: &[&str], title: Option<&str>) -> Result<String, String> {
+    if values.is_empty() {
+        return Err("At least one value is needed".to_string());
+    }
+    let mut chart = Svg::new(SVG_WIDTH as u32, SVG_HEIGHT as u32);
+    if let Some(title) = title {
+        chart.title(title);
+        chart.text(SVG_WIDTH / 2.0, MARGIN_TOP / 2.0 + 6.0, "middle", 18, title);
+    }
+    match kind {
+        Kind::Pie => pie(&mut chart, values, labels)?,
+        _ => plot(&mut chart, kind, values, labels),
+    }
+    Ok(chart.finish())
+}
+
+// Line and bar charts share the value axis, which always includes zero
+fn plot(chart: &mut Svg, kind: Kind, values: &[f64], labels: &[&str]) {
+    let low = values.iter().cloned().fold(0.0, f64::min);
+    let mut high = values.iter().cloned().fold(0.0, f64::max);
+    if high == low {
+        high = low + 1.0;
+    }
+    let (left, right) = (MARGIN_LEFT, SVG_WIDTH - MARGIN_RIGHT);
+    let (top, bottom) = (MARGIN_TOP, SVG_HEIGHT - MARGIN_BOTTOM);
+    let y = |value: f64| bottom - (value - low) / (high - low) * (bottom - top);
+
+    for tick in 0..=TICKS {
+        let value = low + (high - low) * tick as f64 / TICKS as f64;
+        chart.line((left, y(value)), (right, y(value)), "#e0e0e0");
+        chart.text(left - 8.0, y(value) + 4.0, "end", 12, &format_value((value * 100.0).round() / 100.0));
+    }
+    chart.line((left, y(0.0)), (right, y(0.0)), "#666");
+
+    // Each value gets an equal slot; bars fill most of it and line points
+    // sit at its centre
+    let slot = (right - left) / values.len() as f64;
+    let centre = |index: usize| left + slot * (index as f64 + 0.5);
+    match kind {
+        Kind::Bar => {
+            for (index, value) in values.iter().enumerate() {
+                let (from, to) = (y(value.max(0.0)), y(value.min(0.0)));
+                chart.rect(centre(index) - slot * 0.4, from, slot * 0.8, to - from, PALETTE[0]);
+            }
+        }
+        _ => {
+            let points: Vec<String> = values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| format!("{},{}", svg::number(centre(index)), svg::number(y(*value))))
+                .collect();
+            chart.element(
+                "polyline",
+                &[("points", points.join(" ")), ("fill", "none".to_string()), ("stroke", PALETTE[0].to_string()), ("stroke-width", "2".to_string())],
+                None,
+            );
+            for (index, value) in values.iter().enumerate() {
+                chart.element(
+                    "circle",
+                    &[("cx", svg::number(centre(index))), ("cy", svg::number(y(*value))), ("r", "3".to_string()), ("fill", PALETTE[0].to_string())],
+                    None,
+                );
+            }
+        }
+    }
+    for (index, label) in labels.iter().enumerate().take(values.len()) {
+        chart.text(centre(index), bottom + 20.0, "middle", 12, label);
+    }
+}
+
+fn pie(chart: &mut Svg, values: &[f64], labels: &[&str]) -> Result<(), String> {
+    if let Some(value) = values.iter().find(|value| **value < 0.0) {
+        return Err(format!("Pie charts need values of at least 0, not {}", format_value(*value)));
+    }
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return Err("Pie charts need a total above 0".to_string());
+    }
+    let radius = (SVG_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM) / 2.0;
+    let (cx, cy) = (MARGIN_LEFT + radius, MARGIN_TOP + radius);
+    // Angles run clockwise from twelve o'clock
+    let point = |angle: f64| (cx + radius * angle.sin(), cy - radius * angle.cos());
+    let mut angle = 0.0;
+    for (index, value) in values.iter().enumerate() {
+        let colour = PALETTE[index % PALETTE.len()];
+        let sweep = value / total * core::f64::consts::TAU;
+        if sweep >= core::f64::consts::TAU - 1e-9 {
+            // An arc cannot start and end at the same point
+            chart.element("circle", &[("cx", svg::number(cx)), ("cy", svg::number(cy)), ("r", svg::number(radius)), ("fill", colour.to_string())], None);
+        } else if sweep > 0.0 {
+            let (start, end) = (point(angle), point(angle + sweep));
+            let path = format!(
+                "M{} {}L{} {}A{r} {r} 0 {} 1 {} {}Z",
+                svg::number(cx),
+                svg::number(cy),
+                svg::number(start.0),
+                svg::number(start.1),
+                u8::from(sweep > core::f64::consts::PI),
+                svg::number(end.0),
+                svg::number(end.1),
+                r = svg::number(radius)
+            );
+            chart.element("path", &[("d", path), ("fill", colour.to_string()), ("stroke", "white".to_string())], None);
+        }
+        angle += sweep;
+
+        // Legend to the right, one line per value
+        let legend_y = MARGIN_TOP + 20.0 * index as f64;
+        if legend_y < SVG_HEIGHT - 12.0 {
+            let legend_x = cx + radius + 40.0;
+            chart.rect(legend_x, legend_y, 12.0, 12.0, colour);
+            let label = labels.get(index).copied().unwrap_or("");
+            let share = format!("{} ({:.1}%)", format_value(*value), value / total * 100.0);
+            let text = if label.is_empty() { share } else { format!("{}: {}", label, share) };
+            chart.text(legend_x + 18.0, legend_y + 11.0, "start", 12, &text);
+        }
+    }
+    Ok(())
+}
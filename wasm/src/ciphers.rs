@@ -0,0 +1,76 @@
+// Classic substitution ciphers over the ASCII alphabet, plus an XOR stream
+// cipher. Only A-Z and a-z are shifted (keeping their case); every other
+// character, including non-ASCII letters, is copied through unchanged.
+
+use crate::rand::Rng;
+
+pub const ALGORITHMS: [&str; 3] = ["rot13", "caesar", "vigenere"];
+
+fn shift_letter(c: char, shift: u8) -> char {
+    let base = match c {
+        'a'..='z' => b'a',
+        'A'..='Z' => b'A',
+        _ => return c,
+    };
+    char::from(base + (c as u8 - base + shift) % 26)
+}
+
+// Decrypting is shifting by the negated amount
+pub fn caesar(text: &str, shift: i64) -> String {
+    let shift = shift.rem_euclid(26) as u8;
+    text.chars().map(|c| shift_letter(c, shift)).collect()
+}
+
+pub fn rot13(text: &str) -> String {
+    caesar(text, 13)
+}
+
+// The key advances only on letters, so spacing and punctuation in the text
+// do not change how the remaining letters are enciphered
+pub fn vigenere(text: &str, key: &str, decrypt: bool) -> Result<String, String> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err("Key must be a non-empty string of ASCII letters".to_string());
+    }
+    let shifts: Vec<u8> = key
+        .bytes()
+        .map(|b| {
+            let shift = b.to_ascii_lowercase() - b'a';
+            if decrypt { (26 - shift) % 26 } else { shift }
+        })
+        .collect();
+    let mut position = 0;
+    Ok(text
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphabetic() {
+                let shifted = shift_letter(c, shifts[position % shifts.len()]);
+                position += 1;
+                shifted
+            } else {
+                c
+            }
+        })
+        .collect())
+}
+
+// XOR with a keystream drawn from the seeded PRNG; applying it twice with the
+// same seed restores the input. NOT secure: xoshiro256** is predictable and a
+// u64 seed is easy to brute force, so this is only fit for obfuscation demos.
+pub fn xor_keystream(bytes: &mut [u8], seed: u64) {
+    let mut rng = Rng::new(seed);
+    for chunk in bytes.chunks_mut(8) {
+        for (byte, key) in chunk.iter_mut().zip(rng.next_u64().to_le_bytes()) {
+            *byte ^= key;
+        }
+    }
+}
+
+// In-place variant for buffers from alloc; returns 0 for a null pointer
+#[no_mangle]
+pub extern "C" fn xor_keystream_in_place(ptr: *mut u8, len: usize, seed: u64) -> i32 {
+    if ptr.is_null() {
+        return 0;
+    }
+    xor_keystream(unsafe { std::slice::from_raw_parts_mut(ptr, len) }, seed);
+    1
+}
@@ -0,0 +1,67 @@
+// Exact counting functions and the N-queens puzzle
+// Results are computed with BigUint, so large inputs stay exact instead of
+// overflowing or losing precision.
+
+use alloc::vec::Vec;
+use crate::bignum::BigUint;
+use crate::budget;
+
+pub const MAX_N: u32 = 1000;
+pub const MAX_QUEENS: u32 = 14;
+
+// n! / (k! (n - k)!), built up so every intermediate division is exact
+pub fn n_choose_k(n: u32, k: u32) -> BigUint {
+    if k > n {
+        return BigUint::zero();
+    }
+    let k = k.min(n - k);
+    (0..k).fold(BigUint::from_u64(1), |acc, i| acc.mul_small(n - i).div_small(i + 1).0)
+}
+
+// n! / (n - k)!
+pub fn n_permute_k(n: u32, k: u32) -> BigUint {
+    if k > n {
+        return BigUint::zero();
+    }
+    (0..k).fold(BigUint::from_u64(1), |acc, i| acc.mul_small(n - i))
+}
+
+// C(2n, n) / (n + 1)
+pub fn catalan(n: u32) -> BigUint {
+    n_choose_k(2 * n, n).div_small(n + 1).0
+}
+
+pub struct Queens {
+    pub solutions: u64,
+    // Column of the queen in each row for the first solution found
+    pub first: Option<Vec<usize>>,
+}
+
+// Count placements of n non-attacking queens with bitmask backtracking,
+// charging the computation budget one step per partial board
+pub fn n_queens(n: u32) -> Queens {
+    fn place(n: u32, row: u32, columns: u32, left: u32, right: u32, board: &mut Vec<usize>, result: &mut Queens) {
+        budget::charge(1);
+        if budget::exhausted() {
+            return;
+        }
+        if row == n {
+            result.solutions += 1;
+            result.first.get_or_insert_with(|| board.clone());
+            return;
+        }
+        let full = (1u32 << n) - 1;
+        let mut free = full & !(columns | left | right);
+        while free != 0 {
+            let bit = free & free.wrapping_neg();
+            free &= free - 1;
+            board.push(bit.trailing_zeros() as usize);
+            place(n, row + 1, columns | bit, ((left | bit) << 1) & full, (right | bit) >> 1, board, result);
+            board.pop();
+        }
+    }
+
+    let mut result = Queens { solutions: 0, first: None };
+    place(n, 0, 0, 0, 0, &mut Vec::new(), &mut result);
+    result
+}
@@ -0,0 +1,479 @@
+// DEFLATE (RFC 1951) with the zlib (RFC 1950) and gzip (RFC 1952) wrappers
+// Decoding handles every block type. Encoding finds repeats with hash chains
+// and writes a single block with the fixed Huffman codes, which costs a few
+// percent against dynamic codes but keeps the encoder small.
+
+use crate::hashing::Crc32;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// The order code length code lengths are sent in
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+const MAX_BITS: usize = 15;
+const WINDOW: usize = 32 * 1024;
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before b could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+struct BitReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+    bit: u8,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u8) -> Result<u32, String> {
+        let mut value = 0u32;
+        for index in 0..count {
+            let Some(&byte) = self.input.get(self.pos) else {
+                return Err("Compressed data ends early".to_string());
+            };
+            value |= u32::from(byte >> self.bit & 1) << index;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+// A canonical Huffman code as symbol counts per length and the symbols in
+// code order, decoded a bit at a time as in zlib's puff.c
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, String> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        // Over-subscribed codes cannot be decoded; incomplete ones are fine
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = left * 2 - i32::from(count);
+            if left < 0 {
+                return Err("Invalid Huffman code lengths".to_string());
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = alloc::vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[length]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Invalid Huffman code".to_string())
+    }
+}
+
+fn fixed_codes() -> Result<(Huffman, Huffman), String> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+    if literals > 286 || distances > 30 {
+        return Err("Too many length or distance codes".to_string());
+    }
+    let mut lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[index] = reader.bits(3)? as u8;
+    }
+    let lengths_code = Huffman::new(&lengths)?;
+
+    let mut lengths = alloc::vec![0u8; literals + distances];
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = lengths_code.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 if index == 0 => return Err("Length repeat with no previous length".to_string()),
+            16 => (lengths[index - 1], 3 + reader.bits(2)? as usize),
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if index + repeat > lengths.len() {
+            return Err("Code lengths overflow the table".to_string());
+        }
+        lengths[index..index + repeat].fill(value);
+        index += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err("Missing end-of-block code".to_string());
+    }
+    Ok((Huffman::new(&lengths[..literals])?, Huffman::new(&lengths[literals..])?))
+}
+
+// Raw DEFLATE data, returning the output and how many input bytes it used;
+// output beyond `max_output` bytes is an error, which stops zip bombs
+pub fn inflate(input: &[u8], max_output: usize) -> Result<(Vec<u8>, usize), String> {
+    let mut reader = BitReader { input, pos: 0, bit: 0 };
+    let mut out: Vec<u8> = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = input.get(reader.pos..reader.pos + 4).ok_or("Compressed data ends early")?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err("Stored block length check failed".to_string());
+                }
+                reader.pos += 4;
+                let block = input.get(reader.pos..reader.pos + length as usize).ok_or("Compressed data ends early")?;
+                if out.len() + block.len() > max_output {
+                    return Err(format!("Output is larger than {} bytes", max_output));
+                }
+                out.extend_from_slice(block);
+                reader.pos += length as usize;
+            }
+            kind @ (1 | 2) => {
+                let (literals, distances) = if kind == 1 { fixed_codes()? } else { dynamic_codes(&mut reader)? };
+                loop {
+                    let symbol = literals.decode(&mut reader)? as usize;
+                    if symbol < 256 {
+                        if out.len() == max_output {
+                            return Err(format!("Output is larger than {} bytes", max_output));
+                        }
+                        out.push(symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        break;
+                    }
+                    let symbol = symbol - 257;
+                    if symbol >= LENGTH_BASE.len() {
+                        return Err("Invalid length code".to_string());
+                    }
+                    let length = LENGTH_BASE[symbol] as usize + reader.bits(LENGTH_EXTRA[symbol])? as usize;
+                    let code = distances.decode(&mut reader)? as usize;
+                    if code >= DISTANCE_BASE.len() {
+                        return Err("Invalid distance code".to_string());
+                    }
+                    let distance = DISTANCE_BASE[code] as usize + reader.bits(DISTANCE_EXTRA[code])? as usize;
+                    if distance > out.len() {
+                        return Err("Distance reaches before the start of the output".to_string());
+                    }
+                    if out.len() + length > max_output {
+                        return Err(format!("Output is larger than {} bytes", max_output));
+                    }
+                    // Byte by byte, since a match may overlap its own output
+                    let start = out.len() - distance;
+                    for index in 0..length {
+                        out.push(out[start + index]);
+                    }
+                }
+            }
+            _ => return Err("Invalid block type".to_string()),
+        }
+        if last {
+            reader.align();
+            return Ok((out, reader.pos));
+        }
+    }
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, count: u8) {
+        self.buffer |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes go most significant bit first
+    fn code(&mut self, code: u32, length: u8) {
+        let reversed = code.reverse_bits() >> (32 - length);
+        self.bits(reversed, length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+fn fixed_literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.code(0x30 + symbol, 8),
+        144..=255 => writer.code(0x190 + symbol - 144, 9),
+        256..=279 => writer.code(symbol - 256, 7),
+        _ => writer.code(0xC0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap_or(0);
+    fixed_literal(writer, 257 + code as u32);
+    writer.bits((length - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code]);
+    let code = DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap_or(0);
+    writer.code(code as u32, 5);
+    writer.bits((distance - DISTANCE_BASE[code] as usize) as u32, DISTANCE_EXTRA[code]);
+}
+
+const HASH_BITS: u32 = 15;
+// How many earlier positions with the same hash are tried per byte
+const MAX_CHAIN: usize = 64;
+
+fn hash(bytes: &[u8]) -> usize {
+    ((u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2])).wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+// Raw DEFLATE data in one fixed-code block
+pub fn deflate(input: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter { out: Vec::with_capacity(input.len() / 2 + 16), buffer: 0, count: 0 };
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+    // Most recent position per hash, and for each position the previous one
+    // with the same hash
+    let mut head = alloc::vec![usize::MAX; 1 << HASH_BITS];
+    let mut previous = alloc::vec![usize::MAX; input.len()];
+    let mut pos = 0;
+    while pos < input.len() {
+        let (mut best_length, mut best_distance) = (0, 0);
+        if pos + 3 <= input.len() {
+            let mut candidate = head[hash(&input[pos..])];
+            let limit = (input.len() - pos).min(258);
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || pos - candidate > WINDOW {
+                    break;
+                }
+                let length = input[candidate..].iter().zip(&input[pos..pos + limit]).take_while(|(a, b)| a == b).count();
+                if length > best_length {
+                    (best_length, best_distance) = (length, pos - candidate);
+                    if length == limit {
+                        break;
+                    }
+                }
+                candidate = previous[candidate];
+            }
+        }
+        if best_length >= 3 {
+            write_match(&mut writer, best_length, best_distance);
+            for index in pos..pos + best_length {
+                insert(input, index, &mut head, &mut previous);
+            }
+            pos += best_length;
+        } else {
+            fixed_literal(&mut writer, u32::from(input[pos]));
+            insert(input, pos, &mut head, &mut previous);
+            pos += 1;
+        }
+    }
+    fixed_literal(&mut writer, 256);
+    writer.finish()
+}
+
+fn insert(input: &[u8], pos: usize, head: &mut [usize], previous: &mut [usize]) {
+    if pos + 3 <= input.len() {
+        let key = hash(&input[pos..]);
+        previous[pos] = head[key];
+        head[key] = pos;
+    }
+}
+
+pub fn zlib_encode(input: &[u8]) -> Vec<u8> {
+    // 32 KiB window, default level, header check bits (RFC 1950, 2.2)
+    let mut out = alloc::vec![0x78, 0x9C];
+    out.extend(deflate(input));
+    out.extend_from_slice(&adler32(input).to_be_bytes());
+    out
+}
+
+pub fn zlib_decode(input: &[u8], max_output: usize) -> Result<Vec<u8>, String> {
+    let [cmf, flg, ..] = *input else {
+        return Err("zlib stream is too short".to_string());
+    };
+    if cmf & 0x0F != 8 || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return Err("Invalid zlib header".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+    let (out, used) = inflate(&input[2..], max_output)?;
+    let checksum = input.get(2 + used..6 + used).ok_or("zlib stream is missing its checksum")?;
+    if checksum != adler32(&out).to_be_bytes() {
+        return Err("zlib checksum mismatch".to_string());
+    }
+    Ok(out)
+}
+
+pub fn gzip_encode(input: &[u8]) -> Vec<u8> {
+    // No name or timestamp, so output depends only on the input; OS 255 is
+    // "unknown"
+    let mut out = alloc::vec![0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 255];
+    out.extend(deflate(input));
+    let mut crc = Crc32::new();
+    crc.update(input);
+    out.extend_from_slice(&crc.finalize().to_le_bytes());
+    out.extend_from_slice(&(input.len() as u32).to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = b"The quick brown fox jumps over the lazy dog. The quick brown fox jumps over the lazy dog. \
+The quick brown fox jumps over the lazy dog. Pack my box with five dozen liquor jugs.";
+
+    // TEXT through zlib at level 9, a single dynamic-code block
+    const DYNAMIC: [u8; 79] = [
+        0xb5, 0xcb, 0xc7, 0x01, 0x80, 0x20, 0x10, 0x05, 0xd1, 0x56, 0x7e, 0x05, 0xd4, 0xe2, 0xc1, 0x06, 0x40, 0x49, 0x06, 0x56,
+        0xb2, 0x50, 0xbd, 0xdb, 0x84, 0xe7, 0x79, 0xb3, 0x3a, 0x8d, 0x58, 0xfd, 0x76, 0x42, 0x25, 0xea, 0x01, 0x86, 0x5e, 0x1c,
+        0xf5, 0x7e, 0x32, 0xa8, 0xe9, 0x84, 0xc2, 0xf9, 0x92, 0x73, 0x60, 0x27, 0x2b, 0xb0, 0xfe, 0x86, 0x17, 0xc9, 0xee, 0x1e,
+        0x50, 0x8c, 0xba, 0x2f, 0x0e, 0xc6, 0x37, 0xcd, 0x69, 0xea, 0x80, 0xcb, 0xc7, 0x4a, 0x89, 0x5f, 0x9b, 0xc5, 0x07,
+    ];
+
+    // One fixed-code block from the symbols and (length, distance) matches
+    fn fixed_block(items: &[(u32, usize)]) -> Vec<u8> {
+        let mut writer = BitWriter { out: Vec::new(), buffer: 0, count: 0 };
+        writer.bits(1, 1);
+        writer.bits(1, 2);
+        for &(symbol, distance) in items {
+            if distance == 0 {
+                fixed_literal(&mut writer, symbol);
+            } else {
+                write_match(&mut writer, symbol as usize, distance);
+            }
+        }
+        fixed_literal(&mut writer, 256);
+        writer.finish()
+    }
+
+    #[test]
+    fn inflates_dynamic_blocks() {
+        let (out, used) = inflate(&DYNAMIC, 1024).unwrap();
+        assert_eq!(out, TEXT);
+        assert_eq!(used, DYNAMIC.len());
+    }
+
+    #[test]
+    fn inflates_stored_blocks_and_reports_the_bytes_used() {
+        // A stored block that is not the last, then an empty last one, then
+        // bytes that belong to whatever follows
+        let input = [0x00, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c', 0x01, 0x00, 0x00, 0xff, 0xff, 0xaa, 0xbb];
+        assert_eq!(inflate(&input, 16).unwrap(), (b"abc".to_vec(), 13));
+    }
+
+    #[test]
+    fn round_trips_through_deflate() {
+        let repeated = TEXT.repeat(100);
+        let noisy: Vec<u8> = (0u32..5000).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        for input in [&b""[..], b"a", TEXT, &repeated, &noisy] {
+            let compressed = deflate(input);
+            assert_eq!(inflate(&compressed, input.len()).unwrap(), (input.to_vec(), compressed.len()));
+        }
+        assert!(deflate(&repeated).len() < repeated.len() / 10);
+    }
+
+    #[test]
+    fn overlapping_matches_repeat_their_own_output() {
+        let block = fixed_block(&[(u32::from(b'x'), 0), (u32::from(b'y'), 0), (7, 2)]);
+        assert_eq!(inflate(&block, 9).unwrap().0, b"xyxyxyxyx");
+    }
+
+    #[test]
+    fn output_is_capped() {
+        let block = fixed_block(&[(u32::from(b'z'), 0), (258, 1), (258, 1)]);
+        assert_eq!(inflate(&block, 517).unwrap().0.len(), 517);
+        assert!(inflate(&block, 516).unwrap_err().contains("larger than 516"));
+        assert!(inflate(&[0x01, 0x03, 0x00, 0xfc, 0xff, 1, 2, 3], 2).is_err());
+        assert!(inflate(&DYNAMIC, TEXT.len() - 1).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        assert!(inflate(&fixed_block(&[(3, 1)]), 16).unwrap_err().contains("before the start"));
+        assert!(inflate(&[0x07], 16).unwrap_err().contains("block type"));
+        assert!(inflate(&[0x01, 0x03, 0x00, 0x00, 0x00], 16).unwrap_err().contains("length check"));
+        assert!(inflate(&[0x01, 0x05, 0x00, 0xfa, 0xff, 1], 16).is_err());
+        assert!(inflate(&DYNAMIC[..40], 1024).is_err());
+        assert!(inflate(&[], 16).is_err());
+    }
+
+    #[test]
+    fn zlib_checks_header_and_checksum() {
+        let encoded = zlib_encode(TEXT);
+        assert_eq!(zlib_decode(&encoded, 1024).unwrap(), TEXT);
+        let mut corrupt = encoded.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(zlib_decode(&corrupt, 1024).unwrap_err().contains("checksum"));
+        assert!(zlib_decode(&encoded[..encoded.len() - 2], 1024).is_err());
+        assert!(zlib_decode(&[0x78, 0x9d], 1024).unwrap_err().contains("header"));
+    }
+
+    #[test]
+    fn adler32_matches_known_values() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
@@ -0,0 +1,476 @@
+// Runtime configuration supplied by the host through init_config
+// Every field has a default, so the module works without any configuration.
+
+use crate::arena;
+use crate::assets;
+use crate::experiments;
+use crate::flags;
+use crate::hashring;
+use crate::ffi;
+use crate::json::{self, Value};
+use crate::pow;
+use crate::routing;
+use crate::security;
+use crate::url;
+use crate::webhook;
+use std::cell::RefCell;
+
+// How the router treats a request path that differs from its route only by a
+// trailing slash
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrailingSlash {
+    // /status/ is routed as /status
+    Ignore,
+    // GET and HEAD get a 301 to the canonical path
+    Redirect,
+    // /status/ is not found
+    Strict,
+}
+
+#[derive(Clone)]
+pub struct Config {
+    // Requests beyond these are refused before dispatch
+    pub max_body_bytes: usize,
+    pub max_query_length: usize,
+    // Steps of big-integer and search work a request may spend (see budget.rs)
+    pub compute_budget: u64,
+    // Upper bound for /pi and /e, which grow quadratically in cost
+    pub max_constant_digits: usize,
+    // Distinguishes isolates generating snowflake IDs (0..=1023)
+    pub snowflake_node_id: u16,
+    // Unix milliseconds that snowflake timestamps count from
+    pub snowflake_epoch_ms: u64,
+    // Largest /paste body in bytes
+    pub max_paste_bytes: usize,
+    // Longest lifetime a paste can ask for
+    pub max_paste_ttl_seconds: u32,
+    // Largest /blob upload in bytes
+    pub max_blob_bytes: usize,
+    // How long a POST response is replayed for its Idempotency-Key
+    pub idempotency_ttl_seconds: u32,
+    // Signing secrets for /webhook/verify, keyed by provider
+    pub webhook_secrets: Vec<(String, String)>,
+    // Signs /oauth/state tokens; they cannot be issued without it
+    pub oauth_state_secret: Option<String>,
+    // Signs /captcha tokens, which likewise need it
+    pub captcha_secret: Option<String>,
+    // Signs /pow challenges, and the fewest zero bits they may ask for
+    pub pow_secret: Option<String>,
+    pub pow_difficulty: u32,
+    // Added to HTML responses, as lower case name and value
+    pub security_headers: Vec<(String, String)>,
+    // Responses kept by the response cache (0 disables it) and for how long
+    pub response_cache_entries: usize,
+    pub response_cache_ttl_seconds: u32,
+    // Requests taking at least this long are logged and listed in /metrics
+    pub slow_request_ms: u32,
+    // How long a handler may run before the request is logged as overdue
+    pub request_deadline_ms: u32,
+    pub trailing_slash: TrailingSlash,
+    // Lets /Status reach /status
+    pub case_insensitive_routes: bool,
+    // Honours X-HTTP-Method-Override and _method on POST requests
+    pub allow_method_override: bool,
+    // Served as /robots.txt and /favicon.ico
+    pub robots_txt: String,
+    pub favicon_svg: String,
+    // Contact URIs for /.well-known/security.txt, which is not found without
+    pub security_contacts: Vec<String>,
+    // Origins /fetch may request, as scheme://host[:port]
+    pub fetch_origins: Vec<String>,
+    // Consecutive failures that open an origin's circuit breaker, and how
+    // long it then stays open
+    pub breaker_failure_threshold: u32,
+    pub breaker_cooldown_ms: u32,
+    // Attempts the host makes at a failing KV or fetch call, and the bounds
+    // of the backoff between them (see retry.rs)
+    pub retry_max_attempts: u32,
+    pub retry_base_delay_ms: u32,
+    pub retry_max_delay_ms: u32,
+    // Variants and weights of the /ab experiments, by experiment name
+    pub experiments: Vec<(String, Vec<experiments::Variant>)>,
+    // Feature flags by name, which KV entries can override
+    pub flags: Vec<flags::Flag>,
+    // Nodes the isolate's consistent hashing ring starts with, and the
+    // points each takes on it
+    pub ring_nodes: Vec<String>,
+    pub ring_vnodes: u32,
+    // Tried in order by /route/decide, falling back to the default origin
+    pub routing_rules: Vec<routing::Rule>,
+    pub routing_default_origin: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_body_bytes: 10 * 1024 * 1024,
+            max_query_length: 4096,
+            compute_budget: 200_000_000,
+            max_constant_digits: 10_000,
+            snowflake_node_id: 0,
+            // 2024-01-01T00:00:00Z
+            snowflake_epoch_ms: 1_704_067_200_000,
+            max_paste_bytes: 64 * 1024,
+            // One week
+            max_paste_ttl_seconds: 604_800,
+            max_blob_bytes: 1024 * 1024,
+            // One day
+            idempotency_ttl_seconds: 86_400,
+            webhook_secrets: Vec::new(),
+            oauth_state_secret: None,
+            captcha_secret: None,
+            pow_secret: None,
+            // About a quarter of a million hashes, under a second in a browser
+            pow_difficulty: 18,
+            security_headers: security::DEFAULT_HEADERS
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            response_cache_entries: 256,
+            response_cache_ttl_seconds: 300,
+            slow_request_ms: 100,
+            // The default CPU limit of a Workers request
+            request_deadline_ms: 30_000,
+            trailing_slash: TrailingSlash::Ignore,
+            case_insensitive_routes: false,
+            allow_method_override: false,
+            robots_txt: assets::DEFAULT_ROBOTS_TXT.to_string(),
+            favicon_svg: assets::DEFAULT_FAVICON_SVG.to_string(),
+            security_contacts: Vec::new(),
+            fetch_origins: Vec::new(),
+            breaker_failure_threshold: 5,
+            breaker_cooldown_ms: 30_000,
+            retry_max_attempts: 3,
+            retry_base_delay_ms: 100,
+            retry_max_delay_ms: 2000,
+            experiments: Vec::new(),
+            flags: Vec::new(),
+            ring_nodes: Vec::new(),
+            ring_vnodes: hashring::DEFAULT_VNODES,
+            routing_rules: Vec::new(),
+            routing_default_origin: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn webhook_secret(&self, provider: &str) -> Option<&str> {
+        self.webhook_secrets.iter().find(|(name, _)| name == provider).map(|(_, secret)| secret.as_str())
+    }
+}
+
+thread_local! {
+    static CONFIG: RefCell<Config> = RefCell::new(Config::default());
+}
+
+// Outside the arena, which would otherwise hold the default config built on
+// first use
+pub fn current() -> Config {
+    arena::outside(|| CONFIG.with(|config| config.borrow().clone()))
+}
+
+// Apply the recognised keys of a JSON object on top of the current config
+pub fn apply(text: &str) -> Result<(), String> {
+    let Value::Object(members) = json::parse(text)? else {
+        return Err("Config must be a JSON object".to_string());
+    };
+    let mut config = current();
+    for (key, value) in members {
+        match (key.as_str(), value) {
+            ("max_body_bytes", Value::Number(number)) if number.fract() == 0.0 && number >= 0.0 => {
+                config.max_body_bytes = number as usize;
+            }
+            ("max_body_bytes", _) => return Err("max_body_bytes must be a non-negative integer".to_string()),
+            ("max_query_length", Value::Number(number)) if number.fract() == 0.0 && number >= 0.0 => {
+                config.max_query_length = number as usize;
+            }
+            ("max_query_length", _) => return Err("max_query_length must be a non-negative integer".to_string()),
+            ("compute_budget", Value::Number(number)) if number.fract() == 0.0 && number >= 1.0 => {
+                config.compute_budget = number as u64;
+            }
+            ("compute_budget", _) => return Err("compute_budget must be a positive integer".to_string()),
+            ("max_constant_digits", Value::Number(number)) if number >= 1.0 => {
+                config.max_constant_digits = number as usize;
+            }
+            ("max_constant_digits", _) => return Err("max_constant_digits must be a positive number".to_string()),
+            ("snowflake_node_id", Value::Number(number)) if number.fract() == 0.0 && (0.0..1024.0).contains(&number) => {
+                config.snowflake_node_id = number as u16;
+            }
+            ("snowflake_node_id", _) => return Err("snowflake_node_id must be an integer from 0 to 1023".to_string()),
+            ("snowflake_epoch_ms", Value::Number(number)) if number.fract() == 0.0 && number >= 0.0 => {
+                config.snowflake_epoch_ms = number as u64;
+            }
+            ("snowflake_epoch_ms", _) => return Err("snowflake_epoch_ms must be a non-negative integer".to_string()),
+            // KV values are capped at 25 MiB
+            ("max_paste_bytes", Value::Number(number)) if (1.0..=25.0 * 1024.0 * 1024.0).contains(&number) => {
+                config.max_paste_bytes = number as usize;
+            }
+            ("max_paste_bytes", _) => return Err("max_paste_bytes must be a number from 1 to 26214400".to_string()),
+            ("max_paste_ttl_seconds", Value::Number(number))
+                if number.fract() == 0.0 && (60.0..=u32::MAX as f64).contains(&number) =>
+            {
+                config.max_paste_ttl_seconds = number as u32;
+            }
+            ("max_paste_ttl_seconds", _) => return Err("max_paste_ttl_seconds must be an integer of at least 60".to_string()),
+            // Blobs are stored as base64, which must fit KV's 25 MiB values
+            ("max_blob_bytes", Value::Number(number)) if (1.0..=18.0 * 1024.0 * 1024.0).contains(&number) => {
+                config.max_blob_bytes = number as usize;
+            }
+            ("max_blob_bytes", _) => return Err("max_blob_bytes must be a number from 1 to 18874368".to_string()),
+            ("idempotency_ttl_seconds", Value::Number(number))
+                if number.fract() == 0.0 && (60.0..=u32::MAX as f64).contains(&number) =>
+            {
+                config.idempotency_ttl_seconds = number as u32;
+            }
+            ("idempotency_ttl_seconds", _) => return Err("idempotency_ttl_seconds must be an integer of at least 60".to_string()),
+            ("webhook_secrets", Value::Object(secrets)) => {
+                let mut parsed = Vec::new();
+                for (provider, secret) in secrets {
+                    if !webhook::PROVIDERS.contains(&provider.as_str()) {
+                        return Err(format!("Unknown webhook provider '{}'", provider));
+                    }
+                    match secret {
+                        Value::String(secret) if !secret.is_empty() => parsed.push((provider, secret)),
+                        _ => return Err(format!("Webhook secret for {} must be a non-empty string", provider)),
+                    }
+                }
+                config.webhook_secrets = parsed;
+            }
+            ("webhook_secrets", _) => return Err("webhook_secrets must be an object of provider secrets".to_string()),
+            ("oauth_state_secret", Value::String(secret)) if !secret.is_empty() => {
+                config.oauth_state_secret = Some(secret);
+            }
+            ("oauth_state_secret", _) => return Err("oauth_state_secret must be a non-empty string".to_string()),
+            ("captcha_secret", Value::String(secret)) if !secret.is_empty() => config.captcha_secret = Some(secret),
+            ("captcha_secret", _) => return Err("captcha_secret must be a non-empty string".to_string()),
+            ("pow_secret", Value::String(secret)) if !secret.is_empty() => config.pow_secret = Some(secret),
+            ("pow_secret", _) => return Err("pow_secret must be a non-empty string".to_string()),
+            ("pow_difficulty", Value::Number(number)) if number.fract() == 0.0 && (1.0..=f64::from(pow::MAX_DIFFICULTY)).contains(&number) => {
+                config.pow_difficulty = number as u32;
+            }
+            ("pow_difficulty", _) => return Err(format!("pow_difficulty must be an integer from 1 to {}", pow::MAX_DIFFICULTY)),
+            // Merged into the defaults; null removes a header
+            ("security_headers", Value::Object(headers)) => {
+                for (name, value) in headers {
+                    let name = name.to_ascii_lowercase();
+                    if !security::is_valid_name(&name) {
+                        return Err(format!("Invalid security header name '{}'", name));
+                    }
+                    config.security_headers.retain(|(existing, _)| *existing != name);
+                    match value {
+                        Value::Null => {}
+                        Value::String(value) if security::is_valid_value(&value) => config.security_headers.push((name, value)),
+                        _ => return Err(format!("Security header {} must be null or a single-line string without '|'", name)),
+                    }
+                }
+            }
+            ("security_headers", _) => return Err("security_headers must be an object of header values".to_string()),
+            ("response_cache_entries", Value::Number(number)) if number.fract() == 0.0 && (0.0..=4096.0).contains(&number) => {
+                config.response_cache_entries = number as usize;
+            }
+            ("response_cache_entries", _) => return Err("response_cache_entries must be an integer from 0 to 4096".to_string()),
+            ("response_cache_ttl_seconds", Value::Number(number))
+                if number.fract() == 0.0 && (1.0..=u32::MAX as f64).contains(&number) =>
+            {
+                config.response_cache_ttl_seconds = number as u32;
+            }
+            ("response_cache_ttl_seconds", _) => return Err("response_cache_ttl_seconds must be a positive integer".to_string()),
+            ("slow_request_ms", Value::Number(number)) if number.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&number) => {
+                config.slow_request_ms = number as u32;
+            }
+            ("slow_request_ms", _) => return Err("slow_request_ms must be a non-negative integer".to_string()),
+            ("request_deadline_ms", Value::Number(number)) if number.fract() == 0.0 && (1.0..=u32::MAX as f64).contains(&number) => {
+                config.request_deadline_ms = number as u32;
+            }
+            ("request_deadline_ms", _) => return Err("request_deadline_ms must be a positive integer".to_string()),
+            ("trailing_slash", Value::String(mode)) if matches!(mode.as_str(), "ignore" | "redirect" | "strict") => {
+                config.trailing_slash = match mode.as_str() {
+                    "ignore" => TrailingSlash::Ignore,
+                    "redirect" => TrailingSlash::Redirect,
+                    _ => TrailingSlash::Strict,
+                };
+            }
+            ("trailing_slash", _) => return Err("trailing_slash must be \"ignore\", \"redirect\" or \"strict\"".to_string()),
+            ("case_insensitive_routes", Value::Bool(enabled)) => config.case_insensitive_routes = enabled,
+            ("case_insensitive_routes", _) => return Err("case_insensitive_routes must be true or false".to_string()),
+            ("allow_method_override", Value::Bool(enabled)) => config.allow_method_override = enabled,
+            ("allow_method_override", _) => return Err("allow_method_override must be true or false".to_string()),
+            ("robots_txt", Value::String(text)) => config.robots_txt = text,
+            ("robots_txt", _) => return Err("robots_txt must be a string".to_string()),
+            ("favicon_svg", Value::String(svg)) if svg.trim_start().starts_with("<svg") => config.favicon_svg = svg,
+            ("favicon_svg", _) => return Err("favicon_svg must be a string holding an <svg> element".to_string()),
+            // A single contact or a list of them
+            ("security_contacts", Value::String(contact)) if assets::is_valid_contact(&contact) => {
+                config.security_contacts = vec![contact];
+            }
+            ("security_contacts", Value::Array(contacts)) => {
+                let mut parsed = Vec::new();
+                for contact in contacts {
+                    match contact {
+                        Value::String(contact) if assets::is_valid_contact(&contact) => parsed.push(contact),
+                        _ => return Err("Security contacts must be mailto:, https: or tel: URIs".to_string()),
+                    }
+                }
+                config.security_contacts = parsed;
+            }
+            ("security_contacts", _) => return Err("security_contacts must be a mailto:, https: or tel: URI or a list of them".to_string()),
+            ("fetch_origins", Value::Array(origins)) => {
+                let mut parsed = Vec::new();
+                for origin in origins {
+                    match origin {
+                        Value::String(origin) => match url::parse(&origin) {
+                            Ok(url) if matches!(url.scheme.as_str(), "http" | "https") && url.origin() == origin.trim_end_matches('/') => {
+                                parsed.push(url.origin())
+                            }
+                            _ => return Err(format!("'{}' is not an http(s) origin such as https://example.com", origin)),
+                        },
+                        _ => return Err("Fetch origins must be strings".to_string()),
+                    }
+                }
+                config.fetch_origins = parsed;
+            }
+            ("fetch_origins", _) => return Err("fetch_origins must be a list of origins".to_string()),
+            ("breaker_failure_threshold", Value::Number(number)) if number.fract() == 0.0 && (1.0..=u32::MAX as f64).contains(&number) => {
+                config.breaker_failure_threshold = number as u32;
+            }
+            ("breaker_failure_threshold", _) => return Err("breaker_failure_threshold must be a positive integer".to_string()),
+            ("breaker_cooldown_ms", Value::Number(number)) if number.fract() == 0.0 && (1.0..=u32::MAX as f64).contains(&number) => {
+                config.breaker_cooldown_ms = number as u32;
+            }
+            ("breaker_cooldown_ms", _) => return Err("breaker_cooldown_ms must be a positive integer".to_string()),
+            // Each attempt holds the request open, so a handful is plenty
+            ("retry_max_attempts", Value::Number(number)) if number.fract() == 0.0 && (1.0..=5.0).contains(&number) => {
+                config.retry_max_attempts = number as u32;
+            }
+            ("retry_max_attempts", _) => return Err("retry_max_attempts must be an integer from 1 to 5".to_string()),
+            ("retry_base_delay_ms", Value::Number(number)) if number.fract() == 0.0 && (0.0..=60_000.0).contains(&number) => {
+                config.retry_base_delay_ms = number as u32;
+            }
+            ("retry_base_delay_ms", _) => return Err("retry_base_delay_ms must be an integer from 0 to 60000".to_string()),
+            ("retry_max_delay_ms", Value::Number(number)) if number.fract() == 0.0 && (0.0..=60_000.0).contains(&number) => {
+                config.retry_max_delay_ms = number as u32;
+            }
+            ("retry_max_delay_ms", _) => return Err("retry_max_delay_ms must be an integer from 0 to 60000".to_string()),
+            // {"checkout":{"control":90,"new":10}}
+            ("experiments", Value::Object(entries)) => {
+                let mut parsed = Vec::new();
+                for (name, variants) in entries {
+                    if !experiments::is_valid_name(&name) {
+                        return Err(format!("Experiment name '{}' must be 1 to 64 letters, digits, '-' or '_'", name));
+                    }
+                    let Value::Object(variants) = variants else {
+                        return Err(format!("Experiment {} must be an object of variant weights", name));
+                    };
+                    let mut split: Vec<experiments::Variant> = Vec::new();
+                    for (variant, weight) in variants {
+                        let Value::Number(weight) = weight else {
+                            return Err(format!("Weight of {} in {} must be a number", variant, name));
+                        };
+                        experiments::check_variant(&split, &variant, weight)?;
+                        split.push(experiments::Variant { name: variant, weight });
+                    }
+                    if !split.iter().any(|variant| variant.weight > 0.0) {
+                        return Err(format!("Experiment {} needs a variant with a positive weight", name));
+                    }
+                    parsed.push((name, split));
+                }
+                config.experiments = parsed;
+            }
+            ("experiments", _) => return Err("experiments must be an object of experiments".to_string()),
+            // {"new-checkout":{"rollout":25,"rules":[{"attribute":"country","in":["PL"]}]}}
+            ("flags", Value::Object(entries)) => {
+                config.flags = entries.into_iter().map(|(name, definition)| flags::parse(&name, definition)).collect::<Result<_, _>>()?;
+            }
+            ("flags", _) => return Err("flags must be an object of flags".to_string()),
+            ("ring_nodes", Value::Array(nodes)) if nodes.len() <= hashring::MAX_NODES => {
+                let mut parsed: Vec<String> = Vec::new();
+                for node in nodes {
+                    match node {
+                        Value::String(node) if hashring::is_valid_node(&node) && !parsed.contains(&node) => parsed.push(node),
+                        _ => return Err("Ring nodes must be distinct strings of 1 to 256 characters without commas".to_string()),
+                    }
+                }
+                config.ring_nodes = parsed;
+            }
+            ("ring_nodes", _) => return Err(format!("ring_nodes must be a list of at most {} nodes", hashring::MAX_NODES)),
+            ("ring_vnodes", Value::Number(number)) if number.fract() == 0.0 && (1.0..=hashring::MAX_VNODES as f64).contains(&number) => {
+                config.ring_vnodes = number as u32;
+            }
+            ("ring_vnodes", _) => return Err(format!("ring_vnodes must be an integer from 1 to {}", hashring::MAX_VNODES)),
+            ("routing_rules", Value::Array(rules)) => {
+                config.routing_rules = rules.into_iter().enumerate().map(|(index, rule)| routing_rule(index, rule)).collect::<Result<_, _>>()?;
+            }
+            ("routing_rules", _) => return Err("routing_rules must be a list of rules".to_string()),
+            ("routing_default_origin", Value::String(origin)) if is_http_url(&origin) => config.routing_default_origin = Some(origin),
+            ("routing_default_origin", Value::Null) => config.routing_default_origin = None,
+            ("routing_default_origin", _) => return Err("routing_default_origin must be an http(s) URL or null".to_string()),
+            _ => {}
+        }
+    }
+    CONFIG.with(|current| *current.borrow_mut() = config);
+    Ok(())
+}
+
+fn is_http_url(text: &str) -> bool {
+    url::parse(text).is_ok_and(|url| matches!(url.scheme.as_str(), "http" | "https"))
+}
+
+// {"name":"eu","countries":["DE","PL"],"languages":["de","pl"],"origins":["https://eu.example.com"]},
+// where "origin" may stand in for a single origin
+fn routing_rule(index: usize, rule: Value) -> Result<routing::Rule, String> {
+    let Value::Object(members) = rule else {
+        return Err(format!("Routing rule {} must be an object", index + 1));
+    };
+    let label = format!("routing rule {}", index + 1);
+    let mut parsed = routing::Rule { name: format!("rule-{}", index + 1), countries: Vec::new(), languages: Vec::new(), origins: Vec::new() };
+    let strings = |key: &str, value: Value| match value {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(item) if !item.is_empty() => Ok(item),
+                _ => Err(format!("{} of {} must be non-empty strings", key, label)),
+            })
+            .collect::<Result<Vec<String>, String>>(),
+        _ => Err(format!("{} of {} must be a list", key, label)),
+    };
+    for (key, value) in members {
+        match (key.as_str(), value) {
+            ("name", Value::String(name)) if !name.is_empty() => parsed.name = name,
+            ("countries", value) => {
+                parsed.countries = strings("countries", value)?.into_iter().map(|country| country.to_ascii_uppercase()).collect();
+                if parsed.countries.iter().any(|country| country.len() != 2) {
+                    return Err(format!("Countries of {} must be two-letter codes", label));
+                }
+            }
+            ("languages", value) => parsed.languages = strings("languages", value)?.into_iter().map(|language| language.to_ascii_lowercase()).collect(),
+            ("origin", Value::String(origin)) => parsed.origins = vec![origin],
+            ("origins", value) => parsed.origins = strings("origins", value)?,
+            (key, _) => return Err(format!("Unknown or invalid {} in {}", key, label)),
+        }
+    }
+    if parsed.origins.is_empty() || !parsed.origins.iter().all(|origin| is_http_url(origin) && hashring::is_valid_node(origin)) {
+        return Err(format!("{} needs an origin or a list of origins that are http(s) URLs", label));
+    }
+    Ok(parsed)
+}
+
+// Load configuration from a JSON object in WASM memory; returns 1 on success
+// and 0 (leaving the previous config in place) on invalid input
+#[no_mangle]
+pub extern "C" fn init_config(ptr: *const u8, len: usize) -> i32 {
+    match unsafe { ffi::text(ptr, len) }.map_err(|_| String::new()).and_then(apply) {
+        Ok(()) => 1,
+        Err(_) => 0,
+    }
+}
+
+// Limits the host enforces before copying a request into WASM memory
+#[no_mangle]
+pub extern "C" fn max_body_bytes() -> usize {
+    CONFIG.with(|config| config.borrow().max_body_bytes)
+}
+
+#[no_mangle]
+pub extern "C" fn max_query_length() -> usize {
+    CONFIG.with(|config| config.borrow().max_query_length)
+}
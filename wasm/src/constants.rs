@@ -0,0 +1,64 @@
+// Decimal digits of pi and e with fixed-point big integers
+// Both series are summed as integers scaled by 10^(digits + GUARD_DIGITS);
+// the guard digits absorb truncation error from each division.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use crate::bignum::BigUint;
+use crate::budget;
+
+const GUARD_DIGITS: usize = 10;
+
+// Sum of the series for arctan(1/x) scaled by `scale`
+fn arctan_inverse(x: u32, scale: &BigUint) -> BigUint {
+    let x_squared = x * x;
+    let mut power = scale.div_small(x).0;
+    let mut sum = power.clone();
+    let mut k = 1;
+    loop {
+        power = power.div_small(x_squared).0;
+        if power.is_zero() || budget::exhausted() {
+            return sum;
+        }
+        let term = power.div_small(2 * k + 1).0;
+        sum = if k % 2 == 1 { sum.checked_sub(&term).unwrap() } else { sum.add(&term) };
+        k += 1;
+    }
+}
+
+// Machin's formula: pi = 16 arctan(1/5) - 4 arctan(1/239)
+fn pi_scaled(scale: &BigUint) -> BigUint {
+    let a = arctan_inverse(5, scale).mul_small(16);
+    let b = arctan_inverse(239, scale).mul_small(4);
+    a.checked_sub(&b).unwrap()
+}
+
+// e = sum of 1/k!
+fn e_scaled(scale: &BigUint) -> BigUint {
+    let mut term = scale.clone();
+    let mut sum = BigUint::zero();
+    let mut k = 1;
+    while !term.is_zero() && !budget::exhausted() {
+        sum = sum.add(&term);
+        term = term.div_small(k).0;
+        k += 1;
+    }
+    sum
+}
+
+// "3.1415...", truncated to `digits` decimals; None for unknown constants
+// and when the computation budget ran out first
+pub fn digits_of(constant: &str, digits: usize) -> Option<String> {
+    let scale = BigUint::pow10(digits + GUARD_DIGITS);
+    let scaled = match constant {
+        "pi" => pi_scaled(&scale),
+        "e" => e_scaled(&scale),
+        _ => return None,
+    };
+    if budget::exhausted() {
+        return None;
+    }
+    let text = scaled.to_string();
+    // Both constants lie between 1 and 10, so there is one integer digit
+    Some(format!("{}.{}", &text[..1], &text[1..=digits]))
+}
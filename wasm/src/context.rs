@@ -0,0 +1,102 @@
+// State scoped to the request being handled
+// handle_request builds a RequestContext once the request has parsed and
+// passes it to every handler. The request ID is also kept per isolate from
+// before parsing until end_request, so error responses and log lines built
+// anywhere can carry it without it being threaded through. handle_request
+// never yields to the host, so requests an isolate interleaves cannot see
+// each other's ID.
+
+use crate::config::Config;
+use crate::flags::{self, Evaluation};
+use crate::headers::{self, Headers};
+use crate::host;
+use crate::ids;
+use crate::kv::Pending;
+use crate::params::QueryParams;
+use crate::routing;
+use std::cell::RefCell;
+
+pub struct RequestContext<'a> {
+    pub method: &'a str,
+    // Normalised as the router matched it, see worker::route_path
+    pub path: String,
+    pub params: QueryParams,
+    pub headers: Headers,
+    pub body: &'a [u8],
+    // Snapshot taken when the request arrived
+    pub config: &'a Config,
+    // Host clock reading the request should be done by
+    pub deadline_ms: f64,
+}
+
+impl<'a> RequestContext<'a> {
+    pub fn new(method: &'a str, path: String, params: QueryParams, headers: Headers, body: &'a [u8], config: &'a Config) -> Self {
+        let deadline_ms = host::now_ms() + config.request_deadline_ms as f64;
+        RequestContext { method, path, params, headers, body, config, deadline_ms }
+    }
+
+    // Only ever true where the host clock advances during a request
+    pub fn past_deadline(&self) -> bool {
+        host::now_ms() > self.deadline_ms
+    }
+
+    // Lines are prefixed with the request ID like every other log line
+    pub fn log(&self, message: &str) {
+        host::log(message);
+    }
+
+    // What flag rules can target about the request: the user, and country
+    // and language from its headers
+    pub fn flag_attributes(&self, user: &str) -> Vec<(String, String)> {
+        let mut attributes = vec![("user".to_string(), user.to_string())];
+        if let Some(country) = routing::country(self.headers.get("cf-ipcountry")) {
+            attributes.push(("country".to_string(), country));
+        }
+        if let Some((tag, _)) = headers::accept_languages(self.headers.get("accept-language").unwrap_or("")).into_iter().next() {
+            let language = tag.split('-').next().unwrap_or(&tag).to_ascii_lowercase();
+            attributes.push(("language".to_string(), language));
+        }
+        attributes
+    }
+
+    // A flag from CONFIG or KV evaluated for `user`. One that is not defined
+    // is off; so is one whose KV definition is invalid, which is logged.
+    pub fn flag(&self, name: &str, user: &str) -> Result<Evaluation, Pending> {
+        match flags::find(&self.config.flags, name)? {
+            Ok(Some(flag)) => Ok(flags::evaluate(&flag, user, &self.flag_attributes(user))),
+            Ok(None) => Ok(flags::undefined(name, user)),
+            Err(reason) => {
+                self.log(&reason);
+                Ok(flags::undefined(name, user))
+            }
+        }
+    }
+}
+
+thread_local! {
+    static REQUEST_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// IDs from clients are echoed into headers and logs, so only short runs of
+// token characters are taken; anything else gets a fresh ID instead
+fn is_valid_request_id(id: &str) -> bool {
+    (1..=64).contains(&id.len()) && id.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b))
+}
+
+// Uses the caller's X-Request-Id when it is acceptable, otherwise a new ULID
+pub fn begin(headers: Option<&Headers>) {
+    let id = match headers.and_then(|headers| headers.get("x-request-id")) {
+        Some(id) if is_valid_request_id(id) => id.to_string(),
+        _ => ids::ulid(),
+    };
+    REQUEST_ID.with(|current| *current.borrow_mut() = Some(id));
+}
+
+pub fn end() {
+    REQUEST_ID.with(|current| *current.borrow_mut() = None);
+}
+
+// None outside a request, e.g. while the host runs warmup
+pub fn request_id() -> Option<String> {
+    REQUEST_ID.with(|current| current.borrow().clone())
+}
@@ -0,0 +1,98 @@
+// Per-isolate request counters
+// Cloudflare reuses an isolate (and this module instance) for many requests,
+// but runs many isolates side by side and evicts them at will, so these
+// numbers describe one instance only - never global traffic.
+
+use crate::ffi;
+use crate::host;
+use crate::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Paths counted individually; anything else is counted as "other".
+// Keep in sync with the routes in worker.rs.
+pub const ROUTES: [&str; 103] = [
+    "/", "/status", "/counter", "/metrics", "/robots.txt", "/favicon.ico",
+    "/.well-known/security.txt", "/sitemap.xml", "/add", "/factorial", "/prime", "/fibonacci",
+    "/hash", "/generate", "/feed", "/random", "/roll", "/validate/card", "/validate/iban",
+    "/validate/isbn", "/validate/email", "/validate/url", "/validate/phone", "/semver/parse",
+    "/semver/satisfies", "/autocomplete", "/search/query", "/sort", "/bench", "/sudoku/solve",
+    "/sudoku/generate", "/life", "/maze", "/chart", "/chart.svg", "/banner", "/nqueens",
+    "/combinatorics", "/pi", "/e", "/sentiment", "/language", "/text/stem", "/text/plural",
+    "/text/translit", "/text/analyze", "/phonetic", "/format/number", "/format/bytes",
+    "/format/duration", "/cipher", "/cipher/xor", "/morse", "/rsa/demo", "/ulid", "/nanoid",
+    "/id/snowflake", "/fetch", "/rewrite", "/meta/extract", "/links/extract", "/robots/check",
+    "/jsonld/validate", "/cache/analyze", "/ab", "/flags/evaluate", "/ring/locate", "/route/decide",
+    "/oauth/pkce", "/oauth/state", "/captcha/new", "/captcha/verify", "/pow/challenge",
+    "/pow/verify", "/ratelimit/test", "/s/:code", "/paste/:id", "/blob/:id", "/grpc/:method",
+    "/minify", "/detect", "/upload/inspect", "/checksum", "/proto/decode", "/parse/bencode",
+    "/image/resize", "/image/strip", "/zip/list", "/zip/extract", "/tar/create", "/merkle",
+    "/search/index", "/graph/shortest", "/graphql", "/fft", "/fit", "/cluster", "/table",
+    "/shorten", "/paste", "/blob", "/webhook/verify", "other",
+];
+
+static TOTAL: AtomicU64 = AtomicU64::new(0);
+static ROUTE_HITS: [AtomicU64; ROUTES.len()] = [const { AtomicU64::new(0) }; ROUTES.len()];
+// Time of the first request, standing in for instantiation since the module
+// has no start hook that could read the host clock
+static STARTED_AT_MS: AtomicU64 = AtomicU64::new(0);
+
+fn route_index(path: &str) -> usize {
+    let path = match path {
+        _ if path.starts_with("/s/") => "/s/:code",
+        _ if path.starts_with("/paste/") => "/paste/:id",
+        _ if path.starts_with("/blob/") => "/blob/:id",
+        _ if path.starts_with("/grpc/") => "/grpc/:method",
+        _ => path,
+    };
+    ROUTES.iter().position(|&route| route == path).unwrap_or(ROUTES.len() - 1)
+}
+
+// The name a path is counted under, for other per-route statistics
+pub fn route_name(path: &str) -> &'static str {
+    ROUTES[route_index(path)]
+}
+
+pub fn record(path: &str) {
+    let now = host::now_ms() as u64;
+    let _ = STARTED_AT_MS.compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed);
+    TOTAL.fetch_add(1, Ordering::Relaxed);
+    ROUTE_HITS[route_index(path)].fetch_add(1, Ordering::Relaxed);
+}
+
+// Takes back a record() for a pass the host will replay
+pub fn unrecord(path: &str) {
+    TOTAL.fetch_sub(1, Ordering::Relaxed);
+    ROUTE_HITS[route_index(path)].fetch_sub(1, Ordering::Relaxed);
+}
+
+// Time since the isolate's first request
+pub fn uptime_ms() -> u64 {
+    let started_at = STARTED_AT_MS.load(Ordering::Relaxed);
+    if started_at == 0 { 0 } else { (host::now_ms() as u64).saturating_sub(started_at) }
+}
+
+// Snapshot of all counters as a JSON object
+pub fn to_json() -> String {
+    let started_at = STARTED_AT_MS.load(Ordering::Relaxed);
+    let uptime = uptime_ms();
+    let routes: Vec<String> = ROUTES
+        .iter()
+        .zip(&ROUTE_HITS)
+        .map(|(route, hits)| (route, hits.load(Ordering::Relaxed)))
+        .filter(|&(_, hits)| hits > 0)
+        .map(|(route, hits)| format!("\"{}\":{}", json::escape(route), hits))
+        .collect();
+    format!(
+        r#"{{"total_requests":{},"started_at_ms":{},"uptime_ms":{},"routes":{{{}}}}}"#,
+        TOTAL.load(Ordering::Relaxed),
+        started_at,
+        uptime,
+        routes.join(",")
+    )
+}
+
+// Counter snapshot for the host; free the result with free_bytes
+#[no_mangle]
+pub extern "C" fn get_counters() -> *mut u8 {
+    ffi::output(to_json().as_bytes())
+}
@@ -0,0 +1,112 @@
+// Unix millisecond timestamps as RFC 3339 and RFC 822 dates, always in UTC,
+// and HTTP dates back to timestamps
+
+use alloc::format;
+use alloc::string::String;
+
+pub const MS_PER_DAY: u64 = 86_400_000;
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+struct DateTime {
+    year: u64,
+    month: u64,
+    day: u64,
+    weekday: &'static str,
+    hour: u64,
+    minute: u64,
+    second: u64,
+}
+
+fn date_time(ms: u64) -> DateTime {
+    let days = ms / MS_PER_DAY;
+    let seconds = ms % MS_PER_DAY / 1000;
+    let (year, month, day) = civil_from_days(days);
+    DateTime {
+        year,
+        month,
+        day,
+        // 1970-01-01 was a Thursday
+        weekday: WEEKDAYS[(days % 7) as usize],
+        hour: seconds / 3600,
+        minute: seconds / 60 % 60,
+        second: seconds % 60,
+    }
+}
+
+// 2024-01-01T00:00:00Z
+pub fn rfc3339(ms: u64) -> String {
+    let t = date_time(ms);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", t.year, t.month, t.day, t.hour, t.minute, t.second)
+}
+
+// Mon, 01 Jan 2024 00:00:00 GMT, with the four-digit year RFC 1123 brought in
+pub fn rfc822(ms: u64) -> String {
+    let t = date_time(ms);
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        t.weekday,
+        t.day,
+        MONTHS[t.month as usize - 1],
+        t.year,
+        t.hour,
+        t.minute,
+        t.second
+    )
+}
+
+// An HTTP date in any of the three forms RFC 9110 asks recipients to accept:
+// "Sun, 06 Nov 1994 08:49:37 GMT", "Sunday, 06-Nov-94 08:49:37 GMT" and
+// "Sun Nov  6 08:49:37 1994". Two-digit years are taken as 19xx from 70 up
+// and 20xx below; the weekday is not checked.
+pub fn parse_http_date(text: &str) -> Option<u64> {
+    let (_, rest) = text.trim().split_once([',', ' '])?;
+    let fields: alloc::vec::Vec<&str> = rest.split([' ', '-']).filter(|field| !field.is_empty()).collect();
+    let (day, month, year, time) = match fields[..] {
+        [day, month, year, time, "GMT"] => (day, month, year, time),
+        [month, day, time, year] => (day, month, year, time),
+        _ => return None,
+    };
+    let month = MONTHS.iter().position(|name| *name == month)? as u64 + 1;
+    let day: u64 = day.parse().ok().filter(|day| (1..=31).contains(day))?;
+    let year: u64 = match (year.len(), year.parse().ok()?) {
+        (2, year) if year < 70 => year + 2000,
+        (2, year) => year + 1900,
+        (4, year) if year >= 1970 => year,
+        _ => return None,
+    };
+    let mut clock = time.split(':').map(|field| field.parse::<u64>().ok());
+    let (Some(Some(hour)), Some(Some(minute)), Some(Some(second)), None) = (clock.next(), clock.next(), clock.next(), clock.next()) else {
+        return None;
+    };
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * MS_PER_DAY + (hour * 3600 + minute * 60 + second) * 1000)
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date, after Howard Hinnant's
+// civil_from_days
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+// The inverse of civil_from_days, for dates from 1970 on
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    (era * 146_097 + day_of_era).saturating_sub(719_468)
+}
@@ -0,0 +1,161 @@
+// Dice notation parsing, rolling and outcome statistics
+// Supports sums of dice groups and constants, e.g. "3d6+2", "d20-1", "2d8+1d4+3".
+
+use crate::rand::Rng;
+
+const MAX_DICE: u32 = 100;
+const MAX_SIDES: u32 = 1000;
+// Exact distributions are only listed while the number of outcomes stays small
+const MAX_DISTRIBUTION_OUTCOMES: i64 = 200;
+
+pub struct Term {
+    pub sign: i64,
+    pub count: u32,
+    // 0 for a constant modifier, whose value is stored in `count`
+    pub sides: u32,
+}
+
+pub struct Roll {
+    pub rolls: Vec<Vec<u32>>,
+    pub total: i64,
+}
+
+pub struct Stats {
+    pub min: i64,
+    pub max: i64,
+    pub mean: f64,
+    pub std_dev: f64,
+    // Probability of each total from min to max, when small enough to list
+    pub distribution: Option<Vec<f64>>,
+}
+
+pub fn parse(notation: &str) -> Result<Vec<Term>, String> {
+    let notation: String = notation
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if notation.is_empty() {
+        return Err("Dice notation is empty".to_string());
+    }
+
+    let mut terms = Vec::new();
+    let mut total_dice = 0;
+    let mut rest = notation.as_str();
+    let mut sign = 1;
+    loop {
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let term = parse_term(&rest[..end], sign)?;
+        if term.sides > 0 {
+            total_dice += term.count;
+        }
+        terms.push(term);
+
+        if end == rest.len() {
+            break;
+        }
+        sign = if rest.as_bytes()[end] == b'-' { -1 } else { 1 };
+        rest = &rest[end + 1..];
+    }
+
+    if total_dice == 0 {
+        return Err("Notation must contain at least one die, e.g. 3d6".to_string());
+    }
+    if total_dice > MAX_DICE {
+        return Err(format!("At most {} dice can be rolled at once", MAX_DICE));
+    }
+    Ok(terms)
+}
+
+fn parse_term(term: &str, sign: i64) -> Result<Term, String> {
+    let invalid = || format!("Invalid dice term '{}'", term);
+    match term.split_once('d') {
+        Some((count, sides)) => {
+            let count = if count.is_empty() { 1 } else { count.parse().map_err(|_| invalid())? };
+            let sides: u32 = sides.parse().map_err(|_| invalid())?;
+            if count == 0 || !(2..=MAX_SIDES).contains(&sides) {
+                return Err(format!("Dice need 1 or more dice with 2 to {} sides", MAX_SIDES));
+            }
+            Ok(Term { sign, count, sides })
+        }
+        None => {
+            let value: u32 = term.parse().map_err(|_| invalid())?;
+            if value > 1_000_000 {
+                return Err(invalid());
+            }
+            Ok(Term { sign, count: value, sides: 0 })
+        }
+    }
+}
+
+pub fn roll(terms: &[Term], rng: &mut Rng) -> Roll {
+    let mut rolls = Vec::new();
+    let mut total = 0;
+    for term in terms {
+        if term.sides == 0 {
+            total += term.sign * term.count as i64;
+            continue;
+        }
+        let group: Vec<u32> = (0..term.count)
+            .map(|_| rng.range(1, term.sides as i64) as u32)
+            .collect();
+        total += term.sign * group.iter().map(|&face| face as i64).sum::<i64>();
+        rolls.push(group);
+    }
+    Roll { rolls, total }
+}
+
+pub fn stats(terms: &[Term]) -> Stats {
+    let mut min = 0;
+    let mut max = 0;
+    let mut mean = 0.0;
+    let mut variance = 0.0;
+    for term in terms {
+        if term.sides == 0 {
+            min += term.sign * term.count as i64;
+            max += term.sign * term.count as i64;
+            mean += (term.sign * term.count as i64) as f64;
+            continue;
+        }
+        let count = term.count as i64;
+        let sides = term.sides as f64;
+        let (low, high) = (count, count * term.sides as i64);
+        if term.sign > 0 {
+            min += low;
+            max += high;
+        } else {
+            min -= high;
+            max -= low;
+        }
+        mean += term.sign as f64 * count as f64 * (sides + 1.0) / 2.0;
+        variance += count as f64 * (sides * sides - 1.0) / 12.0;
+    }
+
+    let distribution = if max - min < MAX_DISTRIBUTION_OUTCOMES {
+        Some(distribution(terms))
+    } else {
+        None
+    };
+
+    Stats { min, max, mean, std_dev: variance.sqrt(), distribution }
+}
+
+// Exact outcome probabilities by convolving one die at a time. Each die is
+// uniform over its offsets from its lowest face whether it is added or
+// subtracted, so index i is the chance of rolling a total of min + i.
+fn distribution(terms: &[Term]) -> Vec<f64> {
+    let mut probabilities = vec![1.0];
+    for term in terms.iter().filter(|term| term.sides > 0) {
+        let face = 1.0 / term.sides as f64;
+        for _ in 0..term.count {
+            let mut next = vec![0.0; probabilities.len() + term.sides as usize - 1];
+            for (offset, &p) in probabilities.iter().enumerate() {
+                for side in 0..term.sides as usize {
+                    next[offset + side] += p * face;
+                }
+            }
+            probabilities = next;
+        }
+    }
+    probabilities
+}
@@ -0,0 +1,114 @@
+// Documented endpoints
+// The home page's endpoint list and /sitemap.xml are both built from this
+// one table, so a route is described in a single place. Routing itself stays
+// in worker.rs; an entry here only documents it.
+
+pub struct Endpoint {
+    pub path: &'static str,
+    pub methods: &'static [&'static str],
+    // Path and query of a request that works as it stands
+    pub example: &'static str,
+    // HTML, shown next to the example
+    pub description: &'static str,
+    // Listed in the sitemap: a plain GET of the bare path answers with a page
+    // and changes nothing
+    pub public: bool,
+}
+
+pub const ENDPOINTS: &[Endpoint] = &[
+    Endpoint { path: "/status", methods: &["GET"], example: "/status", description: "Check WASM status", public: true },
+    Endpoint { path: "/counter", methods: &["GET"], example: "/counter", description: "Request counters for the serving isolate", public: true },
+    Endpoint { path: "/metrics", methods: &["GET"], example: "/metrics", description: "Per-route timings and slow requests for the serving isolate", public: true },
+    Endpoint { path: "/add", methods: &["GET"], example: "/add?a=5&b=3", description: "Add two numbers", public: true },
+    Endpoint { path: "/factorial", methods: &["GET"], example: "/factorial?n=5", description: "Calculate factorial", public: true },
+    Endpoint { path: "/prime", methods: &["GET"], example: "/prime?n=17", description: "Check if number is prime", public: true },
+    Endpoint { path: "/fibonacci", methods: &["GET"], example: "/fibonacci?n=10", description: "Get Fibonacci number", public: true },
+    Endpoint { path: "/hash", methods: &["GET"], example: "/hash?input=test", description: "Simple hash function", public: true },
+    Endpoint { path: "/generate", methods: &["GET"], example: "/generate?kind=names&count=5&seed=42", description: "Deterministic fake data", public: true },
+    Endpoint { path: "/feed", methods: &["GET"], example: "/feed?format=atom&count=5", description: "RSS or Atom demo feed", public: true },
+    Endpoint { path: "/graphql", methods: &["GET", "POST"], example: "/graphql?query={add(a:2,b:3) factorial(n:5)}", description: "GraphQL subset over add, factorial and status", public: false },
+    Endpoint { path: "/random", methods: &["GET"], example: "/random?min=1&max=6&count=3&seed=7", description: "Seeded random numbers", public: true },
+    Endpoint { path: "/roll", methods: &["GET"], example: "/roll?dice=3d6+2", description: "Roll dice with outcome statistics", public: true },
+    Endpoint { path: "/validate/card", methods: &["GET"], example: "/validate/card?number=4111111111111111", description: "Luhn check and card brand", public: false },
+    Endpoint { path: "/validate/iban", methods: &["GET"], example: "/validate/iban?iban=DE89370400440532013000", description: "IBAN check digits and length", public: false },
+    Endpoint { path: "/validate/isbn", methods: &["GET"], example: "/validate/isbn?isbn=0-306-40615-2", description: "ISBN-10/13 checksum and conversion", public: false },
+    Endpoint { path: "/validate/email", methods: &["GET"], example: "/validate/email?email=user@example.com", description: "Email syntax with error reasons", public: false },
+    Endpoint { path: "/validate/url", methods: &["GET"], example: "/validate/url?url=https%3A%2F%2Fexample.com", description: "URL syntax with error reasons", public: false },
+    Endpoint { path: "/validate/phone", methods: &["GET"], example: "/validate/phone?number=0601234567&region=FR", description: "E.164 phone normalization", public: false },
+    Endpoint { path: "/semver/parse", methods: &["GET"], example: "/semver/parse?version=1.2.3-beta.1", description: "Parse a semantic version", public: false },
+    Endpoint { path: "/semver/satisfies", methods: &["GET"], example: "/semver/satisfies?version=1.2.3&range=^1.2", description: "Check a version against a range", public: false },
+    Endpoint { path: "/autocomplete", methods: &["GET"], example: "/autocomplete?prefix=th&limit=5", description: "Frequency-ranked completions from a warm trie", public: true },
+    Endpoint { path: "/search/query", methods: &["GET"], example: "/search/query?q=rust%20wasm", description: "TF-IDF ranked search over indexed documents", public: false },
+    Endpoint { path: "/sort", methods: &["GET"], example: "/sort?algo=quick&n=100000&seed=7", description: "Sorting benchmark (quick, merge, heap, pdq)", public: true },
+    Endpoint { path: "/sudoku/solve", methods: &["GET"], example: "/sudoku/solve?puzzle=53..7....", description: "Solve an 81-cell sudoku and check uniqueness", public: false },
+    Endpoint { path: "/sudoku/generate", methods: &["GET"], example: "/sudoku/generate?difficulty=hard&seed=1", description: "Generate a puzzle with a unique solution", public: true },
+    Endpoint { path: "/life", methods: &["GET", "POST"], example: "/life?width=32&height=16&steps=50&seed=1", description: "Game of Life (POST a grid to evolve your own)", public: true },
+    Endpoint { path: "/maze", methods: &["GET"], example: "/maze?w=12&h=8&seed=1&solve=true&format=svg", description: "Generate and solve a maze (json, ascii, svg)", public: true },
+    Endpoint { path: "/chart", methods: &["GET"], example: "/chart?data=1,5,3,8&type=spark", description: "Text bar chart or sparkline of comma-separated values", public: false },
+    Endpoint { path: "/chart.svg", methods: &["GET", "POST"], example: "/chart.svg?data=1,5,3,8&type=bar", description: "SVG line, bar or pie chart (POST values, labels and a title as JSON)", public: false },
+    Endpoint { path: "/banner", methods: &["GET"], example: "/banner?text=HELLO&font=small", description: "FIGlet-style text banner from embedded bitmap fonts", public: false },
+    Endpoint { path: "/sentiment", methods: &["GET"], example: "/sentiment?text=not%20bad%20at%20all", description: "Lexicon sentiment score with negation handling", public: false },
+    Endpoint { path: "/language", methods: &["GET"], example: "/language?text=Wo%20ist%20der%20Bahnhof", description: "Detect the language of a text (20 languages)", public: false },
+    Endpoint { path: "/text/stem", methods: &["GET"], example: "/text/stem?text=running%20quickly&lang=en", description: "Porter stems with stop words flagged (en, de, fr, es, it, pt, nl)", public: false },
+    Endpoint { path: "/text/plural", methods: &["GET"], example: "/text/plural?word=mouse&count=3", description: "English singular and plural forms, picked by count", public: false },
+    Endpoint { path: "/text/translit", methods: &["GET"], example: "/text/translit?text=Привет%20мир", description: "Cyrillic, Greek and accented Latin to ASCII, plus a URL slug", public: false },
+    Endpoint { path: "/text/analyze", methods: &["GET"], example: "/text/analyze?check=anagram&a=Listen&b=Silent", description: "Anagram and palindrome checks ignoring case, punctuation and accents", public: false },
+    Endpoint { path: "/phonetic", methods: &["GET"], example: "/phonetic?word=Robert&word2=Rupert", description: "Soundex or Metaphone code of a word, and whether two words sound alike", public: false },
+    Endpoint { path: "/format/number", methods: &["GET"], example: "/format/number?value=1234567.891&locale=pl-PL&style=currency", description: "Format a number or an amount of money for a locale", public: false },
+    Endpoint { path: "/format/bytes", methods: &["GET"], example: "/format/bytes?n=123456789", description: "A size in SI and IEC units", public: false },
+    Endpoint { path: "/format/duration", methods: &["GET"], example: "/format/duration?ms=987654", description: "A duration in short, spelled-out and ISO 8601 forms", public: false },
+    Endpoint { path: "/cipher", methods: &["GET"], example: "/cipher?algo=vigenere&key=lemon&text=attack%20at%20dawn", description: "ROT13, Caesar and Vigenère (mode=enc|dec)", public: false },
+    Endpoint { path: "/cipher/xor", methods: &["GET"], example: "/cipher/xor?seed=42&text=hello", description: "XOR with a seeded PRNG keystream (not secure; hex= decrypts)", public: false },
+    Endpoint { path: "/morse", methods: &["GET"], example: "/morse?text=SOS&wpm=15", description: "Morse code both ways, with PARIS timing and tone lengths", public: false },
+    Endpoint { path: "/rsa/demo", methods: &["GET"], example: "/rsa/demo?bits=512&message=hello", description: "Generate a small RSA key, sign and verify with timings (not secure)", public: true },
+    Endpoint { path: "/ulid", methods: &["GET"], example: "/ulid?count=5", description: "Time-ordered ULIDs from host time and crypto randomness", public: true },
+    Endpoint { path: "/nanoid", methods: &["GET"], example: "/nanoid?length=21&alphabet=0123456789abcdef", description: "NanoIDs with an optional custom alphabet", public: true },
+    Endpoint { path: "/id/snowflake", methods: &["GET"], example: "/id/snowflake?count=5", description: "Monotonic 64-bit snowflake IDs (node id and epoch from CONFIG)", public: true },
+    Endpoint { path: "/ab", methods: &["GET"], example: "/ab?experiment=checkout&id=user-42&variants=control:90,new:10", description: "Deterministic A/B variant for an id, pinned by a cookie", public: false },
+    Endpoint { path: "/flags/evaluate", methods: &["GET"], example: "/flags/evaluate?flag=new-checkout&user=42&plan=pro", description: "Evaluate a feature flag from CONFIG or KV for a user", public: false },
+    Endpoint { path: "/ring/locate", methods: &["GET"], example: "/ring/locate?key=user:42&nodes=origin-a,origin-b,origin-c&replicas=2", description: "Node owning a key on a consistent hashing ring", public: false },
+    Endpoint { path: "/route/decide", methods: &["GET"], example: "/route/decide?country=PL&lang=pl&path=/shop", description: "Origin for a visitor from the routing rules in CONFIG", public: true },
+    Endpoint { path: "/fetch", methods: &["GET"], example: "/fetch?url=https://example.com/", description: "Relay a GET to one of the fetch_origins behind a circuit breaker", public: false },
+    Endpoint { path: "/rewrite", methods: &["GET", "POST"], example: "/rewrite?url=https://example.com/&nonce=random", description: "Inject meta tags, rewrite URL prefixes and add script nonces to a fetched or posted page", public: false },
+    Endpoint { path: "/oauth/pkce", methods: &["GET"], example: "/oauth/pkce?length=64", description: "PKCE code_verifier and S256 code_challenge", public: true },
+    Endpoint { path: "/oauth/state", methods: &["GET"], example: "/oauth/state", description: "Signed OAuth state token (check one with <code>?token=</code>)", public: false },
+    Endpoint { path: "/captcha/new", methods: &["GET"], example: "/captcha/new?kind=text", description: "Math or distorted-text captcha with a signed token", public: false },
+    Endpoint { path: "/captcha/verify", methods: &["GET"], example: "/captcha/verify?token=...&answer=30", description: "Check a captcha answer; each token gets one attempt", public: false },
+    Endpoint { path: "/pow/challenge", methods: &["GET"], example: "/pow/challenge?difficulty=20", description: "Signed proof-of-work challenge", public: false },
+    Endpoint { path: "/pow/verify", methods: &["GET"], example: "/pow/verify?challenge=...&nonce=8041", description: "Check a proof-of-work nonce; a challenge is spent by its first solution", public: false },
+    Endpoint { path: "/ratelimit/test", methods: &["GET"], example: "/ratelimit/test?algo=sliding_window&key=me&limit=5&window_ms=10000", description: "Count a request against a token bucket, leaky bucket or sliding window kept in KV", public: false },
+    Endpoint { path: "/bench", methods: &["GET"], example: "/bench?op=hash&kib=64&iterations=100", description: "Time the scalar and SIMD byte kernels (hash, hex, scan) on the same data", public: true },
+    Endpoint { path: "/nqueens", methods: &["GET"], example: "/nqueens?n=8", description: "Count N-queens solutions with a sample board", public: true },
+    Endpoint { path: "/combinatorics", methods: &["GET"], example: "/combinatorics?op=nCr&n=100&k=50", description: "Exact nCr, nPr and Catalan numbers", public: false },
+    Endpoint { path: "/pi", methods: &["GET"], example: "/pi?digits=1000", description: "Digits of pi with big integers", public: true },
+    Endpoint { path: "/e", methods: &["GET"], example: "/e?digits=1000", description: "Digits of e with big integers", public: true },
+    Endpoint { path: "/minify", methods: &["POST"], example: "/minify?type=html", description: "Minify an HTML or CSS body", public: false },
+    Endpoint { path: "/detect", methods: &["POST"], example: "/detect?filename=logo.png", description: "Sniff the MIME type of a body", public: false },
+    Endpoint { path: "/upload/inspect", methods: &["POST"], example: "/upload/inspect", description: "Inspect a multipart/form-data upload", public: false },
+    Endpoint { path: "/checksum", methods: &["POST"], example: "/checksum", description: "CRC32, SHA-256 and ETag of a body", public: false },
+    Endpoint { path: "/proto/decode", methods: &["POST"], example: "/proto/decode?encoding=hex", description: "Break a protobuf payload into fields with every reading of each value", public: false },
+    Endpoint { path: "/parse/bencode", methods: &["POST"], example: "/parse/bencode", description: "Bencode to JSON (<code>?to=bencode</code> for the way back)", public: false },
+    Endpoint { path: "/table", methods: &["POST"], example: "/table", description: "Render a JSON array as an aligned ASCII table", public: false },
+    Endpoint { path: "/image/resize", methods: &["POST"], example: "/image/resize?w=128", description: "Resize a PNG with bilinear filtering", public: false },
+    Endpoint { path: "/image/strip", methods: &["POST"], example: "/image/strip?keep=icc", description: "Remove EXIF, XMP and other metadata from a JPEG without re-encoding it", public: false },
+    Endpoint { path: "/zip/list", methods: &["POST"], example: "/zip/list", description: "List the entries of a ZIP archive", public: false },
+    Endpoint { path: "/zip/extract", methods: &["POST"], example: "/zip/extract?name=docs/readme.txt", description: "Extract one entry of a ZIP archive as a download", public: false },
+    Endpoint { path: "/tar/create", methods: &["POST"], example: "/tar/create?gzip=true", description: "Bundle the parts of a multipart upload into a tar (or .tar.gz) archive", public: false },
+    Endpoint { path: "/meta/extract", methods: &["POST"], example: "/meta/extract", description: "Title, description, Open Graph and Twitter card fields of a page", public: false },
+    Endpoint { path: "/links/extract", methods: &["POST"], example: "/links/extract?base=https://example.com/docs/", description: "Links of a page or the URLs of a sitemap, resolved against its base", public: false },
+    Endpoint { path: "/robots/check", methods: &["POST"], example: "/robots/check?agent=Googlebot&path=/private/", description: "Whether a robots.txt lets an agent fetch a path", public: false },
+    Endpoint { path: "/jsonld/validate", methods: &["POST"], example: "/jsonld/validate", description: "Check a page's JSON-LD blocks against what rich results need", public: false },
+    Endpoint { path: "/cache/analyze", methods: &["POST"], example: "/cache/analyze", description: "Whether and for how long a cache keeps a response, from its headers", public: false },
+    Endpoint { path: "/merkle", methods: &["POST"], example: "/merkle?index=0", description: "Merkle root and inclusion proof for a JSON array", public: false },
+    Endpoint { path: "/graph/shortest", methods: &["POST"], example: "/graph/shortest?source=a&target=d", description: "Shortest path through a JSON adjacency list", public: false },
+    Endpoint { path: "/fft", methods: &["POST"], example: "/fft?sample_rate=8&window=hann", description: "Amplitude spectrum of a JSON array of samples", public: false },
+    Endpoint { path: "/fit", methods: &["POST"], example: "/fit?model=linear", description: "Least-squares fit (linear, poly2, exp) of x/y arrays", public: false },
+    Endpoint { path: "/cluster", methods: &["POST"], example: "/cluster?k=2&seed=1", description: "K-means clustering of a JSON array of points", public: false },
+    Endpoint { path: "/cipher/xor", methods: &["POST"], example: "/cipher/xor?seed=42", description: "XOR a binary body with the seeded keystream, returned as hex", public: false },
+    Endpoint { path: "/shorten", methods: &["POST"], example: "/shorten", description: "Shorten the URL in the body; <code>/s/{code}</code> redirects and counts hits (<code>?stats</code> for details)", public: false },
+    Endpoint { path: "/paste", methods: &["POST"], example: "/paste?ttl=3600", description: "Store the body as an expiring paste; <code>/paste/{id}</code> renders it (<code>?raw</code> for plain text)", public: false },
+    Endpoint { path: "/blob", methods: &["POST"], example: "/blob", description: "Store a file in KV under its SHA-256; <code>/blob/{id}</code> serves it with an immutable ETag", public: false },
+    Endpoint { path: "/webhook/verify", methods: &["POST"], example: "/webhook/verify?provider=github", description: "Check a GitHub, Stripe or Slack webhook signature against the body", public: false },
+    Endpoint { path: "/grpc/calculator.Calculator/Add", methods: &["POST"], example: "/grpc/calculator.Calculator/Add", description: "gRPC-Web unary calls to the Calculator service (Add, Factorial, IsPrime)", public: false },
+    Endpoint { path: "/search/index", methods: &["POST"], example: "/search/index?lang=en", description: "Replace the search index with a JSON array of documents", public: false },
+];
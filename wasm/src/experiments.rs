@@ -0,0 +1,79 @@
+// A/B test assignment
+// An (experiment, id) pair hashes to a fixed point in [0, 1), and variants
+// own consecutive slices of that range in proportion to their weights, so a
+// visitor sees the same variant on every request without anything stored.
+// Changing a split only moves the visitors whose points cross a slice
+// boundary; adding a variant at the end takes its share from the others in
+// proportion. Hashing the experiment name in keeps experiments independent.
+
+use crate::hashing::Sha256;
+
+#[derive(Clone)]
+pub struct Variant {
+    pub name: String,
+    pub weight: f64,
+}
+
+// Experiment and variant names end up in cookie names and values
+pub fn is_valid_name(name: &str) -> bool {
+    (1..=64).contains(&name.len()) && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+// "control:90,treatment:10", or "a,b,c" for an even split
+pub fn parse_variants(spec: &str) -> Result<Vec<Variant>, String> {
+    let mut variants: Vec<Variant> = Vec::new();
+    for item in spec.split(',') {
+        let (name, weight) = match item.split_once(':') {
+            Some((name, weight)) => {
+                let weight = weight.trim().parse::<f64>().map_err(|_| format!("Invalid weight '{}'", weight))?;
+                (name.trim(), weight)
+            }
+            None => (item.trim(), 1.0),
+        };
+        check_variant(&variants, name, weight)?;
+        variants.push(Variant { name: name.to_string(), weight });
+    }
+    Ok(variants)
+}
+
+pub fn check_variant(existing: &[Variant], name: &str, weight: f64) -> Result<(), String> {
+    if !is_valid_name(name) {
+        return Err(format!("Variant name '{}' must be 1 to 64 letters, digits, '-' or '_'", name));
+    }
+    if !(weight.is_finite() && weight >= 0.0) {
+        return Err(format!("Weight of {} must be a non-negative number", name));
+    }
+    if existing.iter().any(|variant| variant.name == name) {
+        return Err(format!("Variant {} is listed twice", name));
+    }
+    Ok(())
+}
+
+// Where the id falls for this experiment, from the top 53 bits of a SHA-256
+pub fn position(experiment: &str, id: &str) -> f64 {
+    let mut hasher = Sha256::new();
+    hasher.update(experiment.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(id.as_bytes());
+    let digest = hasher.finalize();
+    let mut top = [0u8; 8];
+    top.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(top) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// The variant whose slice contains `position`; None when every weight is 0
+pub fn assign(variants: &[Variant], position: f64) -> Option<&Variant> {
+    let total: f64 = variants.iter().map(|variant| variant.weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut end = 0.0;
+    for variant in variants.iter().filter(|variant| variant.weight > 0.0) {
+        end += variant.weight / total;
+        if position < end {
+            return Some(variant);
+        }
+    }
+    // Rounding can leave the last slice ending just short of 1
+    variants.iter().rev().find(|variant| variant.weight > 0.0)
+}
@@ -0,0 +1,125 @@
+// RSS 2.0 and Atom 1.0 documents built from a list of items
+// Both formats carry the same fields, so one Feed renders as either. Text is
+// escaped for XML, with characters XML 1.0 cannot represent dropped, and
+// timestamps are Unix milliseconds rendered as RFC 822 (RSS) or RFC 3339
+// (Atom) dates.
+
+use crate::dates;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub struct Feed {
+    pub title: String,
+    // The site the feed belongs to
+    pub link: String,
+    // Where the feed itself is served from
+    pub self_link: String,
+    pub description: String,
+    pub author: String,
+    pub items: Vec<Item>,
+}
+
+pub struct Item {
+    pub title: String,
+    // Doubles as the item's permanent ID
+    pub link: String,
+    pub summary: String,
+    pub author: Option<String>,
+    pub published_ms: u64,
+}
+
+impl Feed {
+    // The newest item's date, or the epoch for an empty feed
+    fn updated_ms(&self) -> u64 {
+        self.items.iter().map(|item| item.published_ms).max().unwrap_or(0)
+    }
+}
+
+// Escapes markup characters and drops the control characters (and
+// non-characters U+FFFE and U+FFFF) XML 1.0 does not allow even as references
+pub fn escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn rss(feed: &Feed) -> String {
+    let items: String = feed
+        .items
+        .iter()
+        .map(|item| {
+            // RSS wants an e-mail address in <author>; names go in dc:creator
+            let author = item
+                .author
+                .as_ref()
+                .map(|author| format!("\n      <dc:creator>{}</dc:creator>", escape(author)))
+                .unwrap_or_default();
+            format!(
+                "\n    <item>\n      <title>{}</title>\n      <link>{}</link>\n      <guid isPermaLink=\"true\">{}</guid>\n      <description>{}</description>{}\n      <pubDate>{}</pubDate>\n    </item>",
+                escape(&item.title),
+                escape(&item.link),
+                escape(&item.link),
+                escape(&item.summary),
+                author,
+                dates::rfc822(item.published_ms)
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n  <channel>\n    <title>{}</title>\n    <link>{}</link>\n    <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n    <description>{}</description>\n    <lastBuildDate>{}</lastBuildDate>{}\n  </channel>\n</rss>\n",
+        escape(&feed.title),
+        escape(&feed.link),
+        escape(&feed.self_link),
+        escape(&feed.description),
+        dates::rfc822(feed.updated_ms()),
+        items
+    )
+}
+
+pub fn atom(feed: &Feed) -> String {
+    let entries: String = feed
+        .items
+        .iter()
+        .map(|item| {
+            let author = item
+                .author
+                .as_ref()
+                .map(|author| format!("\n    <author><name>{}</name></author>", escape(author)))
+                .unwrap_or_default();
+            let published = dates::rfc3339(item.published_ms);
+            format!(
+                "\n  <entry>\n    <title>{}</title>\n    <link href=\"{}\"/>\n    <id>{}</id>\n    <published>{}</published>\n    <updated>{}</updated>{}\n    <summary>{}</summary>\n  </entry>",
+                escape(&item.title),
+                escape(&item.link),
+                escape(&item.link),
+                published,
+                published,
+                author,
+                escape(&item.summary)
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n  <title>{}</title>\n  <subtitle>{}</subtitle>\n  <link href=\"{}\"/>\n  <link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n  <id>{}</id>\n  <updated>{}</updated>\n  <author><name>{}</name></author>{}\n</feed>\n",
+        escape(&feed.title),
+        escape(&feed.description),
+        escape(&feed.link),
+        escape(&feed.self_link),
+        escape(&feed.self_link),
+        dates::rfc3339(feed.updated_ms()),
+        escape(&feed.author),
+        entries
+    )
+}
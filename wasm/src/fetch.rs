@@ -0,0 +1,66 @@
+// Outbound GET requests through the host
+// WASM imports cannot wait for a response, so fetches work like KV reads (see
+// kv.rs): a URL the host has not fetched for this request makes get() return
+// Pending, and the host fetches it once the handler returns and replays the
+// request. Redirects are returned rather than followed, and the host gives up
+// on origins that take longer than its timeout. Callers go through
+// breaker.rs, which stops sending requests to origins that keep failing.
+
+use crate::kv::Pending;
+
+// Larger bodies are refused by the host rather than copied in
+#[cfg(target_arch = "wasm32")]
+const MAX_BYTES: usize = 5 * 1024 * 1024;
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+#[cfg(target_arch = "wasm32")]
+mod imports {
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        // 1 = response, 0 = network error (message in the buffer), -1 = pending.
+        // Responses arrive as "<status>\n<content type>\n<body>".
+        pub fn host_fetch(url_ptr: *const u8, url_len: usize, max_bytes: usize, out_ptr: *mut *mut u8, out_len: *mut usize) -> i32;
+    }
+}
+
+// Ok(Err(message)) when the origin could not be reached
+#[cfg(target_arch = "wasm32")]
+pub fn get(url: &str) -> Result<Result<Response, String>, Pending> {
+    let mut out_ptr = std::ptr::null_mut();
+    let mut out_len = 0;
+    let result = unsafe { imports::host_fetch(url.as_ptr(), url.len(), MAX_BYTES, &mut out_ptr, &mut out_len) };
+    if result < 0 {
+        crate::kv::mark_pending();
+        return Err(Pending);
+    }
+    let bytes = crate::ffi::reclaim(out_ptr, out_len).unwrap_or_default();
+    if result == 0 {
+        return Ok(Err(String::from_utf8_lossy(&bytes).into_owned()));
+    }
+    Ok(parse(bytes))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn parse(mut bytes: Vec<u8>) -> Result<Response, String> {
+    let mut newlines = bytes.iter().enumerate().filter(|&(_, &b)| b == b'\n').map(|(index, _)| index);
+    let (Some(first), Some(second)) = (newlines.next(), newlines.next()) else {
+        return Err("Host returned a malformed response".to_string());
+    };
+    let Some(status) = std::str::from_utf8(&bytes[..first]).ok().and_then(|status| status.parse().ok()) else {
+        return Err("Host returned a malformed status".to_string());
+    };
+    let content_type = String::from_utf8_lossy(&bytes[first + 1..second]).into_owned();
+    let body = bytes.split_off(second + 1);
+    Ok(Response { status, content_type, body })
+}
+
+// Native builds have no network import
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get(_url: &str) -> Result<Result<Response, String>, Pending> {
+    Ok(Err("Outbound fetch needs the Worker host".to_string()))
+}
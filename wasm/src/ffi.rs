@@ -0,0 +1,268 @@
+// Byte-slice boundary shared by every export
+// Inputs arrive as (pointer, length) pairs and outputs leave as a pointer to
+// a 4 byte little-endian length followed by that many bytes, so nothing
+// relies on NUL terminators and text is never silently re-encoded: callers
+// decide what invalid UTF-8 means for them.
+//
+// Every buffer handed to the host is recorded until it comes back, so a
+// second free, a pointer this module never issued or a length that does not
+// match is logged and ignored instead of corrupting the allocator.
+
+use crate::arena;
+use crate::host;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    // From alloc, length in bytes
+    Bytes,
+    // From alloc_f64, length in values
+    Floats,
+    // From output, length in bytes including the prefix
+    Output,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Bytes => "alloc",
+            Kind::Floats => "alloc_f64",
+            Kind::Output => "output",
+        }
+    }
+}
+
+thread_local! {
+    static OUTSTANDING: RefCell<HashMap<usize, (Kind, usize)>> = RefCell::new(HashMap::new());
+    static REJECTED_FREES: Cell<u64> = const { Cell::new(0) };
+}
+
+// The registry outlives the request that happens to grow it
+fn register(ptr: usize, kind: Kind, len: usize) {
+    arena::outside(|| OUTSTANDING.with(|outstanding| outstanding.borrow_mut().insert(ptr, (kind, len))));
+}
+
+// True when `ptr` was issued as `kind` with exactly `len`, which also
+// forgets it; anything else is reported and leaves the registry untouched
+fn release(ptr: usize, kind: Kind, len: usize, export: &str) -> bool {
+    let problem = OUTSTANDING.with(|outstanding| {
+        let mut outstanding = outstanding.borrow_mut();
+        match outstanding.get(&ptr) {
+            Some(&(issued_kind, issued_len)) if issued_kind == kind && issued_len == len => {
+                outstanding.remove(&ptr);
+                None
+            }
+            Some(&(issued_kind, issued_len)) => Some(format!(
+                "{}: pointer {:#x} came from {} with length {}, not {}",
+                export,
+                ptr,
+                issued_kind.name(),
+                issued_len,
+                len
+            )),
+            None => Some(format!("{}: pointer {:#x} is not outstanding (double free or foreign pointer)", export, ptr)),
+        }
+    });
+    match problem {
+        None => true,
+        Some(message) => {
+            REJECTED_FREES.with(|rejected| rejected.set(rejected.get() + 1));
+            host::log(&message);
+            false
+        }
+    }
+}
+
+// Empty for a null pointer or zero length, so the host can skip allocating
+pub unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if ptr.is_null() || len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr, len)
+    }
+}
+
+pub unsafe fn text<'a>(ptr: *const u8, len: usize) -> Result<&'a str, std::str::Utf8Error> {
+    std::str::from_utf8(bytes(ptr, len))
+}
+
+// Length-prefixed copy of `data` for the host; release it with free_bytes
+pub fn output(data: &[u8]) -> *mut u8 {
+    let mut buffer = Vec::with_capacity(data.len() + 4);
+    buffer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(data);
+    let len = buffer.len();
+    let ptr = Box::into_raw(buffer.into_boxed_slice()) as *mut u8;
+    register(ptr as usize, Kind::Output, len);
+    ptr
+}
+
+// Takes ownership of a buffer the host filled after calling alloc
+#[cfg(target_arch = "wasm32")]
+pub fn reclaim(ptr: *mut u8, len: usize) -> Option<Vec<u8>> {
+    if ptr.is_null() || len == 0 || !release(ptr as usize, Kind::Bytes, len, "reclaim") {
+        return None;
+    }
+    Some(unsafe { Vec::from_raw_parts(ptr, len, len) })
+}
+
+// Free a buffer returned by output()
+#[no_mangle]
+pub extern "C" fn free_bytes(ptr: *mut u8) {
+    if ptr.is_null() {
+        return;
+    }
+    // The registry, not the prefix in memory, is trusted for the length
+    let len = OUTSTANDING.with(|outstanding| match outstanding.borrow().get(&(ptr as usize)) {
+        Some(&(Kind::Output, len)) => len,
+        _ => 0,
+    });
+    if release(ptr as usize, Kind::Output, len, "free_bytes") {
+        drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) });
+    }
+}
+
+// Allocate a buffer in WASM memory for the host to copy request data into.
+// Zero-length buffers are never needed, so they come back as null.
+#[no_mangle]
+pub extern "C" fn alloc(len: usize) -> *mut u8 {
+    if len == 0 {
+        return std::ptr::null_mut();
+    }
+    let mut buffer = Vec::<u8>::with_capacity(len);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    register(ptr as usize, Kind::Bytes, len);
+    ptr
+}
+
+// Release a buffer previously returned by alloc
+#[no_mangle]
+pub extern "C" fn dealloc(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() && len > 0 && release(ptr as usize, Kind::Bytes, len, "dealloc") {
+        drop(unsafe { Vec::from_raw_parts(ptr, 0, len) });
+    }
+}
+
+// Allocate an 8-byte aligned buffer of `count` f64 values for Float64Array views
+#[no_mangle]
+pub extern "C" fn alloc_f64(count: usize) -> *mut f64 {
+    if count == 0 {
+        return std::ptr::null_mut();
+    }
+    let mut buffer = Vec::<f64>::with_capacity(count);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    register(ptr as usize, Kind::Floats, count);
+    ptr
+}
+
+// Release a buffer previously returned by alloc_f64
+#[no_mangle]
+pub extern "C" fn dealloc_f64(ptr: *mut f64, count: usize) {
+    if !ptr.is_null() && count > 0 && release(ptr as usize, Kind::Floats, count, "dealloc_f64") {
+        drop(unsafe { Vec::from_raw_parts(ptr, 0, count) });
+    }
+}
+
+// Buffers the host still holds, for spotting leaks in the JS glue:
+// {"outstanding":n,"bytes":n,"by_kind":{...},"rejected_frees":n,"arena":{...}}.
+// The report's own buffer is not included; free it with free_bytes.
+#[no_mangle]
+pub extern "C" fn leak_report() -> *mut u8 {
+    let report = OUTSTANDING.with(|outstanding| {
+        let outstanding = outstanding.borrow();
+        let mut bytes = 0;
+        let mut counts = [0usize; 3];
+        for &(kind, len) in outstanding.values() {
+            let (index, size) = match kind {
+                Kind::Bytes => (0, len),
+                Kind::Floats => (1, len * 8),
+                Kind::Output => (2, len),
+            };
+            counts[index] += 1;
+            bytes += size;
+        }
+        format!(
+            r#"{{"outstanding":{},"bytes":{},"by_kind":{{"alloc":{},"alloc_f64":{},"output":{}}},"rejected_frees":{},"arena":{}}}"#,
+            outstanding.len(),
+            bytes,
+            counts[0],
+            counts[1],
+            counts[2],
+            REJECTED_FREES.with(|rejected| rejected.get()),
+            arena::stats_json()
+        )
+    });
+    output(report.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected() -> u64 {
+        REJECTED_FREES.with(|rejected| rejected.get())
+    }
+
+    fn is_outstanding(ptr: usize) -> bool {
+        OUTSTANDING.with(|outstanding| outstanding.borrow().contains_key(&ptr))
+    }
+
+    #[test]
+    fn release_forgets_a_matching_buffer() {
+        let ptr = alloc(16) as usize;
+        assert!(is_outstanding(ptr));
+        assert!(release(ptr, Kind::Bytes, 16, "test"));
+        assert!(!is_outstanding(ptr));
+        drop(unsafe { Vec::from_raw_parts(ptr as *mut u8, 0, 16) });
+    }
+
+    #[test]
+    fn release_refuses_the_wrong_kind_or_length() {
+        let before = rejected();
+        let ptr = alloc(16) as usize;
+        assert!(!release(ptr, Kind::Floats, 16, "test"));
+        assert!(!release(ptr, Kind::Output, 16, "test"));
+        assert!(!release(ptr, Kind::Bytes, 8, "test"));
+        assert!(is_outstanding(ptr));
+        assert_eq!(rejected(), before + 3);
+        dealloc(ptr as *mut u8, 16);
+        assert!(!is_outstanding(ptr));
+    }
+
+    #[test]
+    fn double_and_foreign_frees_are_rejected() {
+        let before = rejected();
+        let ptr = alloc_f64(4);
+        dealloc_f64(ptr, 4);
+        dealloc_f64(ptr, 4);
+        let foreign = [0u8; 8];
+        dealloc(foreign.as_ptr() as *mut u8, 8);
+        free_bytes(foreign.as_ptr() as *mut u8);
+        assert_eq!(rejected(), before + 3);
+    }
+
+    #[test]
+    fn outputs_are_length_prefixed_and_freed_once() {
+        let before = rejected();
+        let ptr = output(b"hello");
+        let len = unsafe { u32::from_le_bytes(*(ptr as *const [u8; 4])) };
+        assert_eq!(len, 5);
+        assert_eq!(unsafe { bytes(ptr.add(4), 5) }, b"hello");
+        free_bytes(ptr);
+        assert!(!is_outstanding(ptr as usize));
+        free_bytes(ptr);
+        assert_eq!(rejected(), before + 1);
+    }
+
+    #[test]
+    fn null_and_empty_buffers_are_never_issued() {
+        assert!(alloc(0).is_null());
+        assert!(alloc_f64(0).is_null());
+        assert!(unsafe { bytes(std::ptr::null(), 4) }.is_empty());
+        free_bytes(std::ptr::null_mut());
+        dealloc(std::ptr::null_mut(), 4);
+    }
+}
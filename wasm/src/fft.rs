@@ -0,0 +1,107 @@
+// Radix-2 fast Fourier transform over f64 samples
+// Real and imaginary parts are kept in separate slices so the raw exports can
+// work directly on Float64Array views of linear memory.
+
+use std::f64::consts::PI;
+
+pub const MAX_SIZE: usize = 1 << 16;
+
+// In-place iterative Cooley-Tukey transform; the length must be a power of
+// two. The inverse transform is scaled by 1/n so it undoes the forward one.
+pub fn transform(re: &mut [f64], im: &mut [f64], inverse: bool) {
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n);
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        for value in re.iter_mut().chain(im.iter_mut()) {
+            *value /= n as f64;
+        }
+    }
+}
+
+// One-sided amplitude spectrum of real samples, zero-padded to a power of
+// two: n/2 + 1 bins where a unit sine wave peaks at 1.0. A Hann window can
+// be applied first to reduce leakage between bins.
+pub fn amplitudes(samples: &[f64], hann: bool) -> Vec<f64> {
+    let n = samples.len().next_power_of_two().max(2);
+    let mut re = vec![0.0; n];
+    let mut im = vec![0.0; n];
+    for (i, &sample) in samples.iter().enumerate() {
+        let weight = if hann && samples.len() > 1 {
+            0.5 - 0.5 * (2.0 * PI * i as f64 / (samples.len() - 1) as f64).cos()
+        } else {
+            1.0
+        };
+        re[i] = sample * weight;
+    }
+    transform(&mut re, &mut im, false);
+
+    (0..=n / 2)
+        .map(|k| {
+            let magnitude = re[k].hypot(im[k]) / n as f64;
+            if k == 0 || k == n / 2 { magnitude } else { 2.0 * magnitude }
+        })
+        .collect()
+}
+
+fn slice_mut<'a>(ptr: *mut f64, len: usize) -> &'a mut [f64] {
+    unsafe { std::slice::from_raw_parts_mut(ptr, len) }
+}
+
+// Transform f64 buffers (from alloc_f64) in place; returns 0 unless both
+// pointers are set and len is a power of two up to MAX_SIZE
+#[no_mangle]
+pub extern "C" fn fft_in_place(re_ptr: *mut f64, im_ptr: *mut f64, len: usize, inverse: i32) -> i32 {
+    if re_ptr.is_null() || im_ptr.is_null() || !len.is_power_of_two() || len > MAX_SIZE {
+        return 0;
+    }
+    transform(slice_mut(re_ptr, len), slice_mut(im_ptr, len), inverse != 0);
+    1
+}
+
+// Write the amplitude spectrum of `len` samples to `out_ptr`, which must have
+// room for next_power_of_two(len) / 2 + 1 values; returns the number written
+#[no_mangle]
+pub extern "C" fn fft_amplitudes(samples_ptr: *const f64, len: usize, out_ptr: *mut f64, hann: i32) -> usize {
+    if samples_ptr.is_null() || out_ptr.is_null() || len == 0 || len > MAX_SIZE {
+        return 0;
+    }
+    let samples = unsafe { std::slice::from_raw_parts(samples_ptr, len) };
+    let spectrum = amplitudes(samples, hann != 0);
+    slice_mut(out_ptr, spectrum.len()).copy_from_slice(&spectrum);
+    spectrum.len()
+}
@@ -0,0 +1,158 @@
+// Feature flags with percentage rollouts and attribute targeting
+// A flag is defined under flags in CONFIG or, to change it without touching
+// CONFIG, as the same JSON in KV under "flag:<name>", which takes precedence.
+// Evaluating one for a user tries its rules in order; the first whose
+// attribute condition holds decides, and a rule's rollout (default 100)
+// serves the "on" value to that share of its users and "off" to the rest.
+// Users no rule matches get the flag's own rollout. A user's bucket is the
+// experiments hash of the flag and user, so widening a rollout only adds
+// users, and a disabled flag is "off" for everyone.
+//
+// {"enabled":true,"on":"blue","off":"grey","rollout":10,
+//  "rules":[{"name":"staff","attribute":"plan","in":["staff"]}]}
+
+use crate::experiments;
+use crate::json::{self, Value};
+use crate::kv::{self, Pending};
+
+#[derive(Clone)]
+pub struct Rule {
+    pub name: String,
+    pub attribute: String,
+    pub values: Vec<String>,
+    // Matches users whose attribute is not one of the values, or is absent
+    pub negate: bool,
+    pub rollout: f64,
+}
+
+#[derive(Clone)]
+pub struct Flag {
+    pub name: String,
+    pub enabled: bool,
+    // Served values, as JSON
+    pub on: String,
+    pub off: String,
+    pub rollout: f64,
+    pub rules: Vec<Rule>,
+    // "config" or "kv"
+    pub source: &'static str,
+}
+
+pub struct Evaluation {
+    pub on: bool,
+    // As JSON
+    pub value: String,
+    // "disabled", "rule", "rollout" or "undefined"
+    pub reason: &'static str,
+    pub rule: Option<String>,
+    // The user's point in [0, 100), compared with rollouts
+    pub bucket: f64,
+}
+
+// Flag names end up in KV keys and log lines
+pub fn is_valid_name(name: &str) -> bool {
+    experiments::is_valid_name(name)
+}
+
+fn rollout(value: Value, label: &str) -> Result<f64, String> {
+    match value {
+        Value::Number(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!("Rollout of {} must be a percentage from 0 to 100", label)),
+    }
+}
+
+fn rule(flag: &str, index: usize, rule: Value) -> Result<Rule, String> {
+    let label = format!("rule {} of flag {}", index + 1, flag);
+    let Value::Object(members) = rule else {
+        return Err(format!("{} must be an object", label));
+    };
+    let mut parsed = Rule { name: format!("rule-{}", index + 1), attribute: String::new(), values: Vec::new(), negate: false, rollout: 100.0 };
+    let mut has_values = false;
+    for (key, value) in members {
+        match (key.as_str(), value) {
+            ("name", Value::String(name)) if !name.is_empty() => parsed.name = name,
+            ("attribute", Value::String(attribute)) if !attribute.is_empty() => parsed.attribute = attribute,
+            (condition @ ("in" | "not_in"), Value::Array(items)) => {
+                parsed.values = items
+                    .into_iter()
+                    .map(|item| match item {
+                        Value::String(item) => Ok(item),
+                        Value::Number(item) => Ok(item.to_string()),
+                        _ => Err(format!("Values of {} must be strings or numbers", label)),
+                    })
+                    .collect::<Result<_, _>>()?;
+                parsed.negate = condition == "not_in";
+                has_values = true;
+            }
+            ("rollout", value) => parsed.rollout = rollout(value, &label)?,
+            (key, _) => return Err(format!("Unknown or invalid {} in {}", key, label)),
+        }
+    }
+    if parsed.attribute.is_empty() || !has_values {
+        return Err(format!("{} needs an attribute and a list of values under in or not_in", label));
+    }
+    Ok(parsed)
+}
+
+pub fn parse(name: &str, definition: Value) -> Result<Flag, String> {
+    if !is_valid_name(name) {
+        return Err(format!("Flag name '{}' must be 1 to 64 letters, digits, '-' or '_'", name));
+    }
+    let Value::Object(members) = definition else {
+        return Err(format!("Flag {} must be an object", name));
+    };
+    let mut flag = Flag { name: name.to_string(), enabled: true, on: "true".to_string(), off: "false".to_string(), rollout: 100.0, rules: Vec::new(), source: "config" };
+    for (key, value) in members {
+        match (key.as_str(), value) {
+            ("enabled", Value::Bool(enabled)) => flag.enabled = enabled,
+            ("on", value) => flag.on = value.to_json(),
+            ("off", value) => flag.off = value.to_json(),
+            ("rollout", value) => flag.rollout = rollout(value, &format!("flag {}", name))?,
+            ("rules", Value::Array(rules)) => {
+                flag.rules = rules.into_iter().enumerate().map(|(index, definition)| rule(name, index, definition)).collect::<Result<_, _>>()?;
+            }
+            (key, _) => return Err(format!("Unknown or invalid {} in flag {}", key, name)),
+        }
+    }
+    Ok(flag)
+}
+
+// The flag as KV defines it, or else as configured
+pub fn find(configured: &[Flag], name: &str) -> Result<Result<Option<Flag>, String>, Pending> {
+    let Some(stored) = kv::get(&format!("flag:{}", name))? else {
+        return Ok(Ok(configured.iter().find(|flag| flag.name == name).cloned()));
+    };
+    Ok(json::parse(&stored)
+        .and_then(|definition| parse(name, definition))
+        .map(|flag| Some(Flag { source: "kv", ..flag }))
+        .map_err(|reason| format!("Flag {} in KV is invalid: {}", name, reason)))
+}
+
+// `attributes` are the user's, such as country or plan
+pub fn evaluate(flag: &Flag, user: &str, attributes: &[(String, String)]) -> Evaluation {
+    let bucket = experiments::position(&flag.name, user) * 100.0;
+    let serve = |on: bool, reason, rule: Option<&String>| Evaluation {
+        on,
+        value: if on { flag.on.clone() } else { flag.off.clone() },
+        reason,
+        rule: rule.cloned(),
+        bucket,
+    };
+    if !flag.enabled {
+        return serve(false, "disabled", None);
+    }
+    let attribute = |name: &str| attributes.iter().find(|(attribute, _)| attribute == name).map(|(_, value)| value.as_str());
+    for rule in &flag.rules {
+        let listed = attribute(&rule.attribute).is_some_and(|value| rule.values.iter().any(|listed| listed == value));
+        if listed != rule.negate {
+            return serve(bucket < rule.rollout, "rule", Some(&rule.name));
+        }
+    }
+    serve(bucket < flag.rollout, "rollout", None)
+}
+
+// A flag nobody has defined is off, with the default "off" value
+pub fn undefined(name: &str, user: &str) -> Evaluation {
+    let bucket = experiments::position(name, user) * 100.0;
+    Evaluation { on: false, value: "false".to_string(), reason: "undefined", rule: None, bucket }
+}
@@ -0,0 +1,258 @@
+// Locale-aware number and currency formatting, after the CLDR patterns
+// Values are formatted from their decimal digits rather than through a
+// float, so "0.1" or a 30-digit amount come out exactly; rounding is half
+// away from zero, as in Intl.NumberFormat. Each locale carries its
+// separators, grouping sizes (Indian English groups lakhs and crores),
+// the minimum grouping digits that keep "1234" ungrouped in Polish and
+// Spanish, and where its currency symbol goes.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Longest value, and largest exponent, accepted; enough for any real amount
+const MAX_DIGITS: usize = 100;
+const MAX_EXPONENT: i32 = 100;
+pub const MAX_DECIMALS: usize = 20;
+
+const NBSP: &str = "\u{a0}";
+
+pub struct Locale {
+    pub tag: &'static str,
+    decimal: &'static str,
+    group: &'static str,
+    minus: &'static str,
+    // Size of the group nearest the decimal separator, then of the others
+    primary_group: usize,
+    secondary_group: usize,
+    // Integer digits needed beyond the primary group before grouping starts
+    min_grouping: usize,
+    pub currency: &'static str,
+    currency_before: bool,
+    // Whether a symbol such as "€" is spaced from the digits
+    currency_spaced: bool,
+}
+
+const fn locale(tag: &'static str, decimal: &'static str, group: &'static str, currency: &'static str, currency_before: bool) -> Locale {
+    Locale {
+        tag,
+        decimal,
+        group,
+        minus: "-",
+        primary_group: 3,
+        secondary_group: 3,
+        min_grouping: 1,
+        currency,
+        currency_before,
+        currency_spaced: !currency_before,
+    }
+}
+
+// The first locale of each language is the one a bare language tag picks
+pub const LOCALES: [Locale; 16] = [
+    locale("en-US", ".", ",", "USD", true),
+    locale("en-GB", ".", ",", "GBP", true),
+    Locale { secondary_group: 2, ..locale("en-IN", ".", ",", "INR", true) },
+    Locale { min_grouping: 2, ..locale("pl-PL", ",", NBSP, "PLN", false) },
+    locale("de-DE", ",", ".", "EUR", false),
+    Locale { currency_spaced: true, ..locale("de-AT", ",", NBSP, "EUR", true) },
+    locale("de-CH", ".", "’", "CHF", true),
+    locale("fr-FR", ",", "\u{202f}", "EUR", false),
+    locale("fr-CH", ",", "\u{202f}", "CHF", false),
+    Locale { min_grouping: 2, ..locale("es-ES", ",", ".", "EUR", false) },
+    locale("es-MX", ".", ",", "MXN", true),
+    locale("it-IT", ",", ".", "EUR", false),
+    Locale { currency_spaced: true, ..locale("nl-NL", ",", ".", "EUR", true) },
+    Locale { currency_spaced: true, ..locale("pt-BR", ",", ".", "BRL", true) },
+    Locale { minus: "\u{2212}", ..locale("sv-SE", ",", NBSP, "SEK", false) },
+    locale("ja-JP", ".", ",", "JPY", true),
+];
+
+pub struct Currency {
+    pub code: String,
+    symbol: &'static str,
+    // Symbols such as "$" or "kr" are shared by several currencies, so other
+    // locales show the ISO code instead
+    ambiguous: bool,
+    pub digits: usize,
+}
+
+const CURRENCIES: [(&str, &str, bool, usize); 14] = [
+    ("USD", "$", true, 2),
+    ("EUR", "€", false, 2),
+    ("GBP", "£", false, 2),
+    ("JPY", "¥", false, 0),
+    ("INR", "₹", false, 2),
+    ("PLN", "zł", true, 2),
+    ("CHF", "CHF", false, 2),
+    ("MXN", "$", true, 2),
+    ("BRL", "R$", false, 2),
+    ("SEK", "kr", true, 2),
+    ("CZK", "Kč", false, 2),
+    ("KRW", "₩", false, 0),
+    ("KWD", "KWD", false, 3),
+    ("CNY", "¥", true, 2),
+];
+
+// Exact tags first, then the first locale of the tag's language
+pub fn find_locale(tag: &str) -> Option<&'static Locale> {
+    let tag = tag.replace('_', "-");
+    LOCALES.iter().find(|locale| locale.tag.eq_ignore_ascii_case(&tag)).or_else(|| {
+        let language = tag.split('-').next().unwrap_or("");
+        LOCALES.iter().find(|locale| locale.tag.split('-').next().is_some_and(|own| own.eq_ignore_ascii_case(language)))
+    })
+}
+
+// Any three-letter ISO 4217 code; those without an entry show the code and
+// two decimals
+pub fn find_currency(code: &str) -> Option<Currency> {
+    if code.len() != 3 || !code.bytes().all(|byte| byte.is_ascii_alphabetic()) {
+        return None;
+    }
+    let code = code.to_ascii_uppercase();
+    let (symbol, ambiguous, digits) = CURRENCIES
+        .iter()
+        .find(|(known, ..)| *known == code)
+        .map_or(("", true, 2), |&(_, symbol, ambiguous, digits)| (symbol, ambiguous, digits));
+    Some(Currency { code, symbol, ambiguous, digits })
+}
+
+pub struct Number {
+    negative: bool,
+    // Decimal digits, as values 0-9, of the integer part and the fraction
+    integer: Vec<u8>,
+    fraction: Vec<u8>,
+}
+
+// "-1234.5", "+.5" or "1.2e6"
+pub fn parse(value: &str) -> Result<Number, String> {
+    let invalid = || format!("'{}' is not a decimal number", value);
+    let (mantissa, exponent) = match value.find(['e', 'E']) {
+        Some(index) => {
+            let exponent: i32 = value[index + 1..].parse().map_err(|_| invalid())?;
+            if exponent.abs() > MAX_EXPONENT {
+                return Err(format!("Exponent must be between -{0} and {0}", MAX_EXPONENT));
+            }
+            (&value[..index], exponent)
+        }
+        None => (value, 0),
+    };
+    let (negative, unsigned) = match mantissa.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || !integer.bytes().chain(fraction.bytes()).all(|byte| byte.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if integer.len() + fraction.len() > MAX_DIGITS {
+        return Err(format!("Value must have at most {} digits", MAX_DIGITS));
+    }
+    // Moving the decimal point by the exponent
+    let mut digits: Vec<u8> = integer.bytes().chain(fraction.bytes()).map(|byte| byte - b'0').collect();
+    let mut point = integer.len() as i32 + exponent;
+    if point < 0 {
+        digits.splice(0..0, core::iter::repeat_n(0, (-point) as usize));
+        point = 0;
+    }
+    if point as usize > digits.len() {
+        digits.resize(point as usize, 0);
+    }
+    let fraction = digits.split_off(point as usize);
+    let first = digits.iter().position(|&digit| digit != 0).unwrap_or(digits.len());
+    Ok(Number { negative, integer: digits[first..].to_vec(), fraction })
+}
+
+impl Number {
+    // Rounds half away from zero to at most `max` decimals, then drops
+    // trailing zeros down to `min`
+    fn round(&self, min: usize, max: usize) -> (Vec<u8>, Vec<u8>) {
+        let mut integer = self.integer.clone();
+        let mut fraction = self.fraction.clone();
+        if fraction.len() > max {
+            let round_up = fraction[max] >= 5;
+            fraction.truncate(max);
+            if round_up {
+                let mut carry = true;
+                for digit in fraction.iter_mut().rev().chain(integer.iter_mut().rev()) {
+                    if *digit == 9 {
+                        *digit = 0;
+                    } else {
+                        *digit += 1;
+                        carry = false;
+                        break;
+                    }
+                }
+                if carry {
+                    integer.insert(0, 1);
+                }
+            }
+        }
+        while fraction.len() > min && fraction.last() == Some(&0) {
+            fraction.pop();
+        }
+        fraction.resize(fraction.len().max(min), 0);
+        (integer, fraction)
+    }
+}
+
+fn group(digits: &[u8], locale: &Locale) -> String {
+    let text: String = if digits.is_empty() { "0".to_string() } else { digits.iter().map(|&digit| char::from(b'0' + digit)).collect() };
+    if text.len() < locale.primary_group + locale.min_grouping {
+        return text;
+    }
+    let (head, tail) = text.split_at(text.len() - locale.primary_group);
+    let mut groups = Vec::new();
+    let mut end = head.len();
+    while end > 0 {
+        let start = end.saturating_sub(locale.secondary_group);
+        groups.push(&head[start..end]);
+        end = start;
+    }
+    groups.reverse();
+    groups.push(tail);
+    groups.join(locale.group)
+}
+
+// The digits with separators, and whether the rounded value is negative;
+// "-0.001" rounded to two places is plain zero
+fn digits(number: &Number, locale: &Locale, min: usize, max: usize) -> (String, bool) {
+    let (integer, fraction) = number.round(min, max);
+    let negative = number.negative && integer.iter().chain(&fraction).any(|&digit| digit != 0);
+    let mut text = group(&integer, locale);
+    if !fraction.is_empty() {
+        text.push_str(locale.decimal);
+        text.extend(fraction.iter().map(|&digit| char::from(b'0' + digit)));
+    }
+    (text, negative)
+}
+
+// Between `min` and `max` decimals, as Intl.NumberFormat's default of 0 to 3
+pub fn decimal(number: &Number, locale: &Locale, min: usize, max: usize) -> String {
+    match digits(number, locale, min, max) {
+        (text, true) => format!("{}{}", locale.minus, text),
+        (text, false) => text,
+    }
+}
+
+// `decimals` overrides the currency's minor unit digits
+pub fn currency(number: &Number, locale: &Locale, currency: &Currency, decimals: Option<usize>) -> String {
+    let places = decimals.unwrap_or(currency.digits);
+    let (text, negative) = digits(number, locale, places, places);
+    let symbol = if currency.symbol.is_empty() || currency.ambiguous && currency.code != locale.currency {
+        currency.code.as_str()
+    } else {
+        currency.symbol
+    };
+    // Letter symbols such as "CHF" are spaced from the digits everywhere
+    let spaced = locale.currency_spaced || symbol.chars().last().is_some_and(char::is_alphabetic);
+    let space = if spaced { NBSP } else { "" };
+    let sign = if negative { locale.minus } else { "" };
+    if locale.currency_before {
+        format!("{}{}{}{}", sign, symbol, space, text)
+    } else {
+        format!("{}{}{}{}", sign, text, space, symbol)
+    }
+}
@@ -0,0 +1,118 @@
+// Deterministic fake data for load testing and demos
+// Every generator draws from the seeded PRNG, so the same seed always
+// produces the same output.
+
+use crate::json;
+use crate::rand::Rng;
+
+const LOREM_WORDS: [&str; 48] = [
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit",
+    "sed", "do", "eiusmod", "tempor", "incididunt", "ut", "labore", "et",
+    "dolore", "magna", "aliqua", "enim", "ad", "minim", "veniam", "quis",
+    "nostrud", "exercitation", "ullamco", "laboris", "nisi", "aliquip", "ex", "ea",
+    "commodo", "consequat", "duis", "aute", "irure", "in", "reprehenderit", "voluptate",
+    "velit", "esse", "cillum", "fugiat", "nulla", "pariatur", "excepteur", "sint",
+];
+
+const FIRST_NAMES: [&str; 20] = [
+    "Anna", "Piotr", "Maria", "Jan", "Olivia", "Liam", "Emma", "Noah", "Zofia", "Jakub",
+    "Sofia", "Lucas", "Mia", "Leon", "Hanna", "Felix", "Chloe", "Mateo", "Ava", "Oskar",
+];
+
+const LAST_NAMES: [&str; 20] = [
+    "Nowak", "Kowalski", "Smith", "Johnson", "Garcia", "Müller", "Wiśniewski", "Brown",
+    "Martin", "Rossi", "Novak", "Jones", "Silva", "Kim", "Wójcik", "Schmidt", "Taylor",
+    "Dubois", "Moreau", "Lewandowski",
+];
+
+// Reserved for documentation (RFC 2606), so generated addresses never reach real inboxes
+const EMAIL_DOMAINS: [&str; 3] = ["example.com", "example.org", "example.net"];
+
+pub const KINDS: [&str; 5] = ["lorem", "names", "emails", "uuid", "json"];
+
+// Generate `count` items of the given kind, each rendered as a JSON value
+pub fn generate(kind: &str, count: usize, seed: u64) -> Option<Vec<String>> {
+    let mut rng = Rng::new(seed);
+    let item: fn(&mut Rng, usize) -> String = match kind {
+        "lorem" => |rng, _| quoted(&sentence(rng)),
+        "names" => |rng, _| quoted(&full_name(rng)),
+        "emails" => |rng, _| quoted(&email(rng)),
+        "uuid" => |rng, _| quoted(&uuid_v4(rng)),
+        "json" => record,
+        _ => return None,
+    };
+    Some((0..count).map(|index| item(&mut rng, index)).collect())
+}
+
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", json::escape(value))
+}
+
+pub fn sentence(rng: &mut Rng) -> String {
+    let length = rng.range(6, 14) as usize;
+    let words: Vec<&str> = (0..length).map(|_| *rng.choose(&LOREM_WORDS)).collect();
+    let mut text = words.join(" ");
+    if let Some(first) = text.get_mut(0..1) {
+        first.make_ascii_uppercase();
+    }
+    text.push('.');
+    text
+}
+
+pub fn full_name(rng: &mut Rng) -> String {
+    format!("{} {}", rng.choose(&FIRST_NAMES), rng.choose(&LAST_NAMES))
+}
+
+fn email(rng: &mut Rng) -> String {
+    let first = rng.choose(&FIRST_NAMES).to_lowercase();
+    let last = *rng.choose(&LAST_NAMES);
+    let last = ascii_fold(last);
+    let number = rng.below(100);
+    format!("{}.{}{}@{}", first, last, number, rng.choose(&EMAIL_DOMAINS))
+}
+
+// Random (version 4) UUID built from PRNG bytes
+pub fn uuid_v4(rng: &mut Rng) -> String {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+fn record(rng: &mut Rng, index: usize) -> String {
+    let first = *rng.choose(&FIRST_NAMES);
+    let last = *rng.choose(&LAST_NAMES);
+    let email = format!("{}.{}@{}", first.to_lowercase(), ascii_fold(last), rng.choose(&EMAIL_DOMAINS));
+    format!(
+        r#"{{"id":{},"uuid":"{}","name":"{} {}","email":"{}","age":{},"active":{}}}"#,
+        index + 1,
+        uuid_v4(rng),
+        json::escape(first),
+        json::escape(last),
+        json::escape(&email),
+        rng.range(18, 90),
+        rng.below(2) == 1
+    )
+}
+
+// Lowercase ASCII version of a name for use in email local parts
+fn ascii_fold(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'ü' => 'u',
+            'ś' => 's',
+            'ó' => 'o',
+            'ń' => 'n',
+            'ł' => 'l',
+            'ą' => 'a',
+            'ę' => 'e',
+            'ć' => 'c',
+            'ż' | 'ź' => 'z',
+            c => c.to_ascii_lowercase(),
+        })
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
@@ -0,0 +1,169 @@
+// Directed graphs from JSON adjacency lists and shortest paths
+// {"a": ["b", "c"]} lists unweighted edges and is searched breadth-first;
+// {"a": {"b": 2.5}} gives edge weights and is searched with Dijkstra.
+
+use crate::json::Value;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+const MAX_NODES: usize = 10_000;
+
+pub struct Graph {
+    pub names: Vec<String>,
+    index: HashMap<String, usize>,
+    edges: Vec<Vec<(usize, f64)>>,
+    pub weighted: bool,
+}
+
+pub struct Path {
+    pub distance: f64,
+    pub nodes: Vec<usize>,
+}
+
+impl Graph {
+    pub fn from_json(value: &Value) -> Result<Graph, String> {
+        let Value::Object(members) = value else {
+            return Err("Graph must be a JSON object mapping each node to its neighbours".to_string());
+        };
+        let mut graph = Graph { names: Vec::new(), index: HashMap::new(), edges: Vec::new(), weighted: false };
+        for (name, neighbours) in members {
+            let from = graph.node(name)?;
+            match neighbours {
+                Value::Array(targets) => {
+                    for target in targets {
+                        let Value::String(target) = target else {
+                            return Err(format!("Neighbours of '{}' must be node names", name));
+                        };
+                        let to = graph.node(target)?;
+                        graph.edges[from].push((to, 1.0));
+                    }
+                }
+                Value::Object(targets) => {
+                    graph.weighted = true;
+                    for (target, weight) in targets {
+                        let weight = match weight {
+                            Value::Number(weight) if *weight >= 0.0 && weight.is_finite() => *weight,
+                            _ => return Err(format!("Edge {} -> {} needs a non-negative number weight", name, target)),
+                        };
+                        let to = graph.node(target)?;
+                        graph.edges[from].push((to, weight));
+                    }
+                }
+                _ => return Err(format!("Neighbours of '{}' must be an array or an object of weights", name)),
+            }
+        }
+        Ok(graph)
+    }
+
+    // Index of a node, adding it on first sight
+    fn node(&mut self, name: &str) -> Result<usize, String> {
+        if let Some(&index) = self.index.get(name) {
+            return Ok(index);
+        }
+        if self.names.len() == MAX_NODES {
+            return Err(format!("Graphs are limited to {} nodes", MAX_NODES));
+        }
+        self.index.insert(name.to_string(), self.names.len());
+        self.names.push(name.to_string());
+        self.edges.push(Vec::new());
+        Ok(self.names.len() - 1)
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    pub fn shortest_path(&self, source: usize, target: usize) -> Option<Path> {
+        if self.weighted {
+            self.dijkstra(source, target)
+        } else {
+            self.bfs(source, target)
+        }
+    }
+
+    // Fewest edges, ignoring weights
+    pub fn bfs(&self, source: usize, target: usize) -> Option<Path> {
+        let mut previous = vec![None; self.names.len()];
+        let mut seen = vec![false; self.names.len()];
+        let mut queue = VecDeque::from([source]);
+        seen[source] = true;
+        while let Some(node) = queue.pop_front() {
+            if node == target {
+                let nodes = walk_back(&previous, target);
+                return Some(Path { distance: (nodes.len() - 1) as f64, nodes });
+            }
+            for &(next, _) in &self.edges[node] {
+                if !seen[next] {
+                    seen[next] = true;
+                    previous[next] = Some(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn dijkstra(&self, source: usize, target: usize) -> Option<Path> {
+        let mut distance = vec![f64::INFINITY; self.names.len()];
+        let mut previous = vec![None; self.names.len()];
+        let mut heap = BinaryHeap::from([Visit { distance: 0.0, node: source }]);
+        distance[source] = 0.0;
+        while let Some(Visit { distance: reached, node }) = heap.pop() {
+            if node == target {
+                return Some(Path { distance: reached, nodes: walk_back(&previous, target) });
+            }
+            // Skip stale heap entries superseded by a shorter route
+            if reached > distance[node] {
+                continue;
+            }
+            for &(next, weight) in &self.edges[node] {
+                let candidate = reached + weight;
+                if candidate < distance[next] {
+                    distance[next] = candidate;
+                    previous[next] = Some(node);
+                    heap.push(Visit { distance: candidate, node: next });
+                }
+            }
+        }
+        None
+    }
+}
+
+fn walk_back(previous: &[Option<usize>], target: usize) -> Vec<usize> {
+    let mut nodes = vec![target];
+    while let Some(node) = previous[*nodes.last().unwrap()] {
+        nodes.push(node);
+    }
+    nodes.reverse();
+    nodes
+}
+
+// Min-heap entry ordered by distance
+struct Visit {
+    distance: f64,
+    node: usize,
+}
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Visit {}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance).then(other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,305 @@
+// Parser for a restricted subset of GraphQL queries
+// A document is a single query: an optional `query` keyword and operation
+// name, then one selection set of fields with optional aliases and literal
+// arguments, e.g. `{ sum: add(a: 2, b: 3) factorial(n: 5) status }`.
+// Variables, fragments, directives, nested selections and mutations are
+// rejected with a message saying so. Commas and `#` comments are ignored, as
+// in full GraphQL.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Keeps a query from fanning out into unbounded work
+pub const MAX_FIELDS: usize = 32;
+
+#[derive(Clone, PartialEq)]
+pub enum Argument {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    Null,
+}
+
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, Argument)>,
+}
+
+impl Field {
+    // The key the field's value is returned under
+    pub fn key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn argument(&self, name: &str) -> Option<&Argument> {
+        self.arguments.iter().find(|(key, _)| key == name).map(|(_, value)| value)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    // Whitespace, commas and comments are insignificant between tokens
+    fn skip_ignored(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\n' | '\r' | ',' | '\u{feff}' => self.pos += c.len_utf8(),
+                '#' => {
+                    let rest = &self.input[self.pos..];
+                    self.pos += rest.find(['\n', '\r']).unwrap_or(rest.len());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    // "at line 1, column 3" for the current position
+    fn location(&self) -> String {
+        let before = &self.input[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |text| text.chars().count()) + 1;
+        format!("at line {}, column {}", line, column)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} {}", message, self.location())
+    }
+
+    fn expect(&mut self, token: char) -> Result<(), String> {
+        self.skip_ignored();
+        match self.peek() {
+            Some(c) if c == token => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("Expected '{}' but found '{}'", token, c))),
+            None => Err(self.error(&format!("Expected '{}' but the query ended", token))),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        self.skip_ignored();
+        let rest = &self.input[self.pos..];
+        let length = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if length == 0 || rest.as_bytes()[0].is_ascii_digit() {
+            return Err(match self.peek() {
+                Some(c) => self.error(&format!("Expected a name but found '{}'", c)),
+                None => self.error("Expected a name but the query ended"),
+            });
+        }
+        self.pos += length;
+        Ok(rest[..length].to_string())
+    }
+
+    // Anything the subset leaves out gets a precise refusal rather than a
+    // generic syntax error
+    fn unsupported(&self, c: char) -> Option<String> {
+        let what = match c {
+            '$' => "Variables are",
+            '@' => "Directives are",
+            '.' => "Fragments are",
+            '{' => "Nested selections are",
+            _ => return None,
+        };
+        Some(self.error(&format!("{} not supported", what)))
+    }
+
+    fn document(&mut self) -> Result<Vec<Field>, String> {
+        self.skip_ignored();
+        if self.peek() != Some('{') {
+            let start = self.pos;
+            match self.name()?.as_str() {
+                "query" => {}
+                "mutation" | "subscription" => {
+                    self.pos = start;
+                    return Err(self.error("Only queries are supported"));
+                }
+                other => {
+                    self.pos = start;
+                    return Err(self.error(&format!("Expected '{{' or 'query' but found '{}'", other)));
+                }
+            }
+            self.skip_ignored();
+            if self.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+                self.name()?;
+            }
+            self.skip_ignored();
+            match self.peek() {
+                Some('(') => return Err(self.error("Variables are not supported")),
+                Some('@') => return Err(self.error("Directives are not supported")),
+                _ => {}
+            }
+        }
+        let fields = self.selection_set()?;
+        self.skip_ignored();
+        match self.peek() {
+            None => Ok(fields),
+            Some(_) => Err(self.error("Only one operation per document is supported")),
+        }
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Field>, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        loop {
+            self.skip_ignored();
+            if self.peek() == Some('}') {
+                break;
+            }
+            if let Some(message) = self.peek().and_then(|c| self.unsupported(c)) {
+                return Err(message);
+            }
+            if fields.len() == MAX_FIELDS {
+                return Err(self.error(&format!("A query may select at most {} fields", MAX_FIELDS)));
+            }
+            fields.push(self.field()?);
+        }
+        self.pos += 1;
+        if fields.is_empty() {
+            return Err(self.error("A selection set must select at least one field"));
+        }
+        Ok(fields)
+    }
+
+    fn field(&mut self) -> Result<Field, String> {
+        let mut name = self.name()?;
+        let mut alias = None;
+        self.skip_ignored();
+        if self.peek() == Some(':') {
+            self.pos += 1;
+            alias = Some(name);
+            name = self.name()?;
+            self.skip_ignored();
+        }
+        let mut arguments: Vec<(String, Argument)> = Vec::new();
+        if self.peek() == Some('(') {
+            self.pos += 1;
+            loop {
+                self.skip_ignored();
+                if self.peek() == Some(')') {
+                    self.pos += 1;
+                    break;
+                }
+                let argument = self.name()?;
+                if arguments.iter().any(|(existing, _)| *existing == argument) {
+                    return Err(self.error(&format!("Argument '{}' is given twice", argument)));
+                }
+                self.expect(':')?;
+                let value = self.value()?;
+                arguments.push((argument, value));
+            }
+            if arguments.is_empty() {
+                return Err(self.error("An argument list must not be empty"));
+            }
+        }
+        self.skip_ignored();
+        if let Some(message) = self.peek().and_then(|c| self.unsupported(c)) {
+            return Err(message);
+        }
+        Ok(Field { alias, name, arguments })
+    }
+
+    fn value(&mut self) -> Result<Argument, String> {
+        self.skip_ignored();
+        match self.peek() {
+            Some('"') => self.string().map(Argument::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some('$') => Err(self.error("Variables are not supported")),
+            Some('[' | '{') => Err(self.error("List and object arguments are not supported")),
+            Some(_) => match self.name()?.as_str() {
+                "true" => Ok(Argument::Bool(true)),
+                "false" => Ok(Argument::Bool(false)),
+                "null" => Ok(Argument::Null),
+                other => Err(self.error(&format!("Enum value '{}' is not supported", other))),
+            },
+            None => Err(self.error("Expected a value but the query ended")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Argument, String> {
+        let rest = &self.input[self.pos..];
+        let length = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let text = &rest[..length];
+        let digits = text.strip_prefix('-').unwrap_or(text);
+        let value = if digits.len() > 1 && digits.starts_with('0') && digits.as_bytes()[1].is_ascii_digit() {
+            None
+        } else if text.contains(['.', 'e', 'E']) {
+            text.parse().ok().filter(|value: &f64| value.is_finite()).map(Argument::Float)
+        } else {
+            text.parse().ok().map(Argument::Int)
+        };
+        match value {
+            Some(value) => {
+                self.pos += length;
+                Ok(value)
+            }
+            None => Err(self.error(&format!("Invalid number '{}'", text))),
+        }
+    }
+
+    // Double-quoted strings with the JSON-style escapes GraphQL shares;
+    // block strings are not supported
+    fn string(&mut self) -> Result<String, String> {
+        if self.input[self.pos..].starts_with("\"\"\"") {
+            return Err(self.error("Block strings are not supported"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("Unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(out),
+                '\n' | '\r' => return Err(self.error("Unterminated string")),
+                '\\' => {
+                    let Some(escaped) = self.peek() else {
+                        return Err(self.error("Unterminated string"));
+                    };
+                    self.pos += escaped.len_utf8();
+                    out.push(match escaped {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let hex = self.input.get(self.pos..self.pos + 4).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+                            let decoded = hex.and_then(|hex| u32::from_str_radix(hex, 16).ok()).and_then(char::from_u32);
+                            match decoded {
+                                Some(decoded) => {
+                                    self.pos += 4;
+                                    decoded
+                                }
+                                None => return Err(self.error("Invalid \\u escape")),
+                            }
+                        }
+                        other => return Err(self.error(&format!("Invalid escape '\\{}'", other))),
+                    });
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+// Parses a query into its top-level fields, in document order
+pub fn parse(query: &str) -> Result<Vec<Field>, String> {
+    Parser { input: query, pos: 0 }.document()
+}
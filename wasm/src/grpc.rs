@@ -0,0 +1,134 @@
+// gRPC-Web unary calls to a small Calculator service
+// A request body is one length-prefixed frame holding the protobuf message;
+// the reply is the response message's frame followed by a trailer frame
+// carrying grpc-status. The service, as a .proto file:
+//
+//   package calculator;
+//   service Calculator {
+//     rpc Add(AddRequest) returns (AddReply);
+//     rpc Factorial(FactorialRequest) returns (FactorialReply);
+//     rpc IsPrime(IsPrimeRequest) returns (IsPrimeReply);
+//   }
+//   message AddRequest { int32 a = 1; int32 b = 2; }
+//   message AddReply { int64 sum = 1; }
+//   message FactorialRequest { uint32 n = 1; }
+//   message FactorialReply { uint64 result = 1; }
+//   message IsPrimeRequest { uint32 n = 1; }
+//   message IsPrimeReply { bool prime = 1; }
+
+use crate::protobuf::{self, Encoder};
+use crate::{factorial, is_prime};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub const SERVICE: &str = "calculator.Calculator";
+
+// The status codes this service answers with besides 0 (OK)
+pub const INVALID_ARGUMENT: u8 = 3;
+pub const UNIMPLEMENTED: u8 = 12;
+
+pub struct Status {
+    pub code: u8,
+    pub message: String,
+}
+
+impl Status {
+    pub fn new(code: u8, message: impl Into<String>) -> Self {
+        Status { code, message: message.into() }
+    }
+}
+
+const COMPRESSED_FLAG: u8 = 0x01;
+const TRAILER_FLAG: u8 = 0x80;
+
+fn frame(flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(5 + payload.len());
+    out.push(flags);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+// The message of a unary request, which must be a single uncompressed frame
+pub fn unframe(body: &[u8]) -> Result<&[u8], Status> {
+    let Some((&flags, rest)) = body.split_first() else {
+        return Err(Status::new(INVALID_ARGUMENT, "Request body has no message frame"));
+    };
+    if flags & COMPRESSED_FLAG != 0 {
+        return Err(Status::new(UNIMPLEMENTED, "Compressed messages are not supported"));
+    }
+    if flags != 0 {
+        return Err(Status::new(INVALID_ARGUMENT, format!("Unexpected frame flags {:#04x}", flags)));
+    }
+    let Some((length, message)) = rest.split_first_chunk::<4>() else {
+        return Err(Status::new(INVALID_ARGUMENT, "Truncated frame header"));
+    };
+    let length = u32::from_be_bytes(*length) as usize;
+    match message.len() {
+        n if n < length => Err(Status::new(INVALID_ARGUMENT, "Truncated message frame")),
+        n if n > length => Err(Status::new(INVALID_ARGUMENT, "Unary calls take exactly one message")),
+        _ => Ok(message),
+    }
+}
+
+// grpc-message is percent-encoded (gRPC over HTTP/2, "Responses")
+pub fn encode_message(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        match byte {
+            b' '..=b'~' if byte != b'%' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+// The response body for a successful call: the message frame, then trailers
+pub fn reply(message: &[u8]) -> Vec<u8> {
+    let mut body = frame(0, message);
+    body.extend(frame(TRAILER_FLAG, b"grpc-status:0\r\n"));
+    body
+}
+
+fn int32(fields: &[(u32, protobuf::Value)], number: u32) -> Result<i32, Status> {
+    // Negative int32 values arrive sign-extended to 64 bits
+    let value = protobuf::last_varint(fields, number).map_err(|reason| Status::new(INVALID_ARGUMENT, reason))? as i64;
+    i32::try_from(value).map_err(|_| Status::new(INVALID_ARGUMENT, format!("Field {} is out of range for int32", number)))
+}
+
+fn uint32(fields: &[(u32, protobuf::Value)], number: u32) -> Result<u32, Status> {
+    let value = protobuf::last_varint(fields, number).map_err(|reason| Status::new(INVALID_ARGUMENT, reason))?;
+    u32::try_from(value).map_err(|_| Status::new(INVALID_ARGUMENT, format!("Field {} is out of range for uint32", number)))
+}
+
+// Runs `method` ("Add", ...) on a request message, returning the reply message
+pub fn call(method: &str, request: &[u8]) -> Result<Vec<u8>, Status> {
+    let fields = protobuf::decode(request).map_err(|reason| Status::new(INVALID_ARGUMENT, format!("Invalid request message: {}", reason)))?;
+    let mut reply = Encoder::new();
+    match method {
+        "Add" => {
+            let (a, b) = (int32(&fields, 1)?, int32(&fields, 2)?);
+            reply.varint(1, (i64::from(a) + i64::from(b)) as u64);
+        }
+        "Factorial" => match uint32(&fields, 1)? {
+            n @ 0..=20 => {
+                reply.varint(1, factorial(n));
+            }
+            _ => return Err(Status::new(INVALID_ARGUMENT, "n must be between 0 and 20")),
+        },
+        "IsPrime" => {
+            reply.varint(1, u64::from(is_prime(uint32(&fields, 1)?) != 0));
+        }
+        _ => return Err(Status::new(UNIMPLEMENTED, format!("Unknown method {}/{}", SERVICE, method))),
+    }
+    Ok(reply.finish())
+}
+
+// Splits "calculator.Calculator/Add" into the method, for this service only
+pub fn method(path: &str) -> Result<&str, Status> {
+    match path.split_once('/') {
+        Some((SERVICE, method)) if !method.is_empty() && !method.contains('/') => Ok(method),
+        _ => Err(Status::new(UNIMPLEMENTED, format!("Unknown service or method '{}'", path))),
+    }
+}
@@ -0,0 +1,145 @@
+// Consistent hashing ring for spreading keys over nodes such as origins
+// Every node is hashed onto a 64-bit ring at `vnodes` points, and a key
+// belongs to the node owning the first point at or after the key's hash,
+// wrapping around. Adding or removing a node only moves the keys next to its
+// points, about 1/n of them, where a plain hash modulo n would move almost
+// all. More virtual nodes even out the share each node gets.
+//
+// The isolate keeps one ring, seeded from ring_nodes in CONFIG, which the
+// host can change through the ring_add and ring_remove exports.
+
+use crate::arena;
+use crate::config;
+use crate::ffi;
+use crate::hashing::Sha256;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_VNODES: u32 = 100;
+pub const MAX_VNODES: u32 = 1000;
+pub const MAX_NODES: usize = 1000;
+
+pub struct Ring {
+    vnodes: u32,
+    points: BTreeMap<u64, String>,
+    nodes: Vec<String>,
+}
+
+pub fn is_valid_node(node: &str) -> bool {
+    (1..=256).contains(&node.len()) && !node.contains(',') && !node.chars().any(char::is_control)
+}
+
+pub fn hash(key: &str) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    let digest = hasher.finalize();
+    let mut top = [0u8; 8];
+    top.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(top)
+}
+
+impl Ring {
+    pub fn new(vnodes: u32) -> Ring {
+        Ring { vnodes: vnodes.clamp(1, MAX_VNODES), points: BTreeMap::new(), nodes: Vec::new() }
+    }
+
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    pub fn vnodes(&self) -> u32 {
+        self.vnodes
+    }
+
+    // false when the node is already on the ring or invalid
+    pub fn add(&mut self, node: &str) -> bool {
+        if !is_valid_node(node) || self.nodes.len() >= MAX_NODES || self.nodes.iter().any(|existing| existing == node) {
+            return false;
+        }
+        for replica in 0..self.vnodes {
+            // Two nodes landing on the same point is a 2^-64 event; the
+            // node added first keeps it
+            self.points.entry(hash(&format!("{}#{}", node, replica))).or_insert_with(|| node.to_string());
+        }
+        self.nodes.push(node.to_string());
+        true
+    }
+
+    pub fn remove(&mut self, node: &str) -> bool {
+        let Some(index) = self.nodes.iter().position(|existing| existing == node) else {
+            return false;
+        };
+        self.nodes.remove(index);
+        self.points.retain(|_, owner| owner != node);
+        true
+    }
+
+    // Distinct nodes in ring order from the key's position: the owner first,
+    // then where its replicas would go
+    pub fn preference(&self, key: &str, count: usize) -> Vec<&str> {
+        let start = hash(key);
+        let mut found: Vec<&str> = Vec::new();
+        for owner in self.points.range(start..).chain(self.points.range(..start)).map(|(_, owner)| owner.as_str()) {
+            if found.len() >= count.min(self.nodes.len()) {
+                break;
+            }
+            if !found.contains(&owner) {
+                found.push(owner);
+            }
+        }
+        found
+    }
+
+    pub fn locate(&self, key: &str) -> Option<&str> {
+        self.preference(key, 1).first().copied()
+    }
+}
+
+thread_local! {
+    static RING: RefCell<Option<Ring>> = const { RefCell::new(None) };
+}
+
+// Runs `f` on the isolate's ring, building it from CONFIG on first use
+pub fn with_isolate_ring<T>(f: impl FnOnce(&mut Ring) -> T) -> T {
+    arena::outside(|| RING.with(|ring| {
+        let mut ring = ring.borrow_mut();
+        let ring = ring.get_or_insert_with(|| {
+            let config = config::current();
+            let mut ring = Ring::new(config.ring_vnodes);
+            for node in &config.ring_nodes {
+                ring.add(node);
+            }
+            ring
+        });
+        f(ring)
+    }))
+}
+
+fn read_str<'a>(ptr: *const u8, len: usize) -> Option<&'a str> {
+    unsafe { ffi::text(ptr, len) }.ok()
+}
+
+// Returns 1 when the node was added, 0 when it is invalid or already there
+#[no_mangle]
+pub extern "C" fn ring_add(ptr: *const u8, len: usize) -> i32 {
+    read_str(ptr, len).is_some_and(|node| with_isolate_ring(|ring| ring.add(node))) as i32
+}
+
+// Returns 1 when the node was on the ring
+#[no_mangle]
+pub extern "C" fn ring_remove(ptr: *const u8, len: usize) -> i32 {
+    read_str(ptr, len).is_some_and(|node| with_isolate_ring(|ring| ring.remove(node))) as i32
+}
+
+// The node owning a key, as a buffer to release with free_bytes, or null
+// when the ring is empty
+#[no_mangle]
+pub extern "C" fn ring_locate(ptr: *const u8, len: usize) -> *mut u8 {
+    let Some(key) = read_str(ptr, len) else {
+        return std::ptr::null_mut();
+    };
+    with_isolate_ring(|ring| match ring.locate(key) {
+        Some(node) => ffi::output(node.as_bytes()),
+        None => std::ptr::null_mut(),
+    })
+}
@@ -0,0 +1,86 @@
+// Request headers as passed by the JS host: one "name: value" pair per line
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub struct Headers {
+    entries: Vec<(String, String)>,
+}
+
+impl Headers {
+    pub fn parse(raw: &str) -> Self {
+        let entries = raw
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        Headers { entries }
+    }
+
+    // Header names are case-insensitive
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Look up a parameter such as `boundary` or `charset` in a header value like
+// `multipart/form-data; boundary="abc"`
+pub fn parameter(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let value = value.trim();
+        Some(value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value).to_string())
+    })
+}
+
+// Language ranges from an Accept-Language header with their q-values, most
+// preferred first and lower-cased, leaving out those with q=0
+pub fn accept_languages(header: &str) -> Vec<(String, f32)> {
+    let mut ranges: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|range| {
+            let tag = range.split(';').next()?.trim();
+            let quality = parameter(range, "q").and_then(|q| q.parse::<f32>().ok()).unwrap_or(1.0).clamp(0.0, 1.0);
+            (!tag.is_empty() && quality > 0.0).then(|| (tag.to_ascii_lowercase(), quality))
+        })
+        .collect();
+    // A stable sort keeps header order among equal q-values
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges
+}
+
+// The value of cookie `name` in a Cookie header such as `theme=dark; id=42`
+pub fn cookie<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    header.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key.trim() == name).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+// The quality an Accept header gives `media_type`, e.g. 0.8 for text/html in
+// `application/json, */*;q=0.8`. The most specific matching range decides;
+// a type no range matches gets 0.
+pub fn accept_quality(accept: &str, media_type: &str) -> f32 {
+    let main_type = media_type.split('/').next().unwrap_or(media_type);
+    let mut best: Option<(u8, f32)> = None;
+    for range in accept.split(',') {
+        let name = range.split(';').next().unwrap_or("").trim();
+        let specificity = match name.split_once('/') {
+            _ if name.eq_ignore_ascii_case(media_type) => 2,
+            Some((range_type, "*")) if range_type.eq_ignore_ascii_case(main_type) => 1,
+            Some(("*", "*")) => 0,
+            _ => continue,
+        };
+        let quality = parameter(range, "q").and_then(|q| q.parse::<f32>().ok()).unwrap_or(1.0).clamp(0.0, 1.0);
+        if best.is_none_or(|(best_specificity, _)| specificity > best_specificity) {
+            best = Some((specificity, quality));
+        }
+    }
+    best.map_or(0.0, |(_, quality)| quality)
+}
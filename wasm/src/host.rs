@@ -0,0 +1,60 @@
+// Functions imported from the JS host
+// The Worker passes these in the "env" import object; native builds (cargo
+// test, clippy) fall back to std so the crate still links off-wasm.
+
+#[cfg(target_arch = "wasm32")]
+mod imports {
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        pub fn host_now_ms() -> f64;
+        pub fn host_random_fill(ptr: *mut u8, len: usize);
+        pub fn host_log(ptr: *const u8, len: usize);
+    }
+}
+
+// Milliseconds since the Unix epoch. Inside a Worker the clock only advances
+// between I/O events, so it is constant for the duration of one request.
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    unsafe { imports::host_now_ms() }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
+
+// crypto.getRandomValues fills at most 64 KiB per call
+#[cfg(target_arch = "wasm32")]
+pub fn random_bytes(bytes: &mut [u8]) {
+    for chunk in bytes.chunks_mut(65536) {
+        unsafe { imports::host_random_fill(chunk.as_mut_ptr(), chunk.len()) }
+    }
+}
+
+// Native builds have no entropy import; the module PRNG keeps them linking
+#[cfg(not(target_arch = "wasm32"))]
+pub fn random_bytes(bytes: &mut [u8]) {
+    crate::rand::with_global(|rng| rng.fill_bytes(bytes));
+}
+
+// Diagnostics for whoever maintains the JS glue; the Worker sends them to
+// console.warn. Lines logged during a request start with its ID.
+pub fn log(message: &str) {
+    match crate::context::request_id() {
+        Some(id) => write_log(&format!("[{}] {}", id, message)),
+        None => write_log(message),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_log(message: &str) {
+    unsafe { imports::host_log(message.as_ptr(), message.len()) }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_log(message: &str) {
+    eprintln!("{}", message);
+}
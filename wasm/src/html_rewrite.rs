@@ -0,0 +1,442 @@
+// Tag-level HTML rewriting for pages passing through the Worker
+// Like the Workers HTMLRewriter, the rewriter is fed the page in chunks and
+// returns what it can as it goes: text and untouched tags are copied
+// byte-for-byte, and only a tag cut off by the end of a chunk is held back
+// for the next one. It does not build a tree, so it only sees one tag at a
+// time: the <meta> elements it injects go before </head> (or <body>, when
+// the head is left open) unless the head already had one of the same name,
+// whose content is replaced instead; href, src and action values starting
+// with a given prefix get another; and script, style and stylesheet link
+// elements get a CSP nonce. Prefixes are compared with attribute values as
+// written, entities and all. Script, style, textarea and title contents are
+// raw text, not markup, and are passed through. tags() reads a page with the
+// same tokenizer, for handlers that extract rather than rewrite.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::html;
+
+const RAW_TEXT_TAGS: [&str; 4] = ["script", "style", "textarea", "title"];
+const URL_ATTRIBUTES: [&str; 3] = ["href", "src", "action"];
+
+#[derive(Default)]
+pub struct Rules {
+    // Names and contents; "og:" names are written as property attributes
+    pub meta: Vec<(String, String)>,
+    // Leading parts of URLs and their replacements, tried in order
+    pub prefixes: Vec<(String, String)>,
+    pub nonce: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Stats {
+    pub meta_injected: usize,
+    pub meta_replaced: usize,
+    pub urls_rewritten: usize,
+    pub nonces_added: usize,
+}
+
+enum Token {
+    // More input is needed to tell what this is
+    Incomplete,
+    // A '<' that starts no markup
+    Text,
+    Comment(usize),
+    Tag(usize),
+}
+
+struct Attribute<'a> {
+    name: String,
+    // As written, without quotes
+    value: Option<&'a str>,
+    source: &'a str,
+}
+
+pub struct Rewriter<'a> {
+    rules: &'a Rules,
+    pending: String,
+    // The raw text element being copied, until its end tag
+    raw: Option<String>,
+    // Which of the rules' meta names the head already had
+    present: Vec<bool>,
+    injected: bool,
+    stats: Stats,
+}
+
+fn find_ignoring_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+fn tag_end(rest: &[u8]) -> Option<usize> {
+    let mut quote = None;
+    for (index, &byte) in rest.iter().enumerate().skip(1) {
+        match quote {
+            Some(open) if byte == open => quote = None,
+            Some(_) => {}
+            None if byte == b'"' || byte == b'\'' => quote = Some(byte),
+            None if byte == b'>' => return Some(index + 1),
+            None => {}
+        }
+    }
+    None
+}
+
+// `rest` starts with '<'
+fn token(rest: &str) -> Token {
+    let bytes = rest.as_bytes();
+    if bytes.len() < 4 && b"<!--".starts_with(bytes) || bytes.len() < 3 && bytes.starts_with(b"</") {
+        return Token::Incomplete;
+    }
+    if bytes.starts_with(b"<!--") {
+        return find_ignoring_case(&bytes[4..], b"-->").map_or(Token::Incomplete, |end| Token::Comment(end + 7));
+    }
+    let markup = match bytes[1] {
+        b'/' => bytes[2].is_ascii_alphabetic(),
+        b'!' | b'?' => true,
+        byte => byte.is_ascii_alphabetic(),
+    };
+    if !markup {
+        return Token::Text;
+    }
+    tag_end(bytes).map_or(Token::Incomplete, Token::Tag)
+}
+
+// The last index at or before `index` on a char boundary
+fn boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+// The lower-case name, whether it is an end tag, and where the name ends
+fn tag_name(tag: &str) -> (String, bool, usize) {
+    let bytes = tag.as_bytes();
+    let closing = bytes[1] == b'/';
+    let name_start = if closing { 2 } else { 1 };
+    let name_end = name_start + bytes[name_start..].iter().take_while(|byte| byte.is_ascii_alphanumeric() || **byte == b'-').count();
+    (tag[name_start..name_end].to_ascii_lowercase(), closing, name_end)
+}
+
+fn attributes(tag: &str, name_end: usize) -> Vec<Attribute<'_>> {
+    let bytes = tag.as_bytes();
+    let end = tag.len() - 1;
+    let mut attributes = Vec::new();
+    let mut i = name_end;
+    while i < end {
+        if bytes[i].is_ascii_whitespace() || bytes[i] == b'/' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < end && !bytes[i].is_ascii_whitespace() && !matches!(bytes[i], b'=' | b'/') {
+            i += 1;
+        }
+        let name = tag[start..i].to_ascii_lowercase();
+        let mut j = i;
+        while j < end && bytes[j].is_ascii_whitespace() {
+            j += 1;
+        }
+        let mut value = None;
+        if j < end && bytes[j] == b'=' {
+            j += 1;
+            while j < end && bytes[j].is_ascii_whitespace() {
+                j += 1;
+            }
+            if j < end && (bytes[j] == b'"' || bytes[j] == b'\'') {
+                let close = tag[j + 1..end].find(bytes[j] as char).map_or(end, |offset| j + 1 + offset);
+                value = Some(&tag[j + 1..close]);
+                i = (close + 1).min(end);
+            } else {
+                let value_start = j;
+                while j < end && !bytes[j].is_ascii_whitespace() {
+                    j += 1;
+                }
+                value = Some(&tag[value_start..j]);
+                i = j;
+            }
+        }
+        attributes.push(Attribute { name, value, source: &tag[start..i] });
+    }
+    attributes
+}
+
+fn attribute_value<'a>(attributes: &[Attribute<'a>], name: &str) -> Option<&'a str> {
+    attributes.iter().find(|attribute| attribute.name == name).and_then(|attribute| attribute.value)
+}
+
+fn has_rel(attributes: &[Attribute], rel: &str) -> bool {
+    attribute_value(attributes, "rel").is_some_and(|listed| listed.split_ascii_whitespace().any(|listed| listed.eq_ignore_ascii_case(rel)))
+}
+
+// Double-quoted, with only quotes escaped since the value is already markup
+fn quoted(name: &str, value: &str) -> String {
+    format!("{}=\"{}\"", name, value.replace('"', "&quot;"))
+}
+
+fn meta_element(name: &str, content: &str) -> String {
+    let attribute = if name.starts_with("og:") { "property" } else { "name" };
+    format!("<meta {}=\"{}\" content=\"{}\">", attribute, html::escape(name), html::escape(content))
+}
+
+impl<'a> Rewriter<'a> {
+    pub fn new(rules: &'a Rules) -> Self {
+        Rewriter { rules, pending: String::new(), raw: None, present: alloc::vec![false; rules.meta.len()], injected: false, stats: Stats::default() }
+    }
+
+    // The rewritten output so far, which may stop short of the chunk's end
+    pub fn write(&mut self, chunk: &str) -> String {
+        let mut input = core::mem::take(&mut self.pending);
+        input.push_str(chunk);
+        let mut out = String::with_capacity(input.len());
+        let mut i = 0;
+        loop {
+            if let Some(name) = &self.raw {
+                let closing = format!("</{}", name);
+                match find_ignoring_case(&input.as_bytes()[i..], closing.as_bytes()) {
+                    Some(offset) => {
+                        out.push_str(&input[i..i + offset]);
+                        i += offset;
+                        self.raw = None;
+                    }
+                    None => {
+                        // The end tag may be split across chunks
+                        let keep = boundary(&input, input.len().saturating_sub(closing.len()).max(i));
+                        out.push_str(&input[i..keep]);
+                        self.pending = input[keep..].to_string();
+                        return out;
+                    }
+                }
+            }
+            let Some(offset) = input[i..].find('<') else {
+                out.push_str(&input[i..]);
+                return out;
+            };
+            out.push_str(&input[i..i + offset]);
+            i += offset;
+            match token(&input[i..]) {
+                Token::Incomplete => {
+                    self.pending = input[i..].to_string();
+                    return out;
+                }
+                Token::Text => {
+                    out.push('<');
+                    i += 1;
+                }
+                Token::Comment(length) => {
+                    out.push_str(&input[i..i + length]);
+                    i += length;
+                }
+                Token::Tag(length) => {
+                    self.tag(&input[i..i + length], &mut out);
+                    i += length;
+                }
+            }
+        }
+    }
+
+    // Whatever was held back, as it was, and what was changed
+    pub fn end(mut self) -> (String, Stats) {
+        let rest = core::mem::take(&mut self.pending);
+        (rest, self.stats)
+    }
+
+    fn inject(&mut self, out: &mut String) {
+        if self.injected {
+            return;
+        }
+        self.injected = true;
+        for ((name, content), present) in self.rules.meta.iter().zip(&self.present) {
+            if !present {
+                out.push_str(&meta_element(name, content));
+                self.stats.meta_injected += 1;
+            }
+        }
+    }
+
+    fn tag(&mut self, tag: &str, out: &mut String) {
+        let (name, closing, name_end) = tag_name(tag);
+        if closing || matches!(tag.as_bytes()[1], b'!' | b'?') {
+            if closing && name == "head" {
+                self.inject(out);
+            }
+            out.push_str(tag);
+            return;
+        }
+        if name == "body" {
+            self.inject(out);
+        }
+        let self_closing = tag.ends_with("/>");
+        if RAW_TEXT_TAGS.contains(&name.as_str()) && !self_closing {
+            self.raw = Some(name.clone());
+        }
+
+        let attributes = attributes(tag, name_end);
+        // Replacement source text by attribute index, and attributes to add
+        let mut replaced: Vec<(usize, String)> = Vec::new();
+        let mut added: Vec<String> = Vec::new();
+
+        if name == "meta" && !self.injected {
+            let meta_name = attribute_value(&attributes, "name").or(attribute_value(&attributes, "property"));
+            let rule = meta_name.and_then(|meta_name| self.rules.meta.iter().position(|(name, _)| name.eq_ignore_ascii_case(meta_name)));
+            if let Some(rule) = rule.filter(|&rule| !self.present[rule]) {
+                self.present[rule] = true;
+                let content = quoted("content", &html::escape(&self.rules.meta[rule].1));
+                match attributes.iter().position(|attribute| attribute.name == "content") {
+                    Some(index) => replaced.push((index, content)),
+                    None => added.push(content),
+                }
+                self.stats.meta_replaced += 1;
+            }
+        }
+        for (index, attribute) in attributes.iter().enumerate() {
+            let Some(value) = attribute.value.filter(|_| URL_ATTRIBUTES.contains(&attribute.name.as_str())) else {
+                continue;
+            };
+            if let Some((from, to)) = self.rules.prefixes.iter().find(|(from, _)| value.starts_with(from.as_str())) {
+                replaced.push((index, quoted(&attribute.name, &format!("{}{}", html::escape(to), &value[from.len()..]))));
+                self.stats.urls_rewritten += 1;
+            }
+        }
+        let stylesheet = name == "link" && has_rel(&attributes, "stylesheet");
+        if let Some(nonce) = self.rules.nonce.as_deref().filter(|_| name == "script" || name == "style" || stylesheet) {
+            let attribute = quoted("nonce", &html::escape(nonce));
+            match attributes.iter().position(|attribute| attribute.name == "nonce") {
+                Some(index) => replaced.push((index, attribute)),
+                None => added.push(attribute),
+            }
+            self.stats.nonces_added += 1;
+        }
+
+        if replaced.is_empty() && added.is_empty() {
+            out.push_str(tag);
+            return;
+        }
+        // Rebuilt with single spaces between attributes
+        out.push_str(&tag[..name_end]);
+        for (index, attribute) in attributes.iter().enumerate() {
+            out.push(' ');
+            match replaced.iter().find(|(replaced, _)| *replaced == index) {
+                Some((_, source)) => out.push_str(source),
+                None => out.push_str(attribute.source),
+            }
+        }
+        for source in &added {
+            out.push(' ');
+            out.push_str(source);
+        }
+        out.push_str(if self_closing { " />" } else { ">" });
+    }
+}
+
+// The whole page in one chunk
+pub fn rewrite(page: &str, rules: &Rules) -> (String, Stats) {
+    let mut rewriter = Rewriter::new(rules);
+    let mut out = rewriter.write(page);
+    let (rest, stats) = rewriter.end();
+    out.push_str(&rest);
+    (out, stats)
+}
+
+// A tag of a page being read rather than rewritten
+pub struct Tag<'a> {
+    pub name: String,
+    pub closing: bool,
+    attributes: Vec<Attribute<'a>>,
+    // What a raw text element such as <title> or <script> holds, as written
+    pub text: Option<&'a str>,
+    // Where the tag itself is in the page
+    pub range: Range<usize>,
+}
+
+impl<'a> Tag<'a> {
+    // As written; see html::unescape
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        attribute_value(&self.attributes, name)
+    }
+
+    // Whether the rel attribute lists this link type
+    pub fn has_rel(&self, rel: &str) -> bool {
+        has_rel(&self.attributes, rel)
+    }
+}
+
+pub struct Tags<'a> {
+    page: &'a str,
+    position: usize,
+}
+
+// Start and end tags in order, skipping comments and declarations such as
+// <!DOCTYPE>; a tag left unclosed at the end of the page is dropped
+pub fn tags(page: &str) -> Tags<'_> {
+    Tags { page, position: 0 }
+}
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = Tag<'a>;
+
+    fn next(&mut self) -> Option<Tag<'a>> {
+        loop {
+            let start = self.position + self.page[self.position..].find('<')?;
+            let length = match token(&self.page[start..]) {
+                Token::Incomplete => {
+                    self.position = self.page.len();
+                    return None;
+                }
+                Token::Text => 1,
+                Token::Comment(length) => length,
+                Token::Tag(length) => length,
+            };
+            self.position = start + length;
+            let source = &self.page[start..self.position];
+            if length == 1 || source.starts_with("<!--") || matches!(source.as_bytes()[1], b'!' | b'?') {
+                continue;
+            }
+            let (name, closing, name_end) = tag_name(source);
+            let mut text = None;
+            if !closing && RAW_TEXT_TAGS.contains(&name.as_str()) && !source.ends_with("/>") {
+                let rest = &self.page[self.position..];
+                let end = find_ignoring_case(rest.as_bytes(), format!("</{}", name).as_bytes()).unwrap_or(rest.len());
+                text = Some(&rest[..end]);
+                self.position += end;
+            }
+            return Some(Tag { name, closing, attributes: attributes(source, name_end), text, range: start..start + length });
+        }
+    }
+}
+
+// What a stretch of markup reads as, like textContent: tags and comments
+// dropped, scripts and styles skipped, references decoded and whitespace
+// collapsed
+pub fn text_content(markup: &str) -> String {
+    let mut text = String::new();
+    let mut i = 0;
+    while let Some(offset) = markup[i..].find('<') {
+        text.push_str(&markup[i..i + offset]);
+        i += offset;
+        match token(&markup[i..]) {
+            Token::Incomplete => {
+                i = markup.len();
+                break;
+            }
+            Token::Text => {
+                text.push('<');
+                i += 1;
+            }
+            Token::Comment(length) => i += length,
+            Token::Tag(length) => {
+                let (name, closing, _) = tag_name(&markup[i..i + length]);
+                i += length;
+                if !closing && (name == "script" || name == "style") {
+                    let closing = format!("</{}", name);
+                    i += find_ignoring_case(&markup.as_bytes()[i..], closing.as_bytes()).unwrap_or(markup.len() - i);
+                }
+            }
+        }
+    }
+    text.push_str(&markup[i..]);
+    html::unescape(&text).split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,127 @@
+// Friendly renderings of byte sizes and durations, for people reading JSON
+// Sizes keep three significant digits in SI ("123 MB") or IEC ("118 MiB")
+// units. Durations under a minute keep three significant digits as well;
+// longer ones give their two largest units ("16m 28s"), and there are
+// spelled-out and ISO 8601 forms.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const SI_UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+const IEC_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+const SECOND: u64 = 1000;
+const MINUTE: u64 = 60 * SECOND;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+// Three significant digits without trailing zeros: 1.5, 12.3, 123
+fn significant(value: f64) -> String {
+    let decimals = if value >= 100.0 { 0 } else if value >= 10.0 { 1 } else { 2 };
+    let text = format!("{:.*}", decimals, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+fn scaled(bytes: u64, base: f64, units: &[&str; 7]) -> String {
+    if (bytes as f64) < base {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    // 999.96 kB rounds to 1000 kB, which reads better as 1 MB
+    if significant(value).parse::<f64>().is_ok_and(|rounded| rounded >= base) && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    format!("{} {}", significant(value), units[unit])
+}
+
+// Powers of 1000: "123 MB"
+pub fn bytes_si(bytes: u64) -> String {
+    scaled(bytes, 1000.0, &SI_UNITS)
+}
+
+// Powers of 1024: "118 MiB"
+pub fn bytes_iec(bytes: u64) -> String {
+    scaled(bytes, 1024.0, &IEC_UNITS)
+}
+
+// "0.42 ms", "12.3 ms", "4.56 s", "16m 28s", "2h 5m" or "3d 4h"
+pub fn duration(ms: f64) -> String {
+    let ms = ms.max(0.0);
+    if ms < 1000.0 && significant(ms) != "1000" {
+        return format!("{} ms", significant(ms));
+    }
+    if ms < 60_000.0 && significant(ms / 1000.0) != "60" {
+        return format!("{} s", significant(ms / 1000.0));
+    }
+    let ms = ms as u64;
+    // The smaller unit is rounded, which may carry into the larger one
+    let (large, small, large_name, small_name) = if ms + SECOND / 2 < HOUR {
+        (MINUTE, SECOND, "m", "s")
+    } else if ms + MINUTE / 2 < DAY {
+        (HOUR, MINUTE, "h", "m")
+    } else {
+        (DAY, HOUR, "d", "h")
+    };
+    let rounded = (ms + small / 2) / small * small;
+    format!("{}{} {}{}", rounded / large, large_name, rounded % large / small, small_name)
+}
+
+fn components(ms: u64) -> [(u64, &'static str); 5] {
+    [
+        (ms / DAY, "day"),
+        (ms % DAY / HOUR, "hour"),
+        (ms % HOUR / MINUTE, "minute"),
+        (ms % MINUTE / SECOND, "second"),
+        (ms % SECOND, "millisecond"),
+    ]
+}
+
+// "16 minutes, 27 seconds and 654 milliseconds"
+pub fn duration_long(ms: u64) -> String {
+    let parts: Vec<String> = components(ms)
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{} {}{}", count, name, if *count == 1 { "" } else { "s" }))
+        .collect();
+    match parts.split_last() {
+        None => "0 milliseconds".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+// "PT16M27.654S"; days are taken as 24 hours
+pub fn duration_iso8601(ms: u64) -> String {
+    let [(days, _), (hours, _), (minutes, _), (seconds, _), (millis, _)] = components(ms);
+    let mut text = "P".to_string();
+    if days > 0 {
+        text.push_str(&format!("{}D", days));
+    }
+    if hours + minutes + seconds + millis > 0 || days == 0 {
+        text.push('T');
+    }
+    if hours > 0 {
+        text.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        text.push_str(&format!("{}M", minutes));
+    }
+    if millis > 0 {
+        let fraction = format!("{:03}", millis);
+        text.push_str(&format!("{}.{}S", seconds, fraction.trim_end_matches('0')));
+    } else if seconds > 0 || ms == 0 {
+        text.push_str(&format!("{}S", seconds));
+    }
+    text
+}
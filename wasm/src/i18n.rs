@@ -0,0 +1,206 @@
+// Message catalogs for the pages people read: the home page and HTML error
+// pages. JSON error messages stay in English, as clients match on them.
+// The language comes from ?lang= when it names a catalog, otherwise from
+// the best Accept-Language range that does, and is English failing both.
+
+use crate::headers;
+
+// Statuses with a reason phrase of their own; others fall back to the
+// generic client or server error phrase
+const STATUSES: [u16; 15] = [400, 401, 403, 404, 405, 409, 410, 413, 414, 415, 422, 429, 500, 502, 503];
+
+pub struct Catalog {
+    pub code: &'static str,
+    // The language's name in itself, for the language links
+    pub name: &'static str,
+    pub home_intro: &'static str,
+    pub home_endpoints: &'static str,
+    pub language: &'static str,
+    pub back_to_endpoints: &'static str,
+    // "{}" marks where the suggested routes go
+    pub did_you_mean: &'static str,
+    pub or: &'static str,
+    pub request_id: &'static str,
+    reasons: [&'static str; STATUSES.len()],
+    client_error: &'static str,
+    server_error: &'static str,
+}
+
+impl Catalog {
+    pub fn reason(&self, status: u16) -> &'static str {
+        match STATUSES.iter().position(|&known| known == status) {
+            Some(index) => self.reasons[index],
+            None if status < 500 => self.client_error,
+            None => self.server_error,
+        }
+    }
+}
+
+pub const ENGLISH: Catalog = Catalog {
+    code: "en",
+    name: "English",
+    home_intro: "This request was processed entirely in WebAssembly!",
+    home_endpoints: "Available Endpoints:",
+    language: "Language",
+    back_to_endpoints: "Back to the endpoint list",
+    did_you_mean: "Did you mean {}?",
+    or: "or",
+    request_id: "Request ID",
+    reasons: [
+        "Bad Request",
+        "Unauthorized",
+        "Forbidden",
+        "Not Found",
+        "Method Not Allowed",
+        "Conflict",
+        "Gone",
+        "Payload Too Large",
+        "URI Too Long",
+        "Unsupported Media Type",
+        "Unprocessable Content",
+        "Too Many Requests",
+        "Internal Server Error",
+        "Bad Gateway",
+        "Service Unavailable",
+    ],
+    client_error: "Client Error",
+    server_error: "Server Error",
+};
+
+pub const CATALOGS: [&Catalog; 5] = [
+    &ENGLISH,
+    &Catalog {
+        code: "pl",
+        name: "Polski",
+        home_intro: "To żądanie zostało w całości obsłużone w WebAssembly!",
+        home_endpoints: "Dostępne endpointy:",
+        language: "Język",
+        back_to_endpoints: "Powrót do listy endpointów",
+        did_you_mean: "Czy chodziło o {}?",
+        or: "lub",
+        request_id: "Identyfikator żądania",
+        reasons: [
+            "Nieprawidłowe żądanie",
+            "Brak autoryzacji",
+            "Dostęp zabroniony",
+            "Nie znaleziono",
+            "Niedozwolona metoda",
+            "Konflikt",
+            "Zasób usunięty",
+            "Zbyt duże żądanie",
+            "Zbyt długi adres URI",
+            "Nieobsługiwany typ danych",
+            "Nieprzetwarzalna zawartość",
+            "Zbyt wiele żądań",
+            "Wewnętrzny błąd serwera",
+            "Błędna brama",
+            "Usługa niedostępna",
+        ],
+        client_error: "Błąd klienta",
+        server_error: "Błąd serwera",
+    },
+    &Catalog {
+        code: "de",
+        name: "Deutsch",
+        home_intro: "Diese Anfrage wurde vollständig in WebAssembly verarbeitet!",
+        home_endpoints: "Verfügbare Endpunkte:",
+        language: "Sprache",
+        back_to_endpoints: "Zurück zur Liste der Endpunkte",
+        did_you_mean: "Meinten Sie {}?",
+        or: "oder",
+        request_id: "Anfrage-ID",
+        reasons: [
+            "Ungültige Anfrage",
+            "Nicht autorisiert",
+            "Verboten",
+            "Nicht gefunden",
+            "Methode nicht erlaubt",
+            "Konflikt",
+            "Nicht mehr verfügbar",
+            "Anfrage zu groß",
+            "URI zu lang",
+            "Nicht unterstützter Medientyp",
+            "Nicht verarbeitbarer Inhalt",
+            "Zu viele Anfragen",
+            "Interner Serverfehler",
+            "Fehlerhaftes Gateway",
+            "Dienst nicht verfügbar",
+        ],
+        client_error: "Client-Fehler",
+        server_error: "Serverfehler",
+    },
+    &Catalog {
+        code: "fr",
+        name: "Français",
+        home_intro: "Cette requête a été entièrement traitée en WebAssembly !",
+        home_endpoints: "Points d’accès disponibles :",
+        language: "Langue",
+        back_to_endpoints: "Retour à la liste des points d’accès",
+        did_you_mean: "Vouliez-vous dire {} ?",
+        or: "ou",
+        request_id: "Identifiant de requête",
+        reasons: [
+            "Requête incorrecte",
+            "Non autorisé",
+            "Interdit",
+            "Introuvable",
+            "Méthode non autorisée",
+            "Conflit",
+            "Supprimé",
+            "Contenu trop volumineux",
+            "URI trop longue",
+            "Type de média non pris en charge",
+            "Contenu non traitable",
+            "Trop de requêtes",
+            "Erreur interne du serveur",
+            "Passerelle incorrecte",
+            "Service indisponible",
+        ],
+        client_error: "Erreur du client",
+        server_error: "Erreur du serveur",
+    },
+    &Catalog {
+        code: "es",
+        name: "Español",
+        home_intro: "¡Esta solicitud se procesó íntegramente en WebAssembly!",
+        home_endpoints: "Endpoints disponibles:",
+        language: "Idioma",
+        back_to_endpoints: "Volver a la lista de endpoints",
+        did_you_mean: "¿Quisiste decir {}?",
+        or: "o",
+        request_id: "ID de solicitud",
+        reasons: [
+            "Solicitud incorrecta",
+            "No autorizado",
+            "Prohibido",
+            "No encontrado",
+            "Método no permitido",
+            "Conflicto",
+            "Eliminado",
+            "Contenido demasiado grande",
+            "URI demasiado larga",
+            "Tipo de medio no admitido",
+            "Contenido no procesable",
+            "Demasiadas solicitudes",
+            "Error interno del servidor",
+            "Puerta de enlace incorrecta",
+            "Servicio no disponible",
+        ],
+        client_error: "Error del cliente",
+        server_error: "Error del servidor",
+    },
+];
+
+// "pl-PL" picks the pl catalog
+fn find(tag: &str) -> Option<&'static Catalog> {
+    let primary = tag.split(['-', '_']).next().unwrap_or(tag).trim();
+    CATALOGS.iter().copied().find(|catalog| catalog.code.eq_ignore_ascii_case(primary))
+}
+
+pub fn negotiate(lang: Option<&str>, accept_language: Option<&str>) -> &'static Catalog {
+    if let Some(catalog) = lang.and_then(find) {
+        return catalog;
+    }
+    let ranges = headers::accept_languages(accept_language.unwrap_or(""));
+    ranges.iter().find_map(|(tag, _)| find(tag)).unwrap_or(&ENGLISH)
+}
@@ -0,0 +1,66 @@
+// Idempotency keys for POST requests, kept in KV
+// A client that may retry a POST sends the same Idempotency-Key header with
+// every attempt. The first complete response is stored under a hash of the
+// route and key, next to a fingerprint of the request, and later attempts get
+// that response back instead of running the handler again; a retry whose
+// fingerprint differs was not a retry, and is refused. Server errors are not
+// stored, so a request that failed for a transient reason can be retried
+// for real. KV has no locks, so two attempts racing each other may both run.
+
+use crate::hashing::{self, Sha256};
+use crate::kv::{self, Pending};
+
+pub const MAX_KEY_LENGTH: usize = 255;
+
+// Larger responses are returned without being stored
+pub const MAX_STORED_BYTES: usize = 1024 * 1024;
+
+pub enum Lookup {
+    // No earlier attempt has completed
+    New,
+    // The earlier attempt's response
+    Replay(String),
+    // The key was used for a different request
+    Mismatch,
+}
+
+// Printable ASCII, as for other header values
+pub fn is_valid_key(key: &str) -> bool {
+    (1..=MAX_KEY_LENGTH).contains(&key.len()) && key.bytes().all(|b| (b' '..=b'~').contains(&b))
+}
+
+fn sha256_hex(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Lengths keep ("ab", "c") and ("a", "bc") apart
+        hasher.update(&(part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hashing::to_hex(&hasher.finalize())
+}
+
+fn storage_key(route: &str, key: &str) -> String {
+    format!("idempotency:{}", sha256_hex(&[route.as_bytes(), key.as_bytes()]))
+}
+
+// What makes two attempts the same request: the query and the body
+pub fn fingerprint(query: &str, body: &[u8]) -> String {
+    sha256_hex(&[query.as_bytes(), body])
+}
+
+pub fn lookup(route: &str, key: &str, fingerprint: &str) -> Result<Lookup, Pending> {
+    let Some(stored) = kv::get(&storage_key(route, key))? else {
+        return Ok(Lookup::New);
+    };
+    Ok(match stored.split_once('\n') {
+        Some((stored_fingerprint, response)) if stored_fingerprint == fingerprint => Lookup::Replay(response.to_string()),
+        Some(_) => Lookup::Mismatch,
+        None => Lookup::New,
+    })
+}
+
+pub fn store(route: &str, key: &str, fingerprint: &str, response: &str, ttl_seconds: u32) {
+    if response.len() <= MAX_STORED_BYTES {
+        kv::put(&storage_key(route, key), &format!("{}\n{}", fingerprint, response), ttl_seconds);
+    }
+}
@@ -0,0 +1,83 @@
+// ULID and NanoID generation from host time and host randomness
+// ULIDs are 48 bits of Unix milliseconds followed by 80 random bits in
+// Crockford base32. Within one millisecond (which inside a Worker is a whole
+// request) the random part is incremented instead of redrawn, so IDs from
+// the same isolate always sort in creation order.
+
+use std::cell::Cell;
+
+use crate::ffi;
+use crate::host;
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// URL-safe alphabet used by the reference NanoID implementation
+pub const NANOID_ALPHABET: &str = "useandom-26T198340PX75pxJACKVERYMINDBUSHWOLF_GQZbfghjklqvwyzrict";
+pub const NANOID_LENGTH: usize = 21;
+
+const MAX_TIMESTAMP: u64 = (1 << 48) - 1;
+
+thread_local! {
+    // Timestamp and random part of the previous ULID
+    static LAST_ULID: Cell<(u64, u128)> = const { Cell::new((0, 0)) };
+}
+
+fn random_u80() -> u128 {
+    let mut bytes = [0u8; 16];
+    host::random_bytes(&mut bytes[6..]);
+    u128::from_be_bytes(bytes)
+}
+
+pub fn ulid() -> String {
+    let now = (host::now_ms().max(0.0) as u64).min(MAX_TIMESTAMP);
+    let (last_time, last_random) = LAST_ULID.get();
+    // A clock that went backwards reuses the last timestamp; an exhausted
+    // random part moves on to the next millisecond
+    let (time, random) = if now > last_time {
+        (now, random_u80())
+    } else if last_random < (1 << 80) - 1 {
+        (last_time, last_random + 1)
+    } else {
+        ((last_time + 1).min(MAX_TIMESTAMP), random_u80())
+    };
+    LAST_ULID.set((time, random));
+
+    let value = (time as u128) << 80 | random;
+    (0..26).rev().map(|i| CROCKFORD[(value >> (i * 5)) as usize & 31] as char).collect()
+}
+
+// Draws bytes and masks them down to the next power of two above the
+// alphabet size, rejecting out-of-range values to avoid modulo bias
+pub fn nanoid(alphabet: &[char], length: usize) -> String {
+    let mask = alphabet.len().next_power_of_two() - 1;
+    let mut id = String::with_capacity(length);
+    let mut produced = 0;
+    let mut bytes = [0u8; 64];
+    while produced < length {
+        host::random_bytes(&mut bytes);
+        for &byte in &bytes {
+            if let Some(&c) = alphabet.get(byte as usize & mask) {
+                id.push(c);
+                produced += 1;
+                if produced == length {
+                    break;
+                }
+            }
+        }
+    }
+    id
+}
+
+// Raw exports for the host; free the results with free_bytes
+#[no_mangle]
+pub extern "C" fn ulid_new() -> *mut u8 {
+    ffi::output(ulid().as_bytes())
+}
+
+// NanoID over the default alphabet; a length of 0 means NANOID_LENGTH
+#[no_mangle]
+pub extern "C" fn nanoid_new(length: usize) -> *mut u8 {
+    let alphabet: Vec<char> = NANOID_ALPHABET.chars().collect();
+    let length = if length == 0 { NANOID_LENGTH } else { length.min(256) };
+    ffi::output(nanoid(&alphabet, length).as_bytes())
+}
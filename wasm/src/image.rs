@@ -0,0 +1,57 @@
+// Decoded images as 8-bit RGBA pixels, row by row from the top left
+// Codecs such as png convert to and from this form.
+
+use alloc::vec::Vec;
+
+fn premultiply(pixel: &[u8]) -> [f32; 4] {
+    let alpha = f32::from(pixel[3]) / 255.0;
+    [f32::from(pixel[0]) * alpha, f32::from(pixel[1]) * alpha, f32::from(pixel[2]) * alpha, f32::from(pixel[3])]
+}
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl Image {
+    pub fn is_opaque(&self) -> bool {
+        self.rgba.chunks_exact(4).all(|pixel| pixel[3] == 255)
+    }
+
+    // Bilinear resampling with pixel centres aligned, on premultiplied alpha
+    // so transparent pixels do not bleed their colour into the edges
+    pub fn resize(&self, width: usize, height: usize) -> Image {
+        // Source coordinate, the index at or below it and the weight of the
+        // next index, per output row or column
+        let sample = |out: usize, out_size: usize, in_size: usize| {
+            let position = ((out as f32 + 0.5) * in_size as f32 / out_size as f32 - 0.5).max(0.0);
+            // Truncation is floor here, as position is not negative
+            let low = (position as usize).min(in_size - 1);
+            (low, (low + 1).min(in_size - 1), position - low as f32)
+        };
+        let columns: Vec<(usize, usize, f32)> = (0..width).map(|x| sample(x, width, self.width)).collect();
+
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let (top, bottom, dy) = sample(y, height, self.height);
+            for &(left, right, dx) in &columns {
+                let at = |x: usize, y: usize| premultiply(&self.rgba[(y * self.width + x) * 4..][..4]);
+                let (top_left, top_right, bottom_left, bottom_right) = (at(left, top), at(right, top), at(left, bottom), at(right, bottom));
+                let mut pixel = [0f32; 4];
+                for (channel, value) in pixel.iter_mut().enumerate() {
+                    let upper = top_left[channel] * (1.0 - dx) + top_right[channel] * dx;
+                    let lower = bottom_left[channel] * (1.0 - dx) + bottom_right[channel] * dx;
+                    *value = upper * (1.0 - dy) + lower * dy;
+                }
+                let alpha = pixel[3] / 255.0;
+                for &value in &pixel[..3] {
+                    let colour = if alpha > 0.0 { value / alpha } else { 0.0 };
+                    rgba.push((colour + 0.5).min(255.0) as u8);
+                }
+                rgba.push((pixel[3] + 0.5).min(255.0) as u8);
+            }
+        }
+        Image { width, height, rgba }
+    }
+}
@@ -0,0 +1,80 @@
+// JPEG metadata removal without decoding the image
+// Segments before the first scan are copied except those carrying metadata:
+// EXIF (including its GPS position and thumbnail), XMP, ICC colour profiles,
+// Photoshop/IPTC records and comments. JFIF and Adobe segments stay, as
+// decoders need them to read the colours right. Everything from the start of
+// scan on is copied as is; metadata never follows it in practice. Removing
+// an ICC profile can shift colours slightly, so callers may keep it.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub const SOI: [u8; 2] = [0xFF, 0xD8];
+const SOS: u8 = 0xDA;
+const EOI: u8 = 0xD9;
+
+// What kind of metadata a segment holds, if any
+fn metadata(marker: u8, body: &[u8]) -> Option<&'static str> {
+    match marker {
+        0xE1 if body.starts_with(b"Exif\0") => Some("exif"),
+        0xE1 if body.starts_with(b"http://ns.adobe.com/xap/1.0/\0") || body.starts_with(b"http://ns.adobe.com/xmp/extension/\0") => Some("xmp"),
+        0xE2 if body.starts_with(b"ICC_PROFILE\0") => Some("icc"),
+        0xED if body.starts_with(b"Photoshop 3.0\0") => Some("iptc"),
+        0xFE => Some("comment"),
+        _ => None,
+    }
+}
+
+pub const KINDS: [&str; 5] = ["exif", "xmp", "icc", "iptc", "comment"];
+
+// The image without metadata other than the `keep` kinds, and the kind of
+// each segment removed, in file order
+pub fn strip(data: &[u8], keep: &[&str]) -> Result<(Vec<u8>, Vec<&'static str>), String> {
+    if !data.starts_with(&SOI) {
+        return Err("Not a JPEG file".to_string());
+    }
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&SOI);
+    let mut removed = Vec::new();
+    let mut pos = SOI.len();
+    loop {
+        if data.get(pos) != Some(&0xFF) {
+            return Err(format!("Expected a marker at byte {}", pos));
+        }
+        // Any number of 0xFF fill bytes may precede a marker
+        while data.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let Some(&marker) = data.get(pos + 1) else {
+            return Err("JPEG ends before its image data".to_string());
+        };
+        match marker {
+            EOI => return Err("JPEG has no image data".to_string()),
+            // Markers without a length field
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&data[pos..pos + 2]);
+                pos += 2;
+                continue;
+            }
+            _ => {}
+        }
+        let length = data.get(pos + 2..pos + 4).map(|length| u16::from_be_bytes([length[0], length[1]]) as usize);
+        let segment = match length {
+            Some(length) if length >= 2 => data.get(pos..pos + 2 + length),
+            _ => None,
+        };
+        let Some(segment) = segment else {
+            return Err(format!("Segment at byte {} runs past the end", pos));
+        };
+        if marker == SOS {
+            out.extend_from_slice(&data[pos..]);
+            return Ok((out, removed));
+        }
+        match metadata(marker, &segment[4..]) {
+            Some(kind) if !keep.contains(&kind) => removed.push(kind),
+            _ => out.extend_from_slice(segment),
+        }
+        pos += segment.len();
+    }
+}
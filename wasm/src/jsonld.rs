@@ -0,0 +1,339 @@
+// JSON-LD structured data checks, after Google's rich result requirements
+// A block may hold one item, a list of them or an @graph; each item needs a
+// schema.org @context (its own or the block's) and an @type. Articles
+// (Article, NewsArticle, BlogPosting) and products (Product) are checked
+// property by property: errors are what keeps an item from being eligible,
+// warnings what Google recommends. Items of other types are only checked for
+// @context and @type.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::json::Value;
+use crate::url;
+
+const ARTICLE_TYPES: [&str; 3] = ["Article", "NewsArticle", "BlogPosting"];
+const MAX_HEADLINE_CHARS: usize = 110;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+pub struct Issue {
+    pub severity: Severity,
+    // Where in the item, such as "offers[0].priceCurrency"; empty for the item
+    pub path: String,
+    pub message: String,
+}
+
+pub struct Item {
+    // Where in the block, such as "@graph[1]"; empty for the block itself
+    pub path: String,
+    pub types: Vec<String>,
+    // Whether its type has checks beyond @context and @type
+    pub checked: bool,
+    pub issues: Vec<Issue>,
+}
+
+fn member<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(members) => members.iter().find(|(key, _)| key == name).map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+fn text<'a>(value: &'a Value, name: &str) -> Option<&'a str> {
+    match member(value, name) {
+        Some(Value::String(text)) => Some(text.trim()).filter(|text| !text.is_empty()),
+        _ => None,
+    }
+}
+
+// A property given once or as a list
+fn each(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(value) => alloc::vec![value],
+        None => Vec::new(),
+    }
+}
+
+fn types(value: &Value) -> Vec<String> {
+    each(member(value, "@type"))
+        .into_iter()
+        .filter_map(|value| match value {
+            // "schema:Product" and "https://schema.org/Product" name Product too
+            Value::String(name) => Some(name.rsplit(['/', ':']).next().unwrap_or(name).to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_schema_org(context: &Value) -> bool {
+    match context {
+        Value::String(context) => context.trim_end_matches('/').ends_with("schema.org"),
+        Value::Array(items) => items.iter().any(is_schema_org),
+        Value::Object(members) => members.iter().any(|(_, value)| is_schema_org(value)),
+        _ => false,
+    }
+}
+
+fn is_web_url(text: &str) -> bool {
+    url::parse(text).is_ok_and(|parsed| parsed.scheme == "http" || parsed.scheme == "https")
+}
+
+fn digits(text: &str, count: usize) -> Option<u32> {
+    (text.len() == count && text.bytes().all(|b| b.is_ascii_digit())).then(|| text.parse().ok()).flatten()
+}
+
+// "2024-05-01", "2024-05-01T09:30", "2024-05-01T09:30:00.5+02:00", ...
+pub fn is_iso8601(text: &str) -> bool {
+    let (date, time) = text.split_once('T').map_or((text, None), |(date, time)| (date, Some(time)));
+    let mut parts = date.split('-');
+    let (Some(year), Some(month), Some(day), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    let (Some(_), Some(month), Some(day)) = (digits(year, 4), digits(month, 2), digits(day, 2)) else {
+        return false;
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return false;
+    }
+    let Some(time) = time else {
+        return true;
+    };
+    let (clock, zone) = match time.find(['Z', '+', '-']) {
+        Some(index) => time.split_at(index),
+        None => (time, ""),
+    };
+    let zone_valid = match zone.as_bytes().first() {
+        None => true,
+        Some(b'Z') => zone.len() == 1,
+        Some(_) => zone[1..].split_once(':').is_some_and(|(hours, minutes)| {
+            digits(hours, 2).is_some_and(|hours| hours <= 14) && digits(minutes, 2).is_some_and(|minutes| minutes < 60)
+        }),
+    };
+    let (clock, fraction) = clock.split_once('.').unwrap_or((clock, "0"));
+    let fields: Vec<Option<u32>> = clock.split(':').map(|field| digits(field, 2)).collect();
+    let clock_valid = match fields[..] {
+        [Some(hours), Some(minutes)] => hours < 24 && minutes < 60,
+        [Some(hours), Some(minutes), Some(seconds)] => hours < 24 && minutes < 60 && seconds <= 60,
+        _ => false,
+    };
+    zone_valid && clock_valid && !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(number) => Some(*number),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+struct Checker {
+    issues: Vec<Issue>,
+}
+
+impl Checker {
+    fn error(&mut self, path: &str, message: String) {
+        self.issues.push(Issue { severity: Severity::Error, path: path.to_string(), message });
+    }
+
+    fn warning(&mut self, path: &str, message: String) {
+        self.issues.push(Issue { severity: Severity::Warning, path: path.to_string(), message });
+    }
+
+    // Each image must be a URL or an ImageObject with one
+    fn images(&mut self, item: &Value) {
+        let images = each(member(item, "image"));
+        if images.is_empty() {
+            self.warning("image", "Missing image; add one or more image URLs".to_string());
+        }
+        for (index, image) in images.into_iter().enumerate() {
+            let url = match image {
+                Value::String(url) => Some(url.as_str()),
+                _ => text(image, "url").or(text(image, "contentUrl")),
+            };
+            if !url.is_some_and(is_web_url) {
+                self.error(&format!("image[{}]", index), "Image must be an absolute http(s) URL or an ImageObject with a url".to_string());
+            }
+        }
+    }
+
+    fn date(&mut self, item: &Value, name: &str) {
+        match member(item, name) {
+            None => self.warning(name, format!("Missing {}; add it as an ISO 8601 date, e.g. 2024-05-01T09:30:00+02:00", name)),
+            Some(Value::String(date)) if is_iso8601(date.trim()) => {}
+            Some(_) => self.error(name, format!("{} must be an ISO 8601 date, e.g. 2024-05-01T09:30:00+02:00", name)),
+        }
+    }
+
+    fn article(&mut self, item: &Value) {
+        match text(item, "headline") {
+            None => self.error("headline", "Missing headline; add the article's title".to_string()),
+            Some(headline) if headline.chars().count() > MAX_HEADLINE_CHARS => {
+                self.warning("headline", format!("Headline is {} characters; Google may cut it after {}", headline.chars().count(), MAX_HEADLINE_CHARS))
+            }
+            Some(_) => {}
+        }
+        self.images(item);
+        self.date(item, "datePublished");
+        if member(item, "dateModified").is_some() {
+            self.date(item, "dateModified");
+        }
+        let authors = each(member(item, "author"));
+        if authors.is_empty() {
+            self.warning("author", "Missing author; add a Person or Organization with a name".to_string());
+        }
+        for (index, author) in authors.into_iter().enumerate() {
+            let path = format!("author[{}]", index);
+            match author {
+                Value::String(_) => self.warning(&path, "Author is plain text; use a Person or Organization with a name".to_string()),
+                Value::Object(_) if text(author, "name").is_none() => self.error(&format!("{}.name", path), "Author needs a name".to_string()),
+                Value::Object(_) => {
+                    if text(author, "url").is_some_and(|url| !is_web_url(url)) {
+                        self.error(&format!("{}.url", path), "Author url must be an absolute http(s) URL".to_string());
+                    }
+                }
+                _ => self.error(&path, "Author must be a Person or Organization".to_string()),
+            }
+        }
+    }
+
+    fn offer(&mut self, offer: &Value, path: &str) {
+        let aggregate = types(offer).iter().any(|name| name == "AggregateOffer");
+        let (price_name, price) = if aggregate {
+            ("lowPrice", member(offer, "lowPrice"))
+        } else {
+            ("price", member(offer, "price").or(member(offer, "priceSpecification").and_then(|specification| member(specification, "price"))))
+        };
+        match (price, number(price)) {
+            (None, _) => self.error(&format!("{}.{}", path, price_name), format!("Missing {}; give it as a number such as 19.99", price_name)),
+            (Some(_), None) => self.error(&format!("{}.{}", path, price_name), format!("{} must be a number without a currency symbol, such as 19.99", price_name)),
+            (Some(_), Some(price)) if price < 0.0 => self.error(&format!("{}.{}", path, price_name), format!("{} must not be negative", price_name)),
+            _ => {}
+        }
+        let currency = text(offer, "priceCurrency")
+            .or(member(offer, "priceSpecification").and_then(|specification| text(specification, "priceCurrency")));
+        match currency {
+            None => self.error(&format!("{}.priceCurrency", path), "Missing priceCurrency; add an ISO 4217 code such as EUR".to_string()),
+            Some(code) if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_uppercase()) => {
+                self.error(&format!("{}.priceCurrency", path), format!("priceCurrency '{}' is not an ISO 4217 code such as EUR", code))
+            }
+            Some(_) => {}
+        }
+        if aggregate {
+            if member(offer, "offerCount").is_none() {
+                self.warning(&format!("{}.offerCount", path), "Missing offerCount".to_string());
+            }
+        } else {
+            match text(offer, "availability") {
+                None => self.warning(&format!("{}.availability", path), "Missing availability, e.g. https://schema.org/InStock".to_string()),
+                Some(availability) if !availability.contains("schema.org/") => self.warning(
+                    &format!("{}.availability", path),
+                    format!("availability should be a schema.org URL, e.g. https://schema.org/{}", availability),
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn rating(&mut self, rating: &Value, path: &str, needs_count: bool) {
+        let Some(value) = number(member(rating, "ratingValue")) else {
+            self.error(&format!("{}.ratingValue", path), "Missing ratingValue; give it as a number".to_string());
+            return;
+        };
+        let best = number(member(rating, "bestRating")).unwrap_or(5.0);
+        let worst = number(member(rating, "worstRating")).unwrap_or(1.0);
+        if value < worst || value > best {
+            self.error(&format!("{}.ratingValue", path), format!("ratingValue {} is outside {} to {}; set bestRating and worstRating for other scales", value, worst, best));
+        }
+        if needs_count && number(member(rating, "ratingCount")).or(number(member(rating, "reviewCount"))).is_none() {
+            self.error(&format!("{}.ratingCount", path), "Missing ratingCount or reviewCount".to_string());
+        }
+    }
+
+    fn product(&mut self, item: &Value) {
+        if text(item, "name").is_none() {
+            self.error("name", "Missing name; add the product's name".to_string());
+        }
+        let (offers, reviews) = (each(member(item, "offers")), each(member(item, "review")));
+        let rating = member(item, "aggregateRating");
+        if offers.is_empty() && reviews.is_empty() && rating.is_none() {
+            self.error("offers", "Add offers, review or aggregateRating; Google needs one of them for product results".to_string());
+        }
+        for (index, offer) in offers.into_iter().enumerate() {
+            self.offer(offer, &format!("offers[{}]", index));
+        }
+        for (index, review) in reviews.into_iter().enumerate() {
+            let path = format!("review[{}]", index);
+            if member(review, "author").is_none() {
+                self.error(&format!("{}.author", path), "Review needs an author".to_string());
+            }
+            match member(review, "reviewRating") {
+                Some(rating) => self.rating(rating, &format!("{}.reviewRating", path), false),
+                None => self.error(&format!("{}.reviewRating", path), "Review needs a reviewRating with a ratingValue".to_string()),
+            }
+        }
+        if let Some(rating) = rating {
+            self.rating(rating, "aggregateRating", true);
+        }
+        self.images(item);
+        if text(item, "description").is_none() {
+            self.warning("description", "Missing description".to_string());
+        }
+    }
+}
+
+fn check(item: &Value, path: String, context: Option<&Value>) -> Item {
+    let mut checker = Checker { issues: Vec::new() };
+    if !matches!(item, Value::Object(_)) {
+        checker.error("", "Item must be a JSON object".to_string());
+        return Item { path, types: Vec::new(), checked: false, issues: checker.issues };
+    }
+    match member(item, "@context").or(context) {
+        None => checker.error("@context", "Missing @context; add \"@context\": \"https://schema.org\"".to_string()),
+        Some(context) if !is_schema_org(context) => checker.error("@context", "@context is not schema.org; use \"https://schema.org\"".to_string()),
+        Some(_) => {}
+    }
+    let types = types(item);
+    if types.is_empty() {
+        checker.error("@type", "Missing @type, e.g. \"Product\" or \"Article\"".to_string());
+    }
+    let article = types.iter().any(|name| ARTICLE_TYPES.contains(&name.as_str()));
+    let product = types.iter().any(|name| name == "Product");
+    if article {
+        checker.article(item);
+    }
+    if product {
+        checker.product(item);
+    }
+    Item { path, types, checked: article || product, issues: checker.issues }
+}
+
+// The items of one parsed block
+pub fn validate(block: &Value) -> Vec<Item> {
+    match block {
+        Value::Array(items) => items.iter().enumerate().map(|(index, item)| check(item, format!("[{}]", index), None)).collect(),
+        _ => match member(block, "@graph") {
+            Some(Value::Array(items)) => {
+                let context = member(block, "@context");
+                items.iter().enumerate().map(|(index, item)| check(item, format!("@graph[{}]", index), context)).collect()
+            }
+            _ => alloc::vec![check(block, String::new(), None)],
+        },
+    }
+}
@@ -0,0 +1,101 @@
+// K-means clustering of N-dimensional points
+// Centroids are seeded with k-means++ from the PRNG, then refined with
+// Lloyd's algorithm until assignments stop changing or the iteration budget
+// runs out.
+
+use crate::rand::Rng;
+
+pub struct Clustering {
+    pub centroids: Vec<Vec<f64>>,
+    pub assignments: Vec<usize>,
+    pub iterations: usize,
+    pub converged: bool,
+    // Sum of squared distances from each point to its centroid
+    pub inertia: f64,
+}
+
+fn distance_squared(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum()
+}
+
+fn nearest(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
+    centroids
+        .iter()
+        .map(|centroid| distance_squared(point, centroid))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+}
+
+// Uniform draw in [0, 1)
+fn unit(rng: &mut Rng) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Each new centroid is a point picked with probability proportional to its
+// squared distance from the nearest centroid chosen so far
+fn seed_centroids(points: &[Vec<f64>], k: usize, rng: &mut Rng) -> Vec<Vec<f64>> {
+    let mut centroids = vec![rng.choose(points).clone()];
+    while centroids.len() < k {
+        let weights: Vec<f64> = points.iter().map(|point| nearest(point, &centroids).1).collect();
+        let total: f64 = weights.iter().sum();
+        if total == 0.0 {
+            // Fewer distinct points than clusters; duplicates are unavoidable
+            centroids.push(rng.choose(points).clone());
+            continue;
+        }
+        let mut target = unit(rng) * total;
+        let index = weights
+            .iter()
+            .position(|&weight| {
+                target -= weight;
+                target < 0.0
+            })
+            .unwrap_or(points.len() - 1);
+        centroids.push(points[index].clone());
+    }
+    centroids
+}
+
+// `points` must be non-empty, share one dimension, and 1 <= k <= points.len()
+pub fn cluster(points: &[Vec<f64>], k: usize, max_iterations: usize, rng: &mut Rng) -> Clustering {
+    let dimension = points[0].len();
+    let mut centroids = seed_centroids(points, k, rng);
+    let mut assignments = vec![usize::MAX; points.len()];
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < max_iterations {
+        iterations += 1;
+        let mut changed = false;
+        for (point, assignment) in points.iter().zip(assignments.iter_mut()) {
+            let (closest, _) = nearest(point, &centroids);
+            if *assignment != closest {
+                *assignment = closest;
+                changed = true;
+            }
+        }
+        if !changed {
+            converged = true;
+            break;
+        }
+
+        let mut sums = vec![vec![0.0; dimension]; k];
+        let mut counts = vec![0usize; k];
+        for (point, &cluster) in points.iter().zip(&assignments) {
+            counts[cluster] += 1;
+            for (sum, value) in sums[cluster].iter_mut().zip(point) {
+                *sum += value;
+            }
+        }
+        // A cluster that lost all its points keeps its previous centroid
+        for ((centroid, sum), &count) in centroids.iter_mut().zip(sums).zip(&counts) {
+            if count > 0 {
+                *centroid = sum.into_iter().map(|sum| sum / count as f64).collect();
+            }
+        }
+    }
+
+    let inertia = points.iter().zip(&assignments).map(|(point, &cluster)| distance_squared(point, &centroids[cluster])).sum();
+    Clustering { centroids, assignments, iterations, converged, inertia }
+}
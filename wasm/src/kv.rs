@@ -0,0 +1,82 @@
+// Key-value storage through host imports
+// The Worker backs these with the Workers KV namespace bound as KV, or with an
+// isolate-local Map when none is bound. KV reads are asynchronous in
+// JavaScript and WASM imports are not, so the host answers reads only from
+// values it has already loaded for the request: an unloaded key makes get()
+// return Pending, and once the handler returns the host fetches every missed
+// key and replays the whole request. Writes are buffered by the host, visible
+// to later reads in the same pass, and committed only after the final pass.
+//
+// KV is eventually consistent and has no transactions, so read-modify-write
+// sequences (like hit counters) can lose updates under concurrent traffic.
+
+// Handlers should stop and return kv_pending_response() when they see this;
+// the response is discarded because the host replays the request
+pub struct Pending;
+
+thread_local! {
+    static SAW_PENDING: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Reports whether the handler hit an unloaded key since the last call, so the
+// request entry point can tell a pass that will be replayed
+pub fn take_pending() -> bool {
+    SAW_PENDING.with(|flag| flag.replace(false))
+}
+
+// For other host calls answered between passes, such as fetch.rs
+#[cfg(target_arch = "wasm32")]
+pub fn mark_pending() {
+    SAW_PENDING.with(|flag| flag.set(true));
+}
+
+#[cfg(target_arch = "wasm32")]
+mod imports {
+    #[link(wasm_import_module = "env")]
+    extern "C" {
+        // 1 = found (value in a buffer from alloc), 0 = missing, -1 = pending
+        pub fn host_kv_get(key_ptr: *const u8, key_len: usize, value_ptr: *mut *mut u8, value_len: *mut usize) -> i32;
+        pub fn host_kv_put(key_ptr: *const u8, key_len: usize, value_ptr: *const u8, value_len: usize, ttl_seconds: u32);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn get(key: &str) -> Result<Option<String>, Pending> {
+    let mut value_ptr = std::ptr::null_mut();
+    let mut value_len = 0;
+    match unsafe { imports::host_kv_get(key.as_ptr(), key.len(), &mut value_ptr, &mut value_len) } {
+        // The host encodes values with TextEncoder, so they are always UTF-8
+        1 => {
+            let bytes = crate::ffi::reclaim(value_ptr, value_len).unwrap_or_default();
+            Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+        }
+        0 => Ok(None),
+        _ => {
+            mark_pending();
+            Err(Pending)
+        }
+    }
+}
+
+// A ttl of 0 keeps the value until it is overwritten; Workers KV
+// raises shorter non-zero TTLs to its 60 second minimum
+#[cfg(target_arch = "wasm32")]
+pub fn put(key: &str, value: &str, ttl_seconds: u32) {
+    unsafe { imports::host_kv_put(key.as_ptr(), key.len(), value.as_ptr(), value.len(), ttl_seconds) }
+}
+
+// Native builds keep values in memory (without expiry) so the crate links off-wasm
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static STORE: std::cell::RefCell<std::collections::HashMap<String, String>> = Default::default();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get(key: &str) -> Result<Option<String>, Pending> {
+    Ok(STORE.with(|store| store.borrow().get(key).cloned()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn put(key: &str, value: &str, _ttl_seconds: u32) {
+    crate::arena::outside(|| STORE.with(|store| store.borrow_mut().insert(key.to_string(), value.to_string())));
+}
@@ -0,0 +1,105 @@
+// Language identification from character trigrams
+// Profiles are built from the sample texts in data/languages.txt, which is
+// compiled into the module. Texts are scored with multinomial naive Bayes
+// (add-one smoothing), so confidences are posterior probabilities under a
+// uniform prior.
+
+use crate::arena;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+
+const SAMPLES: &str = include_str!("data/languages.txt");
+
+struct Profile {
+    code: &'static str,
+    name: &'static str,
+    counts: HashMap<String, u32>,
+    total: u32,
+}
+
+pub struct Candidate {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub confidence: f64,
+}
+
+thread_local! {
+    // Built on first use, or by warmup, and kept for the lifetime of the instance
+    static PROFILES: OnceCell<Vec<Profile>> = const { OnceCell::new() };
+}
+
+fn build_profiles() -> Vec<Profile> {
+    SAMPLES
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.is_empty())
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '|');
+            let (code, name, text) = (fields.next()?, fields.next()?, fields.next()?);
+            let mut counts = HashMap::new();
+            for trigram in trigrams(text) {
+                *counts.entry(trigram).or_insert(0) += 1;
+            }
+            let total = counts.values().sum();
+            Some(Profile { code, name, counts, total })
+        })
+        .collect()
+}
+
+fn with_profiles<T>(f: impl FnOnce(&[Profile]) -> T) -> T {
+    PROFILES.with(|profiles| f(profiles.get_or_init(|| arena::outside(build_profiles))))
+}
+
+pub fn warm() {
+    with_profiles(|_| ());
+}
+
+pub fn is_warm() -> bool {
+    PROFILES.with(|profiles| profiles.get().is_some())
+}
+
+// Trigrams of each lowercased word padded with spaces, e.g. " th", "the", "he "
+pub fn trigrams(text: &str) -> Vec<String> {
+    let mut trigrams = Vec::new();
+    for word in text.split(|c: char| !c.is_alphabetic()).filter(|word| !word.is_empty()) {
+        let chars: Vec<char> = std::iter::once(' ')
+            .chain(word.chars().flat_map(char::to_lowercase))
+            .chain(std::iter::once(' '))
+            .collect();
+        trigrams.extend(chars.windows(3).map(|window| window.iter().collect::<String>()));
+    }
+    trigrams
+}
+
+pub fn language_count() -> usize {
+    with_profiles(|profiles| profiles.len())
+}
+
+// Every language ranked by posterior probability, most likely first
+pub fn detect(text: &str) -> Vec<Candidate> {
+    let trigrams = trigrams(text);
+    with_profiles(|profiles| {
+        let vocabulary = profiles.iter().map(|profile| profile.counts.len()).sum::<usize>() as f64;
+        let log_likelihoods: Vec<f64> = profiles
+            .iter()
+            .map(|profile| {
+                let denominator = (profile.total as f64 + vocabulary).ln();
+                trigrams
+                    .iter()
+                    .map(|trigram| (*profile.counts.get(trigram).unwrap_or(&0) as f64 + 1.0).ln() - denominator)
+                    .sum()
+            })
+            .collect();
+
+        // Normalise in log space to avoid underflow on long texts
+        let best = log_likelihoods.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = log_likelihoods.iter().map(|value| (value - best).exp()).collect();
+        let total: f64 = weights.iter().sum();
+        let mut candidates: Vec<Candidate> = profiles
+            .iter()
+            .zip(weights)
+            .map(|(profile, weight)| Candidate { code: profile.code, name: profile.name, confidence: weight / total })
+            .collect();
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        candidates
+    })
+}
@@ -1,186 +1,174 @@
 // Basic WebAssembly exports for Cloudflare Workers
 // Using raw exports instead of wasm-bindgen for better static import compatibility
-
-use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
-
-// Main request handler that processes HTTP requests
-#[no_mangle]
-pub extern "C" fn handle_request(
-    method_ptr: *const c_char,
-    url_ptr: *const c_char,
-    query_ptr: *const c_char,
-) -> *mut c_char {
-    unsafe {
-        // Debug: Add some validation
-        if method_ptr.is_null() || url_ptr.is_null() || query_ptr.is_null() {
-            let error_response = create_error_response(500, "Null pointer received");
-            return CString::new(error_response).unwrap().into_raw();
-        }
-        
-        let method = CStr::from_ptr(method_ptr).to_string_lossy();
-        let url = CStr::from_ptr(url_ptr).to_string_lossy();
-        let query = CStr::from_ptr(query_ptr).to_string_lossy();
-        
-        let response = match method.as_ref() {
-            "GET" => handle_get_request(&url, &query),
-            _ => create_error_response(405, "Method Not Allowed"),
-        };
-        
-        CString::new(response).unwrap().into_raw()
-    }
-}
-
-fn handle_get_request(url: &str, query: &str) -> String {
-    let path = url.split('?').next().unwrap_or(url);
-    
-    match path {
-        "/" => create_html_response(get_home_page()),
-        "/status" => create_json_response(&get_status_json()),
-        "/add" => handle_add_request(query),
-        "/factorial" => handle_factorial_request(query),
-        "/prime" => handle_prime_request(query),
-        "/fibonacci" => handle_fibonacci_request(query),
-        "/hash" => handle_hash_request(query),
-        _ => create_error_response(404, "Not Found"),
-    }
-}
-
-fn handle_add_request(query: &str) -> String {
-    let (a, b) = parse_two_numbers(query, "a", "b");
-    let result = add(a, b);
-    create_json_response(&format!(
-        r#"{{"operation":"add","inputs":{{"a":{},"b":{}}},"result":{}}}"#,
-        a, b, result
-    ))
-}
-
-fn handle_factorial_request(query: &str) -> String {
-    let n = parse_number(query, "n").unwrap_or(5);
-    if n > 20 {
-        return create_error_response(400, "Number must be between 0 and 20");
-    }
-    let result = factorial(n as u32);
-    create_json_response(&format!(
-        r#"{{"operation":"factorial","input":{},"result":"{}"}}"#,
-        n, result
-    ))
-}
-
-fn handle_prime_request(query: &str) -> String {
-    let n = parse_number(query, "n").unwrap_or(17);
-    let result = is_prime(n as u32) != 0;
-    create_json_response(&format!(
-        r#"{{"operation":"is_prime","input":{},"result":{}}}"#,
-        n, result
-    ))
-}
-
-fn handle_fibonacci_request(query: &str) -> String {
-    let n = parse_number(query, "n").unwrap_or(10);
-    if n > 40 {
-        return create_error_response(400, "Number must be between 0 and 40");
-    }
-    let result = fibonacci(n as u32);
-    create_json_response(&format!(
-        r#"{{"operation":"fibonacci","input":{},"result":"{}"}}"#,
-        n, result
-    ))
-}
-
-fn handle_hash_request(query: &str) -> String {
-    let input = parse_string(query, "input").unwrap_or("cloudflare".to_string());
-    let hash = simple_hash_string(&input);
-    create_json_response(&format!(
-        r#"{{"operation":"simple_hash","input":"{}","result":{}}}"#,
-        input, hash
-    ))
-}
-
-fn create_json_response(body: &str) -> String {
-    format!("200|application/json|{}", body)
-}
-
-fn create_html_response(body: String) -> String {
-    format!("200|text/html|{}", body)
-}
-
-fn create_error_response(status: u16, message: &str) -> String {
-    format!("{}|application/json|{{\"error\":\"{}\"}}", status, message)
-}
-
-fn get_status_json() -> String {
-    format!(
-        r#"{{"status":"ok","implementation":"Pure WebAssembly","timestamp":"{}","message":"Handled by WASM"}}"#,
-        "2024-01-01T00:00:00.000Z" // Would need JS to provide real timestamp
-    )
-}
-
-fn parse_number(query: &str, param: &str) -> Option<i32> {
-    query.split('&')
-        .find(|part| part.starts_with(&format!("{}=", param)))
-        .and_then(|part| part.split('=').nth(1))
-        .and_then(|value| value.parse().ok())
-}
-
-fn parse_two_numbers(query: &str, param1: &str, param2: &str) -> (i32, i32) {
-    let a = parse_number(query, param1).unwrap_or(0);
-    let b = parse_number(query, param2).unwrap_or(0);
-    (a, b)
-}
-
-fn parse_string(query: &str, param: &str) -> Option<String> {
-    query.split('&')
-        .find(|part| part.starts_with(&format!("{}=", param)))
-        .and_then(|part| part.split('=').nth(1))
-        .map(|value| urlencoding::decode(value).unwrap_or_default().to_string())
-}
-
-// Free the string allocated by handle_request
-#[no_mangle]
-pub extern "C" fn free_string(ptr: *mut c_char) {
-    unsafe {
-        if !ptr.is_null() {
-            let _ = CString::from_raw(ptr);
-        }
-    }
-}
-
-fn simple_hash_string(input: &str) -> u32 {
-    let bytes = input.as_bytes();
-    let mut hash: u32 = 5381;
-    for &byte in bytes {
-        hash = hash.wrapping_mul(33).wrapping_add(byte as u32);
-    }
-    hash
-}
-
-fn get_home_page() -> String {
-    r#"<!DOCTYPE html>
-<html>
-<head>
-    <title>WASM-Handled Worker</title>
-    <style>
-        body { font-family: Arial, sans-serif; max-width: 800px; margin: 0 auto; padding: 20px; }
-        .endpoint { background: #f5f5f5; padding: 15px; margin: 10px 0; border-radius: 5px; }
-    </style>
-</head>
-<body>
-    <h1>🦀 WASM-Handled Worker</h1>
-    <p>This request was processed entirely in WebAssembly!</p>
-    <div class="endpoint">
-        <h3>📊 Available Endpoints:</h3>
-        <ul>
-            <li><code>/status</code> - Check WASM status</li>
-            <li><code>/add?a=5&b=3</code> - Add two numbers</li>
-            <li><code>/factorial?n=5</code> - Calculate factorial</li>
-            <li><code>/prime?n=17</code> - Check if number is prime</li>
-            <li><code>/fibonacci?n=10</code> - Get Fibonacci number</li>
-            <li><code>/hash?input=test</code> - Simple hash function</li>
-        </ul>
-    </div>
-</body>
-</html>"#.to_string()
+//
+// The computational core (big integers, hashing, parsers) only needs `core`
+// and `alloc`, so it can be lifted into embedded code or other WASM hosts.
+// The Worker itself, with its host imports and per-isolate state, sits behind
+// the default `std` feature; without it the module carries just the core and
+// the integer exports below.
+#![cfg_attr(not(feature = "std"), no_std)]
+// Raw exports receive pointers from the JS host, which owns their validity
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+extern crate alloc;
+
+pub mod allocator;
+pub mod bencode;
+pub mod bignum;
+pub mod budget;
+pub mod cache_policy;
+pub mod combinatorics;
+pub mod compression;
+pub mod constants;
+pub mod dates;
+pub mod feeds;
+pub mod formatting;
+pub mod graphql;
+pub mod grpc;
+pub mod hashing;
+pub mod headers;
+pub mod html;
+pub mod html_rewrite;
+pub mod humanize;
+pub mod image;
+pub mod jpeg;
+pub mod json;
+pub mod jsonld;
+pub mod merkle;
+pub mod minify;
+pub mod morse;
+pub mod multipart;
+pub mod png;
+pub mod protobuf;
+pub mod ranges;
+pub mod robots;
+pub mod semver;
+pub mod simd;
+pub mod similarity;
+pub mod sorting;
+pub mod stemming;
+pub mod tar;
+pub mod url;
+pub mod validators;
+pub mod zip;
+
+#[cfg(feature = "std")]
+mod arena;
+#[cfg(feature = "std")]
+mod assets;
+#[cfg(feature = "std")]
+mod banner;
+#[cfg(feature = "std")]
+mod blob;
+#[cfg(feature = "std")]
+mod breaker;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod captcha;
+#[cfg(feature = "std")]
+mod charts;
+#[cfg(feature = "std")]
+mod ciphers;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+mod context;
+#[cfg(feature = "std")]
+mod counters;
+#[cfg(feature = "std")]
+mod dice;
+#[cfg(feature = "std")]
+mod endpoints;
+#[cfg(feature = "std")]
+mod ffi;
+#[cfg(feature = "std")]
+mod experiments;
+#[cfg(feature = "std")]
+mod fetch;
+#[cfg(feature = "std")]
+mod fft;
+#[cfg(feature = "std")]
+mod flags;
+#[cfg(feature = "std")]
+mod generate;
+#[cfg(feature = "std")]
+mod graph;
+#[cfg(feature = "std")]
+mod host;
+#[cfg(feature = "std")]
+mod hashring;
+#[cfg(feature = "std")]
+mod i18n;
+#[cfg(feature = "std")]
+mod idempotency;
+#[cfg(feature = "std")]
+mod ids;
+#[cfg(feature = "std")]
+mod kv;
+#[cfg(feature = "std")]
+mod kmeans;
+#[cfg(feature = "std")]
+mod langdetect;
+#[cfg(feature = "std")]
+mod life;
+#[cfg(feature = "std")]
+mod maze;
+#[cfg(feature = "std")]
+mod mime;
+#[cfg(feature = "std")]
+mod oauth;
+#[cfg(feature = "std")]
+mod params;
+#[cfg(feature = "std")]
+mod paste;
+#[cfg(feature = "std")]
+mod pow;
+#[cfg(feature = "std")]
+mod rand;
+#[cfg(feature = "std")]
+mod ratelimit;
+#[cfg(feature = "std")]
+mod regression;
+#[cfg(feature = "std")]
+mod response_cache;
+#[cfg(feature = "std")]
+mod retry;
+#[cfg(feature = "std")]
+mod routing;
+#[cfg(feature = "std")]
+mod rsa;
+#[cfg(feature = "std")]
+mod search;
+#[cfg(feature = "std")]
+mod security;
+#[cfg(feature = "std")]
+mod sentiment;
+#[cfg(feature = "std")]
+mod shortener;
+#[cfg(feature = "std")]
+mod snowflake;
+#[cfg(feature = "std")]
+mod sudoku;
+#[cfg(feature = "std")]
+mod svg;
+#[cfg(feature = "std")]
+mod text;
+#[cfg(feature = "std")]
+mod timing;
+#[cfg(feature = "std")]
+mod trie;
+#[cfg(feature = "std")]
+mod warmup;
+#[cfg(feature = "std")]
+mod webhook;
+#[cfg(feature = "std")]
+mod worker;
+
+#[cfg(all(not(feature = "std"), target_arch = "wasm32"))]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
 }
 
 // A simple function that adds two numbers
@@ -206,11 +194,11 @@ pub extern "C" fn is_prime(n: u32) -> i32 {
         false
     } else if n == 2 {
         true
-    } else if n % 2 == 0 {
+    } else if n.is_multiple_of(2) {
         false
     } else {
-        let sqrt_n = (n as f64).sqrt() as u32;
-        !(3..=sqrt_n).step_by(2).any(|i| n % i == 0)
+        // Squared in u64 so the bound cannot overflow; core has no f64 sqrt
+        !(3..).step_by(2).take_while(|&i: &u64| i * i <= n as u64).any(|i| (n as u64).is_multiple_of(i))
     };
     
     if result { 1 } else { 0 }
@@ -238,10 +226,6 @@ pub extern "C" fn fibonacci(n: u32) -> u64 {
 // Simple hash function that works with raw memory
 #[no_mangle]
 pub extern "C" fn simple_hash_bytes(ptr: *const u8, len: usize) -> u32 {
-    let input = unsafe { std::slice::from_raw_parts(ptr, len) };
-    let mut hash: u32 = 5381;
-    for &byte in input {
-        hash = hash.wrapping_mul(33).wrapping_add(byte as u32);
-    }
-    hash
+    let input: &[u8] = if ptr.is_null() || len == 0 { &[] } else { unsafe { core::slice::from_raw_parts(ptr, len) } };
+    simd::djb2(5381, input)
 } 
\ No newline at end of file
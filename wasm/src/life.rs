@@ -0,0 +1,86 @@
+// Conway's Game of Life (B3/S23) on a finite grid
+// Edges wrap around by default, so patterns such as gliders keep moving
+// instead of dying at the border.
+
+use crate::rand::Rng;
+
+pub const MAX_SIDE: usize = 256;
+
+pub struct Life {
+    pub width: usize,
+    pub height: usize,
+    pub wrap: bool,
+    cells: Vec<bool>,
+}
+
+impl Life {
+    // Each cell is alive with probability `density`
+    pub fn random(width: usize, height: usize, density: f64, rng: &mut Rng) -> Life {
+        let threshold = (density.clamp(0.0, 1.0) * u32::MAX as f64) as u64;
+        let cells = (0..width * height).map(|_| (rng.next_u64() >> 32) < threshold).collect();
+        Life { width, height, wrap: true, cells }
+    }
+
+    // Rows of '#', 'O', 'o', '*' or '1' for live cells and '.', '0' or ' '
+    // for dead ones; short rows are padded with dead cells
+    pub fn parse(text: &str) -> Result<Life, String> {
+        let rows: Vec<&str> = text.lines().map(|row| row.trim_end_matches('\r')).filter(|row| !row.is_empty()).collect();
+        let height = rows.len();
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        if height == 0 || width == 0 {
+            return Err("Grid is empty".to_string());
+        }
+        if width > MAX_SIDE || height > MAX_SIDE {
+            return Err(format!("Grid can be at most {}x{} cells", MAX_SIDE, MAX_SIDE));
+        }
+        let mut cells = vec![false; width * height];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                cells[y * width + x] = match c {
+                    '#' | 'O' | 'o' | '*' | '1' => true,
+                    '.' | '0' | ' ' | '-' => false,
+                    _ => return Err(format!("Invalid cell {:?} in row {}", c, y + 1)),
+                };
+            }
+        }
+        Ok(Life { width, height, wrap: true, cells })
+    }
+
+    fn alive(&self, x: isize, y: isize) -> bool {
+        let (width, height) = (self.width as isize, self.height as isize);
+        if self.wrap {
+            self.cells[(y.rem_euclid(height) * width + x.rem_euclid(width)) as usize]
+        } else {
+            (0..width).contains(&x) && (0..height).contains(&y) && self.cells[(y * width + x) as usize]
+        }
+    }
+
+    // Advance one generation; returns false when nothing changed
+    pub fn step(&mut self) -> bool {
+        let mut next = vec![false; self.cells.len()];
+        for y in 0..self.height as isize {
+            for x in 0..self.width as isize {
+                let neighbours = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)]
+                    .iter()
+                    .filter(|&&(dx, dy)| self.alive(x + dx, y + dy))
+                    .count();
+                let index = y as usize * self.width + x as usize;
+                next[index] = matches!((self.cells[index], neighbours), (true, 2) | (_, 3));
+            }
+        }
+        let changed = next != self.cells;
+        self.cells = next;
+        changed
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&alive| alive).count()
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        self.cells
+            .chunks(self.width)
+            .map(|row| row.iter().map(|&alive| if alive { '#' } else { '.' }).collect())
+            .collect()
+    }
+}
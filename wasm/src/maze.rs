@@ -0,0 +1,154 @@
+// Maze generation (recursive backtracker) and breadth-first solving
+// Each cell stores its remaining walls as bits; the maze is entered at the
+// top-left cell and solved to the bottom-right one.
+
+use crate::rand::Rng;
+use std::collections::VecDeque;
+
+pub const NORTH: u8 = 1;
+pub const EAST: u8 = 2;
+pub const SOUTH: u8 = 4;
+pub const WEST: u8 = 8;
+
+pub const MAX_SIDE: usize = 100;
+const SVG_CELL: usize = 20;
+
+pub struct Maze {
+    pub width: usize,
+    pub height: usize,
+    // Wall bits per cell, row-major
+    pub walls: Vec<u8>,
+}
+
+// (wall, opposite wall, dx, dy)
+const DIRECTIONS: [(u8, u8, isize, isize); 4] =
+    [(NORTH, SOUTH, 0, -1), (EAST, WEST, 1, 0), (SOUTH, NORTH, 0, 1), (WEST, EAST, -1, 0)];
+
+impl Maze {
+    // Depth-first carving with an explicit stack, so large mazes cannot
+    // overflow the WASM call stack
+    pub fn generate(width: usize, height: usize, rng: &mut Rng) -> Maze {
+        let mut maze = Maze { width, height, walls: vec![NORTH | EAST | SOUTH | WEST; width * height] };
+        let mut visited = vec![false; width * height];
+        let mut stack = vec![0];
+        visited[0] = true;
+        while let Some(&cell) = stack.last() {
+            let unvisited: Vec<(u8, u8, usize)> = DIRECTIONS
+                .iter()
+                .filter_map(|&(wall, opposite, dx, dy)| maze.neighbour(cell, dx, dy).map(|next| (wall, opposite, next)))
+                .filter(|&(_, _, next)| !visited[next])
+                .collect();
+            if unvisited.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let (wall, opposite, next) = *rng.choose(&unvisited);
+            maze.walls[cell] &= !wall;
+            maze.walls[next] &= !opposite;
+            visited[next] = true;
+            stack.push(next);
+        }
+        maze
+    }
+
+    fn neighbour(&self, cell: usize, dx: isize, dy: isize) -> Option<usize> {
+        let x = (cell % self.width) as isize + dx;
+        let y = (cell / self.width) as isize + dy;
+        let inside = (0..self.width as isize).contains(&x) && (0..self.height as isize).contains(&y);
+        inside.then(|| y as usize * self.width + x as usize)
+    }
+
+    // Cells on the path from the top-left to the bottom-right corner. A
+    // perfect maze always has exactly one such path.
+    pub fn solve(&self) -> Vec<usize> {
+        let target = self.walls.len() - 1;
+        let mut previous = vec![usize::MAX; self.walls.len()];
+        let mut queue = VecDeque::from([0]);
+        previous[0] = 0;
+        while let Some(cell) = queue.pop_front() {
+            if cell == target {
+                break;
+            }
+            for &(wall, _, dx, dy) in &DIRECTIONS {
+                if self.walls[cell] & wall != 0 {
+                    continue;
+                }
+                if let Some(next) = self.neighbour(cell, dx, dy).filter(|&next| previous[next] == usize::MAX) {
+                    previous[next] = cell;
+                    queue.push_back(next);
+                }
+            }
+        }
+        let mut path = vec![target];
+        while *path.last().unwrap() != 0 {
+            path.push(previous[*path.last().unwrap()]);
+        }
+        path.reverse();
+        path
+    }
+
+    pub fn to_ascii(&self, path: &[usize]) -> String {
+        let mut on_path = vec![false; self.walls.len()];
+        for &cell in path {
+            on_path[cell] = true;
+        }
+        let mut out = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push_str(if self.walls[y * self.width + x] & NORTH != 0 { "+---" } else { "+   " });
+            }
+            out.push_str("+\n");
+            for x in 0..self.width {
+                let cell = y * self.width + x;
+                out.push(if self.walls[cell] & WEST != 0 { '|' } else { ' ' });
+                out.push_str(if on_path[cell] { " * " } else { "   " });
+            }
+            out.push_str("|\n");
+        }
+        out.push_str(&"+---".repeat(self.width));
+        out.push_str("+\n");
+        out
+    }
+
+    pub fn to_svg(&self, path: &[usize]) -> String {
+        let (width, height) = (self.width * SVG_CELL, self.height * SVG_CELL);
+        let mut lines = String::new();
+        for (cell, &walls) in self.walls.iter().enumerate() {
+            let x = (cell % self.width) * SVG_CELL;
+            let y = (cell / self.width) * SVG_CELL;
+            // North and west walls per cell, plus the outer east and south edges
+            if walls & NORTH != 0 {
+                lines.push_str(&format!("M{} {}h{}", x, y, SVG_CELL));
+            }
+            if walls & WEST != 0 {
+                lines.push_str(&format!("M{} {}v{}", x, y, SVG_CELL));
+            }
+        }
+        lines.push_str(&format!("M{} 0v{}M0 {}h{}", width, height, height, width));
+
+        let mut solution = String::new();
+        if !path.is_empty() {
+            let points: Vec<String> = path
+                .iter()
+                .map(|&cell| {
+                    let x = (cell % self.width) * SVG_CELL + SVG_CELL / 2;
+                    let y = (cell / self.width) * SVG_CELL + SVG_CELL / 2;
+                    format!("{},{}", x, y)
+                })
+                .collect();
+            solution = format!(
+                r##"<polyline points="{}" fill="none" stroke="#e4572e" stroke-width="4" stroke-linejoin="round"/>"##,
+                points.join(" ")
+            );
+        }
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-2 -2 {} {}" width="{}" height="{}"><rect x="-2" y="-2" width="100%" height="100%" fill="white"/><path d="{}" stroke="black" stroke-width="2" stroke-linecap="square"/>{}</svg>"#,
+            width + 4,
+            height + 4,
+            width + 4,
+            height + 4,
+            lines,
+            solution
+        )
+    }
+}
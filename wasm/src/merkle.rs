@@ -0,0 +1,82 @@
+// Binary Merkle trees over SHA-256 with inclusion proofs
+// Leaves and interior nodes are domain separated as in RFC 6962
+// (H(0x00 || leaf) and H(0x01 || left || right)) so a leaf can never be
+// passed off as a node. An unpaired node at the end of a level is promoted
+// unchanged instead of being duplicated.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::hashing::Sha256;
+
+pub type Hash = [u8; 32];
+
+pub struct ProofStep {
+    pub sibling: Hash,
+    // Whether the sibling is hashed on the left of the running value
+    pub sibling_on_left: bool,
+}
+
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[0x00]);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize()
+}
+
+// Every level of the tree, from the leaves up to the single root
+fn levels(leaves: &[Hash]) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaves.to_vec()];
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+// Root hash and the inclusion proof for `index`; None for an empty tree or
+// an index out of range
+pub fn root_and_proof(leaves: &[Hash], index: usize) -> Option<(Hash, Vec<ProofStep>)> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let levels = levels(leaves);
+    let mut proof = Vec::new();
+    let mut position = index;
+    for level in &levels[..levels.len() - 1] {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            proof.push(ProofStep { sibling: level[sibling], sibling_on_left: sibling < position });
+        }
+        position /= 2;
+    }
+    Some((levels.last().unwrap()[0], proof))
+}
+
+// Recompute the root from a leaf hash and its proof
+pub fn verify(leaf: &Hash, proof: &[ProofStep], root: &Hash) -> bool {
+    let computed = proof.iter().fold(*leaf, |acc, step| {
+        if step.sibling_on_left {
+            node_hash(&step.sibling, &acc)
+        } else {
+            node_hash(&acc, &step.sibling)
+        }
+    });
+    &computed == root
+}
@@ -0,0 +1,102 @@
+// MIME type detection from magic bytes and file extensions
+
+use crate::ffi;
+
+pub struct Detected {
+    pub mime: &'static str,
+    pub description: &'static str,
+}
+
+// Leading byte signatures, checked in order
+const SIGNATURES: [(&[u8], &str, &str); 13] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png", "PNG image"),
+    (b"\xFF\xD8\xFF", "image/jpeg", "JPEG image"),
+    (b"GIF87a", "image/gif", "GIF image"),
+    (b"GIF89a", "image/gif", "GIF image"),
+    (b"%PDF-", "application/pdf", "PDF document"),
+    (b"PK\x03\x04", "application/zip", "ZIP archive"),
+    (b"PK\x05\x06", "application/zip", "ZIP archive (empty)"),
+    (b"PK\x07\x08", "application/zip", "ZIP archive (spanned)"),
+    (b"\x00asm", "application/wasm", "WebAssembly module"),
+    (b"\x1F\x8B", "application/gzip", "gzip compressed data"),
+    (b"\xEF\xBB\xBF", "text/plain; charset=utf-8", "UTF-8 text with BOM"),
+    (b"\xFF\xFE", "text/plain; charset=utf-16le", "UTF-16 little-endian text with BOM"),
+    (b"\xFE\xFF", "text/plain; charset=utf-16be", "UTF-16 big-endian text with BOM"),
+];
+
+pub fn detect_bytes(bytes: &[u8]) -> Detected {
+    if let Some((_, mime, description)) = SIGNATURES.iter().find(|(magic, _, _)| bytes.starts_with(magic)) {
+        return Detected { mime, description };
+    }
+    // RIFF containers carry their format four bytes after the size field
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Detected { mime: "image/webp", description: "WebP image" };
+    }
+    if bytes.is_empty() {
+        return Detected { mime: "application/x-empty", description: "Empty body" };
+    }
+    if !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok() {
+        return Detected { mime: "text/plain; charset=utf-8", description: "UTF-8 text" };
+    }
+    Detected { mime: "application/octet-stream", description: "Unknown binary data" }
+}
+
+const EXTENSIONS: [(&str, &str); 27] = [
+    ("html", "text/html; charset=utf-8"),
+    ("htm", "text/html; charset=utf-8"),
+    ("css", "text/css; charset=utf-8"),
+    ("js", "text/javascript; charset=utf-8"),
+    ("mjs", "text/javascript; charset=utf-8"),
+    ("json", "application/json"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("md", "text/markdown; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("ico", "image/x-icon"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("wasm", "application/wasm"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+];
+
+// MIME type for a file name or path based on its extension
+pub fn from_extension(filename: &str) -> Option<&'static str> {
+    let name = filename.rsplit('/').next().unwrap_or(filename);
+    let (_, extension) = name.rsplit_once('.')?;
+    let extension = extension.to_ascii_lowercase();
+    EXTENSIONS.iter().find(|(ext, _)| *ext == extension).map(|(_, mime)| *mime)
+}
+
+// Same MIME type ignoring parameters such as charset
+pub fn same_type(a: &str, b: &str) -> bool {
+    let essence = |mime: &str| mime.split(';').next().unwrap_or(mime).trim().to_ascii_lowercase();
+    essence(a) == essence(b)
+}
+
+// Types a browser would render as a document or run as a script
+pub fn is_active(mime: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or(mime).trim().to_ascii_lowercase();
+    matches!(essence.as_str(), "text/html" | "application/xhtml+xml" | "image/svg+xml" | "text/xml" | "application/xml")
+        || essence.ends_with("+xml")
+        || essence.contains("javascript")
+        || essence.contains("ecmascript")
+}
+
+// Sniff the MIME type of a buffer in WASM memory; free the result with free_bytes
+#[no_mangle]
+pub extern "C" fn detect(ptr: *const u8, len: usize) -> *mut u8 {
+    let bytes = unsafe { ffi::bytes(ptr, len) };
+    ffi::output(detect_bytes(bytes).mime.as_bytes())
+}
@@ -0,0 +1,219 @@
+// HTML and CSS minification
+// Both minifiers only drop comments and redundant ASCII whitespace, so any
+// non-ASCII content is copied through byte-for-byte and stays valid UTF-8.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// Elements whose contents must not be touched by the HTML whitespace collapse
+const RAW_TEXT_TAGS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+pub fn html(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+
+        if rest.starts_with(b"<!--") {
+            let end = find(rest, b"-->").map(|pos| pos + 3).unwrap_or(rest.len());
+            // Conditional comments carry meaning for old browsers, keep them
+            if rest.starts_with(b"<!--[if") {
+                out.extend_from_slice(&rest[..end]);
+            }
+            i += end;
+            continue;
+        }
+
+        if rest[0] == b'<' && rest.len() > 1 && (rest[1].is_ascii_alphabetic() || rest[1] == b'/' || rest[1] == b'!') {
+            let (tag_len, name) = copy_tag(rest, &mut out);
+            i += tag_len;
+
+            let closing = rest[1] == b'/';
+            if let Some(name) = name.filter(|_| !closing) {
+                if RAW_TEXT_TAGS.contains(&name.as_str()) {
+                    let content = &bytes[i..];
+                    let end = find_closing_tag(content, &name).unwrap_or(content.len());
+                    if name == "style" {
+                        out.extend_from_slice(css(&String::from_utf8_lossy(&content[..end])).as_bytes());
+                    } else {
+                        out.extend_from_slice(&content[..end]);
+                    }
+                    i += end;
+                }
+            }
+            continue;
+        }
+
+        if rest[0].is_ascii_whitespace() {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if !out.is_empty() && out.last() != Some(&b' ') && i < bytes.len() {
+                out.push(b' ');
+            }
+            continue;
+        }
+
+        out.push(rest[0]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Copies a single tag, collapsing whitespace between attributes while keeping
+// quoted attribute values verbatim. Returns the consumed length and tag name.
+fn copy_tag(tag: &[u8], out: &mut Vec<u8>) -> (usize, Option<String>) {
+    let name_start = if tag[1] == b'/' { 2 } else { 1 };
+    let name_len = tag[name_start..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric())
+        .count();
+    let name = if name_len > 0 {
+        Some(String::from_utf8_lossy(&tag[name_start..name_start + name_len]).to_ascii_lowercase())
+    } else {
+        None
+    };
+
+    let mut i = 0;
+    while i < tag.len() {
+        let b = tag[i];
+        match b {
+            b'"' | b'\'' => {
+                let end = tag[i + 1..]
+                    .iter()
+                    .position(|&c| c == b)
+                    .map(|pos| i + pos + 2)
+                    .unwrap_or(tag.len());
+                out.extend_from_slice(&tag[i..end]);
+                i = end;
+            }
+            b'>' => {
+                if out.last() == Some(&b' ') {
+                    out.pop();
+                }
+                out.push(b'>');
+                return (i + 1, name);
+            }
+            _ if b.is_ascii_whitespace() => {
+                while i < tag.len() && tag[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                if out.last() != Some(&b' ') {
+                    out.push(b' ');
+                }
+            }
+            _ => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    (tag.len(), name)
+}
+
+fn find_closing_tag(content: &[u8], name: &str) -> Option<usize> {
+    let needle = format!("</{}", name);
+    content
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+pub fn css(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut pending_space = false;
+    // One entry per open block: true for declaration blocks, false for
+    // at-rule blocks such as @media that contain further rules
+    let mut blocks: Vec<bool> = Vec::new();
+    let mut prelude_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        if bytes[i..].starts_with(b"/*") {
+            let end = find(&bytes[i + 2..], b"*/").map(|pos| i + pos + 4).unwrap_or(bytes.len());
+            pending_space = true;
+            i = end;
+            continue;
+        }
+
+        if b.is_ascii_whitespace() {
+            pending_space = true;
+            i += 1;
+            continue;
+        }
+
+        if pending_space {
+            // A space is only significant between two tokens, never next to
+            // punctuation. Outside declarations a space before ':' or '(' is
+            // kept because it changes meaning in selectors ("a :hover") and
+            // media queries ("and (").
+            let in_declarations = blocks.last() == Some(&true);
+            let after_punct = matches!(out.last(), None | Some(b'{' | b'}' | b';' | b',' | b'>' | b':' | b'('));
+            let before_punct = matches!(b, b'{' | b'}' | b';' | b',' | b'>' | b')' | b'!')
+                || (b == b':' && in_declarations);
+            if !after_punct && !before_punct {
+                out.push(b' ');
+            }
+            pending_space = false;
+        }
+
+        match b {
+            b'"' | b'\'' => {
+                let end = string_end(bytes, i);
+                out.extend_from_slice(&bytes[i..end]);
+                i = end;
+            }
+            b'{' => {
+                blocks.push(out.get(prelude_start) != Some(&b'@'));
+                out.push(b);
+                prelude_start = out.len();
+                i += 1;
+            }
+            b'}' => {
+                if out.last() == Some(&b';') {
+                    out.pop();
+                }
+                blocks.pop();
+                out.push(b);
+                prelude_start = out.len();
+                i += 1;
+            }
+            b';' => {
+                out.push(b);
+                prelude_start = out.len();
+                i += 1;
+            }
+            _ => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Index just past the closing quote of the string starting at `start`
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
@@ -0,0 +1,141 @@
+// International Morse code (ITU-R M.1677-1) with prosigns and timing
+// A prosign is written between angle brackets, "<SK>", and sent as its
+// letters run together without the gap between characters. Timing follows
+// the PARIS standard: a dot is one unit, a dash three, the gap inside a
+// character one, between characters three and between words seven. In the
+// timing notation "=" is a unit of tone and "." a unit of silence.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const CODES: [(char, &str); 54] = [
+    ('A', ".-"), ('B', "-..."), ('C', "-.-."), ('D', "-.."), ('E', "."), ('F', "..-."), ('G', "--."),
+    ('H', "...."), ('I', ".."), ('J', ".---"), ('K', "-.-"), ('L', ".-.."), ('M', "--"), ('N', "-."),
+    ('O', "---"), ('P', ".--."), ('Q', "--.-"), ('R', ".-."), ('S', "..."), ('T', "-"), ('U', "..-"),
+    ('V', "...-"), ('W', ".--"), ('X', "-..-"), ('Y', "-.--"), ('Z', "--.."),
+    ('0', "-----"), ('1', ".----"), ('2', "..---"), ('3', "...--"), ('4', "....-"), ('5', "....."),
+    ('6', "-...."), ('7', "--..."), ('8', "---.."), ('9', "----."),
+    ('.', ".-.-.-"), (',', "--..--"), ('?', "..--.."), ('\'', ".----."), ('!', "-.-.--"), ('/', "-..-."),
+    ('(', "-.--."), (')', "-.--.-"), ('&', ".-..."), (':', "---..."), (';', "-.-.-."), ('=', "-...-"),
+    ('+', ".-.-."), ('-', "-....-"), ('_', "..--.-"), ('"', ".-..-."), ('$', "...-..-"), ('@', ".--.-."),
+];
+
+// Prosigns whose code is no character's; the others (AR is "+", BT "=")
+// decode as the character
+const PROSIGNS: [&str; 6] = ["SOS", "SK", "KA", "BK", "HH", "SN"];
+
+const MAX_PROSIGN_LETTERS: usize = 8;
+
+// Characters of a word, each as its dots and dashes
+pub type Word = Vec<String>;
+
+fn code(c: char) -> Option<&'static str> {
+    CODES.iter().find(|(letter, _)| *letter == c.to_ascii_uppercase()).map(|(_, code)| *code)
+}
+
+fn prosign(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.chars().count() > MAX_PROSIGN_LETTERS {
+        return Err(format!("Prosign <{}> must have 1 to {} letters", name, MAX_PROSIGN_LETTERS));
+    }
+    name.chars()
+        .map(|c| code(c).filter(|_| c.is_ascii_alphanumeric()).ok_or_else(|| format!("Prosign <{}> may only hold letters and digits", name)))
+        .collect()
+}
+
+pub fn encode(text: &str) -> Result<Vec<Word>, String> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut word = Word::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                words.push(core::mem::take(&mut word));
+            }
+        } else if c == '<' {
+            let name: String = chars.by_ref().take_while(|&c| c != '>').collect();
+            word.push(prosign(&name)?);
+        } else {
+            word.push(code(c).ok_or_else(|| format!("'{}' has no Morse code", c))?.to_string());
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+// Characters are separated by spaces and words by "/" (or three or more
+// spaces); "·", "•" and "_", "−", "–" stand for dots and dashes too
+pub fn parse(morse: &str) -> Result<Vec<Word>, String> {
+    let normalized: String = morse
+        .chars()
+        .map(|c| match c {
+            '·' | '•' => '.',
+            '_' | '−' | '–' => '-',
+            other => other,
+        })
+        .collect();
+    let mut words = Vec::new();
+    for word in normalized.split(['/', '|']).flat_map(|part| part.split("   ")) {
+        let characters: Word = word.split_whitespace().map(str::to_string).collect();
+        if let Some(bad) = characters.iter().find(|code| !code.chars().all(|c| c == '.' || c == '-')) {
+            return Err(format!("'{}' is not made of dots and dashes", bad));
+        }
+        if !characters.is_empty() {
+            words.push(characters);
+        }
+    }
+    Ok(words)
+}
+
+pub fn decode(words: &[Word]) -> Result<String, String> {
+    let mut decoded = Vec::new();
+    for word in words {
+        let mut text = String::new();
+        for sequence in word {
+            if let Some((letter, _)) = CODES.iter().find(|(_, code)| code == sequence) {
+                text.push(*letter);
+            } else if let Some(name) = PROSIGNS.iter().find(|name| prosign(name).is_ok_and(|code| code == *sequence)) {
+                text.push_str(&format!("<{}>", name));
+            } else {
+                return Err(format!("Unknown Morse code '{}'", sequence));
+            }
+        }
+        decoded.push(text);
+    }
+    Ok(decoded.join(" "))
+}
+
+pub fn to_text(words: &[Word]) -> String {
+    words.iter().map(|word| word.join(" ")).collect::<Vec<_>>().join(" / ")
+}
+
+// Alternating tone and silence lengths in units, starting with tone
+pub fn durations(words: &[Word]) -> Vec<u32> {
+    let mut units = Vec::new();
+    for (w, word) in words.iter().enumerate() {
+        for (c, sequence) in word.iter().enumerate() {
+            for (e, element) in sequence.chars().enumerate() {
+                if e > 0 {
+                    units.push(1);
+                } else if c > 0 {
+                    units.push(3);
+                } else if w > 0 {
+                    units.push(7);
+                }
+                units.push(if element == '-' { 3 } else { 1 });
+            }
+        }
+    }
+    units
+}
+
+// "=.===" for "A"
+pub fn timing(durations: &[u32]) -> String {
+    durations
+        .iter()
+        .enumerate()
+        .map(|(i, &units)| if i % 2 == 0 { "=" } else { "." }.repeat(units as usize))
+        .collect()
+}
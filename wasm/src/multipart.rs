@@ -0,0 +1,95 @@
+// multipart/form-data body parsing (RFC 7578)
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::headers;
+
+pub struct Part<'a> {
+    pub name: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: &'a [u8],
+}
+
+// Boundary from a Content-Type header, if it declares multipart/form-data
+pub fn boundary(content_type: &str) -> Result<String, String> {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    if !essence.eq_ignore_ascii_case("multipart/form-data") {
+        return Err("Content-Type must be multipart/form-data".to_string());
+    }
+    match headers::parameter(content_type, "boundary") {
+        Some(boundary) if !boundary.is_empty() && boundary.len() <= 70 => Ok(boundary),
+        Some(_) => Err("Multipart boundary must be 1 to 70 characters".to_string()),
+        None => Err("Content-Type is missing the multipart boundary".to_string()),
+    }
+}
+
+pub fn parse<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>, String> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+
+    // Anything before the first delimiter is a preamble and is ignored
+    let mut position = find(body, delimiter, 0).ok_or("Multipart body does not contain the boundary")?;
+    let mut parts = Vec::new();
+
+    loop {
+        let after = position + delimiter.len();
+        if body[after..].starts_with(b"--") {
+            return Ok(parts);
+        }
+        // The delimiter line may carry trailing whitespace before its CRLF
+        let line_end = find(body, b"\r\n", after).ok_or("Unterminated multipart delimiter line")?;
+        let start = line_end + 2;
+
+        let next = find_delimiter(body, delimiter, start).ok_or("Multipart body is missing its closing boundary")?;
+        // The CRLF before the next delimiter belongs to the delimiter
+        parts.push(parse_part(&body[start..next - 2])?);
+        position = next;
+    }
+}
+
+// Next delimiter that starts on its own line
+fn find_delimiter(body: &[u8], delimiter: &[u8], from: usize) -> Option<usize> {
+    let mut search = from;
+    while let Some(index) = find(body, delimiter, search) {
+        if index >= from + 2 && &body[index - 2..index] == b"\r\n" {
+            return Some(index);
+        }
+        search = index + 1;
+    }
+    None
+}
+
+fn parse_part(raw: &[u8]) -> Result<Part<'_>, String> {
+    let (header_block, data) = match find(raw, b"\r\n\r\n", 0) {
+        Some(end) => (&raw[..end], &raw[end + 4..]),
+        // A part with no headers starts with the blank line directly
+        None if raw.starts_with(b"\r\n") => (&raw[..0], &raw[2..]),
+        None => return Err("Multipart part is missing the blank line after its headers".to_string()),
+    };
+
+    let header_text = String::from_utf8_lossy(header_block);
+    let mut part = Part { name: None, filename: None, content_type: None, data };
+    for line in header_text.split("\r\n") {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        if name.trim().eq_ignore_ascii_case("content-disposition") {
+            part.name = headers::parameter(value, "name");
+            part.filename = headers::parameter(value, "filename");
+        } else if name.trim().eq_ignore_ascii_case("content-type") {
+            part.content_type = Some(value.to_string());
+        }
+    }
+    Ok(part)
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from > haystack.len() {
+        return None;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| index + from)
+}
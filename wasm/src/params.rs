@@ -0,0 +1,91 @@
+// Decoded key/value parameters from a query string or an
+// application/x-www-form-urlencoded request body
+
+use crate::simd;
+
+pub struct QueryParams {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryParams {
+    // Query strings keep '+' literal so values like "3d6+2" survive unencoded.
+    // Err names the first parameter that percent-decodes to invalid UTF-8.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        Ok(QueryParams { pairs: decode_pairs(query, false)? })
+    }
+
+    // Form bodies encode spaces as '+', as browsers submit them
+    pub fn parse_form(body: &str) -> Result<Self, String> {
+        Ok(QueryParams { pairs: decode_pairs(body, true)? })
+    }
+
+    // Parameters from `other` are looked up first, falling back to these
+    pub fn merge(&mut self, other: QueryParams) {
+        let mut pairs = other.pairs;
+        pairs.append(&mut self.pairs);
+        self.pairs = pairs;
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Every pair in lookup order, repeated names included
+    pub fn pairs(&self) -> &[(String, String)] {
+        &self.pairs
+    }
+
+    // Re-encoded with the names in order; repeated names keep their original
+    // order, since get only ever sees the first
+    pub fn canonical(&self) -> String {
+        let mut pairs: Vec<_> = self.pairs.iter().collect();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        pairs
+            .iter()
+            .map(|(key, value)| format!("{}={}", urlencoding::encode(key), urlencoding::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+// Delimiters are ASCII, so every index found is a char boundary
+fn decode_pairs(input: &str, plus_as_space: bool) -> Result<Vec<(String, String)>, String> {
+    let mut pairs = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let end = simd::find_byte(rest.as_bytes(), b'&').unwrap_or(rest.len());
+        let part = &rest[..end];
+        rest = rest.get(end + 1..).unwrap_or("");
+        if part.is_empty() {
+            continue;
+        }
+        let (key, value) = match simd::find_byte(part.as_bytes(), b'=') {
+            Some(index) => (&part[..index], &part[index + 1..]),
+            None => (part, ""),
+        };
+        let invalid = || format!("Parameter '{}' is not valid UTF-8 once decoded", key);
+        let name = decode(key, plus_as_space).ok_or_else(invalid)?;
+        let value = decode(value, plus_as_space).ok_or_else(invalid)?;
+        pairs.push((name, value));
+    }
+    Ok(pairs)
+}
+
+fn decode(value: &str, plus_as_space: bool) -> Option<String> {
+    // Most names and values have nothing to decode
+    if simd::find_either(value.as_bytes(), b'%', b'+').is_none() {
+        return Some(value.to_string());
+    }
+    let value = if plus_as_space { value.replace('+', " ") } else { value.to_string() };
+    String::from_utf8(urlencoding::decode_binary(value.as_bytes()).into_owned()).ok()
+}
+
+// Whether a Content-Type header denotes a urlencoded form submission
+pub fn is_form(content_type: Option<&str>) -> bool {
+    content_type
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|essence| essence.trim().eq_ignore_ascii_case("application/x-www-form-urlencoded"))
+}
@@ -0,0 +1,61 @@
+// Expiring text pastes on top of the KV bindings
+// A paste is stored under paste:<id> as "<created_ms> <expires_ms>\n<text>".
+// KV drops the key once its TTL runs out, but expiry is also checked on read
+// because the isolate-local fallback and KV's 60 second TTL granularity can
+// keep a value around slightly longer.
+
+use crate::host;
+use crate::ids;
+use crate::kv::{self, Pending};
+
+pub const ID_LENGTH: usize = 10;
+pub const MIN_TTL_SECONDS: u32 = 60;
+pub const DEFAULT_TTL_SECONDS: u32 = 86_400;
+
+const ID_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+pub struct Paste {
+    pub id: String,
+    pub text: String,
+    pub created_ms: u64,
+    pub expires_ms: u64,
+}
+
+fn key(id: &str) -> String {
+    format!("paste:{}", id)
+}
+
+pub fn is_valid_id(id: &str) -> bool {
+    id.len() == ID_LENGTH && id.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+// Ids are random, so there is no existence check: a read would make the host
+// replay the request, and the replay would pick a different id
+pub fn create(text: &str, ttl_seconds: u32) -> Paste {
+    let alphabet: Vec<char> = ID_ALPHABET.chars().collect();
+    let id = ids::nanoid(&alphabet, ID_LENGTH);
+    let created_ms = host::now_ms() as u64;
+    let expires_ms = created_ms + ttl_seconds as u64 * 1000;
+    kv::put(&key(&id), &format!("{} {}\n{}", created_ms, expires_ms, text), ttl_seconds);
+    Paste { id, text: text.to_string(), created_ms, expires_ms }
+}
+
+// Ok(None) for unknown, expired or unreadable pastes
+pub fn load(id: &str) -> Result<Option<Paste>, Pending> {
+    let Some(stored) = kv::get(&key(id))? else {
+        return Ok(None);
+    };
+    let Some((header, text)) = stored.split_once('\n') else {
+        return Ok(None);
+    };
+    let Some((created_ms, expires_ms)) = header.split_once(' ') else {
+        return Ok(None);
+    };
+    let (Ok(created_ms), Ok(expires_ms)) = (created_ms.parse(), expires_ms.parse()) else {
+        return Ok(None);
+    };
+    if expires_ms <= host::now_ms() as u64 {
+        return Ok(None);
+    }
+    Ok(Some(Paste { id: id.to_string(), text: text.to_string(), created_ms, expires_ms }))
+}
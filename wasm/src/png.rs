@@ -0,0 +1,301 @@
+// PNG decoding and encoding
+// Every colour type and bit depth decodes, interlaced or not, with tRNS
+// transparency; ancillary chunks such as gamma and text are ignored. The
+// encoder writes 8-bit RGB, or RGBA when any pixel is translucent, choosing
+// each row's filter by the usual minimum-sum-of-differences heuristic.
+
+use crate::compression;
+use crate::hashing::Crc32;
+use crate::image::Image;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+// Adam7 passes as (x offset, y offset, x step, y step)
+const ADAM7: [(usize, usize, usize, usize); 7] = [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    colour_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.colour_type {
+            0 | 3 => 1,
+            4 => 2,
+            2 => 3,
+            _ => 4,
+        }
+    }
+
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.channels() * self.bit_depth as usize).div_ceil(8)
+    }
+
+    // Filters look back a whole pixel, or one byte for sub-byte depths
+    fn filter_step(&self) -> usize {
+        (self.channels() * self.bit_depth as usize / 8).max(1)
+    }
+}
+
+// A chunk's type and data
+type Chunk<'a> = ([u8; 4], &'a [u8]);
+
+fn chunks(data: &[u8]) -> Result<Vec<Chunk<'_>>, String> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err("Not a PNG file".to_string());
+    }
+    let mut chunks = Vec::new();
+    let mut pos = SIGNATURE.len();
+    loop {
+        let header = data.get(pos..pos + 8).ok_or("PNG ends before its IEND chunk")?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let body = data.get(pos + 8..pos + 8 + length).ok_or("PNG chunk runs past the end")?;
+        let stored = data.get(pos + 8 + length..pos + 12 + length).ok_or("PNG chunk is missing its CRC")?;
+        let mut crc = Crc32::new();
+        crc.update(&kind);
+        crc.update(body);
+        if crc.finalize().to_be_bytes() != stored {
+            return Err(format!("CRC mismatch in {} chunk", String::from_utf8_lossy(&kind)));
+        }
+        pos += 12 + length;
+        if &kind == b"IEND" {
+            return Ok(chunks);
+        }
+        chunks.push((kind, body));
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = ((p - i16::from(a)).abs(), (p - i16::from(b)).abs(), (p - i16::from(c)).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Reverses the filter on each row of one pass in place; `rows` holds the
+// filter type byte before every row
+fn unfilter(rows: &mut [u8], row_bytes: usize, step: usize) -> Result<(), String> {
+    let mut previous = alloc::vec![0u8; row_bytes];
+    for row in rows.chunks_exact_mut(row_bytes + 1) {
+        let (filter, row) = row.split_first_mut().ok_or("Empty PNG row")?;
+        for index in 0..row_bytes {
+            let left = if index >= step { row[index - step] } else { 0 };
+            let up = previous[index];
+            let up_left = if index >= step { previous[index - step] } else { 0 };
+            row[index] = row[index].wrapping_add(match *filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                other => return Err(format!("Unknown PNG filter type {}", other)),
+            });
+        }
+        previous.copy_from_slice(row);
+    }
+    Ok(())
+}
+
+// Decodes to RGBA, refusing images with more than `max_pixels` pixels
+// before allocating for them
+pub fn decode(data: &[u8], max_pixels: usize) -> Result<Image, String> {
+    let chunks = chunks(data)?;
+    let Some((b"IHDR", ihdr)) = chunks.first().map(|(kind, body)| (kind, *body)) else {
+        return Err("PNG does not start with an IHDR chunk".to_string());
+    };
+    if ihdr.len() != 13 {
+        return Err("IHDR chunk has the wrong length".to_string());
+    }
+    let header = Header {
+        width: u32::from_be_bytes([ihdr[0], ihdr[1], ihdr[2], ihdr[3]]) as usize,
+        height: u32::from_be_bytes([ihdr[4], ihdr[5], ihdr[6], ihdr[7]]) as usize,
+        bit_depth: ihdr[8],
+        colour_type: ihdr[9],
+        interlaced: ihdr[12] == 1,
+    };
+    let valid_depth = match header.colour_type {
+        0 => matches!(header.bit_depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(header.bit_depth, 1 | 2 | 4 | 8),
+        2 | 4 | 6 => matches!(header.bit_depth, 8 | 16),
+        _ => return Err(format!("Unknown PNG colour type {}", header.colour_type)),
+    };
+    if !valid_depth {
+        return Err(format!("Bit depth {} is not allowed for colour type {}", header.bit_depth, header.colour_type));
+    }
+    if ihdr[10] != 0 || ihdr[11] != 0 || ihdr[12] > 1 {
+        return Err("Unknown PNG compression, filter or interlace method".to_string());
+    }
+    if header.width == 0 || header.height == 0 {
+        return Err("PNG has no pixels".to_string());
+    }
+    if header.width.saturating_mul(header.height) > max_pixels {
+        return Err(format!("Images may have at most {} pixels", max_pixels));
+    }
+
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+    for (kind, body) in &chunks[1..] {
+        match kind {
+            b"PLTE" => palette = body,
+            b"tRNS" => transparency = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            _ => {}
+        }
+    }
+    if header.colour_type == 3 && palette.is_empty() {
+        return Err("Palette image has no PLTE chunk".to_string());
+    }
+
+    let passes: Vec<(usize, usize, usize, usize)> = if header.interlaced { ADAM7.to_vec() } else { alloc::vec![(0, 0, 1, 1)] };
+    // Each pass's size in pixels, skipping passes with no pixels at all
+    let sizes: Vec<(usize, usize)> = passes
+        .iter()
+        .map(|&(x, y, dx, dy)| ((header.width.saturating_sub(x)).div_ceil(dx), (header.height.saturating_sub(y)).div_ceil(dy)))
+        .collect();
+    let expected: usize = sizes.iter().filter(|(w, h)| *w > 0 && *h > 0).map(|&(w, h)| (header.row_bytes(w) + 1) * h).sum();
+    let mut raw = compression::zlib_decode(&compressed, expected)?;
+    if raw.len() != expected {
+        return Err("PNG image data is shorter than the image".to_string());
+    }
+
+    let mut rgba = alloc::vec![0u8; header.width * header.height * 4];
+    let mut offset = 0;
+    for (&(x0, y0, dx, dy), &(pass_width, pass_height)) in passes.iter().zip(&sizes) {
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+        let row_bytes = header.row_bytes(pass_width);
+        let pass = &mut raw[offset..offset + (row_bytes + 1) * pass_height];
+        offset += pass.len();
+        unfilter(pass, row_bytes, header.filter_step())?;
+        for (row_index, row) in pass.chunks_exact(row_bytes + 1).enumerate() {
+            for column in 0..pass_width {
+                let pixel = read_pixel(&header, &row[1..], column, palette, transparency)?;
+                let at = ((y0 + row_index * dy) * header.width + x0 + column * dx) * 4;
+                rgba[at..at + 4].copy_from_slice(&pixel);
+            }
+        }
+    }
+    Ok(Image { width: header.width, height: header.height, rgba })
+}
+
+fn read_pixel(header: &Header, row: &[u8], column: usize, palette: &[u8], transparency: &[u8]) -> Result<[u8; 4], String> {
+    let depth = header.bit_depth as usize;
+    // Sample `index` of the pixel at its full depth
+    let sample = |index: usize| -> u16 {
+        let position = column * header.channels() + index;
+        match depth {
+            16 => u16::from_be_bytes([row[position * 2], row[position * 2 + 1]]),
+            8 => u16::from(row[position]),
+            _ => {
+                let bit = position * depth;
+                u16::from(row[bit / 8] >> (8 - depth - bit % 8) & ((1 << depth) - 1) as u8)
+            }
+        }
+    };
+    // Scaled to eight bits
+    let scale = |value: u16| -> u8 {
+        match depth {
+            16 => (value >> 8) as u8,
+            8 => value as u8,
+            _ => (u32::from(value) * 255 / ((1 << depth) - 1)) as u8,
+        }
+    };
+    let matches_key = |values: &[u16]| {
+        transparency.len() == values.len() * 2
+            && values.iter().enumerate().all(|(index, value)| u16::from_be_bytes([transparency[index * 2], transparency[index * 2 + 1]]) == *value)
+    };
+    Ok(match header.colour_type {
+        0 => {
+            let grey = sample(0);
+            let alpha = if matches_key(&[grey]) { 0 } else { 255 };
+            let grey = scale(grey);
+            [grey, grey, grey, alpha]
+        }
+        2 => {
+            let (r, g, b) = (sample(0), sample(1), sample(2));
+            let alpha = if matches_key(&[r, g, b]) { 0 } else { 255 };
+            [scale(r), scale(g), scale(b), alpha]
+        }
+        3 => {
+            let index = sample(0) as usize;
+            let colour = palette.get(index * 3..index * 3 + 3).ok_or_else(|| format!("Palette index {} is out of range", index))?;
+            [colour[0], colour[1], colour[2], transparency.get(index).copied().unwrap_or(255)]
+        }
+        4 => {
+            let grey = scale(sample(0));
+            [grey, grey, grey, scale(sample(1))]
+        }
+        _ => [scale(sample(0)), scale(sample(1)), scale(sample(2)), scale(sample(3))],
+    })
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(body);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+}
+
+pub fn encode(image: &Image) -> Vec<u8> {
+    let opaque = image.is_opaque();
+    let channels = if opaque { 3 } else { 4 };
+    let row_bytes = image.width * channels;
+    let pixels: Vec<u8> =
+        if opaque { image.rgba.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect() } else { image.rgba.clone() };
+
+    let mut filtered = Vec::with_capacity((row_bytes + 1) * image.height);
+    let zero_row = alloc::vec![0u8; row_bytes];
+    for (index, row) in pixels.chunks_exact(row_bytes).enumerate() {
+        let previous = if index == 0 { &zero_row[..] } else { &pixels[(index - 1) * row_bytes..index * row_bytes] };
+        let candidates = (0..5u8).map(|filter| {
+            let bytes: Vec<u8> = (0..row_bytes)
+                .map(|at| {
+                    let left = if at >= channels { row[at - channels] } else { 0 };
+                    let up_left = if at >= channels { previous[at - channels] } else { 0 };
+                    row[at].wrapping_sub(match filter {
+                        0 => 0,
+                        1 => left,
+                        2 => previous[at],
+                        3 => ((u16::from(left) + u16::from(previous[at])) / 2) as u8,
+                        _ => paeth(left, previous[at], up_left),
+                    })
+                })
+                .collect();
+            (filter, bytes)
+        });
+        // Treating bytes as signed favours filters leaving values near zero
+        let (filter, bytes) = candidates
+            .min_by_key(|(_, bytes)| bytes.iter().map(|&byte| u32::from((byte as i8).unsigned_abs())).sum::<u32>())
+            .unwrap_or_default();
+        filtered.push(filter);
+        filtered.extend(bytes);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(image.width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(image.height as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, if opaque { 2 } else { 6 }, 0, 0, 0]);
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &compression::zlib_encode(&filtered));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
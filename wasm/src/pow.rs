@@ -0,0 +1,86 @@
+// Proof-of-work challenges, friction a client pays for in CPU time
+// A challenge is a random prefix and a difficulty: the client must find a
+// nonce for which SHA-256(prefix + nonce) starts with that many zero bits,
+// about 2^difficulty hashes of work against the single hash it takes to
+// check. The challenge token is "<prefix>.<difficulty>.<issued seconds>.
+// <mac>", signed with pow_secret so the difficulty cannot be lowered, and a
+// solved prefix is remembered in KV until it expires, so it is spent once.
+
+use crate::hashing::{self, HmacSha256, Sha256};
+use crate::host;
+use crate::kv::{self, Pending};
+
+pub const MAX_AGE_SECONDS: u64 = 300;
+pub const MAX_DIFFICULTY: u32 = 32;
+pub const MAX_NONCE_LENGTH: usize = 64;
+
+pub struct Check {
+    pub difficulty: u32,
+    pub leading_zero_bits: u32,
+    pub hash: [u8; 32],
+    // None when the nonce solves the challenge
+    pub failure: Option<&'static str>,
+}
+
+fn mac(secret: &str, signed: &str) -> [u8; 32] {
+    let mut hmac = HmacSha256::new(secret.as_bytes());
+    hmac.update(signed.as_bytes());
+    hmac.finalize()
+}
+
+// The token and its prefix
+pub fn challenge(secret: &str, difficulty: u32, now_seconds: u64) -> (String, String) {
+    let mut random = [0u8; 16];
+    host::random_bytes(&mut random);
+    let prefix = hashing::to_base64url(&random);
+    let signed = format!("{}.{}.{}", prefix, difficulty, now_seconds);
+    let token = format!("{}.{}", signed, hashing::to_base64url(&mac(secret, &signed)));
+    (token, prefix)
+}
+
+pub fn hash(prefix: &str, nonce: &str) -> [u8; 32] {
+    let mut sha = Sha256::new();
+    sha.update(prefix.as_bytes());
+    sha.update(nonce.as_bytes());
+    sha.finalize()
+}
+
+pub fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let zero_bytes = hash.iter().take_while(|&&byte| byte == 0).count();
+    zero_bytes as u32 * 8 + hash.get(zero_bytes).map_or(0, |byte| byte.leading_zeros())
+}
+
+// Err when the token is not one this module could have issued
+pub fn verify(secret: &str, token: &str, nonce: &str, now_seconds: u64) -> Result<Result<Check, String>, Pending> {
+    let malformed = || Ok(Err("Challenge must have four dot-separated parts".to_string()));
+    let Some((signed, given)) = token.rsplit_once('.') else {
+        return malformed();
+    };
+    let parts: Vec<&str> = signed.split('.').collect();
+    let [prefix, difficulty, issued] = parts[..] else {
+        return malformed();
+    };
+    let (Ok(difficulty), Ok(issued)) = (difficulty.parse::<u32>(), issued.parse::<u64>()) else {
+        return Ok(Err("Challenge has an invalid difficulty or timestamp".to_string()));
+    };
+    let signature_matches = hashing::from_base64url(given).is_ok_and(|given| hashing::constant_time_eq(&given, &mac(secret, signed)));
+    if !signature_matches {
+        return Ok(Err("Challenge signature does not match".to_string()));
+    }
+
+    let hash = hash(prefix, nonce);
+    let leading_zero_bits = leading_zero_bits(&hash);
+    let age = now_seconds.saturating_sub(issued);
+    let used_key = format!("pow:{}", prefix);
+    let failure = if leading_zero_bits < difficulty {
+        Some("insufficient_work")
+    } else if age > MAX_AGE_SECONDS || issued > now_seconds + 60 {
+        Some("expired")
+    } else if kv::get(&used_key)?.is_some() {
+        Some("already_used")
+    } else {
+        kv::put(&used_key, "1", (MAX_AGE_SECONDS - age).max(60) as u32);
+        None
+    };
+    Ok(Ok(Check { difficulty, leading_zero_bits, hash, failure }))
+}
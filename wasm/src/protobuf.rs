@@ -0,0 +1,209 @@
+// Protocol Buffers wire format without generated code or reflection
+// Decoding yields each field's number with its raw wire value, and messages
+// interpret those themselves (an int32 is a varint, a string is
+// length-delimited bytes, ...). Groups, deprecated since proto2, are rejected.
+// Without a schema, breakdown() shows every reading a value could have.
+
+use crate::hashing;
+use crate::json;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Field numbers are 29 bits
+const MAX_FIELD_NUMBER: u64 = (1 << 29) - 1;
+
+pub enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let Some(&byte) = bytes.get(*pos) else {
+            return Err(format!("Truncated varint at byte {}", *pos));
+        };
+        *pos += 1;
+        // The tenth byte may only carry the top bit
+        if shift == 63 && byte > 1 {
+            return Err(format!("Varint longer than 64 bits ending at byte {}", *pos - 1));
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(format!("Varint longer than 64 bits ending at byte {}", *pos - 1))
+}
+
+fn take<'a>(bytes: &'a [u8], pos: &mut usize, length: usize) -> Result<&'a [u8], String> {
+    let field = bytes.get(*pos..).and_then(|rest| rest.get(..length)).ok_or_else(|| format!("Field at byte {} runs past the end", *pos))?;
+    *pos += length;
+    Ok(field)
+}
+
+// Fields in the order they appear; a field repeated in the input appears as
+// often as it was sent
+pub fn decode(bytes: &[u8]) -> Result<Vec<(u32, Value<'_>)>, String> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        let key = read_varint(bytes, &mut pos)?;
+        let number = key >> 3;
+        if number == 0 || number > MAX_FIELD_NUMBER {
+            return Err(format!("Invalid field number {} at byte {}", number, start));
+        }
+        let value = match key & 7 {
+            0 => Value::Varint(read_varint(bytes, &mut pos)?),
+            1 => Value::Fixed64(u64::from_le_bytes(take(bytes, &mut pos, 8)?.try_into().unwrap_or_default())),
+            2 => {
+                let length = read_varint(bytes, &mut pos)?;
+                Value::Bytes(take(bytes, &mut pos, usize::try_from(length).unwrap_or(usize::MAX))?)
+            }
+            5 => Value::Fixed32(u32::from_le_bytes(take(bytes, &mut pos, 4)?.try_into().unwrap_or_default())),
+            3 | 4 => return Err(format!("Groups are not supported (field {} at byte {})", number, start)),
+            wire_type => return Err(format!("Unknown wire type {} at byte {}", wire_type, start)),
+        };
+        fields.push((number as u32, value));
+    }
+    Ok(fields)
+}
+
+// Proto3 semantics: the last occurrence of a scalar field wins, and a
+// missing one reads as 0
+pub fn last_varint(fields: &[(u32, Value)], number: u32) -> Result<u64, String> {
+    match fields.iter().rev().find(|(field, _)| *field == number) {
+        None => Ok(0),
+        Some((_, Value::Varint(value))) => Ok(*value),
+        Some(_) => Err(format!("Field {} must be a varint", number)),
+    }
+}
+
+// sint32 and sint64 map small negative numbers to small varints:
+// 0, -1, 1, -2, ... become 0, 1, 2, 3, ...
+pub fn zigzag_encode(value: i64) -> u64 {
+    (value << 1 ^ value >> 63) as u64
+}
+
+pub fn zigzag_decode(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+// Nested messages deeper than this are shown as bytes only
+const MAX_BREAKDOWN_DEPTH: usize = 16;
+
+// JSON numbers are only exact up to 2^53, so larger values become strings
+fn exact_unsigned(value: u64) -> String {
+    if value <= 1 << 53 { value.to_string() } else { format!("\"{}\"", value) }
+}
+
+fn exact_signed(value: i64) -> String {
+    if value.unsigned_abs() <= 1 << 53 { value.to_string() } else { format!("\"{}\"", value) }
+}
+
+fn finite(value: f64) -> String {
+    if value.is_finite() { format!("{}", value) } else { "null".to_string() }
+}
+
+// Every field as JSON, with the readings its wire type allows: a varint as
+// unsigned, two's complement and zigzag; fixed widths as integers and
+// floating point; length-delimited bytes as hex, as UTF-8 when valid, and
+// as a nested message when they decode as one
+pub fn breakdown(bytes: &[u8]) -> Result<String, String> {
+    breakdown_at(bytes, 0)
+}
+
+fn breakdown_at(bytes: &[u8], depth: usize) -> Result<String, String> {
+    let fields: Vec<String> = decode(bytes)?
+        .into_iter()
+        .map(|(number, value)| match value {
+            Value::Varint(value) => format!(
+                r#"{{"field":{},"wire_type":"varint","unsigned":{},"signed":{},"zigzag":{}}}"#,
+                number,
+                exact_unsigned(value),
+                exact_signed(value as i64),
+                exact_signed(zigzag_decode(value))
+            ),
+            Value::Fixed64(value) => format!(
+                r#"{{"field":{},"wire_type":"fixed64","unsigned":{},"signed":{},"double":{}}}"#,
+                number,
+                exact_unsigned(value),
+                exact_signed(value as i64),
+                finite(f64::from_bits(value))
+            ),
+            Value::Fixed32(value) => format!(
+                r#"{{"field":{},"wire_type":"fixed32","unsigned":{},"signed":{},"float":{}}}"#,
+                number,
+                value,
+                value as i32,
+                finite(f64::from(f32::from_bits(value)))
+            ),
+            Value::Bytes(value) => {
+                let text = match core::str::from_utf8(value) {
+                    Ok(text) => format!("\"{}\"", json::escape(text)),
+                    Err(_) => "null".to_string(),
+                };
+                // Empty bytes decode as an empty message, which says nothing
+                let message = if depth < MAX_BREAKDOWN_DEPTH && !value.is_empty() {
+                    breakdown_at(value, depth + 1).unwrap_or_else(|_| "null".to_string())
+                } else {
+                    "null".to_string()
+                };
+                format!(
+                    r#"{{"field":{},"wire_type":"length_delimited","length":{},"hex":"{}","string":{},"message":{}}}"#,
+                    number,
+                    value.len(),
+                    hashing::to_hex(value),
+                    text,
+                    message
+                )
+            }
+        })
+        .collect();
+    Ok(format!("[{}]", fields.join(",")))
+}
+
+#[derive(Default)]
+pub struct Encoder {
+    buffer: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder::default()
+    }
+
+    fn raw_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buffer.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buffer.push(value as u8);
+    }
+
+    // int32 and int64 values are sign-extended and sent as 64-bit varints
+    pub fn varint(&mut self, number: u32, value: u64) -> &mut Self {
+        self.raw_varint(u64::from(number) << 3);
+        self.raw_varint(value);
+        self
+    }
+
+    pub fn sint(&mut self, number: u32, value: i64) -> &mut Self {
+        self.varint(number, zigzag_encode(value))
+    }
+
+    pub fn bytes(&mut self, number: u32, value: &[u8]) -> &mut Self {
+        self.raw_varint(u64::from(number) << 3 | 2);
+        self.raw_varint(value.len() as u64);
+        self.buffer.extend_from_slice(value);
+        self
+    }
+
+    pub fn finish(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.buffer)
+    }
+}
@@ -0,0 +1,99 @@
+// Seeded deterministic PRNG (xoshiro256**)
+// Not cryptographically secure - meant for reproducible demo data and tests.
+
+use std::cell::RefCell;
+
+// Seed used by the module-wide generator until the host calls rng_seed
+const DEFAULT_SEED: u64 = 0x5EED;
+
+thread_local! {
+    static GLOBAL: RefCell<Rng> = RefCell::new(Rng::new(DEFAULT_SEED));
+}
+
+// Run a closure against the module-wide generator shared by all requests
+pub fn with_global<T>(f: impl FnOnce(&mut Rng) -> T) -> T {
+    GLOBAL.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+// Reset the module-wide generator so its sequence can be replayed
+#[no_mangle]
+pub extern "C" fn rng_seed(seed: u64) {
+    with_global(|rng| *rng = Rng::new(seed));
+}
+
+#[no_mangle]
+pub extern "C" fn rng_next_u64() -> u64 {
+    with_global(|rng| rng.next_u64())
+}
+
+// Uniform value in min..=max (bounds are swapped if given in reverse)
+#[no_mangle]
+pub extern "C" fn rng_next_range(min: i64, max: i64) -> i64 {
+    with_global(|rng| rng.range(min.min(max), min.max(max)))
+}
+
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    // Expand a single u64 seed into the full state with SplitMix64, as
+    // recommended by the xoshiro authors (an all-zero state would be stuck)
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut state = [0u64; 4];
+        for word in state.iter_mut() {
+            x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            *word = z ^ (z >> 31);
+        }
+        Rng { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+
+        result
+    }
+
+    // Uniform value in 0..n without modulo bias (n == 0 means the full u64 range)
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return self.next_u64();
+        }
+        let zone = u64::MAX - (u64::MAX % n);
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    // Uniform value in min..=max
+    pub fn range(&mut self, min: i64, max: i64) -> i64 {
+        let span = (max.wrapping_sub(min) as u64).wrapping_add(1);
+        min.wrapping_add(self.below(span) as i64)
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    pub fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let value = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+    }
+}
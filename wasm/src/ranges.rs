@@ -0,0 +1,76 @@
+// Byte ranges of a Range request header (RFC 9110 section 14)
+// Only single-part responses are produced: ranges that overlap or touch are
+// merged, and a request for ranges that stay apart gets the whole
+// representation, which the RFC allows in place of multipart/byteranges. A
+// header that is not valid, or not in bytes, is ignored the same way.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+// More ranges than this are not worth merging and are answered in full
+const MAX_RANGES: usize = 16;
+
+pub enum Outcome {
+    // Send the whole representation with 200
+    Full,
+    Partial(Range<usize>),
+    // None of the ranges overlaps the representation: 416
+    Unsatisfiable,
+}
+
+// One "first-last", "first-" or "-suffix" range, or None when it is invalid;
+// Some(None) is a valid one that lies past the end
+fn range(spec: &str, length: usize) -> Option<Option<Range<usize>>> {
+    let (first, last) = spec.trim().split_once('-')?;
+    let number = |text: &str| (!text.is_empty() && text.bytes().all(|b| b.is_ascii_digit())).then(|| text.parse::<u64>().unwrap_or(u64::MAX));
+    let length = length as u64;
+    let (start, end) = match (first, last) {
+        ("", suffix) => {
+            let suffix = number(suffix)?;
+            (length.saturating_sub(suffix), length)
+        }
+        (first, "") => (number(first)?, length),
+        (first, last) => {
+            let (first, last) = (number(first)?, number(last)?);
+            if last < first {
+                return None;
+            }
+            (first, last.saturating_add(1).min(length))
+        }
+    };
+    Some((start < end).then_some(start as usize..end as usize))
+}
+
+// `length` is the size of the full representation in bytes
+pub fn evaluate(header: &str, length: usize) -> Outcome {
+    let Some((unit, specs)) = header.split_once('=') else {
+        return Outcome::Full;
+    };
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return Outcome::Full;
+    }
+    let specs: Vec<&str> = specs.split(',').filter(|spec| !spec.trim().is_empty()).collect();
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return Outcome::Full;
+    }
+    let mut ranges = Vec::new();
+    for spec in specs {
+        match range(spec, length) {
+            None => return Outcome::Full,
+            Some(Some(range)) => ranges.push(range),
+            Some(None) => {}
+        }
+    }
+    ranges.sort_by_key(|range| range.start);
+    let mut ranges = ranges.into_iter();
+    let Some(mut merged) = ranges.next() else {
+        return Outcome::Unsatisfiable;
+    };
+    for range in ranges {
+        if range.start > merged.end {
+            return Outcome::Full;
+        }
+        merged.end = merged.end.max(range.end);
+    }
+    Outcome::Partial(merged)
+}
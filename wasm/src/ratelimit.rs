@@ -0,0 +1,143 @@
+// Rate limiters with their state in KV, one per algorithm and key
+// A token bucket holds up to `limit` tokens, refilled at `rate` a second,
+// and a request spends one; bursts of the full bucket are allowed. A leaky
+// bucket is the same meter turned over: each request adds to a level that
+// drains at `rate` a second and may not pass `limit`, so it smooths bursts
+// into a steady flow once full. A sliding window log keeps the time of every
+// request in the last `window_ms` and allows `limit` of them, exact but with
+// state that grows with the limit.
+//
+// KV has no transactions, so concurrent requests for one key can both read
+// the same state and let one more request through than the limit; without a
+// KV binding the state only lives in the isolate.
+
+use crate::kv::{self, Pending};
+
+pub const MAX_LIMIT: u32 = 1000;
+pub const MAX_WINDOW_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Clone, Copy)]
+pub enum Algorithm {
+    TokenBucket,
+    LeakyBucket,
+    SlidingWindow,
+}
+
+impl Algorithm {
+    pub const NAMES: [&'static str; 3] = ["token_bucket", "leaky_bucket", "sliding_window"];
+
+    pub fn parse(name: &str) -> Option<Algorithm> {
+        match name {
+            "token_bucket" => Some(Algorithm::TokenBucket),
+            "leaky_bucket" => Some(Algorithm::LeakyBucket),
+            "sliding_window" => Some(Algorithm::SlidingWindow),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+}
+
+pub struct Limits {
+    pub limit: u32,
+    // Tokens refilled, or level drained, per second (the buckets)
+    pub rate: f64,
+    // Length of the window (the sliding window log)
+    pub window_ms: u64,
+}
+
+pub struct Decision {
+    pub allowed: bool,
+    // Requests that would be allowed right after this one
+    pub remaining: u32,
+    // Until a request would be allowed again; 0 when one would be now
+    pub retry_after_ms: u64,
+    // Until the limiter is back to its full allowance
+    pub reset_ms: u64,
+}
+
+fn key(algorithm: Algorithm, key: &str) -> String {
+    format!("ratelimit:{}:{}", algorithm.name(), key)
+}
+
+// "<amount> <updated ms>", as tokens left or level reached
+fn meter(state: Option<&str>) -> Option<(f64, f64)> {
+    let (amount, updated) = state?.split_once(' ')?;
+    Some((amount.parse().ok()?, updated.parse().ok()?))
+}
+
+fn ms(seconds: f64) -> u64 {
+    (seconds * 1000.0).ceil().max(0.0) as u64
+}
+
+fn token_bucket(limits: &Limits, state: Option<&str>, now: f64) -> (Decision, String) {
+    let capacity = f64::from(limits.limit);
+    let (tokens, updated) = meter(state).unwrap_or((capacity, now));
+    let tokens = (tokens + (now - updated).max(0.0) / 1000.0 * limits.rate).min(capacity);
+    let allowed = tokens >= 1.0;
+    let tokens = if allowed { tokens - 1.0 } else { tokens };
+    let decision = Decision {
+        allowed,
+        remaining: tokens.floor() as u32,
+        retry_after_ms: if tokens >= 1.0 { 0 } else { ms((1.0 - tokens) / limits.rate) },
+        reset_ms: ms((capacity - tokens) / limits.rate),
+    };
+    (decision, format!("{} {}", tokens, now))
+}
+
+fn leaky_bucket(limits: &Limits, state: Option<&str>, now: f64) -> (Decision, String) {
+    let capacity = f64::from(limits.limit);
+    let (level, updated) = meter(state).unwrap_or((0.0, now));
+    let level = (level - (now - updated).max(0.0) / 1000.0 * limits.rate).max(0.0);
+    let allowed = level + 1.0 <= capacity;
+    let level = if allowed { level + 1.0 } else { level };
+    let decision = Decision {
+        allowed,
+        remaining: (capacity - level).floor() as u32,
+        retry_after_ms: if level + 1.0 <= capacity { 0 } else { ms((level + 1.0 - capacity) / limits.rate) },
+        reset_ms: ms(level / limits.rate),
+    };
+    (decision, format!("{} {}", level, now))
+}
+
+// The state is the request times in milliseconds, oldest first
+fn sliding_window(limits: &Limits, state: Option<&str>, now: f64) -> (Decision, String) {
+    let now = now as u64;
+    let start = now.saturating_sub(limits.window_ms);
+    let mut times: Vec<u64> =
+        state.unwrap_or("").split(' ').filter_map(|time| time.parse().ok()).filter(|&time| time > start).collect();
+    let allowed = times.len() < limits.limit as usize;
+    if allowed {
+        times.push(now);
+    }
+    let limit = limits.limit as usize;
+    // A request is allowed again once the oldest of the last `limit` leaves
+    let retry_after_ms = match times.len().checked_sub(limit) {
+        Some(excess) => times[excess] + limits.window_ms - now,
+        None => 0,
+    };
+    let decision = Decision {
+        allowed,
+        remaining: limit.saturating_sub(times.len()) as u32,
+        retry_after_ms,
+        reset_ms: times.last().map_or(0, |&newest| newest + limits.window_ms - now),
+    };
+    let state = times.iter().map(u64::to_string).collect::<Vec<_>>().join(" ");
+    (decision, state)
+}
+
+// Counts one request against the key and stores the new state, which expires
+// once the limiter has fully recovered
+pub fn hit(algorithm: Algorithm, name: &str, limits: &Limits, now_ms: f64) -> Result<Decision, Pending> {
+    let key = key(algorithm, name);
+    let state = kv::get(&key)?;
+    let (decision, state) = match algorithm {
+        Algorithm::TokenBucket => token_bucket(limits, state.as_deref(), now_ms),
+        Algorithm::LeakyBucket => leaky_bucket(limits, state.as_deref(), now_ms),
+        Algorithm::SlidingWindow => sliding_window(limits, state.as_deref(), now_ms),
+    };
+    kv::put(&key, &state, decision.reset_ms.div_ceil(1000).max(60) as u32);
+    Ok(decision)
+}
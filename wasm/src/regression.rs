@@ -0,0 +1,107 @@
+// Least-squares curve fitting
+// Polynomials are fitted through the normal equations, solved by Gaussian
+// elimination with partial pivoting; the exponential model fits ln(y)
+// linearly.
+
+pub const MODELS: [&str; 3] = ["linear", "poly2", "exp"];
+
+type Predictor = fn(&[f64], f64) -> f64;
+
+pub struct Fit {
+    // Lowest order first: y = c0 + c1 x + c2 x^2, or y = c0 e^(c1 x) for exp
+    pub coefficients: Vec<f64>,
+    pub predicted: Vec<f64>,
+    pub r_squared: f64,
+}
+
+// Coefficients of the degree `degree` polynomial minimising squared error
+pub fn polynomial(x: &[f64], y: &[f64], degree: usize) -> Result<Vec<f64>, String> {
+    let size = degree + 1;
+    if x.len() != y.len() {
+        return Err("x and y must have the same length".to_string());
+    }
+    if x.len() < size {
+        return Err(format!("At least {} points are needed", size));
+    }
+
+    // Augmented normal equations: sum(x^(i+j)) c_j = sum(x^i y)
+    let mut matrix = vec![vec![0.0; size + 1]; size];
+    for (&xi, &yi) in x.iter().zip(y) {
+        for (row, equation) in matrix.iter_mut().enumerate() {
+            for (column, cell) in equation.iter_mut().take(size).enumerate() {
+                *cell += xi.powi((row + column) as i32);
+            }
+            equation[size] += xi.powi(row as i32) * yi;
+        }
+    }
+
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))
+            .unwrap();
+        if matrix[pivot][column].abs() < 1e-12 {
+            return Err("Points do not determine a unique fit (too few distinct x values)".to_string());
+        }
+        matrix.swap(column, pivot);
+        let (upper, lower) = matrix.split_at_mut(column + 1);
+        let pivot_row = &upper[column];
+        for row in lower {
+            let factor = row[column] / pivot_row[column];
+            for (cell, &value) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *cell -= factor * value;
+            }
+        }
+    }
+    let mut coefficients = vec![0.0; size];
+    for row in (0..size).rev() {
+        let known: f64 = (row + 1..size).map(|k| matrix[row][k] * coefficients[k]).sum();
+        coefficients[row] = (matrix[row][size] - known) / matrix[row][row];
+    }
+    Ok(coefficients)
+}
+
+pub fn fit(model: &str, x: &[f64], y: &[f64]) -> Result<Fit, String> {
+    let (coefficients, predict): (Vec<f64>, Predictor) = match model {
+        "linear" | "poly2" => {
+            let degree = if model == "linear" { 1 } else { 2 };
+            let predict = |c: &[f64], x: f64| c.iter().rev().fold(0.0, |acc, &c| acc * x + c);
+            (polynomial(x, y, degree)?, predict)
+        }
+        "exp" => {
+            if y.iter().any(|&y| y <= 0.0) {
+                return Err("The exp model needs every y value to be positive".to_string());
+            }
+            let log_y: Vec<f64> = y.iter().map(|y| y.ln()).collect();
+            let line = polynomial(x, &log_y, 1)?;
+            let predict = |c: &[f64], x: f64| c[0] * (c[1] * x).exp();
+            (vec![line[0].exp(), line[1]], predict)
+        }
+        _ => return Err(format!("Unknown model '{}', expected one of: {}", model, MODELS.join(", "))),
+    };
+
+    let predicted: Vec<f64> = x.iter().map(|&x| predict(&coefficients, x)).collect();
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    let total: f64 = y.iter().map(|y| (y - mean).powi(2)).sum();
+    let residual: f64 = y.iter().zip(&predicted).map(|(y, p)| (y - p).powi(2)).sum();
+    // A constant y is explained perfectly by any fit that reproduces it
+    let r_squared = if total == 0.0 { if residual == 0.0 { 1.0 } else { 0.0 } } else { 1.0 - residual / total };
+    Ok(Fit { coefficients, predicted, r_squared })
+}
+
+// Fit a polynomial of `degree` to `len` points in f64 buffers, writing
+// degree + 1 coefficients (lowest order first) to out_ptr; returns 1 on
+// success and 0 on invalid input or a degenerate fit
+#[no_mangle]
+pub extern "C" fn least_squares_fit(x_ptr: *const f64, y_ptr: *const f64, len: usize, degree: usize, out_ptr: *mut f64) -> i32 {
+    if x_ptr.is_null() || y_ptr.is_null() || out_ptr.is_null() || len == 0 || degree > 8 {
+        return 0;
+    }
+    let (x, y) = unsafe { (std::slice::from_raw_parts(x_ptr, len), std::slice::from_raw_parts(y_ptr, len)) };
+    match polynomial(x, y, degree) {
+        Ok(coefficients) => {
+            unsafe { std::slice::from_raw_parts_mut(out_ptr, degree + 1) }.copy_from_slice(&coefficients);
+            1
+        }
+        Err(_) => 0,
+    }
+}
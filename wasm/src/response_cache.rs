@@ -0,0 +1,95 @@
+// LRU cache of complete responses for deterministic GET endpoints
+// Handlers opt in through worker::cached, which keys entries by the path and
+// the query parameters sorted by name, so /add?b=2&a=1 and /add?a=1&b=2 share
+// an entry. Each isolate keeps its own cache; CONFIG sets its size and how
+// long entries stay fresh.
+
+use crate::arena;
+use crate::config::{self, Config};
+use crate::host;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+// Larger responses are cheaper to recompute than to hold on to
+const MAX_RESPONSE_BYTES: usize = 256 * 1024;
+
+struct Entry {
+    response: String,
+    stored_at: f64,
+    // Value of the use clock when the entry was last read or written
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<String, Entry>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+}
+
+pub fn get(key: &str, config: &Config) -> Option<String> {
+    let now = host::now_ms();
+    CACHE.with(|cache| {
+        let cache = &mut *cache.borrow_mut();
+        cache.clock += 1;
+        let fresh = match cache.entries.get_mut(key) {
+            Some(entry) if now - entry.stored_at < config.response_cache_ttl_seconds as f64 * 1000.0 => {
+                entry.last_used = cache.clock;
+                Some(entry.response.clone())
+            }
+            Some(_) => {
+                cache.entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        match fresh {
+            Some(_) => cache.hits += 1,
+            None => cache.misses += 1,
+        }
+        fresh
+    })
+}
+
+// Evicts the least recently used entries to make room; a capacity of 0
+// disables the cache. The entry is copied out of the request arena.
+pub fn put(key: &str, response: &str, config: &Config) {
+    let capacity = config.response_cache_entries;
+    if capacity == 0 || response.len() > MAX_RESPONSE_BYTES {
+        return;
+    }
+    let now = host::now_ms();
+    arena::outside(|| CACHE.with(|cache| {
+        let cache = &mut *cache.borrow_mut();
+        cache.clock += 1;
+        cache.entries.remove(key);
+        while cache.entries.len() >= capacity {
+            let Some(oldest) = cache.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            cache.entries.remove(&oldest);
+        }
+        cache.entries.insert(key.to_string(), Entry { response: response.to_string(), stored_at: now, last_used: cache.clock });
+    }))
+}
+
+// {"entries":n,"capacity":n,"ttl_seconds":n,"hits":n,"misses":n}
+pub fn stats_json() -> String {
+    let config = config::current();
+    CACHE.with(|cache| {
+        let cache = cache.borrow();
+        format!(
+            r#"{{"entries":{},"capacity":{},"ttl_seconds":{},"hits":{},"misses":{}}}"#,
+            cache.entries.len(),
+            config.response_cache_entries,
+            config.response_cache_ttl_seconds,
+            cache.hits,
+            cache.misses
+        )
+    })
+}
@@ -0,0 +1,36 @@
+// Retry policy for host calls that fail transiently
+// KV loads, KV writes and outbound fetches run in the host between passes
+// (see kv.rs and fetch.rs), so the host is what waits and tries again, but
+// it asks retry_delay_ms after every failed attempt and the policy lives
+// here with the rest of the configuration. Delays grow exponentially from
+// retry_base_delay_ms up to retry_max_delay_ms, with full jitter so that
+// isolates retrying the same failure spread out instead of arriving
+// together.
+
+use crate::config::{self, Config};
+use crate::host;
+
+// Statuses worth another attempt; 0 stands for a call that threw
+pub fn is_retryable(status: u16) -> bool {
+    matches!(status, 0 | 408 | 429 | 500 | 502 | 503 | 504)
+}
+
+// How long to wait after failed attempt `attempt` (counting from 1), or None
+// to give up
+pub fn delay_ms(config: &Config, attempt: u32, status: u16) -> Option<u32> {
+    if attempt == 0 || attempt >= config.retry_max_attempts || !is_retryable(status) {
+        return None;
+    }
+    let ceiling = config.retry_base_delay_ms.saturating_mul(1 << (attempt - 1).min(20)).min(config.retry_max_delay_ms);
+    let mut random = [0u8; 4];
+    host::random_bytes(&mut random);
+    // A uniform choice from 0..=ceiling
+    Some(((u64::from(u32::from_le_bytes(random)) * (u64::from(ceiling) + 1)) >> 32) as u32)
+}
+
+// -1 when the host should give up and report the last failure
+#[no_mangle]
+pub extern "C" fn retry_delay_ms(attempt: u32, status: u32) -> i32 {
+    let status = u16::try_from(status).unwrap_or(0);
+    delay_ms(&config::current(), attempt, status).map_or(-1, |delay| delay as i32)
+}
@@ -0,0 +1,195 @@
+// robots.txt parsing and matching (RFC 9309)
+// Rules come in groups, each headed by one or more user-agent lines. A
+// crawler obeys every group naming its product token, compared without case,
+// or else every "*" group, or else nothing is disallowed. Of the allow and
+// disallow rules matching a path, the longest pattern wins and allow wins a
+// tie; "*" matches any run of characters and a final "$" anchors the pattern
+// to the end of the path. Paths and patterns are compared with their percent
+// escapes normalised. Crawl-delay is not in the RFC but widely used, and is
+// taken from the first matching group that has one.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub struct Rule {
+    pub allow: bool,
+    pub pattern: String,
+    pub line: usize,
+}
+
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<f64>,
+}
+
+pub struct Robots {
+    groups: Vec<Group>,
+    pub sitemaps: Vec<String>,
+}
+
+pub struct Verdict<'a> {
+    pub allowed: bool,
+    // The user-agent the obeyed groups name, or None when no group applies
+    pub group: Option<String>,
+    // The deciding rule; None when no rule matches
+    pub rule: Option<&'a Rule>,
+    pub crawl_delay: Option<f64>,
+}
+
+// "Googlebot/2.1" is obeyed as "googlebot"
+pub fn product_token(agent: &str) -> String {
+    let agent = agent.trim();
+    if agent.starts_with('*') {
+        return "*".to_string();
+    }
+    agent.chars().take_while(|c| c.is_ascii_alphabetic() || *c == '_' || *c == '-').collect::<String>().to_ascii_lowercase()
+}
+
+// Lines that are not rules, or rules before any user-agent line, are ignored
+// as the RFC asks
+pub fn parse(text: &str) -> Robots {
+    let mut robots = Robots { groups: Vec::new(), sitemaps: Vec::new() };
+    // Whether the last group is still collecting user-agent lines
+    let mut in_agents = false;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user-agent" => {
+                if !in_agents {
+                    robots.groups.push(Group { agents: Vec::new(), rules: Vec::new(), crawl_delay: None });
+                    in_agents = true;
+                }
+                if let Some(group) = robots.groups.last_mut() {
+                    group.agents.push(product_token(value));
+                }
+            }
+            key @ ("allow" | "disallow") => {
+                in_agents = false;
+                // An empty disallow allows everything, which needs no rule
+                if let (Some(group), false) = (robots.groups.last_mut(), value.is_empty()) {
+                    group.rules.push(Rule { allow: key == "allow", pattern: value.to_string(), line: index + 1 });
+                }
+            }
+            "crawl-delay" => {
+                in_agents = false;
+                if let (Some(group), Ok(delay)) = (robots.groups.last_mut(), value.parse::<f64>()) {
+                    if delay.is_finite() && delay >= 0.0 {
+                        group.crawl_delay = group.crawl_delay.or(Some(delay));
+                    }
+                }
+            }
+            "sitemap" if !value.is_empty() => robots.sitemaps.push(value.to_string()),
+            _ => {}
+        }
+    }
+    robots
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+// Escapes of unreserved characters decoded, other escapes upper-cased, and
+// bytes that are not printable ASCII escaped; '*' and '$' are kept
+fn normalize(path: &str) -> Vec<u8> {
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes.get(i..i + 3) {
+            Some(&[b'%', high, low]) if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                let digit = |b: u8| (b as char).to_digit(16).unwrap_or(0) as u8;
+                let decoded = digit(high) << 4 | digit(low);
+                if is_unreserved(decoded) {
+                    out.push(decoded);
+                } else {
+                    out.extend_from_slice(format!("%{:02X}", decoded).as_bytes());
+                }
+                i += 3;
+            }
+            _ => {
+                let byte = bytes[i];
+                if byte.is_ascii_graphic() {
+                    out.push(byte);
+                } else {
+                    out.extend_from_slice(format!("%{:02X}", byte).as_bytes());
+                }
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+// Whether the pattern matches the start of the path, or all of it with "$"
+fn matches(pattern: &[u8], path: &[u8]) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix(b"$") {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    // Wildcard matching with backtracking to the last '*'
+    let (mut p, mut s) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    loop {
+        if p == pattern.len() && (!anchored || s == path.len()) {
+            return true;
+        }
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, s));
+            p += 1;
+        } else if p < pattern.len() && s < path.len() && pattern[p] == path[s] {
+            p += 1;
+            s += 1;
+        } else if let Some((star_p, star_s)) = star.filter(|&(_, star_s)| star_s < path.len()) {
+            star = Some((star_p, star_s + 1));
+            p = star_p + 1;
+            s = star_s + 1;
+        } else {
+            return false;
+        }
+    }
+}
+
+impl Robots {
+    // `path` is the path and query of the URL, starting with '/'
+    pub fn check(&self, agent: &str, path: &str) -> Verdict<'_> {
+        let token = product_token(agent);
+        let named: Vec<&Group> = self.groups.iter().filter(|group| !token.is_empty() && group.agents.contains(&token)).collect();
+        let (groups, group) = if !named.is_empty() {
+            (named, Some(token))
+        } else {
+            let wildcard: Vec<&Group> = self.groups.iter().filter(|group| group.agents.iter().any(|name| name == "*")).collect();
+            let group = (!wildcard.is_empty()).then(|| "*".to_string());
+            (wildcard, group)
+        };
+        let crawl_delay = groups.iter().find_map(|group| group.crawl_delay);
+
+        // /robots.txt itself is always allowed
+        if path == "/robots.txt" {
+            return Verdict { allowed: true, group, rule: None, crawl_delay };
+        }
+        let path = normalize(path);
+        let mut best: Option<(&Rule, usize)> = None;
+        for rule in groups.iter().flat_map(|group| &group.rules) {
+            let pattern = normalize(&rule.pattern);
+            if !matches(&pattern, &path) {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((current, length)) => pattern.len() > length || pattern.len() == length && rule.allow && !current.allow,
+            };
+            if better {
+                best = Some((rule, pattern.len()));
+            }
+        }
+        let rule = best.map(|(rule, _)| rule);
+        Verdict { allowed: rule.is_none_or(|rule| rule.allow), group, rule, crawl_delay }
+    }
+}
@@ -0,0 +1,89 @@
+// Traffic steering from the visitor's country and languages
+// Rules from routing_rules in CONFIG are tried in order. A rule matches when
+// its countries, if any, include the CF-IPCountry of the request and its
+// languages, if any, include one the visitor accepts; the list of origins of
+// the first match serves the request, or routing_default_origin when none
+// does. A rule with several origins spreads keys over them on a hashing
+// ring. The cache key holds what the decision depended on, so a cache in
+// front of the origins never serves one visitor's variant to another.
+
+use crate::hashring::{self, Ring};
+
+#[derive(Clone)]
+pub struct Rule {
+    pub name: String,
+    // Upper-case ISO 3166 codes
+    pub countries: Vec<String>,
+    // Lower-case primary language subtags such as "pl"
+    pub languages: Vec<String>,
+    pub origins: Vec<String>,
+}
+
+pub struct Decision<'a> {
+    pub rule: Option<&'a Rule>,
+    pub origin: Option<String>,
+    // The visitor's language the rule matched on
+    pub language: Option<String>,
+    pub cache_key: String,
+}
+
+// "XX" is Cloudflare's code for an unknown country; "T1" (Tor) is kept
+pub fn country(header: Option<&str>) -> Option<String> {
+    let code = header?.trim().to_ascii_uppercase();
+    (code.len() == 2 && code.bytes().all(|b| b.is_ascii_alphanumeric()) && code != "XX").then_some(code)
+}
+
+// The first of the visitor's languages, in order of preference, that the
+// rule lists; "pl-PL" matches "pl"
+fn matching_language(rule: &Rule, languages: &[String]) -> Option<String> {
+    languages.iter().find_map(|language| {
+        let primary = language.split('-').next().unwrap_or(language);
+        rule.languages.iter().any(|listed| listed == primary).then(|| primary.to_string())
+    })
+}
+
+pub fn decide<'a>(
+    rules: &'a [Rule],
+    default_origin: Option<&str>,
+    country: Option<&str>,
+    languages: &[String],
+    key: &str,
+) -> Decision<'a> {
+    let matched = rules.iter().find_map(|rule| {
+        let country_matches = rule.countries.is_empty() || country.is_some_and(|country| rule.countries.iter().any(|listed| listed == country));
+        if !country_matches {
+            return None;
+        }
+        if rule.languages.is_empty() {
+            return Some((rule, None));
+        }
+        matching_language(rule, languages).map(|language| (rule, Some(language)))
+    });
+    let (rule, language) = match matched {
+        Some((rule, language)) => (Some(rule), language),
+        None => (None, None),
+    };
+    let origin = match rule {
+        Some(rule) if rule.origins.len() == 1 => Some(rule.origins[0].clone()),
+        Some(rule) => {
+            let mut ring = Ring::new(hashring::DEFAULT_VNODES);
+            for origin in &rule.origins {
+                ring.add(origin);
+            }
+            ring.locate(key).map(str::to_string)
+        }
+        None => default_origin.map(str::to_string),
+    };
+
+    // Only dimensions some rule looks at split the cache
+    let by_country = rules.iter().any(|rule| !rule.countries.is_empty());
+    let by_language = rules.iter().any(|rule| !rule.languages.is_empty());
+    let mut cache_key = format!("{}|{}", origin.as_deref().unwrap_or("none"), key);
+    if by_country {
+        cache_key.push_str(&format!("|country={}", country.unwrap_or("*")));
+    }
+    if by_language {
+        cache_key.push_str(&format!("|lang={}", language.as_deref().unwrap_or("*")));
+    }
+    Decision { rule, origin, language, cache_key }
+}
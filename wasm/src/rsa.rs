@@ -0,0 +1,184 @@
+// Textbook RSA key generation, signing and verification on BigUint
+// Signatures are the raw SHA-256 digest (reduced mod n) raised to d, with no
+// padding, and keys come from the non-cryptographic PRNG: this exists to show
+// what big-integer arithmetic costs inside a request, not to protect anything.
+
+use crate::arena;
+use crate::bignum::BigUint;
+use crate::budget;
+use crate::rand::Rng;
+use std::cell::OnceCell;
+
+pub const PUBLIC_EXPONENT: u64 = 65537;
+pub const MIN_BITS: usize = 128;
+pub const MAX_BITS: usize = 1024;
+
+// Miller-Rabin rounds per candidate, after trial division by small primes
+const ROUNDS: usize = 20;
+const TRIAL_DIVISION_LIMIT: u32 = 2000;
+
+pub struct KeyPair {
+    pub n: BigUint,
+    pub e: BigUint,
+    pub d: BigUint,
+    pub p: BigUint,
+    pub q: BigUint,
+}
+
+pub struct Generation {
+    pub keys: KeyPair,
+    // Random odd numbers drawn before both primes were found
+    pub candidates: usize,
+}
+
+thread_local! {
+    // Sieved once per instance, by the first key generation or by warmup
+    static SMALL_PRIMES: OnceCell<Vec<u32>> = const { OnceCell::new() };
+}
+
+fn sieve() -> Vec<u32> {
+    let limit = TRIAL_DIVISION_LIMIT as usize;
+    let mut composite = vec![false; limit + 1];
+    let mut primes = Vec::new();
+    for i in 2..=limit {
+        if !composite[i] {
+            primes.push(i as u32);
+            for multiple in (i * i..=limit).step_by(i) {
+                composite[multiple] = true;
+            }
+        }
+    }
+    primes
+}
+
+// Odd number of exactly `bits` bits with the top two set, so the product of
+// two such primes has exactly twice as many bits
+fn random_odd(bits: usize, rng: &mut Rng) -> BigUint {
+    let mut value = BigUint::zero();
+    let mut remaining = bits;
+    while remaining > 0 {
+        let take = remaining.min(16);
+        let mut chunk = rng.below(1 << take) as u32;
+        if remaining == bits {
+            chunk |= 0b11 << (take - 2);
+        }
+        value = value.mul_small(1 << take).add(&BigUint::from_u64(chunk as u64));
+        remaining -= take;
+    }
+    if value.is_even() {
+        value = value.add(&BigUint::from_u64(1));
+    }
+    value
+}
+
+fn is_probable_prime(n: &BigUint, small_primes: &[u32], rng: &mut Rng) -> bool {
+    for &prime in small_primes {
+        if *n == BigUint::from_u64(prime as u64) {
+            return true;
+        }
+        if n.div_small(prime).1 == 0 {
+            return false;
+        }
+    }
+
+    let one = BigUint::from_u64(1);
+    let n_minus_one = n.checked_sub(&one).unwrap();
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while d.is_even() {
+        d = d.div_small(2).0;
+        s += 1;
+    }
+    'witness: for _ in 0..ROUNDS {
+        // n exceeds TRIAL_DIVISION_LIMIT^2 here, so any base below 2^32 is in range
+        let a = BigUint::from_u64(2 + rng.below(1 << 32));
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.mul(&x).rem(n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn random_prime(bits: usize, small_primes: &[u32], rng: &mut Rng, candidates: &mut usize) -> BigUint {
+    loop {
+        let candidate = random_odd(bits, rng);
+        *candidates += 1;
+        // Whatever is returned here is discarded by generate()
+        if budget::exhausted() {
+            return candidate;
+        }
+        // e is prime, so it is coprime to p - 1 unless p = 1 (mod e)
+        if candidate.div_small(PUBLIC_EXPONENT as u32).1 != 1 && is_probable_prime(&candidate, small_primes, rng) {
+            return candidate;
+        }
+    }
+}
+
+// Inverse of a modulo m via the extended Euclidean algorithm, with the
+// Bezout coefficient kept reduced mod m so it never goes negative
+fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let (mut old_r, mut r) = (m.clone(), a.rem(m));
+    let (mut old_t, mut t) = (BigUint::zero(), BigUint::from_u64(1));
+    while !r.is_zero() {
+        let (quotient, remainder) = old_r.div_rem(&r);
+        old_r = std::mem::replace(&mut r, remainder);
+        let step = quotient.mul(&t).rem(m);
+        let next = old_t.add(m).checked_sub(&step).unwrap().rem(m);
+        old_t = std::mem::replace(&mut t, next);
+    }
+    (old_r == BigUint::from_u64(1)).then_some(old_t)
+}
+
+// Key pair with an n of exactly `bits` bits (MIN_BITS..=MAX_BITS), or None
+// if the computation budget runs out first
+pub fn generate(bits: usize, rng: &mut Rng) -> Option<Generation> {
+    SMALL_PRIMES.with(|small_primes| {
+        let small_primes = small_primes.get_or_init(|| arena::outside(sieve));
+        let one = BigUint::from_u64(1);
+        let e = BigUint::from_u64(PUBLIC_EXPONENT);
+        let mut candidates = 0;
+        loop {
+            let p = random_prime(bits / 2, small_primes, rng, &mut candidates);
+            let q = random_prime(bits - bits / 2, small_primes, rng, &mut candidates);
+            if budget::exhausted() {
+                return None;
+            }
+            if p == q {
+                continue;
+            }
+            let phi = p.checked_sub(&one).unwrap().mul(&q.checked_sub(&one).unwrap());
+            let Some(d) = mod_inverse(&e, &phi) else {
+                continue;
+            };
+            let n = p.mul(&q);
+            return Some(Generation { keys: KeyPair { n, e, d, p, q }, candidates });
+        }
+    })
+}
+
+// Builds the trial division table ahead of the first key generation
+pub fn warm() {
+    SMALL_PRIMES.with(|small_primes| {
+        small_primes.get_or_init(|| arena::outside(sieve));
+    });
+}
+
+pub fn is_warm() -> bool {
+    SMALL_PRIMES.with(|small_primes| small_primes.get().is_some())
+}
+
+pub fn sign(digest: &BigUint, keys: &KeyPair) -> BigUint {
+    digest.rem(&keys.n).modpow(&keys.d, &keys.n)
+}
+
+pub fn verify(digest: &BigUint, signature: &BigUint, n: &BigUint, e: &BigUint) -> bool {
+    signature.modpow(e, n) == digest.rem(n)
+}
@@ -0,0 +1,164 @@
+// In-memory full-text search with TF-IDF ranking
+// The index lives in module memory, so documents posted once stay searchable
+// for later requests served by the same isolate. Words are reduced to terms
+// by stemming::normalize for the index language, so stop words are skipped
+// and English words match their inflections.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::arena;
+use crate::stemming;
+
+pub struct Document {
+    // Identifier rendered as JSON (a string or number from the caller)
+    pub id: String,
+    pub text: String,
+    length: usize,
+}
+
+pub struct Index {
+    pub language: &'static str,
+    pub documents: Vec<Document>,
+    // term -> (document index, occurrences) for every document containing it
+    postings: HashMap<String, Vec<(usize, usize)>>,
+}
+
+pub struct Hit {
+    pub document: usize,
+    pub score: f64,
+}
+
+// Characters of context kept on each side of the first match in a snippet
+const SNIPPET_CONTEXT: usize = 40;
+const SNIPPET_LENGTH: usize = 160;
+
+thread_local! {
+    static INDEX: RefCell<Index> = RefCell::new(Index::new("en"));
+}
+
+pub fn with_index<T>(f: impl FnOnce(&mut Index) -> T) -> T {
+    arena::outside(|| INDEX.with(|index| f(&mut index.borrow_mut())))
+}
+
+// Lowercased alphanumeric words with their byte ranges in the input
+fn tokens(text: &str) -> Vec<(String, usize, usize)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, c.is_alphanumeric()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                tokens.push((text[s..i].to_lowercase(), s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+impl Index {
+    pub fn new(language: &'static str) -> Self {
+        Index { language, documents: Vec::new(), postings: HashMap::new() }
+    }
+
+    // Normalized terms with their byte ranges in the input
+    fn terms_with_ranges(&self, text: &str) -> Vec<(String, usize, usize)> {
+        tokens(text)
+            .into_iter()
+            .filter_map(|(word, start, end)| Some((stemming::normalize(self.language, &word)?, start, end)))
+            .collect()
+    }
+
+    pub fn terms(&self, query: &str) -> Vec<String> {
+        let mut terms: Vec<String> = self.terms_with_ranges(query).into_iter().map(|(term, _, _)| term).collect();
+        terms.sort();
+        terms.dedup();
+        terms
+    }
+
+    // Copies the document, which usually lives in the request arena
+    pub fn add(&mut self, id: &str, text: &str) {
+        let document = self.documents.len();
+        let terms = self.terms_with_ranges(text);
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (term, _, _) in &terms {
+            *counts.entry(term.clone()).or_default() += 1;
+        }
+        for (term, count) in counts {
+            self.postings.entry(term).or_default().push((document, count));
+        }
+        self.documents.push(Document { id: id.to_string(), text: text.to_string(), length: terms.len() });
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.postings.len()
+    }
+
+    // Documents containing any of the terms, best first. Each term adds
+    // tf * idf with tf = occurrences / document length and a smoothed
+    // idf = ln((N + 1) / (df + 1)) + 1.
+    pub fn search(&self, terms: &[String]) -> Vec<Hit> {
+        let total = self.documents.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let idf = ((total + 1.0) / (postings.len() as f64 + 1.0)).ln() + 1.0;
+            for &(document, count) in postings {
+                let tf = count as f64 / self.documents[document].length as f64;
+                *scores.entry(document).or_default() += tf * idf;
+            }
+        }
+        let mut hits: Vec<Hit> = scores.into_iter().map(|(document, score)| Hit { document, score }).collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.document.cmp(&b.document)));
+        hits
+    }
+
+    // Excerpt around the first matching word with every match wrapped in
+    // <mark></mark>. The text is not HTML-escaped; callers rendering it as HTML
+    // must escape everything outside the marks themselves.
+    pub fn snippet(&self, text: &str, terms: &[String]) -> String {
+        let matches: Vec<(usize, usize)> = self
+            .terms_with_ranges(text)
+            .into_iter()
+            .filter(|(term, _, _)| terms.contains(term))
+            .map(|(_, start, end)| (start, end))
+            .collect();
+        excerpt(text, &matches)
+    }
+}
+
+fn excerpt(text: &str, matches: &[(usize, usize)]) -> String {
+    let first = matches.first().map_or(0, |&(start, _)| start);
+
+    let start = text[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let end = text[start..]
+        .char_indices()
+        .nth(SNIPPET_LENGTH)
+        .map_or(text.len(), |(i, _)| start + i);
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    let mut cursor = start;
+    for &(match_start, match_end) in matches.iter().filter(|&&(s, e)| s >= start && e <= end) {
+        out.push_str(&text[cursor..match_start]);
+        out.push_str("<mark>");
+        out.push_str(&text[match_start..match_end]);
+        out.push_str("</mark>");
+        cursor = match_end;
+    }
+    out.push_str(&text[cursor..end]);
+    if end < text.len() {
+        out.push('…');
+    }
+    out
+}
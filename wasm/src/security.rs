@@ -0,0 +1,51 @@
+// Security headers added to every HTML response
+// The defaults suit the pages this module renders (inline styles, no
+// scripts); CONFIG can override any of them, add others or remove one by
+// setting it to null.
+
+// Names are lower case so lookups and overrides can compare them directly
+pub const DEFAULT_HEADERS: [(&str, &str); 5] = [
+    (
+        "content-security-policy",
+        "default-src 'self'; style-src 'self' 'unsafe-inline'; img-src 'self' data:; base-uri 'none'; form-action 'self'; frame-ancestors 'none'",
+    ),
+    ("x-content-type-options", "nosniff"),
+    ("x-frame-options", "DENY"),
+    ("referrer-policy", "strict-origin-when-cross-origin"),
+    ("strict-transport-security", "max-age=31536000; includeSubDomains"),
+];
+
+// HTTP token characters, minus '|' which would split the response string
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`~".contains(&b))
+}
+
+// Values travel inside the "status|content-type|body" response string
+pub fn is_valid_value(value: &str) -> bool {
+    !value.contains(['|', '\r', '\n'])
+}
+
+// Appends the headers to an HTML response unless the handler already set them
+pub fn apply(response: String, headers: &[(String, String)]) -> String {
+    let Some((status, rest)) = response.split_once('|') else {
+        return response;
+    };
+    let Some((header_block, body)) = rest.split_once('|') else {
+        return response;
+    };
+    let mut lines = header_block.split('\n');
+    let content_type = lines.next().unwrap_or("");
+    if !content_type.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case("text/html") {
+        return response;
+    }
+    let existing: Vec<String> =
+        lines.filter_map(|line| line.split_once(':')).map(|(name, _)| name.trim().to_ascii_lowercase()).collect();
+
+    let mut header_block = header_block.to_string();
+    for (name, value) in headers {
+        if !existing.contains(name) {
+            header_block.push_str(&format!("\n{}: {}", name, value));
+        }
+    }
+    format!("{}|{}|{}", status, header_block, body)
+}
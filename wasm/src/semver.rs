@@ -0,0 +1,415 @@
+// Semantic Versioning 2.0.0 parsing, precedence and node-style ranges
+// Range syntax follows the npm `semver` package: comparators (<, <=, >, >=, =),
+// caret and tilde ranges, x-ranges, hyphen ranges and `||` unions.
+
+use alloc::{format, vec};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Identifier {
+    Numeric(u64),
+    Alphanumeric(String),
+}
+
+#[derive(Clone)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub prerelease: Vec<Identifier>,
+    pub build: Vec<String>,
+}
+
+impl Version {
+    fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version { major, minor, patch, prerelease: Vec::new(), build: Vec::new() }
+    }
+
+    // The lowest possible prerelease of a version, used as an exclusive upper bound
+    fn lowest_prerelease(major: u64, minor: u64, patch: u64) -> Self {
+        Version { prerelease: vec![Identifier::Numeric(0)], ..Version::new(major, minor, patch) }
+    }
+
+    fn same_release(&self, other: &Version) -> bool {
+        (self.major, self.minor, self.patch) == (other.major, other.minor, other.patch)
+    }
+
+    // Precedence as defined by SemVer 2.0 section 11; build metadata is ignored
+    pub fn precedence(&self, other: &Version) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.prerelease.is_empty(), other.prerelease.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_prerelease(&self.prerelease, &other.prerelease),
+            })
+    }
+}
+
+fn compare_prerelease(a: &[Identifier], b: &[Identifier]) -> Ordering {
+    for (left, right) in a.iter().zip(b) {
+        let ordering = match (left, right) {
+            (Identifier::Numeric(x), Identifier::Numeric(y)) => x.cmp(y),
+            (Identifier::Numeric(_), Identifier::Alphanumeric(_)) => Ordering::Less,
+            (Identifier::Alphanumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+            (Identifier::Alphanumeric(x), Identifier::Alphanumeric(y)) => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{}", n),
+            Identifier::Alphanumeric(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.prerelease.is_empty() {
+            let identifiers: Vec<String> = self.prerelease.iter().map(|id| id.to_string()).collect();
+            write!(f, "-{}", identifiers.join("."))?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+// Strict SemVer 2.0 parsing (a leading "v" is tolerated, as npm does)
+pub fn parse(input: &str) -> Result<Version, String> {
+    let partial = parse_partial(input.trim())?;
+    match (partial.major, partial.minor, partial.patch) {
+        (Some(major), Some(minor), Some(patch)) => Ok(Version {
+            major,
+            minor,
+            patch,
+            prerelease: partial.prerelease,
+            build: partial.build,
+        }),
+        _ => Err(format!("'{}' is not a full MAJOR.MINOR.PATCH version", input.trim())),
+    }
+}
+
+// A possibly incomplete version as written in ranges ("1", "1.2.x", "*")
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    prerelease: Vec<Identifier>,
+    build: Vec<String>,
+}
+
+fn parse_partial(input: &str) -> Result<Partial, String> {
+    let text = input.strip_prefix(['v', 'V']).unwrap_or(input);
+    let (text, build) = match text.split_once('+') {
+        Some((text, build)) => (text, parse_build(build)?),
+        None => (text, Vec::new()),
+    };
+    let (core, prerelease) = match text.split_once('-') {
+        Some((core, pre)) => (core, parse_prerelease(pre)?),
+        None => (text, Vec::new()),
+    };
+
+    let parts: Vec<&str> = core.split('.').collect();
+    if parts.len() > 3 {
+        return Err(format!("'{}' has more than three version components", input));
+    }
+    let mut numbers = [None; 3];
+    let mut wildcard_seen = false;
+    for (i, part) in parts.iter().enumerate() {
+        if matches!(*part, "x" | "X" | "*") {
+            wildcard_seen = true;
+            continue;
+        }
+        if wildcard_seen {
+            return Err(format!("'{}' has a number after a wildcard", input));
+        }
+        numbers[i] = Some(parse_numeric(part).map_err(|reason| format!("'{}': {}", input, reason))?);
+    }
+    if numbers[2].is_none() && !prerelease.is_empty() {
+        return Err(format!("'{}' has a prerelease on an incomplete version", input));
+    }
+
+    Ok(Partial {
+        major: numbers[0],
+        minor: numbers[1],
+        patch: numbers[2],
+        prerelease,
+        build,
+    })
+}
+
+fn parse_numeric(part: &str) -> Result<u64, String> {
+    if part.is_empty() {
+        return Err("empty version component".to_string());
+    }
+    if !part.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("'{}' is not a number", part));
+    }
+    if part.len() > 1 && part.starts_with('0') {
+        return Err(format!("'{}' has a leading zero", part));
+    }
+    part.parse().map_err(|_| format!("'{}' is too large", part))
+}
+
+fn parse_prerelease(pre: &str) -> Result<Vec<Identifier>, String> {
+    pre.split('.')
+        .map(|id| {
+            check_identifier(id, "prerelease")?;
+            if id.bytes().all(|b| b.is_ascii_digit()) {
+                parse_numeric(id).map(Identifier::Numeric)
+            } else {
+                Ok(Identifier::Alphanumeric(id.to_string()))
+            }
+        })
+        .collect()
+}
+
+fn parse_build(build: &str) -> Result<Vec<String>, String> {
+    build
+        .split('.')
+        .map(|id| check_identifier(id, "build").map(|_| id.to_string()))
+        .collect()
+}
+
+fn check_identifier(id: &str, kind: &str) -> Result<(), String> {
+    if id.is_empty() {
+        return Err(format!("Empty {} identifier", kind));
+    }
+    if !id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+        return Err(format!("Invalid character in {} identifier '{}'", kind, id));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+pub struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, version: &Version) -> bool {
+        let ordering = version.precedence(&self.version);
+        match self.op {
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Eq => ordering == Ordering::Equal,
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Eq => "",
+        };
+        write!(f, "{}{}", op, self.version)
+    }
+}
+
+// A union (`||`) of comparator sets; a set matches when all comparators do
+pub struct Range {
+    sets: Vec<Vec<Comparator>>,
+}
+
+impl Range {
+    pub fn satisfies(&self, version: &Version) -> bool {
+        self.sets.iter().any(|set| {
+            set.iter().all(|comparator| comparator.matches(version))
+                // Prereleases only match sets that opt into the same release
+                // line, so ^1.0.0 does not pick up 1.5.0-beta by accident
+                && (version.prerelease.is_empty()
+                    || set.iter().any(|comparator| {
+                        !comparator.version.prerelease.is_empty()
+                            && comparator.version.same_release(version)
+                    }))
+        })
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sets: Vec<String> = self
+            .sets
+            .iter()
+            .map(|set| {
+                if set.is_empty() {
+                    "*".to_string()
+                } else {
+                    set.iter().map(|comparator| comparator.to_string()).collect::<Vec<_>>().join(" ")
+                }
+            })
+            .collect();
+        write!(f, "{}", sets.join(" || "))
+    }
+}
+
+pub fn parse_range(input: &str) -> Result<Range, String> {
+    let sets = input.split("||").map(parse_comparator_set).collect::<Result<_, _>>()?;
+    Ok(Range { sets })
+}
+
+fn parse_comparator_set(input: &str) -> Result<Vec<Comparator>, String> {
+    // Glue operators to their versions so ">= 1.2.3" reads as one token
+    let mut tokens: Vec<String> = Vec::new();
+    for token in input.split_whitespace() {
+        match tokens.last_mut() {
+            Some(last) if matches!(last.as_str(), "<" | "<=" | ">" | ">=" | "=" | "~" | "^" | "~>") => last.push_str(token),
+            _ => tokens.push(token.to_string()),
+        }
+    }
+
+    if tokens.len() == 3 && tokens[1] == "-" {
+        return hyphen_range(&tokens[0], &tokens[2]);
+    }
+
+    let mut comparators = Vec::new();
+    for token in &tokens {
+        comparators.extend(desugar(token)?);
+    }
+    Ok(comparators)
+}
+
+// "1.2 - 2.3" means >=1.2.0 <2.4.0-0
+fn hyphen_range(low: &str, high: &str) -> Result<Vec<Comparator>, String> {
+    let low = parse_partial(low)?;
+    let high = parse_partial(high)?;
+    let mut comparators = Vec::new();
+    if low.major.is_some() {
+        comparators.push(Comparator { op: Op::Ge, version: floor(&low) });
+    }
+    match (high.major, high.minor, high.patch) {
+        (None, _, _) => {}
+        (Some(major), None, _) => comparators.push(Comparator { op: Op::Lt, version: Version::lowest_prerelease(major + 1, 0, 0) }),
+        (Some(major), Some(minor), None) => {
+            comparators.push(Comparator { op: Op::Lt, version: Version::lowest_prerelease(major, minor + 1, 0) })
+        }
+        _ => comparators.push(Comparator { op: Op::Le, version: floor(&high) }),
+    }
+    Ok(comparators)
+}
+
+// The smallest version a partial can stand for, with missing parts as zero
+fn floor(partial: &Partial) -> Version {
+    Version {
+        prerelease: partial.prerelease.clone(),
+        ..Version::new(partial.major.unwrap_or(0), partial.minor.unwrap_or(0), partial.patch.unwrap_or(0))
+    }
+}
+
+// The exclusive upper bound of an x-range, or None when unbounded
+fn x_range_ceiling(partial: &Partial) -> Option<Version> {
+    match (partial.major, partial.minor, partial.patch) {
+        (None, _, _) => None,
+        (Some(major), None, _) => Some(Version::lowest_prerelease(major + 1, 0, 0)),
+        (Some(major), Some(minor), None) => Some(Version::lowest_prerelease(major, minor + 1, 0)),
+        _ => None,
+    }
+}
+
+fn desugar(token: &str) -> Result<Vec<Comparator>, String> {
+    let (op, rest) = ["<=", ">=", "~>", "<", ">", "=", "~", "^"]
+        .iter()
+        .find_map(|op| token.strip_prefix(op).map(|rest| (*op, rest)))
+        .unwrap_or(("", token));
+    let partial = parse_partial(rest)?;
+    let low = floor(&partial);
+    let complete = partial.patch.is_some();
+
+    let comparators = match op {
+        "" | "=" => {
+            if complete {
+                vec![Comparator { op: Op::Eq, version: low }]
+            } else {
+                bounded(&partial, low, x_range_ceiling(&partial))
+            }
+        }
+        "~" | "~>" => {
+            let ceiling = match (partial.major, partial.minor) {
+                (Some(major), Some(minor)) => Some(Version::lowest_prerelease(major, minor + 1, 0)),
+                _ => x_range_ceiling(&partial),
+            };
+            bounded(&partial, low, ceiling)
+        }
+        "^" => {
+            // The ceiling bumps the left-most non-zero component that was given
+            let ceiling = match (partial.major, partial.minor, partial.patch) {
+                (None, _, _) => None,
+                (Some(major), _, _) if major > 0 => Some(Version::lowest_prerelease(major + 1, 0, 0)),
+                (Some(0), None, _) => Some(Version::lowest_prerelease(1, 0, 0)),
+                (Some(0), Some(minor), _) if minor > 0 => Some(Version::lowest_prerelease(0, minor + 1, 0)),
+                (Some(0), Some(0), None) => Some(Version::lowest_prerelease(0, 1, 0)),
+                (Some(0), Some(0), Some(patch)) => Some(Version::lowest_prerelease(0, 0, patch + 1)),
+                _ => None,
+            };
+            bounded(&partial, low, ceiling)
+        }
+        ">=" => bounded(&partial, low, None),
+        "<" => {
+            let version = if complete { low } else { Version::lowest_prerelease(low.major, low.minor, low.patch) };
+            vec![Comparator { op: Op::Lt, version }]
+        }
+        ">" => match x_range_ceiling(&partial) {
+            // ">1.2" means anything past the whole 1.2.x line
+            Some(ceiling) => vec![Comparator { op: Op::Ge, version: ceiling_release(ceiling) }],
+            None if partial.major.is_none() => vec![Comparator { op: Op::Lt, version: Version::lowest_prerelease(0, 0, 0) }],
+            None => vec![Comparator { op: Op::Gt, version: low }],
+        },
+        "<=" => match x_range_ceiling(&partial) {
+            Some(ceiling) => vec![Comparator { op: Op::Lt, version: ceiling }],
+            None => {
+                if partial.major.is_none() {
+                    Vec::new()
+                } else {
+                    vec![Comparator { op: Op::Le, version: low }]
+                }
+            }
+        },
+        _ => unreachable!(),
+    };
+    Ok(comparators)
+}
+
+// Drop the "-0" marker from a ceiling to get the first release above a line
+fn ceiling_release(ceiling: Version) -> Version {
+    Version::new(ceiling.major, ceiling.minor, ceiling.patch)
+}
+
+fn bounded(partial: &Partial, low: Version, ceiling: Option<Version>) -> Vec<Comparator> {
+    let mut comparators = Vec::new();
+    if partial.major.is_some() {
+        comparators.push(Comparator { op: Op::Ge, version: low });
+    }
+    if let Some(ceiling) = ceiling {
+        comparators.push(Comparator { op: Op::Lt, version: ceiling });
+    }
+    comparators
+}
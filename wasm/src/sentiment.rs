@@ -0,0 +1,136 @@
+// Lexicon-based sentiment scoring
+// Words carry AFINN-style weights from -5 to 5. A negator ("not", "never",
+// "don't", ...) flips and dampens the next few words until the clause ends,
+// and intensifiers scale the word that follows them.
+
+// Sorted by word for binary search
+const LEXICON: [(&str, i32); 158] = [
+    ("abandon", -2), ("abuse", -3), ("accept", 1), ("accomplish", 2), ("admire", 3), ("adore", 3),
+    ("afraid", -2), ("agree", 1), ("alarming", -2), ("amazing", 4), ("angry", -3), ("annoy", -2),
+    ("annoying", -2), ("anxious", -2), ("appreciate", 2), ("awesome", 4), ("awful", -3),
+    ("bad", -3), ("badly", -3), ("beautiful", 3), ("best", 3), ("better", 2), ("bland", -1),
+    ("bliss", 3), ("boring", -2), ("brilliant", 4), ("broken", -1), ("bug", -1), ("buggy", -2),
+    ("calm", 2), ("care", 2), ("charming", 3), ("cheap", -1), ("cheerful", 2), ("clean", 2),
+    ("clear", 1), ("clever", 2), ("comfortable", 2), ("confused", -2), ("cool", 1), ("crash", -2),
+    ("crashes", -2), ("crazy", -2), ("cruel", -3), ("cry", -1), ("damage", -3), ("danger", -2),
+    ("dead", -3), ("delight", 3), ("delightful", 3), ("depressed", -2), ("disappoint", -2),
+    ("disappointed", -2), ("disappointing", -2), ("disaster", -2), ("disgusting", -3),
+    ("dislike", -2), ("dull", -2), ("easy", 1), ("efficient", 2), ("elegant", 2), ("enjoy", 2),
+    ("excellent", 3), ("excited", 3), ("exciting", 3), ("fail", -2), ("failed", -2),
+    ("failure", -2), ("fantastic", 4), ("fast", 1), ("fault", -2), ("fear", -2), ("fine", 2),
+    ("flawless", 4), ("fun", 4), ("funny", 4), ("glad", 3), ("good", 3), ("gorgeous", 3),
+    ("great", 3), ("grief", -2), ("happy", 3), ("harm", -2), ("hate", -3), ("hated", -3),
+    ("helpful", 2), ("hope", 2), ("horrible", -3), ("hurt", -2), ("ideal", 2), ("impressive", 3),
+    ("inspire", 2), ("interesting", 2), ("joy", 3), ("kind", 2), ("lame", -2), ("laugh", 1),
+    ("lazy", -1), ("like", 2), ("love", 3), ("loved", 3), ("lovely", 3), ("luck", 3), ("mess", -2),
+    ("miserable", -3), ("miss", -2), ("nasty", -3), ("nice", 3), ("outstanding", 5), ("pain", -2),
+    ("panic", -3), ("perfect", 3), ("pleasant", 3), ("pleased", 3), ("poor", -2), ("positive", 2),
+    ("problem", -2), ("proud", 2), ("quick", 2), ("recommend", 2), ("reliable", 2), ("rude", -2),
+    ("sad", -2), ("safe", 1), ("satisfied", 2), ("scary", -2), ("secure", 2), ("slow", -2),
+    ("smart", 1), ("smooth", 2), ("sorry", -1), ("stable", 2), ("stupid", -2), ("succeed", 3),
+    ("success", 2), ("superb", 5), ("sweet", 2), ("terrible", -3), ("thank", 2), ("thanks", 2),
+    ("thrilled", 5), ("trouble", -2), ("trust", 1), ("ugly", -3), ("unhappy", -2),
+    ("unreliable", -2), ("upset", -2), ("useful", 2), ("useless", -2), ("waste", -1), ("weak", -2),
+    ("win", 4), ("wonderful", 4), ("worried", -3), ("worse", -3), ("worst", -3), ("wow", 4),
+    ("wrong", -2),
+];
+
+const NEGATORS: [&str; 11] = ["cannot", "neither", "never", "no", "nobody", "none", "nor", "not", "nothing", "nowhere", "without"];
+
+const INTENSIFIERS: [(&str, f64); 10] = [
+    ("absolutely", 1.5), ("barely", 0.5), ("extremely", 1.5), ("highly", 1.5), ("incredibly", 1.5),
+    ("really", 1.3), ("slightly", 0.5), ("so", 1.3), ("somewhat", 0.7), ("very", 1.3),
+];
+
+// How many words after a negator it still applies to
+const NEGATION_SCOPE: usize = 3;
+// Negated words count against their usual polarity, but less strongly
+const NEGATION_FACTOR: f64 = -0.75;
+
+pub struct Contribution {
+    pub token: String,
+    pub weight: i32,
+    pub negated: bool,
+    pub multiplier: f64,
+    pub score: f64,
+}
+
+pub struct Analysis {
+    pub tokens: usize,
+    pub score: f64,
+    pub contributions: Vec<Contribution>,
+}
+
+impl Analysis {
+    // Score per token, comparable across texts of different lengths
+    pub fn comparative(&self) -> f64 {
+        if self.tokens == 0 { 0.0 } else { self.score / self.tokens as f64 }
+    }
+
+    pub fn polarity(&self) -> &'static str {
+        match self.score {
+            score if score > 0.0 => "positive",
+            score if score < 0.0 => "negative",
+            _ => "neutral",
+        }
+    }
+}
+
+fn is_negator(token: &str) -> bool {
+    NEGATORS.contains(&token) || token.ends_with("n't")
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+pub fn analyze(text: &str) -> Analysis {
+    let mut analysis = Analysis { tokens: 0, score: 0.0, contributions: Vec::new() };
+    let mut negation_left = 0;
+    let mut multiplier = 1.0;
+
+    // Curly apostrophes are folded so "don’t" negates like "don't"
+    let normalized = text.to_lowercase().replace('\u{2019}', "'");
+    let mut chars = normalized.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if !is_word_char(c) {
+            chars.next();
+            // Clause punctuation ends any pending negation
+            if matches!(c, '.' | ',' | ';' | ':' | '!' | '?') {
+                negation_left = 0;
+                multiplier = 1.0;
+            }
+            continue;
+        }
+        let mut token = String::new();
+        while let Some(c) = chars.next_if(|&c| is_word_char(c)) {
+            token.push(c);
+        }
+        let token = token.trim_matches('\'').to_string();
+        if token.is_empty() {
+            continue;
+        }
+        analysis.tokens += 1;
+
+        if is_negator(&token) {
+            negation_left = NEGATION_SCOPE;
+            continue;
+        }
+        if let Some(&(_, factor)) = INTENSIFIERS.iter().find(|(word, _)| *word == token) {
+            multiplier = factor;
+            continue;
+        }
+
+        let negated = negation_left > 0;
+        if let Ok(index) = LEXICON.binary_search_by_key(&token.as_str(), |&(word, _)| word) {
+            let weight = LEXICON[index].1;
+            let applied = multiplier * if negated { NEGATION_FACTOR } else { 1.0 };
+            let score = weight as f64 * applied;
+            analysis.score += score;
+            analysis.contributions.push(Contribution { token, weight, negated, multiplier: applied, score });
+        }
+        negation_left = negation_left.saturating_sub(1);
+        multiplier = 1.0;
+    }
+    analysis
+}
@@ -0,0 +1,86 @@
+// URL shortener on top of the KV bindings
+// Codes are the leading base62 digits of the URL's SHA-256, so shortening the
+// same URL twice returns the same code. A code already taken by another URL
+// is lengthened one digit at a time.
+
+use crate::hashing::Sha256;
+use crate::kv::{self, Pending};
+
+pub const MIN_CODE_LENGTH: usize = 7;
+pub const MAX_CODE_LENGTH: usize = 11;
+
+const BASE62: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+pub struct Link {
+    pub code: String,
+    pub url: String,
+    pub hits: u64,
+    pub created: bool,
+}
+
+fn url_key(code: &str) -> String {
+    format!("short:{}", code)
+}
+
+fn hits_key(code: &str) -> String {
+    format!("short:{}:hits", code)
+}
+
+// 11 base62 digits of the first 8 digest bytes (62^11 > 2^64)
+fn digest_code(url: &str) -> String {
+    let mut sha = Sha256::new();
+    sha.update(url.as_bytes());
+    let digest = sha.finalize();
+    let mut value = u64::from_be_bytes(digest[..8].try_into().unwrap());
+    let mut code = Vec::with_capacity(MAX_CODE_LENGTH);
+    for _ in 0..MAX_CODE_LENGTH {
+        code.push(BASE62[(value % 62) as usize]);
+        value /= 62;
+    }
+    String::from_utf8(code).unwrap()
+}
+
+pub fn is_valid_code(code: &str) -> bool {
+    (MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&code.len()) && code.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+fn hits(code: &str) -> Result<u64, Pending> {
+    Ok(kv::get(&hits_key(code))?.and_then(|hits| hits.parse().ok()).unwrap_or(0))
+}
+
+// Ok(None) when every code length is taken by other URLs
+pub fn shorten(url: &str) -> Result<Option<Link>, Pending> {
+    let full = digest_code(url);
+    for length in MIN_CODE_LENGTH..=MAX_CODE_LENGTH {
+        let code = &full[..length];
+        match kv::get(&url_key(code))? {
+            Some(existing) if existing == url => {
+                let hits = hits(code)?;
+                return Ok(Some(Link { code: code.to_string(), url: existing, hits, created: false }));
+            }
+            Some(_) => continue,
+            None => {
+                kv::put(&url_key(code), url, 0);
+                kv::put(&hits_key(code), "0", 0);
+                return Ok(Some(Link { code: code.to_string(), url: url.to_string(), hits: 0, created: true }));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// Looks a code up, counting the visit when `count_hit` is set
+pub fn resolve(code: &str, count_hit: bool) -> Result<Option<Link>, Pending> {
+    // Read both keys before bailing out so one replay loads them together
+    let url = kv::get(&url_key(code));
+    let hits = hits(code);
+    let Some(url) = url? else {
+        return Ok(None);
+    };
+    let mut hits = hits?;
+    if count_hit {
+        hits += 1;
+        kv::put(&hits_key(code), &hits.to_string(), 0);
+    }
+    Ok(Some(Link { code: code.to_string(), url, hits, created: false }))
+}
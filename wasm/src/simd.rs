@@ -0,0 +1,197 @@
+// Byte kernels with optional wasm32 SIMD
+// The hot byte loops (the djb2 hash behind simple_hash_bytes, hex encoding
+// and delimiter scanning in query strings) have a scalar version that always
+// exists and, with the simd128 feature, a 128-bit one that is used instead.
+// Every current engine including V8 implements simd128, but a host without it
+// will refuse to load a module built with the feature.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kernels {
+    Scalar,
+    #[cfg(all(feature = "simd128", target_arch = "wasm32"))]
+    Simd128,
+}
+
+// Fastest first; /bench runs every one of them
+pub const AVAILABLE: &[Kernels] = &[
+    #[cfg(all(feature = "simd128", target_arch = "wasm32"))]
+    Kernels::Simd128,
+    Kernels::Scalar,
+];
+
+impl Kernels {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kernels::Scalar => "scalar",
+            #[cfg(all(feature = "simd128", target_arch = "wasm32"))]
+            Kernels::Simd128 => "simd128",
+        }
+    }
+
+    // Continues a djb2 hash (hash * 33 + byte) over `bytes`
+    pub fn djb2(self, hash: u32, bytes: &[u8]) -> u32 {
+        match self {
+            Kernels::Scalar => scalar::djb2(hash, bytes),
+            // Safe on wasm32: an engine without simd128 rejects the module
+            #[cfg(all(feature = "simd128", target_arch = "wasm32"))]
+            Kernels::Simd128 => vector::djb2(hash, bytes),
+        }
+    }
+
+    // Appends lower case hex digits for `bytes` to `out`
+    pub fn hex(self, bytes: &[u8], out: &mut Vec<u8>) {
+        match self {
+            Kernels::Scalar => scalar::hex(bytes, out),
+            #[cfg(all(feature = "simd128", target_arch = "wasm32"))]
+            Kernels::Simd128 => vector::hex(bytes, out),
+        }
+    }
+
+    // Index of the first byte equal to `a` or `b`
+    pub fn find_either(self, haystack: &[u8], a: u8, b: u8) -> Option<usize> {
+        match self {
+            Kernels::Scalar => scalar::find_either(haystack, a, b),
+            #[cfg(all(feature = "simd128", target_arch = "wasm32"))]
+            Kernels::Simd128 => vector::find_either(haystack, a, b),
+        }
+    }
+}
+
+pub fn djb2(hash: u32, bytes: &[u8]) -> u32 {
+    AVAILABLE[0].djb2(hash, bytes)
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    let mut out = Vec::with_capacity(bytes.len() * 2);
+    AVAILABLE[0].hex(bytes, &mut out);
+    // Only ASCII hex digits were written
+    unsafe { String::from_utf8_unchecked(out) }
+}
+
+pub fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+    AVAILABLE[0].find_either(haystack, needle, needle)
+}
+
+pub fn find_either(haystack: &[u8], a: u8, b: u8) -> Option<usize> {
+    AVAILABLE[0].find_either(haystack, a, b)
+}
+
+mod scalar {
+    use super::HEX_DIGITS;
+    use alloc::vec::Vec;
+
+    pub fn djb2(hash: u32, bytes: &[u8]) -> u32 {
+        bytes.iter().fold(hash, |hash, &byte| hash.wrapping_mul(33).wrapping_add(byte as u32))
+    }
+
+    pub fn hex(bytes: &[u8], out: &mut Vec<u8>) {
+        for &byte in bytes {
+            out.push(HEX_DIGITS[(byte >> 4) as usize]);
+            out.push(HEX_DIGITS[(byte & 0x0f) as usize]);
+        }
+    }
+
+    pub fn find_either(haystack: &[u8], a: u8, b: u8) -> Option<usize> {
+        haystack.iter().position(|&byte| byte == a || byte == b)
+    }
+}
+
+#[cfg(all(feature = "simd128", target_arch = "wasm32"))]
+mod vector {
+    use super::{scalar, HEX_DIGITS};
+    use alloc::vec::Vec;
+    use core::arch::wasm32::*;
+
+    // 33^0 ..= 33^16, wrapping like the hash itself
+    const POWERS: [u32; 17] = {
+        let mut powers = [1u32; 17];
+        let mut i = 1;
+        while i < 17 {
+            powers[i] = powers[i - 1].wrapping_mul(33);
+            i += 1;
+        }
+        powers
+    };
+
+    // Sixteen steps of hash * 33 + byte add up to
+    // hash * 33^16 + sum(byte[i] * 33^(15 - i)), so each lane keeps its own
+    // weighted sum and the lanes are only added together at the end
+    #[target_feature(enable = "simd128")]
+    pub fn djb2(hash: u32, bytes: &[u8]) -> u32 {
+        let p = &POWERS;
+        let weights = [
+            u32x4(p[15], p[14], p[13], p[12]),
+            u32x4(p[11], p[10], p[9], p[8]),
+            u32x4(p[7], p[6], p[5], p[4]),
+            u32x4(p[3], p[2], p[1], p[0]),
+        ];
+        let step = u32x4_splat(p[16]);
+        let mut sums = [u32x4_splat(0); 4];
+        let mut hash = hash;
+        let mut chunks = bytes.chunks_exact(16);
+        for chunk in &mut chunks {
+            let block = unsafe { v128_load(chunk.as_ptr() as *const v128) };
+            let (low, high) = (u16x8_extend_low_u8x16(block), u16x8_extend_high_u8x16(block));
+            let lanes = [
+                u32x4_extend_low_u16x8(low),
+                u32x4_extend_high_u16x8(low),
+                u32x4_extend_low_u16x8(high),
+                u32x4_extend_high_u16x8(high),
+            ];
+            for i in 0..4 {
+                sums[i] = i32x4_add(i32x4_mul(sums[i], step), i32x4_mul(lanes[i], weights[i]));
+            }
+            hash = hash.wrapping_mul(p[16]);
+        }
+        for sum in sums {
+            hash = hash
+                .wrapping_add(u32x4_extract_lane::<0>(sum))
+                .wrapping_add(u32x4_extract_lane::<1>(sum))
+                .wrapping_add(u32x4_extract_lane::<2>(sum))
+                .wrapping_add(u32x4_extract_lane::<3>(sum));
+        }
+        scalar::djb2(hash, chunks.remainder())
+    }
+
+    // Nibbles index a table of digits, then the high and low digits interleave
+    #[target_feature(enable = "simd128")]
+    pub fn hex(bytes: &[u8], out: &mut Vec<u8>) {
+        let digits = unsafe { v128_load(HEX_DIGITS.as_ptr() as *const v128) };
+        let mask = u8x16_splat(0x0f);
+        let mut chunks = bytes.chunks_exact(16);
+        for chunk in &mut chunks {
+            let block = unsafe { v128_load(chunk.as_ptr() as *const v128) };
+            let high = u8x16_swizzle(digits, u8x16_shr(block, 4));
+            let low = u8x16_swizzle(digits, v128_and(block, mask));
+            let mut pair = [0u8; 32];
+            let first = u8x16_shuffle::<0, 16, 1, 17, 2, 18, 3, 19, 4, 20, 5, 21, 6, 22, 7, 23>(high, low);
+            let second = u8x16_shuffle::<8, 24, 9, 25, 10, 26, 11, 27, 12, 28, 13, 29, 14, 30, 15, 31>(high, low);
+            unsafe {
+                v128_store(pair.as_mut_ptr() as *mut v128, first);
+                v128_store(pair.as_mut_ptr().add(16) as *mut v128, second);
+            }
+            out.extend_from_slice(&pair);
+        }
+        scalar::hex(chunks.remainder(), out);
+    }
+
+    #[target_feature(enable = "simd128")]
+    pub fn find_either(haystack: &[u8], a: u8, b: u8) -> Option<usize> {
+        let (a_lanes, b_lanes) = (u8x16_splat(a), u8x16_splat(b));
+        let mut offset = 0;
+        while offset + 16 <= haystack.len() {
+            let block = unsafe { v128_load(haystack.as_ptr().add(offset) as *const v128) };
+            let hits = u8x16_bitmask(v128_or(u8x16_eq(block, a_lanes), u8x16_eq(block, b_lanes)));
+            if hits != 0 {
+                return Some(offset + hits.trailing_zeros() as usize);
+            }
+            offset += 16;
+        }
+        scalar::find_either(&haystack[offset..], a, b).map(|index| offset + index)
+    }
+}
@@ -0,0 +1,34 @@
+// Edit distance between short strings, for "did you mean" suggestions
+// Distances count Unicode scalar values, not bytes, so a typo in a non-ASCII
+// word costs one edit like any other.
+
+use alloc::vec::Vec;
+
+// Levenshtein distance: insertions, deletions and substitutions each cost one
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // One row of the distance table at a time
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Candidates within `max_distance` of `target`, nearest first and in
+// candidate order among equals, at most `limit` of them
+pub fn closest<'a>(target: &str, candidates: impl IntoIterator<Item = &'a str>, max_distance: usize, limit: usize) -> Vec<&'a str> {
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(target, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect();
+    scored.sort_by_key(|&(distance, _)| distance);
+    scored.into_iter().take(limit).map(|(_, candidate)| candidate).collect()
+}
@@ -0,0 +1,87 @@
+// Snowflake-style 64-bit IDs: 41 bits of milliseconds since the configured
+// epoch, 10 bits of node id and a 12-bit per-millisecond sequence
+// IDs from one isolate are strictly increasing. When the host clock goes
+// backwards the last timestamp is reused, and when a millisecond's sequence
+// runs out the timestamp is borrowed from the next one, so generation never
+// blocks (inside a Worker the clock does not move during a request anyway).
+
+use std::cell::Cell;
+
+use crate::config::{self, Config};
+use crate::host;
+
+const NODE_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+const MAX_SEQUENCE: u64 = (1 << SEQUENCE_BITS) - 1;
+const MAX_TIMESTAMP: u64 = (1 << 41) - 1;
+
+#[derive(Clone, Copy, Default)]
+struct State {
+    // Milliseconds since the epoch and sequence of the previous ID
+    timestamp: u64,
+    sequence: u64,
+    started: bool,
+    clock_regressions: u64,
+}
+
+thread_local! {
+    static STATE: Cell<State> = Cell::new(State::default());
+}
+
+pub struct Snowflake {
+    pub id: u64,
+    pub timestamp_ms: u64,
+    pub node_id: u16,
+    pub sequence: u64,
+}
+
+pub fn decode(id: u64, epoch_ms: u64) -> Snowflake {
+    Snowflake {
+        id,
+        timestamp_ms: epoch_ms + (id >> (NODE_BITS + SEQUENCE_BITS)),
+        node_id: ((id >> SEQUENCE_BITS) & ((1 << NODE_BITS) - 1)) as u16,
+        sequence: id & MAX_SEQUENCE,
+    }
+}
+
+// Times the clock was seen going backwards since the isolate started
+pub fn clock_regressions() -> u64 {
+    STATE.get().clock_regressions
+}
+
+pub fn generate(config: &Config) -> Result<u64, String> {
+    let now = host::now_ms().max(0.0) as u64;
+    let Some(elapsed) = now.checked_sub(config.snowflake_epoch_ms) else {
+        return Err("Clock is before the configured snowflake epoch".to_string());
+    };
+
+    let mut state = STATE.get();
+    if !state.started || elapsed > state.timestamp {
+        state.timestamp = elapsed;
+        state.sequence = 0;
+    } else {
+        if elapsed < state.timestamp {
+            state.clock_regressions += 1;
+        }
+        if state.sequence < MAX_SEQUENCE {
+            state.sequence += 1;
+        } else {
+            state.timestamp += 1;
+            state.sequence = 0;
+        }
+    }
+    if state.timestamp > MAX_TIMESTAMP {
+        return Err("Snowflake timestamp overflowed 41 bits".to_string());
+    }
+    state.started = true;
+    STATE.set(state);
+
+    Ok(state.timestamp << (NODE_BITS + SEQUENCE_BITS) | (config.snowflake_node_id as u64) << SEQUENCE_BITS | state.sequence)
+}
+
+// Next ID for the host (a BigInt in JavaScript), or 0 if the clock is before
+// the configured epoch
+#[no_mangle]
+pub extern "C" fn generate_snowflake() -> u64 {
+    generate(&config::current()).unwrap_or(0)
+}
@@ -0,0 +1,143 @@
+// Classic sorting algorithms over a caller supplied comparator
+// Every algorithm takes `compare` by mutable reference so callers can count
+// or instrument comparisons without the algorithms knowing about it.
+
+use core::cmp::Ordering;
+
+pub const ALGORITHMS: [&str; 4] = ["quick", "merge", "heap", "pdq"];
+
+// Below this length insertion sort beats the divide and conquer overhead
+const INSERTION_THRESHOLD: usize = 16;
+
+pub fn sort<T: Clone, F: FnMut(&T, &T) -> Ordering>(algorithm: &str, v: &mut [T], compare: &mut F) -> bool {
+    match algorithm {
+        "quick" => quick_sort(v, compare),
+        "merge" => merge_sort(v, compare),
+        "heap" => heap_sort(v, compare),
+        // The standard library's unstable sort is pattern-defeating quicksort
+        "pdq" => v.sort_unstable_by(|a, b| compare(a, b)),
+        _ => return false,
+    }
+    true
+}
+
+pub fn insertion_sort<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], compare: &mut F) {
+    for i in 1..v.len() {
+        let mut j = i;
+        while j > 0 && compare(&v[j], &v[j - 1]) == Ordering::Less {
+            v.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+// Quicksort with a median-of-three pivot and Hoare partitioning. Recursing
+// only into the smaller side keeps the stack depth logarithmic.
+pub fn quick_sort<T, F: FnMut(&T, &T) -> Ordering>(mut v: &mut [T], compare: &mut F) {
+    loop {
+        let len = v.len();
+        if len <= INSERTION_THRESHOLD {
+            insertion_sort(v, compare);
+            return;
+        }
+
+        let mid = len / 2;
+        if compare(&v[mid], &v[0]) == Ordering::Less {
+            v.swap(mid, 0);
+        }
+        if compare(&v[len - 1], &v[mid]) == Ordering::Less {
+            v.swap(len - 1, mid);
+            if compare(&v[mid], &v[0]) == Ordering::Less {
+                v.swap(mid, 0);
+            }
+        }
+        v.swap(0, mid);
+
+        let (mut i, mut j) = (1, len - 1);
+        loop {
+            while i <= j && compare(&v[i], &v[0]) == Ordering::Less {
+                i += 1;
+            }
+            while i <= j && compare(&v[0], &v[j]) == Ordering::Less {
+                j -= 1;
+            }
+            if i >= j {
+                break;
+            }
+            v.swap(i, j);
+            i += 1;
+            j -= 1;
+        }
+        v.swap(0, j);
+
+        let (left, right) = v.split_at_mut(j);
+        let right = &mut right[1..];
+        if left.len() < right.len() {
+            quick_sort(left, compare);
+            v = right;
+        } else {
+            quick_sort(right, compare);
+            v = left;
+        }
+    }
+}
+
+// Stable top-down merge sort using one scratch buffer
+pub fn merge_sort<T: Clone, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], compare: &mut F) {
+    let mut scratch = v.to_vec();
+    merge_sort_into(v, &mut scratch, compare);
+}
+
+fn merge_sort_into<T: Clone, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], scratch: &mut [T], compare: &mut F) {
+    let len = v.len();
+    if len <= INSERTION_THRESHOLD {
+        insertion_sort(v, compare);
+        return;
+    }
+    let mid = len / 2;
+    merge_sort_into(&mut v[..mid], &mut scratch[..mid], compare);
+    merge_sort_into(&mut v[mid..], &mut scratch[mid..], compare);
+
+    let (mut i, mut j) = (0, mid);
+    for slot in scratch[..len].iter_mut() {
+        // Ties take from the left half, which keeps the sort stable
+        let take_left = j == len || (i < mid && compare(&v[j], &v[i]) != Ordering::Less);
+        if take_left {
+            *slot = v[i].clone();
+            i += 1;
+        } else {
+            *slot = v[j].clone();
+            j += 1;
+        }
+    }
+    v.clone_from_slice(&scratch[..len]);
+}
+
+// In-place heapsort on a max-heap
+pub fn heap_sort<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], compare: &mut F) {
+    let len = v.len();
+    for root in (0..len / 2).rev() {
+        sift_down(v, root, len, compare);
+    }
+    for end in (1..len).rev() {
+        v.swap(0, end);
+        sift_down(v, 0, end, compare);
+    }
+}
+
+fn sift_down<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut root: usize, end: usize, compare: &mut F) {
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= end {
+            return;
+        }
+        if child + 1 < end && compare(&v[child], &v[child + 1]) == Ordering::Less {
+            child += 1;
+        }
+        if compare(&v[root], &v[child]) != Ordering::Less {
+            return;
+        }
+        v.swap(root, child);
+        root = child;
+    }
+}
@@ -0,0 +1,251 @@
+// Porter stemming and stop-word filtering
+// The stemmer implements Porter's original 1980 algorithm for English (with
+// the "abli" -> "ble" and "logi" -> "log" amendments from his reference
+// implementation). Other languages only get stop-word removal.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub const LANGUAGES: [&str; 7] = ["en", "de", "fr", "es", "it", "pt", "nl"];
+
+const STOP_WORDS: [(&str, &[&str]); 7] = [
+    ("en", &[
+        "a", "about", "after", "all", "an", "and", "any", "are", "as", "at", "be", "been", "but", "by", "can", "could",
+        "did", "do", "does", "for", "from", "had", "has", "have", "he", "her", "his", "how", "i", "if", "in", "into",
+        "is", "it", "its", "me", "my", "no", "not", "of", "on", "or", "our", "she", "so", "than", "that", "the",
+        "their", "them", "then", "there", "these", "they", "this", "to", "up", "us", "was", "we", "were", "what",
+        "when", "which", "who", "will", "with", "would", "you", "your",
+    ]),
+    ("de", &[
+        "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "das", "dass", "dem", "den", "der",
+        "des", "die", "du", "ein", "eine", "einem", "einen", "einer", "er", "es", "für", "hat", "ich", "ihr", "im",
+        "in", "ist", "ja", "mit", "nach", "nicht", "noch", "nur", "oder", "sich", "sie", "sind", "so", "um", "und",
+        "uns", "von", "vor", "war", "was", "wie", "wir", "zu", "zum", "zur",
+    ]),
+    ("fr", &[
+        "à", "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "est", "et", "il", "ils",
+        "je", "la", "le", "les", "leur", "lui", "ma", "mais", "me", "mes", "mon", "ne", "nous", "on", "ou", "où",
+        "par", "pas", "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sur", "ta", "te", "tu", "un", "une",
+        "vous",
+    ]),
+    ("es", &[
+        "a", "al", "como", "con", "de", "del", "el", "en", "es", "esta", "está", "este", "ha", "la", "las", "le",
+        "lo", "los", "me", "más", "mi", "no", "nos", "o", "para", "pero", "por", "que", "se", "su", "sus", "te",
+        "tu", "un", "una", "uno", "y", "ya", "yo",
+    ]),
+    ("it", &[
+        "a", "ai", "al", "alla", "anche", "che", "ci", "come", "con", "da", "dei", "del", "della", "di", "e", "è",
+        "gli", "ha", "i", "il", "in", "io", "la", "le", "lo", "ma", "mi", "nel", "non", "per", "più", "se", "si",
+        "sono", "su", "tu", "un", "una", "uno",
+    ]),
+    ("pt", &[
+        "a", "ao", "as", "com", "como", "da", "das", "de", "do", "dos", "e", "é", "ela", "ele", "em", "eu", "mais",
+        "mas", "me", "na", "nas", "no", "nos", "não", "o", "os", "ou", "para", "pela", "pelo", "por", "que", "se",
+        "seu", "sua", "um", "uma",
+    ]),
+    ("nl", &[
+        "aan", "als", "bij", "dat", "de", "die", "dit", "een", "en", "er", "het", "hij", "ik", "in", "is", "je",
+        "maar", "met", "niet", "nog", "of", "om", "ook", "op", "te", "tot", "uit", "van", "voor", "was", "wat",
+        "we", "wij", "ze", "zij", "zijn",
+    ]),
+];
+
+// Expects a lowercased word
+pub fn is_stop_word(language: &str, word: &str) -> bool {
+    STOP_WORDS
+        .iter()
+        .find(|(code, _)| *code == language)
+        .is_some_and(|(_, words)| words.contains(&word))
+}
+
+// Index term for a lowercased word, or None for stop words
+pub fn normalize(language: &str, word: &str) -> Option<String> {
+    if is_stop_word(language, word) {
+        None
+    } else if language == "en" {
+        Some(stem(word))
+    } else {
+        Some(word.to_string())
+    }
+}
+
+// Words that are short or not plain ASCII letters are returned unchanged
+pub fn stem(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+    let mut stemmer = Stemmer { b: word.as_bytes().to_vec(), j: 0 };
+    stemmer.step1ab();
+    if stemmer.b.len() > 1 {
+        stemmer.step1c();
+        stemmer.step2();
+        stemmer.step3();
+        stemmer.step4();
+        stemmer.step5();
+    }
+    String::from_utf8(stemmer.b).unwrap_or_default()
+}
+
+struct Stemmer {
+    b: Vec<u8>,
+    // Length of the stem left by the last successful ends() match
+    j: usize,
+}
+
+impl Stemmer {
+    fn cons(&self, i: usize) -> bool {
+        match self.b[i] {
+            b'a' | b'e' | b'i' | b'o' | b'u' => false,
+            b'y' => i == 0 || !self.cons(i - 1),
+            _ => true,
+        }
+    }
+
+    // Number of vowel-consonant sequences in the stem, the m of [C](VC)^m[V]
+    fn m(&self) -> usize {
+        let mut n = 0;
+        let mut i = 0;
+        while i < self.j && self.cons(i) {
+            i += 1;
+        }
+        loop {
+            while i < self.j && !self.cons(i) {
+                i += 1;
+            }
+            if i >= self.j {
+                return n;
+            }
+            while i < self.j && self.cons(i) {
+                i += 1;
+            }
+            n += 1;
+        }
+    }
+
+    fn vowel_in_stem(&self) -> bool {
+        (0..self.j).any(|i| !self.cons(i))
+    }
+
+    // The prefix of the given length ends in a double consonant
+    fn double_c(&self, end: usize) -> bool {
+        end >= 2 && self.b[end - 1] == self.b[end - 2] && self.cons(end - 1)
+    }
+
+    // The prefix ends consonant-vowel-consonant with the last not w, x or y
+    fn cvc(&self, end: usize) -> bool {
+        end >= 3
+            && self.cons(end - 1)
+            && !self.cons(end - 2)
+            && self.cons(end - 3)
+            && !matches!(self.b[end - 1], b'w' | b'x' | b'y')
+    }
+
+    fn ends(&mut self, suffix: &str) -> bool {
+        if self.b.ends_with(suffix.as_bytes()) {
+            self.j = self.b.len() - suffix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn set_to(&mut self, replacement: &str) {
+        self.b.truncate(self.j);
+        self.b.extend_from_slice(replacement.as_bytes());
+    }
+
+    // Replaces the first matching suffix when the remaining stem has m > 0
+    fn replace_first(&mut self, rules: &[(&str, &str)]) {
+        if let Some(&(_, replacement)) = rules.iter().find(|(suffix, _)| self.ends(suffix)) {
+            if self.m() > 0 {
+                self.set_to(replacement);
+            }
+        }
+    }
+
+    // Plurals and -ed / -ing
+    fn step1ab(&mut self) {
+        if self.b.last() == Some(&b's') {
+            if self.ends("sses") || self.ends("ies") {
+                self.b.truncate(self.b.len() - 2);
+            } else if self.b[self.b.len() - 2] != b's' {
+                self.b.pop();
+            }
+        }
+        if self.ends("eed") {
+            if self.m() > 0 {
+                self.b.pop();
+            }
+        } else if (self.ends("ed") || self.ends("ing")) && self.vowel_in_stem() {
+            self.b.truncate(self.j);
+            self.j = self.b.len();
+            if self.ends("at") || self.ends("bl") || self.ends("iz") {
+                self.b.push(b'e');
+            } else if self.double_c(self.b.len()) {
+                if !matches!(self.b.last(), Some(b'l' | b's' | b'z')) {
+                    self.b.pop();
+                }
+            } else if self.m() == 1 && self.cvc(self.b.len()) {
+                self.b.push(b'e');
+            }
+        }
+    }
+
+    // Terminal y to i when there is another vowel in the stem
+    fn step1c(&mut self) {
+        if self.ends("y") && self.vowel_in_stem() {
+            let last = self.b.len() - 1;
+            self.b[last] = b'i';
+        }
+    }
+
+    // Double suffixes to single ones
+    fn step2(&mut self) {
+        self.replace_first(&[
+            ("ational", "ate"), ("tional", "tion"), ("enci", "ence"), ("anci", "ance"), ("izer", "ize"),
+            ("bli", "ble"), ("alli", "al"), ("entli", "ent"), ("eli", "e"), ("ousli", "ous"), ("ization", "ize"),
+            ("ation", "ate"), ("ator", "ate"), ("alism", "al"), ("iveness", "ive"), ("fulness", "ful"),
+            ("ousness", "ous"), ("aliti", "al"), ("iviti", "ive"), ("biliti", "ble"), ("logi", "log"),
+        ]);
+    }
+
+    // -ic-, -full, -ness and similar
+    fn step3(&mut self) {
+        self.replace_first(&[
+            ("icate", "ic"), ("ative", ""), ("alize", "al"), ("iciti", "ic"), ("ical", "ic"), ("ful", ""),
+            ("ness", ""),
+        ]);
+    }
+
+    // -ant, -ence and the like when m > 1
+    fn step4(&mut self) {
+        const SUFFIXES: [&str; 19] = [
+            "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ion", "ou", "ism",
+            "ate", "iti", "ous", "ive", "ize",
+        ];
+        let Some(suffix) = SUFFIXES.iter().find(|suffix| self.ends(suffix)) else {
+            return;
+        };
+        if *suffix == "ion" && !(self.j > 0 && matches!(self.b[self.j - 1], b's' | b't')) {
+            return;
+        }
+        if self.m() > 1 {
+            self.b.truncate(self.j);
+        }
+    }
+
+    // Final -e and -ll
+    fn step5(&mut self) {
+        self.j = self.b.len();
+        if self.b.last() == Some(&b'e') {
+            let m = self.m();
+            if m > 1 || (m == 1 && !self.cvc(self.b.len() - 1)) {
+                self.b.pop();
+                self.j = self.b.len();
+            }
+        }
+        if self.b.last() == Some(&b'l') && self.double_c(self.b.len()) && self.m() > 1 {
+            self.b.pop();
+        }
+    }
+}
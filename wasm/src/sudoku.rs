@@ -0,0 +1,159 @@
+// Sudoku solving and generation with a bitmask backtracking solver
+// Grids are 81 cells in row-major order with 0 for an empty cell. Each row,
+// column and box tracks its used digits as bits 1-9 of a u16, and the search
+// always branches on the empty cell with the fewest candidates.
+
+use crate::rand::Rng;
+
+pub type Grid = [u8; 81];
+
+pub const DIFFICULTIES: [(&str, usize); 3] = [("easy", 40), ("medium", 32), ("hard", 26)];
+
+const ALL_DIGITS: u16 = 0b11_1111_1110;
+
+struct Solver {
+    grid: Grid,
+    rows: [u16; 9],
+    cols: [u16; 9],
+    boxes: [u16; 9],
+}
+
+fn box_of(cell: usize) -> usize {
+    (cell / 27) * 3 + (cell % 9) / 3
+}
+
+impl Solver {
+    // None when two givens already conflict
+    fn new(grid: &Grid) -> Option<Solver> {
+        let mut solver = Solver { grid: [0; 81], rows: [0; 9], cols: [0; 9], boxes: [0; 9] };
+        for (cell, &digit) in grid.iter().enumerate() {
+            if digit != 0 {
+                if solver.candidates(cell) & (1 << digit) == 0 {
+                    return None;
+                }
+                solver.place(cell, digit);
+            }
+        }
+        Some(solver)
+    }
+
+    fn candidates(&self, cell: usize) -> u16 {
+        ALL_DIGITS & !(self.rows[cell / 9] | self.cols[cell % 9] | self.boxes[box_of(cell)])
+    }
+
+    fn place(&mut self, cell: usize, digit: u8) {
+        let bit = 1 << digit;
+        self.grid[cell] = digit;
+        self.rows[cell / 9] |= bit;
+        self.cols[cell % 9] |= bit;
+        self.boxes[box_of(cell)] |= bit;
+    }
+
+    fn clear(&mut self, cell: usize) {
+        let bit = !(1u16 << self.grid[cell]);
+        self.grid[cell] = 0;
+        self.rows[cell / 9] &= bit;
+        self.cols[cell % 9] &= bit;
+        self.boxes[box_of(cell)] &= bit;
+    }
+
+    // Empty cell with the fewest candidates, or None once the grid is full
+    fn most_constrained(&self) -> Option<(usize, u16)> {
+        let mut best: Option<(usize, u16)> = None;
+        for cell in (0..81).filter(|&cell| self.grid[cell] == 0) {
+            let candidates = self.candidates(cell);
+            if best.is_none_or(|(_, current)| candidates.count_ones() < current.count_ones()) {
+                best = Some((cell, candidates));
+                if candidates.count_ones() <= 1 {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    // Count solutions up to `limit`, keeping the first one found in `first`.
+    // With a random generator, digits are tried in shuffled order.
+    fn search(&mut self, limit: usize, first: &mut Option<Grid>, mut rng: Option<&mut Rng>) -> usize {
+        let Some((cell, candidates)) = self.most_constrained() else {
+            first.get_or_insert(self.grid);
+            return 1;
+        };
+        let mut digits: Vec<u8> = (1..=9).filter(|&digit| candidates & (1 << digit) != 0).collect();
+        if let Some(rng) = rng.as_deref_mut() {
+            for i in (1..digits.len()).rev() {
+                digits.swap(i, rng.below(i as u64 + 1) as usize);
+            }
+        }
+
+        let mut found = 0;
+        for digit in digits {
+            self.place(cell, digit);
+            found += self.search(limit - found, first, rng.as_deref_mut());
+            self.clear(cell);
+            if found >= limit {
+                break;
+            }
+        }
+        found
+    }
+}
+
+pub fn parse(puzzle: &str) -> Result<Grid, String> {
+    let cells: Vec<char> = puzzle.chars().filter(|c| !c.is_whitespace()).collect();
+    if cells.len() != 81 {
+        return Err(format!("Puzzle must have 81 cells, got {}", cells.len()));
+    }
+    let mut grid = [0; 81];
+    for (cell, &c) in cells.iter().enumerate() {
+        grid[cell] = match c {
+            '1'..='9' => c as u8 - b'0',
+            '0' | '.' => 0,
+            _ => return Err(format!("Invalid cell {:?} at position {}; use 1-9 and 0 or . for blanks", c, cell)),
+        };
+    }
+    Ok(grid)
+}
+
+pub fn format(grid: &Grid) -> String {
+    grid.iter().map(|&digit| if digit == 0 { '.' } else { (b'0' + digit) as char }).collect()
+}
+
+// A solution plus whether it is the only one; None if the givens conflict or
+// no solution exists
+pub fn solve(grid: &Grid) -> Option<(Grid, bool)> {
+    let mut solver = Solver::new(grid)?;
+    let mut first = None;
+    let count = solver.search(2, &mut first, None);
+    first.map(|solution| (solution, count == 1))
+}
+
+// Random full grid, then clues removed in random order while the puzzle keeps
+// a unique solution. Harder puzzles may stop above their target clue count
+// when no further clue can be removed.
+pub fn generate(target_clues: usize, rng: &mut Rng) -> (Grid, Grid) {
+    let mut solution = None;
+    Solver::new(&[0; 81]).unwrap().search(1, &mut solution, Some(rng));
+    let solution = solution.unwrap();
+
+    let mut puzzle = solution;
+    let mut cells: Vec<usize> = (0..81).collect();
+    for i in (1..cells.len()).rev() {
+        cells.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+    let mut clues = 81;
+    for cell in cells {
+        if clues <= target_clues {
+            break;
+        }
+        let digit = puzzle[cell];
+        puzzle[cell] = 0;
+        let mut first = None;
+        if Solver::new(&puzzle).unwrap().search(2, &mut first, None) == 1 {
+            clues -= 1;
+        } else {
+            puzzle[cell] = digit;
+        }
+    }
+    (puzzle, solution)
+}
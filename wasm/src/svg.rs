@@ -0,0 +1,96 @@
+// A minimal SVG document builder
+// Elements are appended in paint order; attribute values and text are
+// escaped here, so callers can pass user-supplied labels straight in.
+
+pub struct Svg {
+    width: u32,
+    height: u32,
+    title: Option<String>,
+    body: String,
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            // Control characters other than tab and newlines are not allowed in XML
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// Coordinates to two decimals without trailing zeros, e.g. 12.5 or 40
+pub fn number(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+impl Svg {
+    pub fn new(width: u32, height: u32) -> Svg {
+        Svg { width, height, title: None, body: String::new() }
+    }
+
+    // Shown as a tooltip and read out by screen readers
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    // Appends `<name attributes>text</name>`, or a self-closing element
+    // without text
+    pub fn element(&mut self, name: &str, attributes: &[(&str, String)], text: Option<&str>) -> &mut Self {
+        self.body.push('<');
+        self.body.push_str(name);
+        for (attribute, value) in attributes {
+            self.body.push_str(&format!(" {}=\"{}\"", attribute, escape(value)));
+        }
+        match text {
+            Some(text) => self.body.push_str(&format!(">{}</{}>", escape(text), name)),
+            None => self.body.push_str("/>"),
+        }
+        self
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: &str) -> &mut Self {
+        self.element(
+            "rect",
+            &[("x", number(x)), ("y", number(y)), ("width", number(width)), ("height", number(height)), ("fill", fill.to_string())],
+            None,
+        )
+    }
+
+    pub fn line(&mut self, from: (f64, f64), to: (f64, f64), stroke: &str) -> &mut Self {
+        self.element(
+            "line",
+            &[("x1", number(from.0)), ("y1", number(from.1)), ("x2", number(to.0)), ("y2", number(to.1)), ("stroke", stroke.to_string())],
+            None,
+        )
+    }
+
+    // `anchor` is start, middle or end
+    pub fn text(&mut self, x: f64, y: f64, anchor: &str, size: u32, text: &str) -> &mut Self {
+        self.element(
+            "text",
+            &[("x", number(x)), ("y", number(y)), ("text-anchor", anchor.to_string()), ("font-size", size.to_string())],
+            Some(text),
+        )
+    }
+
+    pub fn finish(&self) -> String {
+        let title = self.title.as_deref().map_or(String::new(), |title| format!("<title>{}</title>", escape(title)));
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" role="img" font-family="sans-serif">{}<rect width="100%" height="100%" fill="white"/>{}</svg>"#,
+            title,
+            self.body,
+            w = self.width,
+            h = self.height
+        )
+    }
+}
@@ -0,0 +1,103 @@
+// Writing POSIX ustar archives
+// Each file is a 512-byte header and its data padded to 512 bytes, and two
+// zero blocks end the archive. Paths too long for the ustar name and prefix
+// fields get a pax extended header instead, which current tar tools read.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const BLOCK: usize = 512;
+
+pub struct Writer {
+    out: Vec<u8>,
+    mtime: u64,
+}
+
+// Archive paths are relative and may not climb out of the extraction
+// directory
+pub fn check_path(path: &str) -> Result<(), String> {
+    if path.is_empty() || path.starts_with('/') || path.contains('\0') || path.contains('\\') {
+        return Err(format!("'{}' is not a relative path", path));
+    }
+    if path.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+        return Err(format!("'{}' has an empty, '.' or '..' component", path));
+    }
+    Ok(())
+}
+
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+// ustar splits paths over `prefix/name`, at a slash, when they exceed 100 bytes
+fn split_path(path: &str) -> Option<(&str, &str)> {
+    if path.len() <= 100 {
+        return Some(("", path));
+    }
+    path.match_indices('/')
+        .map(|(index, _)| (&path[..index], &path[index + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100 && !name.is_empty())
+}
+
+impl Writer {
+    // Every entry gets modification time `mtime`, in seconds since 1970
+    pub fn new(mtime: u64) -> Writer {
+        Writer { out: Vec::new(), mtime }
+    }
+
+    fn header(&mut self, name: &str, prefix: &str, size: usize, kind: u8) {
+        let mut header = [0u8; BLOCK];
+        // Names longer than the field were written as pax records already
+        let name = &name.as_bytes()[..name.len().min(100)];
+        header[..name.len()].copy_from_slice(name);
+        octal(&mut header[100..108], 0o644);
+        octal(&mut header[108..116], 0);
+        octal(&mut header[116..124], 0);
+        octal(&mut header[124..136], size as u64);
+        octal(&mut header[136..148], self.mtime);
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        // The checksum counts its own field as spaces
+        header[148..156].fill(b' ');
+        let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+        octal(&mut header[148..155], u64::from(checksum));
+        self.out.extend_from_slice(&header);
+    }
+
+    fn data(&mut self, data: &[u8]) {
+        self.out.extend_from_slice(data);
+        self.out.resize(self.out.len().next_multiple_of(BLOCK), 0);
+    }
+
+    pub fn append(&mut self, path: &str, data: &[u8]) -> Result<(), String> {
+        check_path(path)?;
+        match split_path(path) {
+            Some((prefix, name)) => self.header(name, prefix, data.len(), b'0'),
+            None => {
+                // A pax record is "<length> path=<path>\n", its length
+                // counting its own digits
+                let body = format!(" path={}\n", path);
+                let mut length = body.len() + 1;
+                while (length.to_string() + &body).len() > length {
+                    length += 1;
+                }
+                let record = length.to_string() + &body;
+                self.header("PaxHeader", "", record.len(), b'x');
+                self.data(record.as_bytes());
+                self.header(path, "", data.len(), b'0');
+            }
+        }
+        self.data(data);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.out.resize(self.out.len() + 2 * BLOCK, 0);
+        self.out
+    }
+}
@@ -0,0 +1,404 @@
+// Text helpers: English inflection, transliteration to ASCII, slugs,
+// phonetic codes and anagram and palindrome checks
+// Pluralization is table driven: uncountable nouns and irregular forms are
+// looked up first, then the first matching suffix rule applies. Input is
+// assumed to be in the opposite form (pluralize expects a singular noun).
+
+const UNCOUNTABLE: [&str; 32] = [
+    "advice", "aircraft", "baggage", "bison", "deer", "equipment", "evidence", "feedback", "firmware", "fish",
+    "furniture", "hardware", "homework", "information", "knowledge", "luggage", "money", "moose", "music", "news",
+    "offspring", "police", "research", "rice", "salmon", "series", "sheep", "software", "species", "swine",
+    "traffic", "weather",
+];
+
+// (singular, plural)
+const IRREGULAR: [(&str, &str); 78] = [
+    ("ache", "aches"), ("alias", "aliases"), ("alumnus", "alumni"), ("analysis", "analyses"),
+    ("appendix", "appendices"), ("atlas", "atlases"), ("avalanche", "avalanches"), ("axis", "axes"),
+    ("bacterium", "bacteria"), ("basis", "bases"), ("bias", "biases"), ("bonus", "bonuses"), ("bus", "buses"),
+    ("cache", "caches"), ("cactus", "cacti"), ("calf", "calves"), ("calorie", "calories"), ("campus", "campuses"),
+    ("canvas", "canvases"), ("child", "children"), ("cookie", "cookies"), ("crisis", "crises"),
+    ("criterion", "criteria"), ("curriculum", "curricula"), ("datum", "data"), ("diagnosis", "diagnoses"),
+    ("die", "dice"), ("echo", "echoes"), ("elf", "elves"), ("foot", "feet"), ("fungus", "fungi"), ("gas", "gases"),
+    ("goose", "geese"), ("half", "halves"), ("hero", "heroes"), ("hypothesis", "hypotheses"), ("index", "indices"),
+    ("knife", "knives"), ("leaf", "leaves"), ("lens", "lenses"), ("life", "lives"), ("loaf", "loaves"),
+    ("louse", "lice"), ("man", "men"), ("matrix", "matrices"), ("medium", "media"), ("memorandum", "memoranda"),
+    ("mouse", "mice"), ("movie", "movies"), ("niche", "niches"), ("nucleus", "nuclei"), ("octopus", "octopuses"),
+    ("ox", "oxen"), ("parenthesis", "parentheses"), ("person", "people"), ("phenomenon", "phenomena"),
+    ("potato", "potatoes"), ("quiz", "quizzes"), ("radius", "radii"), ("scarf", "scarves"), ("self", "selves"),
+    ("shelf", "shelves"), ("status", "statuses"), ("stimulus", "stimuli"), ("syllabus", "syllabi"),
+    ("synopsis", "synopses"), ("thesis", "theses"), ("thief", "thieves"), ("tomato", "tomatoes"),
+    ("tooth", "teeth"), ("torpedo", "torpedoes"), ("veto", "vetoes"), ("vertex", "vertices"), ("virus", "viruses"),
+    ("wife", "wives"), ("wolf", "wolves"), ("woman", "women"), ("zombie", "zombies"),
+];
+
+// (suffix, replacement), first match wins; an empty suffix always matches
+const PLURAL_RULES: [(&str, &str); 14] = [
+    ("sis", "ses"), ("ay", "ays"), ("ey", "eys"), ("iy", "iys"), ("oy", "oys"), ("uy", "uys"), ("y", "ies"),
+    ("ss", "sses"), ("sh", "shes"), ("ch", "ches"), ("x", "xes"), ("z", "zes"), ("s", "ses"), ("", "s"),
+];
+
+const SINGULAR_RULES: [(&str, &str); 10] = [
+    ("sses", "ss"), ("shes", "sh"), ("ches", "ch"), ("xes", "x"), ("zzes", "zz"), ("ies", "y"), ("ss", "ss"),
+    ("us", "us"), ("is", "is"), ("s", ""),
+];
+
+pub fn pluralize(word: &str) -> String {
+    inflect(word, |lower| {
+        if let Some((_, plural)) = IRREGULAR.iter().find(|(singular, plural)| *singular == lower || *plural == lower) {
+            return plural.to_string();
+        }
+        apply_rules(lower, &PLURAL_RULES).unwrap_or_else(|| lower.to_string())
+    })
+}
+
+pub fn singularize(word: &str) -> String {
+    inflect(word, |lower| {
+        if let Some((singular, _)) = IRREGULAR.iter().find(|(singular, plural)| *singular == lower || *plural == lower) {
+            return singular.to_string();
+        }
+        // "ties" and "pies" only drop the s
+        if lower.len() <= 4 && lower.ends_with("ies") {
+            return lower[..lower.len() - 1].to_string();
+        }
+        apply_rules(lower, &SINGULAR_RULES).unwrap_or_else(|| lower.to_string())
+    })
+}
+
+fn apply_rules(word: &str, rules: &[(&str, &str)]) -> Option<String> {
+    rules
+        .iter()
+        .find(|(suffix, _)| word.ends_with(suffix))
+        .map(|(suffix, replacement)| format!("{}{}", &word[..word.len() - suffix.len()], replacement))
+}
+
+// Inflects the last word of a phrase, keeping its capitalization
+fn inflect(phrase: &str, rule: impl Fn(&str) -> String) -> String {
+    let start = phrase.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let (prefix, word) = phrase.split_at(start);
+    let lower = word.to_lowercase();
+    if word.is_empty() || UNCOUNTABLE.contains(&lower.as_str()) {
+        return phrase.to_string();
+    }
+    let inflected = rule(&lower);
+
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    let inflected = if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        inflected.to_uppercase()
+    } else if word.starts_with(char::is_uppercase) {
+        let mut chars = inflected.chars();
+        chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+    } else {
+        inflected
+    };
+    format!("{}{}", prefix, inflected)
+}
+
+// Transliteration to ASCII
+// Latin letters with diacritics fold to their base letter via compact range
+// strings ('_' marks code points handled by SPECIAL or left unmapped).
+// Cyrillic and Greek use per-script tables of lowercase romanizations, with
+// case restored from the source letter. Anything else non-ASCII becomes '?'
+// and makes the result lossy.
+
+const LATIN_RANGES: [(u32, &str); 6] = [
+    (0x00C0, "AAAAAA_CEEEEIIIIDNOOOOO_OUUUUY__aaaaaa_ceeeeiiiidnooooo_ouuuuy_y"),
+    (0x0100, "AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIiIiJjKkkLlLlLlL"),
+    (0x0140, "lLlNnNnNnnNnOoOoOo__RrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZzs"),
+    (0x01A0, "Oo"),
+    (0x01AF, "Uu"),
+    (0x0218, "SsTt"),
+];
+
+const VIETNAMESE: (u32, &str) = (0x1EA0, "AaAaAaAaAaAaAaAaAaAaAaAaEeEeEeEeEeEeEeEeIiIiOoOoOoOoOoOoOoOoOoOoOoOoUuUuUuUuUuUuUuYyYyYyYy");
+
+const SPECIAL: [(char, &str); 20] = [
+    ('\u{a0}', " "), ('«', "\""), ('»', "\""), ('Æ', "AE"), ('Þ', "TH"), ('ß', "ss"), ('æ', "ae"), ('þ', "th"),
+    ('Œ', "OE"), ('œ', "oe"), ('‐', "-"), ('–', "-"), ('—', "-"), ('‘', "'"), ('’', "'"), ('‚', "'"),
+    ('“', "\""), ('”', "\""), ('„', "\""), ('…', "..."),
+];
+
+const SCRIPT_RANGES: [(u32, &[&str]); 5] = [
+    // Greek ΐ, then ά έ ή ί ΰ α-ω ϊ ϋ ό ύ ώ
+    (0x0390, &["i"]),
+    (0x03AC, &[
+        "a", "e", "i", "i", "y", "a", "v", "g", "d", "e", "z", "i", "th", "i", "k", "l", "m", "n", "x", "o", "p", "r",
+        "s", "s", "t", "y", "f", "ch", "ps", "o", "i", "y", "o", "y", "o",
+    ]),
+    // Cyrillic а-я
+    (0x0430, &[
+        "a", "b", "v", "g", "d", "e", "zh", "z", "i", "y", "k", "l", "m", "n", "o", "p", "r", "s", "t", "u", "f",
+        "kh", "ts", "ch", "sh", "shch", "", "y", "", "e", "yu", "ya",
+    ]),
+    // Cyrillic ѐ-џ (Ukrainian, Belarusian, Serbian and Macedonian letters)
+    (0x0450, &["e", "yo", "dj", "gj", "ye", "dz", "i", "yi", "j", "lj", "nj", "c", "kj", "i", "u", "dz"]),
+    (0x0491, &["g"]),
+];
+
+fn latin(c: char) -> Option<char> {
+    let code = c as u32;
+    LATIN_RANGES
+        .iter()
+        .chain(std::iter::once(&VIETNAMESE))
+        .find_map(|&(start, letters)| letters.as_bytes().get(code.checked_sub(start)? as usize).copied())
+        .filter(|&letter| letter != b'_')
+        .map(char::from)
+}
+
+fn script(lower: char) -> Option<&'static str> {
+    let code = lower as u32;
+    SCRIPT_RANGES
+        .iter()
+        .find_map(|&(start, romanized)| romanized.get(code.checked_sub(start)? as usize).copied())
+}
+
+// ASCII form of the text and the number of characters that had no mapping
+pub fn transliterate(text: &str) -> (String, usize) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut unmapped = 0;
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii() {
+            out.push(c);
+        } else if let Some(letter) = latin(c) {
+            out.push(letter);
+        } else if let Some((_, replacement)) = SPECIAL.iter().find(|(special, _)| *special == c) {
+            out.push_str(replacement);
+        } else if let Some(romanized) = c.to_lowercase().next().and_then(script) {
+            // A capital next to another capital is part of an all-caps word
+            let neighbour = match chars.get(i + 1) {
+                Some(next) if next.is_alphabetic() => Some(next),
+                _ => i.checked_sub(1).and_then(|previous| chars.get(previous)),
+            };
+            if !c.is_uppercase() {
+                out.push_str(romanized);
+            } else if neighbour.is_some_and(|n| n.is_uppercase()) {
+                out.push_str(&romanized.to_uppercase());
+            } else {
+                let mut letters = romanized.chars();
+                if let Some(first) = letters.next() {
+                    out.push(first.to_ascii_uppercase());
+                    out.extend(letters);
+                }
+            }
+        } else if ('\u{300}'..='\u{36f}').contains(&c) {
+            // Combining diacritics of decomposed text
+        } else {
+            out.push('?');
+            unmapped += 1;
+        }
+    }
+    (out, unmapped)
+}
+
+// Lowercase ASCII words joined by hyphens, e.g. "Привет, мир!" -> "privet-mir"
+pub fn slugify(text: &str) -> String {
+    let (ascii, _) = transliterate(text);
+    let mut slug = String::with_capacity(ascii.len());
+    for c in ascii.chars().filter(|c| *c != '\'') {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+// Letters of a name for the phonetic codes, uppercase and transliterated
+fn phonetic_letters(word: &str) -> Vec<u8> {
+    transliterate(word).0.bytes().filter(u8::is_ascii_alphabetic).map(|byte| byte.to_ascii_uppercase()).collect()
+}
+
+fn soundex_digit(letter: u8) -> u8 {
+    match letter {
+        b'B' | b'F' | b'P' | b'V' => b'1',
+        b'C' | b'G' | b'J' | b'K' | b'Q' | b'S' | b'X' | b'Z' => b'2',
+        b'D' | b'T' => b'3',
+        b'L' => b'4',
+        b'M' | b'N' => b'5',
+        b'R' => b'6',
+        // H and W are skipped without separating equal digits, vowels separate them
+        b'H' | b'W' => b'-',
+        _ => b'0',
+    }
+}
+
+// American Soundex, as used by the US census: "Robert" and "Rupert" are R163.
+// None when the word has no letters.
+pub fn soundex(word: &str) -> Option<String> {
+    let letters = phonetic_letters(word);
+    let (&first, rest) = letters.split_first()?;
+    let mut code = vec![first];
+    let mut previous = soundex_digit(first);
+    for &letter in rest {
+        let digit = soundex_digit(letter);
+        if digit == b'-' {
+            continue;
+        }
+        if digit != b'0' && digit != previous {
+            code.push(digit);
+        }
+        previous = digit;
+    }
+    code.resize(4, b'0');
+    Some(String::from_utf8_lossy(&code[..4]).into_owned())
+}
+
+fn is_vowel(letter: Option<&u8>) -> bool {
+    matches!(letter, Some(b'A' | b'E' | b'I' | b'O' | b'U'))
+}
+
+// Lawrence Philips' original Metaphone, where "0" stands for "th" and "X"
+// for "sh": "Knight" is NT and "Philips" FLPS. None when the word has no
+// letters.
+pub fn metaphone(word: &str) -> Option<String> {
+    let mut letters = phonetic_letters(word);
+    if letters.is_empty() {
+        return None;
+    }
+    match letters.as_slice() {
+        [b'A', b'E', ..] | [b'G' | b'K' | b'P', b'N', ..] | [b'W', b'R', ..] => {
+            letters.remove(0);
+        }
+        [b'X', ..] => letters[0] = b'S',
+        [b'W', b'H', ..] => {
+            letters.remove(1);
+        }
+        _ => {}
+    }
+    let at = |i: usize| letters.get(i);
+    let follows = |i: usize, text: &str| letters.get(i + 1..).is_some_and(|rest| rest.starts_with(text.as_bytes()));
+    let mut code = String::new();
+    for (i, &letter) in letters.iter().enumerate() {
+        let previous = i.checked_sub(1).and_then(at).copied();
+        // Doubled letters sound once, except CC as in "accent"
+        if previous == Some(letter) && letter != b'C' {
+            continue;
+        }
+        let next = at(i + 1);
+        match letter {
+            b'A' | b'E' | b'I' | b'O' | b'U' => {
+                if i == 0 {
+                    code.push(char::from(letter));
+                }
+            }
+            b'B' => {
+                // Silent in a final "mb", as in "dumb"
+                if !(previous == Some(b'M') && next.is_none()) {
+                    code.push('B');
+                }
+            }
+            b'C' => {
+                if follows(i, "IA") || next == Some(&b'H') && previous != Some(b'S') {
+                    code.push('X');
+                } else if matches!(next, Some(b'I' | b'E' | b'Y')) {
+                    if previous != Some(b'S') {
+                        code.push('S');
+                    }
+                } else {
+                    code.push('K');
+                }
+            }
+            b'D' => code.push(if follows(i, "GE") || follows(i, "GY") || follows(i, "GI") { 'J' } else { 'T' }),
+            b'G' => {
+                // Silent in "gh" before a consonant, a final "gn" or "gned", and
+                // after the D of "dge", which is already a J
+                let silent = next == Some(&b'H') && !(i + 2 >= letters.len() || is_vowel(at(i + 2)))
+                    || previous == Some(b'D') && matches!(next, Some(b'I' | b'E' | b'Y'))
+                    || next == Some(&b'N') && (i + 2 == letters.len() || follows(i, "NED") && i + 4 == letters.len());
+                if !silent {
+                    code.push(if matches!(next, Some(b'I' | b'E' | b'Y')) && previous != Some(b'G') { 'J' } else { 'K' });
+                }
+            }
+            b'H' => {
+                let after_vowel = is_vowel(previous.as_ref()) && !is_vowel(next);
+                if !after_vowel && !matches!(previous, Some(b'C' | b'G' | b'P' | b'S' | b'T')) {
+                    code.push('H');
+                }
+            }
+            b'K' => {
+                if previous != Some(b'C') {
+                    code.push('K');
+                }
+            }
+            b'P' => code.push(if next == Some(&b'H') { 'F' } else { 'P' }),
+            b'Q' => code.push('K'),
+            b'S' => code.push(if next == Some(&b'H') || follows(i, "IO") || follows(i, "IA") { 'X' } else { 'S' }),
+            b'T' => {
+                if follows(i, "IA") || follows(i, "IO") {
+                    code.push('X');
+                } else if next == Some(&b'H') {
+                    code.push('0');
+                } else if !follows(i, "CH") {
+                    code.push('T');
+                }
+            }
+            b'V' => code.push('F'),
+            b'W' | b'Y' => {
+                if is_vowel(next) {
+                    code.push(char::from(letter));
+                }
+            }
+            b'X' => code.push_str("KS"),
+            b'Z' => code.push('S'),
+            other => code.push(char::from(other)),
+        }
+    }
+    Some(code)
+}
+
+// How loosely anagrams and palindromes are compared
+pub struct Folding {
+    pub case: bool,
+    // Spaces and punctuation, anything not a letter or digit
+    pub punctuation: bool,
+    // Diacritics on Latin letters, so "é" counts as "e"
+    pub accents: bool,
+}
+
+// The text as characters with their combining marks, which stay attached
+// when a palindrome is reversed or an anagram sorted
+pub fn fold(text: &str, folding: &Folding) -> Vec<String> {
+    let mut clusters: Vec<String> = Vec::new();
+    for c in text.chars() {
+        let combining = ('\u{300}'..='\u{36f}').contains(&c);
+        if combining {
+            if !folding.accents {
+                if let Some(last) = clusters.last_mut() {
+                    last.push(c);
+                }
+            }
+            continue;
+        }
+        if folding.punctuation && !c.is_alphanumeric() {
+            continue;
+        }
+        let base = if folding.accents { latin(c).unwrap_or(c) } else { c };
+        clusters.push(if folding.case { base.to_lowercase().collect() } else { base.to_string() });
+    }
+    clusters
+}
+
+// Characters of `a` that `b` lacks and of `b` that `a` lacks, each sorted;
+// both are empty for anagrams
+pub fn anagram_difference(a: &[String], b: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut only_a = a.to_vec();
+    let mut only_b = Vec::new();
+    for cluster in b {
+        match only_a.iter().position(|other| other == cluster) {
+            Some(index) => {
+                only_a.remove(index);
+            }
+            None => only_b.push(cluster.clone()),
+        }
+    }
+    only_a.sort();
+    only_b.sort();
+    (only_a, only_b)
+}
+
+// Index, in folded characters, of the first one that differs from its mirror
+pub fn palindrome_mismatch(clusters: &[String]) -> Option<usize> {
+    (0..clusters.len() / 2).find(|&i| clusters[i] != clusters[clusters.len() - 1 - i])
+}
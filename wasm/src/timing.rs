@@ -0,0 +1,171 @@
+// Per-request spans and per-route timings
+// handle_request marks the end of each phase as it passes it: parse (decoding
+// the request parts, limit checks and the query string), route (counters and
+// picking a handler), handler, and serialize (KV replay, budget and security
+// header post-processing). The spans go out in a Server-Timing header, are
+// summed per route for /metrics, and requests slower than slow_request_ms are
+// logged and kept for inspection. Workers only advance the clock between I/O
+// events, so a deployed Worker mostly reports zeros; runtimes without that
+// restriction report real durations.
+
+use crate::arena;
+use crate::config::{self, Config};
+use crate::counters;
+use crate::host;
+use crate::humanize;
+use crate::json;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Clone, Copy)]
+pub enum Span {
+    Parse,
+    Route,
+    Handler,
+    Serialize,
+}
+
+const SPAN_NAMES: [&str; 4] = ["parse", "route", "handler", "serialize"];
+
+// Slow requests kept for /metrics, oldest dropped first
+const SLOW_REQUESTS_KEPT: usize = 16;
+
+#[derive(Clone, Copy)]
+pub struct Timings {
+    started_at: f64,
+    last_mark: f64,
+    spans: [f64; 4],
+}
+
+impl Timings {
+    pub fn total(&self) -> f64 {
+        self.spans.iter().sum()
+    }
+
+    // Server-Timing header value, e.g. "parse;dur=0.1, route;dur=0.0, ..."
+    pub fn header(&self) -> String {
+        SPAN_NAMES
+            .iter()
+            .zip(self.spans)
+            .map(|(name, duration)| format!("{};dur={:.1}", name, duration))
+            .chain([format!("total;dur={:.1}", self.total())])
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[derive(Default)]
+struct RouteTotals {
+    requests: u64,
+    total_ms: f64,
+    max_ms: f64,
+    spans: [f64; 4],
+}
+
+struct SlowRequest {
+    route: &'static str,
+    timings: Timings,
+}
+
+thread_local! {
+    static CURRENT: Cell<Timings> = const { Cell::new(Timings { started_at: 0.0, last_mark: 0.0, spans: [0.0; 4] }) };
+    static ROUTES: RefCell<BTreeMap<&'static str, RouteTotals>> = const { RefCell::new(BTreeMap::new()) };
+    static SLOW: RefCell<VecDeque<SlowRequest>> = const { RefCell::new(VecDeque::new()) };
+}
+
+fn spans_json(spans: &[f64; 4]) -> String {
+    let members: Vec<String> = SPAN_NAMES.iter().zip(spans).map(|(name, ms)| format!("\"{}\":{:.1}", name, ms)).collect();
+    format!("{{{}}}", members.join(","))
+}
+
+pub fn begin() {
+    let now = host::now_ms();
+    CURRENT.with(|current| current.set(Timings { started_at: now, last_mark: now, spans: [0.0; 4] }));
+}
+
+// Charges the time since the previous mark to `span`; marking a span twice
+// adds to it
+pub fn mark(span: Span) {
+    let now = host::now_ms();
+    CURRENT.with(|current| {
+        let mut timings = current.get();
+        timings.spans[span as usize] += now - timings.last_mark;
+        timings.last_mark = now;
+        current.set(timings);
+    })
+}
+
+pub fn current() -> Timings {
+    CURRENT.with(Cell::get)
+}
+
+// Adds a finished request to its route's totals, tracing it if it was slow
+pub fn record(path: &str, timings: Timings, config: &Config) {
+    let route = counters::route_name(path);
+    let total = timings.total();
+    arena::outside(|| ROUTES.with(|routes| {
+        let mut routes = routes.borrow_mut();
+        let totals = routes.entry(route).or_default();
+        totals.requests += 1;
+        totals.total_ms += total;
+        totals.max_ms = totals.max_ms.max(total);
+        for (sum, span) in totals.spans.iter_mut().zip(timings.spans) {
+            *sum += span;
+        }
+    }));
+    if total >= config.slow_request_ms as f64 {
+        host::log(&format!("Slow request to {}: {}", route, timings.header()));
+        arena::outside(|| SLOW.with(|slow| {
+            let mut slow = slow.borrow_mut();
+            if slow.len() == SLOW_REQUESTS_KEPT {
+                slow.pop_front();
+            }
+            slow.push_back(SlowRequest { route, timings });
+        }));
+    }
+}
+
+// {"slow_request_ms":n,"routes":{route:{...}},"slow_requests":[...]}
+pub fn to_json() -> String {
+    let routes: Vec<String> = ROUTES.with(|routes| {
+        routes
+            .borrow()
+            .iter()
+            .map(|(route, totals)| {
+                format!(
+                    r#""{}":{{"requests":{},"total_ms":{:.1},"mean_ms":{:.2},"max_ms":{:.1},"mean":"{}","max":"{}","spans_ms":{}}}"#,
+                    json::escape(route),
+                    totals.requests,
+                    totals.total_ms,
+                    totals.total_ms / totals.requests as f64,
+                    totals.max_ms,
+                    humanize::duration(totals.total_ms / totals.requests as f64),
+                    humanize::duration(totals.max_ms),
+                    spans_json(&totals.spans)
+                )
+            })
+            .collect()
+    });
+    let slow: Vec<String> = SLOW.with(|slow| {
+        slow.borrow()
+            .iter()
+            .rev()
+            .map(|request| {
+                format!(
+                    r#"{{"route":"{}","started_at_ms":{},"total_ms":{:.1},"total":"{}","spans_ms":{}}}"#,
+                    json::escape(request.route),
+                    request.timings.started_at,
+                    request.timings.total(),
+                    humanize::duration(request.timings.total()),
+                    spans_json(&request.timings.spans)
+                )
+            })
+            .collect()
+    });
+    format!(
+        r#"{{"slow_request_ms":{},"routes":{{{}}},"slow_requests":[{}]}}"#,
+        config::current().slow_request_ms,
+        routes.join(","),
+        slow.join(",")
+    )
+}
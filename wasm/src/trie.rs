@@ -0,0 +1,176 @@
+// Prefix trie with frequency-ranked completions
+// Nodes live in one arena vector; each node also records the highest word
+// frequency in its subtree so completion can search best-first and stop after
+// `limit` results instead of visiting every word under the prefix.
+
+use crate::arena;
+use crate::ffi;
+use std::cell::RefCell;
+use std::collections::BinaryHeap;
+use std::cmp::{Ordering, Reverse};
+
+#[derive(Default)]
+struct Node {
+    // Sorted by character for binary search
+    children: Vec<(char, usize)>,
+    frequency: u64,
+    best: u64,
+}
+
+pub struct Trie {
+    nodes: Vec<Node>,
+    words: usize,
+}
+
+// Common English words with rough relative frequencies, used until the host
+// loads its own list through init_dictionary
+const DEFAULT_WORDS: &str = "\
+the 1000\nthat 620\nthis 480\nthey 410\nthere 360\nthen 240\nthem 230\nthese 210\nthink 190\nthrough 150\n\
+thing 140\nthought 110\nthree 100\nthanks 90\ntheory 40\nwith 700\nwhat 450\nwhen 380\nwhich 370\nwho 320\n\
+would 310\nwill 300\nwork 260\nworld 220\nway 210\nwant 200\nwater 120\nword 110\nwrite 100\nwasm 60\n\
+worker 50\nwebassembly 40\ncan 420\ncould 290\ncome 250\ncall 150\ncase 120\nchange 110\ncloud 90\n\
+cloudflare 70\ncode 80\ncompute 40\ncache 30\nmake 300\nmore 290\nmost 180\nmany 170\nmemory 50\n\
+module 40\nsee 280\nsome 270\nsay 260\nshould 200\nsystem 120\nstring 60\nserver 50\nstream 30\n";
+
+thread_local! {
+    static DICTIONARY: RefCell<Option<Trie>> = const { RefCell::new(None) };
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Trie { nodes: vec![Node::default()], words: 0 }
+    }
+}
+
+// Candidate in the best-first search: an unexpanded subtree or a finished
+// word. Higher frequency wins, then alphabetical order.
+#[derive(PartialEq, Eq)]
+struct Candidate {
+    score: u64,
+    text: Reverse<String>,
+    node: usize,
+    is_word: bool,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.score, &self.text, self.is_word).cmp(&(other.score, &other.text, other.is_word))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Trie {
+    // Build from lines of "word" or "word frequency"
+    pub fn from_word_list(list: &str) -> Trie {
+        let mut trie = Trie::default();
+        for line in list.lines() {
+            let mut fields = line.split([' ', '\t', ',']).filter(|field| !field.is_empty());
+            let Some(word) = fields.next() else {
+                continue;
+            };
+            let frequency = fields.next().and_then(|field| field.parse().ok()).unwrap_or(1);
+            trie.insert(&word.to_lowercase(), frequency);
+        }
+        trie
+    }
+
+    // Add a word, summing frequencies when it is already present
+    pub fn insert(&mut self, word: &str, frequency: u64) {
+        let mut path = vec![0];
+        let mut node = 0;
+        for c in word.chars() {
+            node = match self.nodes[node].children.binary_search_by_key(&c, |&(c, _)| c) {
+                Ok(i) => self.nodes[node].children[i].1,
+                Err(i) => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.insert(i, (c, child));
+                    child
+                }
+            };
+            path.push(node);
+        }
+        if self.nodes[node].frequency == 0 {
+            self.words += 1;
+        }
+        self.nodes[node].frequency += frequency;
+        let total = self.nodes[node].frequency;
+        for node in path {
+            self.nodes[node].best = self.nodes[node].best.max(total);
+        }
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words
+    }
+
+    fn find(&self, prefix: &str) -> Option<usize> {
+        prefix.chars().try_fold(0, |node, c| {
+            let children = &self.nodes[node].children;
+            children.binary_search_by_key(&c, |&(c, _)| c).ok().map(|i| children[i].1)
+        })
+    }
+
+    // Up to `limit` words starting with `prefix`, most frequent first
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<(String, u64)> {
+        let mut results = Vec::new();
+        let Some(start) = self.find(prefix) else {
+            return results;
+        };
+
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate { score: self.nodes[start].best, text: Reverse(prefix.to_string()), node: start, is_word: false });
+        while let Some(Candidate { score, text: Reverse(text), node, is_word }) = heap.pop() {
+            if is_word {
+                results.push((text, score));
+                if results.len() == limit {
+                    break;
+                }
+                continue;
+            }
+            let current = &self.nodes[node];
+            if current.frequency > 0 {
+                heap.push(Candidate { score: current.frequency, text: Reverse(text.clone()), node, is_word: true });
+            }
+            for &(c, child) in &current.children {
+                let mut child_text = text.clone();
+                child_text.push(c);
+                heap.push(Candidate { score: self.nodes[child].best, text: Reverse(child_text), node: child, is_word: false });
+            }
+        }
+        results
+    }
+}
+
+// Run a closure against the shared dictionary, loading the built-in word
+// list on first use
+pub fn with_dictionary<T>(f: impl FnOnce(&Trie) -> T) -> T {
+    DICTIONARY.with(|dictionary| {
+        let mut dictionary = dictionary.borrow_mut();
+        f(dictionary.get_or_insert_with(|| arena::outside(|| Trie::from_word_list(DEFAULT_WORDS))))
+    })
+}
+
+// True once the built-in list or one from init_dictionary is loaded
+pub fn is_loaded() -> bool {
+    DICTIONARY.with(|dictionary| dictionary.borrow().is_some())
+}
+
+// Replace the dictionary with a newline separated word list in WASM memory
+// ("word" or "word frequency" per line). The trie stays warm for every later
+// request served by this instance. Returns the number of distinct words.
+#[no_mangle]
+pub extern "C" fn init_dictionary(ptr: *const u8, len: usize) -> usize {
+    let Ok(list) = (unsafe { ffi::text(ptr, len) }) else {
+        return 0;
+    };
+    let trie = Trie::from_word_list(list);
+    let words = trie.word_count();
+    DICTIONARY.with(|dictionary| *dictionary.borrow_mut() = Some(trie));
+    words
+}
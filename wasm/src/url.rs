@@ -0,0 +1,345 @@
+// URL parsing and syntax checks (RFC 3986 flavored)
+// Errors carry a human readable reason so validators can explain failures.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub struct Url {
+    pub scheme: String,
+    pub userinfo: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl Url {
+    // scheme://host[:port], as browsers compare origins
+    pub fn origin(&self) -> String {
+        match self.port {
+            Some(port) => format!("{}://{}:{}", self.scheme, self.host, port),
+            None => format!("{}://{}", self.scheme, self.host),
+        }
+    }
+}
+
+// Written back in one piece, e.g. after resolve
+impl core::fmt::Display for Url {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}:", self.scheme)?;
+        if !self.host.is_empty() || HOST_SCHEMES.contains(&self.scheme.as_str()) {
+            f.write_str("//")?;
+            if let Some(userinfo) = &self.userinfo {
+                write!(f, "{}@", userinfo)?;
+            }
+            f.write_str(&self.host)?;
+            if let Some(port) = self.port {
+                write!(f, ":{}", port)?;
+            }
+        }
+        f.write_str(&self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+// Schemes that always have an authority with a non-empty host
+const HOST_SCHEMES: [&str; 6] = ["http", "https", "ftp", "ws", "wss", "file"];
+
+pub fn parse(input: &str) -> Result<Url, String> {
+    if input.is_empty() {
+        return Err("URL is empty".to_string());
+    }
+    if let Some(c) = input.chars().find(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("URL contains an invalid character {:?}", c));
+    }
+
+    let (scheme, rest) = match input.split_once(':') {
+        Some((scheme, rest)) if !scheme.is_empty() && !scheme.contains('/') => (scheme, rest),
+        _ => return Err("Missing scheme (expected e.g. https://)".to_string()),
+    };
+    if !scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        || !scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        return Err(format!("Invalid scheme '{}'", scheme));
+    }
+    let scheme = scheme.to_ascii_lowercase();
+
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+        None => (rest, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query.to_string())),
+        None => (rest, None),
+    };
+
+    let (authority, path) = match rest.strip_prefix("//") {
+        Some(after) => {
+            let end = after.find('/').unwrap_or(after.len());
+            (Some(&after[..end]), &after[end..])
+        }
+        None => (None, rest),
+    };
+
+    let mut url = Url {
+        scheme,
+        userinfo: None,
+        host: String::new(),
+        port: None,
+        path: path.to_string(),
+        query,
+        fragment,
+    };
+
+    match authority {
+        Some(authority) => parse_authority(authority, &mut url)?,
+        None if HOST_SCHEMES.contains(&url.scheme.as_str()) => {
+            return Err(format!("{} URLs need '//' followed by a host", url.scheme));
+        }
+        None => {}
+    }
+    if url.host.is_empty() && HOST_SCHEMES.contains(&url.scheme.as_str()) && url.scheme != "file" {
+        return Err("Missing host".to_string());
+    }
+
+    check_percent_encoding(&url.path).map_err(|reason| format!("Invalid path: {}", reason))?;
+    if let Some(query) = &url.query {
+        check_percent_encoding(query).map_err(|reason| format!("Invalid query: {}", reason))?;
+    }
+    if let Some(fragment) = &url.fragment {
+        check_percent_encoding(fragment).map_err(|reason| format!("Invalid fragment: {}", reason))?;
+    }
+    Ok(url)
+}
+
+fn parse_authority(authority: &str, url: &mut Url) -> Result<(), String> {
+    let host_port = match authority.rsplit_once('@') {
+        Some((userinfo, host_port)) => {
+            check_percent_encoding(userinfo).map_err(|reason| format!("Invalid userinfo: {}", reason))?;
+            url.userinfo = Some(userinfo.to_string());
+            host_port
+        }
+        None => authority,
+    };
+
+    // IPv6 literals are bracketed and contain colons of their own
+    let (host, port) = if host_port.starts_with('[') {
+        match host_port.find(']') {
+            Some(end) => {
+                let after = &host_port[end + 1..];
+                let port = match after.strip_prefix(':') {
+                    Some(port) => Some(port),
+                    None if after.is_empty() => None,
+                    None => return Err("Unexpected characters after IPv6 address".to_string()),
+                };
+                (&host_port[..=end], port)
+            }
+            None => return Err("Unterminated IPv6 address".to_string()),
+        }
+    } else {
+        match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        }
+    };
+
+    if let Some(port) = port.filter(|port| !port.is_empty()) {
+        url.port = Some(port.parse().map_err(|_| format!("Invalid port '{}'", port))?);
+    }
+
+    if let Some(literal) = host.strip_prefix('[').and_then(|host| host.strip_suffix(']')) {
+        validate_ipv6(literal)?;
+    } else if !host.is_empty() && !is_ipv4(host) {
+        let decoded = percent_decode(host).ok_or_else(|| "Host is not valid UTF-8".to_string())?;
+        check_percent_encoding(host)?;
+        validate_domain(&decoded)?;
+    }
+    url.host = host.to_ascii_lowercase();
+    Ok(())
+}
+
+// Collapses repeated slashes and resolves "." and ".." segments (RFC 3986
+// section 5.2.4) without climbing above the root. A trailing slash, or a
+// final dot-segment, leaves the result ending in '/'.
+pub fn normalize_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let ends_as_directory = path.ends_with('/') || path.ends_with("/.") || path.ends_with("/..");
+    let mut normalized = String::with_capacity(path.len());
+    for segment in &segments {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    if segments.is_empty() || ends_as_directory {
+        normalized.push('/');
+    }
+    normalized
+}
+
+// RFC 3986 section 5.2.4: unlike normalize_path, empty segments are kept
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path.split('/').peekable();
+    // A leading "" stands for the root
+    let absolute = path.starts_with('/');
+    if absolute {
+        parts.next();
+    }
+    while let Some(segment) = parts.next() {
+        let last = parts.peek().is_none();
+        match segment {
+            "." | ".." => {
+                if segment == ".." {
+                    segments.pop();
+                }
+                if last {
+                    segments.push("");
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let joined = segments.join("/");
+    if absolute { format!("/{}", joined) } else { joined }
+}
+
+// Resolves a reference such as "../a.html?b" or "//cdn.example/x" against a
+// base URL (RFC 3986 section 5.2); a reference with its own scheme is parsed
+// as it is. The result is checked as parse would check it.
+pub fn resolve(base: &Url, reference: &str) -> Result<Url, String> {
+    let has_scheme = reference
+        .find(':')
+        .is_some_and(|colon| colon > 0 && !reference[..colon].contains(['/', '?', '#']));
+    if has_scheme {
+        let mut url = parse(reference)?;
+        url.path = remove_dot_segments(&url.path);
+        return Ok(url);
+    }
+    if reference.starts_with("//") {
+        let mut url = parse(&format!("{}:{}", base.scheme, reference))?;
+        url.path = remove_dot_segments(&url.path);
+        return Ok(url);
+    }
+    let (rest, fragment) = match reference.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+        None => (reference, None),
+    };
+    let (path, query) = match rest.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (rest, None),
+    };
+    let (path, query) = if path.is_empty() {
+        (base.path.clone(), query.or(base.query.clone()))
+    } else if path.starts_with('/') {
+        (remove_dot_segments(path), query)
+    } else if base.path.is_empty() && !base.host.is_empty() {
+        (remove_dot_segments(&format!("/{}", path)), query)
+    } else {
+        let directory = base.path.rfind('/').map_or("", |slash| &base.path[..=slash]);
+        (remove_dot_segments(&format!("{}{}", directory, path)), query)
+    };
+    let url = Url {
+        scheme: base.scheme.clone(),
+        userinfo: base.userinfo.clone(),
+        host: base.host.clone(),
+        port: base.port,
+        path,
+        query,
+        fragment,
+    };
+    parse(&url.to_string())
+}
+
+// None when the decoded bytes are not UTF-8; malformed escapes are kept as is
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes.get(i..i + 3) {
+            Some(&[b'%', high, low]) if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                let digit = |b: u8| (b as char).to_digit(16).unwrap_or(0) as u8;
+                decoded.push(digit(high) << 4 | digit(low));
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+// Every '%' must start a two digit hex escape
+pub fn check_percent_encoding(value: &str) -> Result<(), String> {
+    let bytes = value.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'%' {
+            let valid = bytes.len() > i + 2 && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit();
+            if !valid {
+                return Err(format!("invalid percent-encoding at position {}", i));
+            }
+        }
+    }
+    Ok(())
+}
+
+// DNS hostname rules: labels of 1-63 letters, digits or hyphens (not at
+// either end), at most 253 characters overall. Non-ASCII labels are accepted
+// as internationalized names.
+pub fn validate_domain(domain: &str) -> Result<(), String> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    if domain.is_empty() {
+        return Err("Domain is empty".to_string());
+    }
+    if domain.len() > 253 {
+        return Err("Domain is longer than 253 characters".to_string());
+    }
+    for label in domain.split('.') {
+        if label.is_empty() {
+            return Err("Bad domain label: empty label (consecutive dots)".to_string());
+        }
+        if label.len() > 63 {
+            return Err(format!("Bad domain label '{}': longer than 63 characters", label));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("Bad domain label '{}': cannot start or end with a hyphen", label));
+        }
+        if let Some(c) = label.chars().find(|c| c.is_ascii() && !c.is_ascii_alphanumeric() && *c != '-') {
+            return Err(format!("Bad domain label '{}': invalid character {:?}", label, c));
+        }
+    }
+    Ok(())
+}
+
+pub fn is_ipv4(host: &str) -> bool {
+    let parts: Vec<&str> = host.split('.').collect();
+    parts.len() == 4
+        && parts.iter().all(|part| {
+            !part.is_empty() && part.len() <= 3 && part.bytes().all(|b| b.is_ascii_digit()) && part.parse::<u8>().is_ok()
+        })
+}
+
+fn validate_ipv6(literal: &str) -> Result<(), String> {
+    literal
+        .parse::<core::net::Ipv6Addr>()
+        .map(|_| ())
+        .map_err(|_| format!("Invalid IPv6 address '{}'", literal))
+}
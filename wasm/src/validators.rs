@@ -0,0 +1,495 @@
+// Checksum and format validators for common identifiers
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::url;
+
+pub struct CardInfo {
+    pub masked: String,
+    pub length: usize,
+    pub luhn_valid: bool,
+    pub brand: Option<&'static str>,
+    // Whether the length is one the detected brand issues (None without a brand)
+    pub length_valid: Option<bool>,
+}
+
+impl CardInfo {
+    pub fn is_valid(&self) -> bool {
+        self.luhn_valid && self.length_valid != Some(false)
+    }
+}
+
+struct CardBrand {
+    name: &'static str,
+    // Inclusive IIN/BIN ranges, compared on the same number of leading digits
+    prefixes: &'static [(u32, u32)],
+    lengths: &'static [usize],
+}
+
+// Order matters: the first matching brand wins, so broad ranges come last
+const CARD_BRANDS: [CardBrand; 8] = [
+    CardBrand { name: "amex", prefixes: &[(34, 34), (37, 37)], lengths: &[15] },
+    CardBrand { name: "mastercard", prefixes: &[(51, 55), (2221, 2720)], lengths: &[16] },
+    CardBrand {
+        name: "discover",
+        prefixes: &[(6011, 6011), (644, 649), (65, 65), (622126, 622925)],
+        lengths: &[16, 17, 18, 19],
+    },
+    CardBrand { name: "diners", prefixes: &[(300, 305), (36, 36), (38, 39)], lengths: &[14, 15, 16, 17, 18, 19] },
+    CardBrand { name: "jcb", prefixes: &[(3528, 3589)], lengths: &[16, 17, 18, 19] },
+    CardBrand { name: "unionpay", prefixes: &[(62, 62)], lengths: &[16, 17, 18, 19] },
+    CardBrand { name: "visa", prefixes: &[(4, 4)], lengths: &[13, 16, 19] },
+    CardBrand { name: "maestro", prefixes: &[(50, 50), (56, 69)], lengths: &[12, 13, 14, 15, 16, 17, 18, 19] },
+];
+
+// Validate a card number given with optional spaces or dashes between digits
+pub fn card(number: &str) -> Result<CardInfo, String> {
+    let digits: String = number.chars().filter(|c| *c != ' ' && *c != '-').collect();
+    if digits.is_empty() {
+        return Err("Card number is required".to_string());
+    }
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("Card number may only contain digits, spaces and dashes".to_string());
+    }
+    if !(12..=19).contains(&digits.len()) {
+        return Err("Card number must have between 12 and 19 digits".to_string());
+    }
+
+    let brand = CARD_BRANDS.iter().find(|brand| {
+        brand.prefixes.iter().any(|&(low, high)| {
+            let width = low.to_string().len();
+            let prefix: u32 = digits[..width].parse().unwrap_or(0);
+            (low..=high).contains(&prefix)
+        })
+    });
+
+    Ok(CardInfo {
+        masked: mask_card(&digits),
+        length: digits.len(),
+        luhn_valid: luhn(&digits),
+        brand: brand.map(|brand| brand.name),
+        length_valid: brand.map(|brand| brand.lengths.contains(&digits.len())),
+    })
+}
+
+// Luhn (mod 10) checksum over an all-digit string
+pub fn luhn(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let digit = (b - b'0') as u32;
+            if i % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// Keep the BIN (first 6) and last 4 digits, as permitted for display by PCI DSS
+fn mask_card(digits: &str) -> String {
+    let visible_end = digits.len() - 4;
+    digits
+        .char_indices()
+        .map(|(i, c)| if i < 6 || i >= visible_end { c } else { '*' })
+        .collect()
+}
+
+pub struct IbanInfo {
+    pub formatted: String,
+    pub country: String,
+    pub check_digits: String,
+    pub bban: String,
+    pub length: usize,
+    pub expected_length: Option<usize>,
+    pub checksum_valid: bool,
+}
+
+impl IbanInfo {
+    pub fn is_valid(&self) -> bool {
+        self.checksum_valid && self.expected_length == Some(self.length)
+    }
+}
+
+// IBAN lengths by country, from the SWIFT IBAN registry
+const IBAN_LENGTHS: [(&str, usize); 87] = [
+    ("AD", 24), ("AE", 23), ("AL", 28), ("AT", 20), ("AZ", 28), ("BA", 20), ("BE", 16), ("BG", 22),
+    ("BH", 22), ("BI", 27), ("BR", 29), ("BY", 28), ("CH", 21), ("CR", 22), ("CY", 28), ("CZ", 24),
+    ("DE", 22), ("DJ", 27), ("DK", 18), ("DO", 28), ("EE", 20), ("EG", 29), ("ES", 24), ("FI", 18),
+    ("FK", 18), ("FO", 18), ("FR", 27), ("GB", 22), ("GE", 22), ("GI", 23), ("GL", 18), ("GR", 27),
+    ("GT", 28), ("HR", 21), ("HU", 28), ("IE", 22), ("IL", 23), ("IQ", 23), ("IS", 26), ("IT", 27),
+    ("JO", 30), ("KW", 30), ("KZ", 20), ("LB", 28), ("LC", 32), ("LI", 21), ("LT", 20), ("LU", 20),
+    ("LV", 21), ("LY", 25), ("MC", 27), ("MD", 24), ("ME", 22), ("MK", 19), ("MN", 20), ("MR", 27),
+    ("MT", 31), ("MU", 30), ("NI", 28), ("NL", 18), ("NO", 15), ("OM", 23), ("PK", 24), ("PL", 28),
+    ("PS", 29), ("QA", 29), ("RO", 24), ("RS", 22), ("RU", 33), ("SA", 24), ("SC", 31), ("SD", 18),
+    ("SE", 24), ("SI", 19), ("SK", 24), ("SM", 27), ("SO", 23), ("ST", 25), ("SV", 28), ("TL", 23),
+    ("TN", 24), ("TR", 26), ("UA", 29), ("VA", 22), ("VG", 24), ("XK", 20), ("YE", 30),
+];
+
+// Validate an IBAN given in electronic or space-separated print format
+pub fn iban(input: &str) -> Result<IbanInfo, String> {
+    let iban: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if iban.is_empty() {
+        return Err("IBAN is required".to_string());
+    }
+    if !iban.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err("IBAN may only contain letters, digits and spaces".to_string());
+    }
+    let bytes = iban.as_bytes();
+    if iban.len() < 5 || !bytes[..2].iter().all(u8::is_ascii_alphabetic) || !bytes[2..4].iter().all(u8::is_ascii_digit) {
+        return Err("IBAN must start with a country code and two check digits".to_string());
+    }
+
+    let country = &iban[..2];
+    let formatted = iban
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(IbanInfo {
+        formatted,
+        country: country.to_string(),
+        check_digits: iban[2..4].to_string(),
+        bban: iban[4..].to_string(),
+        length: iban.len(),
+        expected_length: IBAN_LENGTHS.iter().find(|(code, _)| *code == country).map(|(_, length)| *length),
+        checksum_valid: iban_mod97(&iban) == 1,
+    })
+}
+
+// ISO 7064 mod 97-10: move the first four characters to the end, map letters
+// to 10..35 and reduce digit by digit to avoid big integer arithmetic
+fn iban_mod97(iban: &str) -> u32 {
+    let rearranged = iban[4..].bytes().chain(iban[..4].bytes());
+    rearranged.fold(0, |remainder, b| {
+        if b.is_ascii_digit() {
+            (remainder * 10 + (b - b'0') as u32) % 97
+        } else {
+            (remainder * 100 + (b - b'A') as u32 + 10) % 97
+        }
+    })
+}
+
+pub struct IsbnInfo {
+    pub format: &'static str,
+    pub checksum_valid: bool,
+    pub isbn10: Option<String>,
+    pub isbn13: Option<String>,
+}
+
+// Validate an ISBN-10 or ISBN-13 (hyphens and spaces allowed) and convert it
+// to the other form where one exists
+pub fn isbn(input: &str) -> Result<IsbnInfo, String> {
+    let isbn: String = input
+        .chars()
+        .filter(|c| *c != '-' && *c != ' ')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if isbn.is_empty() {
+        return Err("ISBN is required".to_string());
+    }
+
+    match isbn.len() {
+        10 => {
+            let body_ok = isbn[..9].bytes().all(|b| b.is_ascii_digit());
+            let last = isbn.as_bytes()[9];
+            if !body_ok || !(last.is_ascii_digit() || last == b'X') {
+                return Err("ISBN-10 must be 9 digits followed by a digit or X".to_string());
+            }
+            let checksum_valid = isbn10_check_digit(&isbn[..9]) == last as char;
+            let isbn13 = format!("978{}", &isbn[..9]);
+            let isbn13 = format!("{}{}", isbn13, isbn13_check_digit(&isbn13));
+            Ok(IsbnInfo {
+                format: "isbn10",
+                checksum_valid,
+                isbn13: checksum_valid.then_some(isbn13),
+                isbn10: Some(isbn),
+            })
+        }
+        13 => {
+            if !isbn.bytes().all(|b| b.is_ascii_digit()) {
+                return Err("ISBN-13 must contain only digits".to_string());
+            }
+            let checksum_valid = isbn13_check_digit(&isbn[..12]) == isbn.as_bytes()[12] as char;
+            // Only the 978 prefix has ISBN-10 equivalents
+            let isbn10 = (checksum_valid && isbn.starts_with("978"))
+                .then(|| format!("{}{}", &isbn[3..12], isbn10_check_digit(&isbn[3..12])));
+            Ok(IsbnInfo {
+                format: "isbn13",
+                checksum_valid,
+                isbn10,
+                isbn13: Some(isbn),
+            })
+        }
+        _ => Err("ISBN must have 10 or 13 characters".to_string()),
+    }
+}
+
+// Weights 10 down to 2 over the first nine digits, mod 11 (10 is written X)
+fn isbn10_check_digit(digits: &str) -> char {
+    let sum: u32 = digits
+        .bytes()
+        .enumerate()
+        .map(|(i, b)| (10 - i as u32) * (b - b'0') as u32)
+        .sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        check => (b'0' + check as u8) as char,
+    }
+}
+
+// Alternating weights 1 and 3 over the first twelve digits, mod 10
+fn isbn13_check_digit(digits: &str) -> char {
+    let sum: u32 = digits
+        .bytes()
+        .enumerate()
+        .map(|(i, b)| if i % 2 == 0 { 1 } else { 3 } * (b - b'0') as u32)
+        .sum();
+    (b'0' + ((10 - sum % 10) % 10) as u8) as char
+}
+
+pub struct EmailInfo {
+    pub local_part: String,
+    pub domain: String,
+}
+
+// Characters allowed unquoted in the local part (RFC 5322 atext)
+const ATEXT_SPECIALS: &str = "!#$%&'*+/=?^_`{|}~-";
+
+// RFC 5321/5322 flavored address syntax check: dot-atom or quoted local part,
+// hostname or address literal domain, and the SMTP length limits
+pub fn email(address: &str) -> Result<EmailInfo, String> {
+    if address.is_empty() {
+        return Err("Email address is empty".to_string());
+    }
+    if address.len() > 254 {
+        return Err("Email address is longer than 254 characters".to_string());
+    }
+    let (local, domain) = match address.rsplit_once('@') {
+        Some(parts) => parts,
+        None => return Err("Missing '@' separator".to_string()),
+    };
+    if local.is_empty() {
+        return Err("Local part is empty".to_string());
+    }
+    if local.len() > 64 {
+        return Err("Local part is longer than 64 characters".to_string());
+    }
+
+    if local.starts_with('"') {
+        let inner = local
+            .strip_prefix('"')
+            .and_then(|local| local.strip_suffix('"'))
+            .filter(|inner| !inner.is_empty() || local.len() == 2)
+            .ok_or("Unterminated quoted local part")?;
+        let mut escaped = false;
+        for c in inner.chars() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => return Err("Unescaped quote inside quoted local part".to_string()),
+                c if c.is_control() => return Err("Control character in quoted local part".to_string()),
+                _ => {}
+            }
+        }
+        if escaped {
+            return Err("Quoted local part ends with a backslash".to_string());
+        }
+    } else {
+        if local.starts_with('.') || local.ends_with('.') {
+            return Err("Local part cannot start or end with a dot".to_string());
+        }
+        if local.contains("..") {
+            return Err("Local part cannot contain consecutive dots".to_string());
+        }
+        if let Some(c) = local
+            .chars()
+            .find(|&c| c.is_ascii() && !c.is_ascii_alphanumeric() && c != '.' && !ATEXT_SPECIALS.contains(c))
+        {
+            return Err(format!("Invalid character {:?} in local part (quote it to use it)", c));
+        }
+    }
+
+    if let Some(literal) = domain.strip_prefix('[').and_then(|domain| domain.strip_suffix(']')) {
+        let valid = match literal.strip_prefix("IPv6:") {
+            Some(ipv6) => ipv6.parse::<core::net::Ipv6Addr>().is_ok(),
+            None => url::is_ipv4(literal),
+        };
+        if !valid {
+            return Err(format!("Invalid address literal '{}'", domain));
+        }
+    } else {
+        url::validate_domain(domain)?;
+        if !domain.contains('.') {
+            return Err("Domain must contain at least one dot".to_string());
+        }
+        let tld = domain.trim_end_matches('.').rsplit('.').next().unwrap_or_default();
+        if tld.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Top-level domain '{}' cannot be numeric", tld));
+        }
+    }
+
+    Ok(EmailInfo {
+        local_part: local.to_string(),
+        domain: domain.to_ascii_lowercase(),
+    })
+}
+
+pub struct PhoneInfo {
+    pub region: &'static str,
+    pub calling_code: &'static str,
+    pub national_number: String,
+    pub e164: String,
+    pub valid: bool,
+    pub reason: Option<String>,
+}
+
+struct PhoneRegion {
+    region: &'static str,
+    calling_code: &'static str,
+    // Inclusive range of national significant number lengths
+    lengths: (usize, usize),
+    // Prefix dialled before national numbers inside the country, dropped in E.164
+    trunk_prefix: &'static str,
+}
+
+// Regions sharing a calling code are listed with the default region first
+const PHONE_REGIONS: [PhoneRegion; 48] = [
+    PhoneRegion { region: "US", calling_code: "1", lengths: (10, 10), trunk_prefix: "1" },
+    PhoneRegion { region: "CA", calling_code: "1", lengths: (10, 10), trunk_prefix: "1" },
+    PhoneRegion { region: "RU", calling_code: "7", lengths: (10, 10), trunk_prefix: "8" },
+    PhoneRegion { region: "KZ", calling_code: "7", lengths: (10, 10), trunk_prefix: "8" },
+    PhoneRegion { region: "EG", calling_code: "20", lengths: (9, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "ZA", calling_code: "27", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "GR", calling_code: "30", lengths: (10, 10), trunk_prefix: "" },
+    PhoneRegion { region: "NL", calling_code: "31", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "BE", calling_code: "32", lengths: (8, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "FR", calling_code: "33", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "ES", calling_code: "34", lengths: (9, 9), trunk_prefix: "" },
+    PhoneRegion { region: "HU", calling_code: "36", lengths: (8, 9), trunk_prefix: "06" },
+    // Italian numbers keep their leading zero internationally
+    PhoneRegion { region: "IT", calling_code: "39", lengths: (6, 11), trunk_prefix: "" },
+    PhoneRegion { region: "RO", calling_code: "40", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "CH", calling_code: "41", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "AT", calling_code: "43", lengths: (4, 13), trunk_prefix: "0" },
+    PhoneRegion { region: "GB", calling_code: "44", lengths: (9, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "DK", calling_code: "45", lengths: (8, 8), trunk_prefix: "" },
+    PhoneRegion { region: "SE", calling_code: "46", lengths: (7, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "NO", calling_code: "47", lengths: (8, 8), trunk_prefix: "" },
+    PhoneRegion { region: "PL", calling_code: "48", lengths: (9, 9), trunk_prefix: "" },
+    PhoneRegion { region: "DE", calling_code: "49", lengths: (6, 13), trunk_prefix: "0" },
+    PhoneRegion { region: "MX", calling_code: "52", lengths: (10, 10), trunk_prefix: "" },
+    PhoneRegion { region: "AR", calling_code: "54", lengths: (10, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "BR", calling_code: "55", lengths: (10, 11), trunk_prefix: "0" },
+    PhoneRegion { region: "AU", calling_code: "61", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "ID", calling_code: "62", lengths: (9, 12), trunk_prefix: "0" },
+    PhoneRegion { region: "NZ", calling_code: "64", lengths: (8, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "SG", calling_code: "65", lengths: (8, 8), trunk_prefix: "" },
+    PhoneRegion { region: "JP", calling_code: "81", lengths: (9, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "KR", calling_code: "82", lengths: (8, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "CN", calling_code: "86", lengths: (10, 11), trunk_prefix: "0" },
+    PhoneRegion { region: "TR", calling_code: "90", lengths: (10, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "IN", calling_code: "91", lengths: (10, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "NG", calling_code: "234", lengths: (8, 10), trunk_prefix: "0" },
+    PhoneRegion { region: "PT", calling_code: "351", lengths: (9, 9), trunk_prefix: "" },
+    PhoneRegion { region: "IE", calling_code: "353", lengths: (7, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "FI", calling_code: "358", lengths: (5, 12), trunk_prefix: "0" },
+    PhoneRegion { region: "LT", calling_code: "370", lengths: (8, 8), trunk_prefix: "8" },
+    PhoneRegion { region: "LV", calling_code: "371", lengths: (8, 8), trunk_prefix: "" },
+    PhoneRegion { region: "EE", calling_code: "372", lengths: (7, 8), trunk_prefix: "" },
+    PhoneRegion { region: "UA", calling_code: "380", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "CZ", calling_code: "420", lengths: (9, 9), trunk_prefix: "" },
+    PhoneRegion { region: "SK", calling_code: "421", lengths: (9, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "HK", calling_code: "852", lengths: (8, 8), trunk_prefix: "" },
+    PhoneRegion { region: "IL", calling_code: "972", lengths: (8, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "AE", calling_code: "971", lengths: (8, 9), trunk_prefix: "0" },
+    PhoneRegion { region: "LU", calling_code: "352", lengths: (4, 11), trunk_prefix: "" },
+];
+
+// Normalize a phone number to E.164. International input ("+48 ...",
+// "0048 ...") is resolved by calling code; national input needs a region.
+pub fn phone(number: &str, region: Option<&str>) -> Result<PhoneInfo, String> {
+    let trimmed = number.trim();
+    if trimmed.is_empty() {
+        return Err("Phone number is required".to_string());
+    }
+    let cleaned: String = trimmed
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')' | '/'))
+        .collect();
+    let (international, digits) = match cleaned.strip_prefix('+') {
+        Some(digits) => (true, digits),
+        None => match cleaned.strip_prefix("00") {
+            Some(digits) => (true, digits),
+            None => (false, cleaned.as_str()),
+        },
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("Phone number may only contain digits, spaces, dashes, dots, parentheses and a leading +".to_string());
+    }
+
+    let requested = match region {
+        Some(code) => {
+            let code = code.to_ascii_uppercase();
+            match PHONE_REGIONS.iter().find(|entry| entry.region == code) {
+                Some(entry) => Some(entry),
+                None => return Err(format!("Unsupported region '{}'", code)),
+            }
+        }
+        None => None,
+    };
+
+    let (entry, national) = if international {
+        // Calling codes are prefix-free, so at most one length can match
+        let code = (1..=3.min(digits.len()))
+            .map(|len| &digits[..len])
+            .find(|code| PHONE_REGIONS.iter().any(|entry| entry.calling_code == *code))
+            .ok_or("Unknown country calling code")?;
+        // Prefer the requested region when it shares the calling code
+        let entry = requested
+            .filter(|entry| entry.calling_code == code)
+            .or_else(|| PHONE_REGIONS.iter().find(|entry| entry.calling_code == code))
+            .ok_or("Unknown country calling code")?;
+        (entry, &digits[code.len()..])
+    } else {
+        let entry = requested.ok_or("A region is required for numbers without a country calling code")?;
+        let national = if entry.trunk_prefix.is_empty() {
+            digits
+        } else {
+            digits.strip_prefix(entry.trunk_prefix).unwrap_or(digits)
+        };
+        (entry, national)
+    };
+
+    let (min, max) = entry.lengths;
+    let reason = if national.len() < min {
+        Some(format!("National number is too short for {} (expected {}-{} digits)", entry.region, min, max))
+    } else if national.len() > max {
+        Some(format!("National number is too long for {} (expected {}-{} digits)", entry.region, min, max))
+    } else if requested.is_some_and(|requested| requested.calling_code != entry.calling_code) {
+        Some(format!("Calling code +{} does not belong to the requested region", entry.calling_code))
+    } else {
+        None
+    };
+
+    Ok(PhoneInfo {
+        region: entry.region,
+        calling_code: entry.calling_code,
+        national_number: national.to_string(),
+        e164: format!("+{}{}", entry.calling_code, national),
+        valid: reason.is_none(),
+        reason,
+    })
+}
@@ -0,0 +1,48 @@
+// Eager construction of tables that requests otherwise build on first use
+// A fresh instance pays for the RSA trial division sieve, the language
+// profiles, the built-in autocomplete dictionary and the minified home page
+// inside whichever request needs them first. The host calls warmup once
+// after instantiating the module so that cost lands before any request is
+// handled. The CRC-32 table needs no warming: it is computed at compile time.
+
+use crate::ffi;
+use crate::host;
+use crate::langdetect;
+use crate::rsa;
+use crate::trie;
+use crate::worker;
+use std::cell::Cell;
+
+thread_local! {
+    // How long the last warmup call took
+    static WARMUP_MS: Cell<Option<f64>> = const { Cell::new(None) };
+}
+
+// {"warmed_up":bool,"elapsed_ms":n|null,"tables":{...}}, where each table
+// reports whether it is built, whether by warmup or by an earlier request
+pub fn status_json() -> String {
+    let elapsed = WARMUP_MS.with(Cell::get);
+    format!(
+        r#"{{"warmed_up":{},"elapsed_ms":{},"tables":{{"rsa_small_primes":{},"language_profiles":{},"dictionary":{},"home_page":{},"crc32":true}}}}"#,
+        elapsed.is_some(),
+        elapsed.map_or("null".to_string(), |elapsed| elapsed.to_string()),
+        rsa::is_warm(),
+        langdetect::is_warm(),
+        trie::is_loaded(),
+        worker::home_page_is_warm()
+    )
+}
+
+// Builds every lazy table and returns status_json as an ffi output; safe to
+// call more than once. A dictionary from init_dictionary is kept, so call
+// that first.
+#[no_mangle]
+pub extern "C" fn warmup() -> *mut u8 {
+    let started = host::now_ms();
+    rsa::warm();
+    langdetect::warm();
+    trie::with_dictionary(|_| ());
+    worker::warm_home_page();
+    WARMUP_MS.with(|elapsed| elapsed.set(Some(host::now_ms() - started)));
+    ffi::output(status_json().as_bytes())
+}
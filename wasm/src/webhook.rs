@@ -0,0 +1,109 @@
+// Webhook signature checks for GitHub, Stripe and Slack
+// Each provider signs an HMAC-SHA256 over the raw body (Stripe and Slack
+// prefix it with a timestamp) and sends the hex MAC in a header. Stripe and
+// Slack signatures also expire so captured requests cannot be replayed later.
+
+use crate::hashing::{self, HmacSha256};
+use crate::headers::Headers;
+
+pub const PROVIDERS: [&str; 3] = ["github", "stripe", "slack"];
+
+// Both Stripe and Slack recommend five minutes
+pub const TOLERANCE_SECONDS: u64 = 300;
+
+pub struct Verification {
+    pub header: &'static str,
+    pub signature_matches: bool,
+    // Set for providers that sign a timestamp
+    pub timestamp: Option<u64>,
+    pub age_seconds: Option<i64>,
+}
+
+impl Verification {
+    pub fn within_tolerance(&self) -> bool {
+        self.age_seconds.is_none_or(|age| age.unsigned_abs() <= TOLERANCE_SECONDS)
+    }
+
+    pub fn valid(&self) -> bool {
+        self.signature_matches && self.within_tolerance()
+    }
+}
+
+// Err for requests that are missing the headers the provider always sends
+pub fn verify(provider: &str, secret: &str, headers: &Headers, body: &[u8], now_seconds: u64) -> Result<Verification, String> {
+    match provider {
+        "github" => {
+            let header = "x-hub-signature-256";
+            let signature = required(headers, header)?
+                .strip_prefix("sha256=")
+                .ok_or_else(|| format!("{} must start with sha256=", header))?;
+            Ok(Verification {
+                header,
+                signature_matches: matches_any(secret, &[body], &[signature]),
+                timestamp: None,
+                age_seconds: None,
+            })
+        }
+        "stripe" => {
+            // t=<unix seconds>,v1=<hex>[,v1=<hex>...] while secrets are rolled
+            let header = "stripe-signature";
+            let value = required(headers, header)?;
+            let mut timestamp = None;
+            let mut signatures = Vec::new();
+            for (key, value) in value.split(',').filter_map(|part| part.trim().split_once('=')) {
+                match key {
+                    "t" => timestamp = Some(value),
+                    "v1" => signatures.push(value),
+                    _ => {}
+                }
+            }
+            let timestamp = timestamp.ok_or_else(|| format!("{} has no t= timestamp", header))?;
+            let seconds = parse_timestamp(timestamp)?;
+            if signatures.is_empty() {
+                return Err(format!("{} has no v1= signature", header));
+            }
+            Ok(Verification {
+                header,
+                signature_matches: matches_any(secret, &[timestamp.as_bytes(), b".", body], &signatures),
+                timestamp: Some(seconds),
+                age_seconds: Some(now_seconds as i64 - seconds as i64),
+            })
+        }
+        "slack" => {
+            let header = "x-slack-signature";
+            let signature = required(headers, header)?
+                .strip_prefix("v0=")
+                .ok_or_else(|| format!("{} must start with v0=", header))?;
+            let timestamp = required(headers, "x-slack-request-timestamp")?;
+            let seconds = parse_timestamp(timestamp)?;
+            Ok(Verification {
+                header,
+                signature_matches: matches_any(secret, &[b"v0:", timestamp.as_bytes(), b":", body], &[signature]),
+                timestamp: Some(seconds),
+                age_seconds: Some(now_seconds as i64 - seconds as i64),
+            })
+        }
+        _ => Err(format!("Unknown provider '{}'", provider)),
+    }
+}
+
+fn required<'a>(headers: &'a Headers, name: &str) -> Result<&'a str, String> {
+    headers.get(name).map(str::trim).ok_or_else(|| format!("Missing {} header", name))
+}
+
+fn parse_timestamp(value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("Invalid timestamp '{}'", value))
+}
+
+// Malformed hex simply fails to match
+fn matches_any(secret: &str, payload: &[&[u8]], signatures: &[&str]) -> bool {
+    let mut hmac = HmacSha256::new(secret.as_bytes());
+    for part in payload {
+        hmac.update(part);
+    }
+    let expected = hmac.finalize();
+    signatures
+        .iter()
+        .filter_map(|signature| hashing::from_hex(signature).ok())
+        .any(|signature| hashing::constant_time_eq(&signature, &expected))
+}
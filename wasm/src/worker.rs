@@ -0,0 +1,4251 @@
+// Request routing for the Cloudflare Worker
+// handle_request and everything it dispatches to, along with the pages and
+// JSON shapes the endpoints return. Handlers take the RequestContext that
+// handle_request builds for each request (see context.rs). This is the part
+// of the crate that needs std: per-isolate state, host imports and the
+// allocation registry.
+
+use crate::config::Config;
+use crate::context::RequestContext;
+use crate::params::QueryParams;
+use crate::{allocator, arena, assets, banner, bencode, bignum, blob, breaker, budget, cache_policy, captcha, charts, ciphers, combinatorics, compression, config, constants, context, counters, dates, dice, endpoints, experiments, feeds, fetch, ffi, fft, flags, formatting, generate, graph, graphql, grpc, hashing, hashring, headers, host, html, html_rewrite, humanize, i18n, idempotency, ids, jpeg, json, jsonld, kv, kmeans, langdetect, life, maze, merkle, mime, minify, morse, multipart, oauth, params, paste, png, pow, protobuf, rand, ranges, ratelimit, regression, response_cache, robots, routing, rsa, search, security, semver, sentiment, shortener, similarity, simd, snowflake, sorting, stemming, sudoku, tar, text, timing, trie, url, validators, warmup, webhook, zip};
+use crate::{add, factorial, fibonacci, is_prime};
+use std::cell::OnceCell;
+
+// Rejects oversized requests before any handler parses them. The host skips
+// copying bodies over max_body_bytes and passes a null pointer with the real
+// length, and truncates queries just past max_query_length, so neither has
+// to fit in WASM memory to be refused.
+fn request_limit_error(config: &Config, query_len: usize, body_len: usize) -> Option<String> {
+    if body_len > config.max_body_bytes {
+        return Some(create_error_response(
+            413,
+            &format!("Request body is larger than {} bytes", config.max_body_bytes),
+        ));
+    }
+    if query_len > config.max_query_length {
+        return Some(create_error_response(
+            414,
+            &format!("Query string is longer than {} bytes", config.max_query_length),
+        ));
+    }
+    None
+}
+
+// Main request handler that processes HTTP requests. Every part of the
+// request arrives as a (pointer, length) pair, normally in buffers from
+// arena_alloc; the response is an arena buffer in the ffi layout holding
+// "status|content-type|body". Everything the request allocates comes from
+// the arena too, and the host calls end_request once it has copied the
+// response out, which releases all of it together.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn handle_request(
+    method_ptr: *const u8,
+    method_len: usize,
+    path_ptr: *const u8,
+    path_len: usize,
+    query_ptr: *const u8,
+    query_len: usize,
+    headers_ptr: *const u8,
+    headers_len: usize,
+    body_ptr: *const u8,
+    body_len: usize,
+) -> *mut u8 {
+    arena::during_request(|| {
+        serve(method_ptr, method_len, path_ptr, path_len, query_ptr, query_len, headers_ptr, headers_len, body_ptr, body_len)
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn serve(
+    method_ptr: *const u8,
+    method_len: usize,
+    path_ptr: *const u8,
+    path_len: usize,
+    query_ptr: *const u8,
+    query_len: usize,
+    headers_ptr: *const u8,
+    headers_len: usize,
+    body_ptr: *const u8,
+    body_len: usize,
+) -> *mut u8 {
+    timing::begin();
+    let (method, path, query, headers, body) = unsafe {
+        (
+            ffi::text(method_ptr, method_len),
+            ffi::text(path_ptr, path_len),
+            ffi::text(query_ptr, query_len),
+            ffi::text(headers_ptr, headers_len),
+            ffi::bytes(body_ptr, body_len),
+        )
+    };
+    // The host encodes everything but the body from JavaScript strings, so
+    // these only fail for a misbehaving caller
+    let (method, path, query, headers) = match (method, path, query, headers) {
+        (Ok(method), Ok(path), Ok(query), Ok(headers)) => (method, path, query, headers::Headers::parse(headers)),
+        (method, path, query, _) => {
+            context::begin(None);
+            let part = match () {
+                _ if method.is_err() => "method",
+                _ if path.is_err() => "path",
+                _ if query.is_err() => "query string",
+                _ => "headers",
+            };
+            let response = create_error_response(400, &format!("Request {} is not valid UTF-8", part));
+            let id = context::request_id();
+            return arena_response(&response, &[("X-Request-Id", id.as_deref())]);
+        }
+    };
+    context::begin(Some(&headers));
+    let wants_html = prefers_html(&headers);
+    // The one copy of the config the whole request works from
+    let config = config::current();
+
+    let params = match request_limit_error(&config, query.len(), body_len) {
+        Some(error) => Err(error),
+        None => QueryParams::parse(query).map_err(|reason| create_error_response(400, &reason)),
+    };
+    timing::mark(timing::Span::Parse);
+    let language = i18n::negotiate(params.as_ref().ok().and_then(|params| params.get("lang")), headers.get("accept-language"));
+
+    let trailing_slash = config.trailing_slash;
+    let normalized = route_path(path, trailing_slash);
+    let routed = canonical_route(&normalized, config.case_insensitive_routes);
+    counters::record(&routed);
+
+    budget::reset(config.compute_budget);
+    let response = match params {
+        Err(error) => error,
+        // Redirects only where a route would answer; other methods are routed
+        // as they are, since clients may not repeat their body after a 301
+        Ok(_) if normalized != path
+            && trailing_slash == config::TrailingSlash::Redirect
+            && matches!(method, "GET" | "HEAD")
+            && route_exists(&routed) =>
+        {
+            let location = if query.is_empty() { normalized.clone() } else { format!("{}?{}", normalized, query) };
+            create_redirect_response(301, &location)
+        }
+        Ok(params) => {
+            let mut ctx = RequestContext::new(method, routed.clone(), params, headers, body, &config);
+            let response = dispatch(&mut ctx);
+            if ctx.past_deadline() {
+                ctx.log(&format!("{} {} ran past its {} ms deadline", method, path, ctx.config.request_deadline_ms));
+            }
+            response
+        }
+    };
+    timing::mark(timing::Span::Handler);
+    let replayed = kv::take_pending();
+    if replayed {
+        counters::unrecord(&routed);
+    }
+    breaker::finish(replayed, &config);
+    let response = if budget::exhausted() { budget_exhausted_response() } else { response };
+    let response = negotiate_error(response, wants_html, language);
+    let response = security::apply(response, &config.security_headers);
+    timing::mark(timing::Span::Serialize);
+    let timings = timing::current();
+    // A pass the host replays is only timed once it completes
+    if !replayed {
+        timing::record(&routed, timings, &config);
+    }
+    let id = context::request_id();
+    // X-Canonical-Path lets logs and analytics group aliases and variants
+    // with their route
+    let canonical = (routed != path && route_exists(&routed)).then_some(routed.as_str());
+    arena_response(
+        &response,
+        &[("Server-Timing", Some(timings.header().as_str())), ("X-Request-Id", id.as_deref()), ("X-Canonical-Path", canonical)],
+    )
+}
+
+// Writes the response into an arena buffer in the ffi layout, adding the
+// headers that have a value the way with_header does, without another full
+// copy of the body
+fn arena_response(response: &str, headers: &[(&str, Option<&str>)]) -> *mut u8 {
+    let mut parts = Vec::new();
+    match response.split_once('|').and_then(|(status, rest)| Some((status, rest.split_once('|')?))) {
+        Some((status, (header_block, body))) => {
+            parts.extend([status, "|", header_block]);
+            for (name, value) in headers {
+                if let Some(value) = value {
+                    parts.extend(["\n", name, ": ", value]);
+                }
+            }
+            parts.extend(["|", body]);
+        }
+        None => parts.push(response),
+    }
+    let len: usize = parts.iter().map(|part| part.len()).sum();
+    let buffer = arena::alloc(len + 4);
+    unsafe {
+        std::ptr::copy_nonoverlapping((len as u32).to_le_bytes().as_ptr(), buffer, 4);
+        let mut offset = 4;
+        for part in parts {
+            std::ptr::copy_nonoverlapping(part.as_ptr(), buffer.add(offset), part.len());
+            offset += part.len();
+        }
+    }
+    buffer
+}
+
+type Handler = fn(&RequestContext) -> String;
+
+// The path the router matches: repeated slashes and dot-segments collapsed,
+// and a trailing slash dropped unless trailing_slash is strict
+fn route_path(path: &str, trailing_slash: config::TrailingSlash) -> String {
+    let mut normalized = url::normalize_path(path);
+    if trailing_slash != config::TrailingSlash::Strict && normalized.len() > 1 && normalized.ends_with('/') {
+        normalized.pop();
+    }
+    normalized
+}
+
+// Alternative names for routes, short ones and Polish ones. Targets must be
+// routes that exist.
+const ROUTE_ALIASES: [(&str, &str); 7] = [
+    ("/fib", "/fibonacci"),
+    ("/health", "/status"),
+    ("/uptime", "/counter"),
+    ("/dodaj", "/add"),
+    ("/silnia", "/factorial"),
+    ("/pierwsza", "/prime"),
+    ("/losuj", "/random"),
+];
+
+fn route_exists(path: &str) -> bool {
+    get_route(path).is_some() || post_route(path).is_some()
+}
+
+// Resolves aliases and, when case_insensitive_routes is set, letter case in
+// the route part of the path. Short codes and paste IDs after /s/ and
+// /paste/ keep their case, as they are case-sensitive.
+fn canonical_route(path: &str, case_insensitive: bool) -> String {
+    if route_exists(path) {
+        return path.to_string();
+    }
+    let folded = if case_insensitive {
+        let lower = path.to_ascii_lowercase();
+        match ["/s/", "/paste/", "/grpc/"].iter().find(|prefix| lower.starts_with(*prefix)) {
+            Some(prefix) => format!("{}{}", prefix, &path[prefix.len()..]),
+            None => lower,
+        }
+    } else {
+        path.to_string()
+    };
+    match ROUTE_ALIASES.iter().find(|(alias, _)| *alias == folded) {
+        Some((_, target)) => target.to_string(),
+        None if route_exists(&folded) => folded,
+        None => path.to_string(),
+    }
+}
+
+// The handler for a GET path, which also serves HEAD
+fn get_route(path: &str) -> Option<Handler> {
+    Some(match path {
+        "/" => |ctx| handle_home_page(ctx),
+        "/status" => |_| create_json_response(&get_status_json()),
+        "/counter" => |_| handle_counter_request(),
+        "/metrics" => |_| handle_metrics_request(),
+        "/robots.txt" => |ctx| create_asset_response("text/plain; charset=utf-8", &ctx.config.robots_txt),
+        "/favicon.ico" => |ctx| create_asset_response("image/svg+xml", &ctx.config.favicon_svg),
+        "/.well-known/security.txt" => handle_security_txt_request,
+        "/sitemap.xml" => handle_sitemap_request,
+        "/add" => |ctx| cached(ctx, || handle_add_request(ctx)),
+        "/factorial" => |ctx| cached(ctx, || handle_factorial_request(ctx)),
+        "/prime" => |ctx| cached(ctx, || handle_prime_request(ctx)),
+        "/fibonacci" => |ctx| cached(ctx, || handle_fibonacci_request(ctx)),
+        "/hash" => |ctx| cached(ctx, || handle_hash_request(ctx)),
+        "/generate" => handle_generate_request,
+        "/feed" => handle_feed_request,
+        "/random" => handle_random_request,
+        "/roll" => handle_roll_request,
+        "/validate/card" => |ctx| cached(ctx, || handle_validate_card_request(ctx)),
+        "/validate/iban" => |ctx| cached(ctx, || handle_validate_iban_request(ctx)),
+        "/validate/isbn" => |ctx| cached(ctx, || handle_validate_isbn_request(ctx)),
+        "/validate/email" => |ctx| cached(ctx, || handle_validate_email_request(ctx)),
+        "/validate/url" => |ctx| cached(ctx, || handle_validate_url_request(ctx)),
+        "/validate/phone" => |ctx| cached(ctx, || handle_validate_phone_request(ctx)),
+        "/semver/parse" => |ctx| cached(ctx, || handle_semver_parse_request(ctx)),
+        "/semver/satisfies" => |ctx| cached(ctx, || handle_semver_satisfies_request(ctx)),
+        "/autocomplete" => handle_autocomplete_request,
+        "/search/query" => handle_search_query_request,
+        "/sort" => handle_sort_request,
+        "/bench" => handle_bench_request,
+        "/sudoku/solve" => |ctx| cached(ctx, || handle_sudoku_solve_request(ctx)),
+        "/sudoku/generate" => handle_sudoku_generate_request,
+        "/life" => |ctx| handle_life_request(ctx, None),
+        "/graphql" => |ctx| handle_graphql_request(ctx.params.get("query")),
+        "/maze" => handle_maze_request,
+        "/chart" => |ctx| cached(ctx, || handle_chart_request(ctx)),
+        "/chart.svg" => handle_chart_svg_request,
+        "/banner" => |ctx| cached(ctx, || handle_banner_request(ctx)),
+        "/sentiment" => |ctx| cached(ctx, || handle_sentiment_request(ctx)),
+        "/language" => |ctx| cached(ctx, || handle_language_request(ctx)),
+        "/text/stem" => |ctx| cached(ctx, || handle_text_stem_request(ctx)),
+        "/text/plural" => |ctx| cached(ctx, || handle_text_plural_request(ctx)),
+        "/text/translit" => |ctx| cached(ctx, || handle_text_translit_request(ctx)),
+        "/text/analyze" => |ctx| cached(ctx, || handle_text_analyze_request(ctx)),
+        "/phonetic" => |ctx| cached(ctx, || handle_phonetic_request(ctx)),
+        "/format/number" => |ctx| cached(ctx, || handle_format_number_request(ctx)),
+        "/format/bytes" => |ctx| cached(ctx, || handle_format_bytes_request(ctx)),
+        "/format/duration" => |ctx| cached(ctx, || handle_format_duration_request(ctx)),
+        "/cipher" => |ctx| cached(ctx, || handle_cipher_request(ctx)),
+        "/cipher/xor" => handle_cipher_xor_request,
+        "/morse" => |ctx| cached(ctx, || handle_morse_request(ctx)),
+        "/rsa/demo" => handle_rsa_demo_request,
+        "/ulid" => handle_ulid_request,
+        "/nanoid" => handle_nanoid_request,
+        "/id/snowflake" => handle_snowflake_request,
+        "/fetch" => handle_fetch_request,
+        "/rewrite" => handle_rewrite_request,
+        "/ab" => handle_ab_request,
+        "/flags/evaluate" => handle_flags_evaluate_request,
+        "/ring/locate" => handle_ring_locate_request,
+        "/route/decide" => handle_route_decide_request,
+        "/oauth/pkce" => handle_pkce_request,
+        "/oauth/state" => handle_oauth_state_request,
+        "/captcha/new" => handle_captcha_new_request,
+        "/captcha/verify" => handle_captcha_verify_request,
+        "/pow/challenge" => handle_pow_challenge_request,
+        "/pow/verify" => handle_pow_verify_request,
+        "/ratelimit/test" => handle_ratelimit_test_request,
+        "/nqueens" => |ctx| cached(ctx, || handle_nqueens_request(ctx)),
+        "/combinatorics" => |ctx| cached(ctx, || handle_combinatorics_request(ctx)),
+        "/pi" => |ctx| cached(ctx, || handle_constant_request(ctx, "pi")),
+        "/e" => |ctx| cached(ctx, || handle_constant_request(ctx, "e")),
+        _ if path.starts_with("/s/") => |ctx| handle_short_link_request(ctx, &ctx.path[3..]),
+        _ if path.starts_with("/paste/") => |ctx| handle_paste_view_request(ctx, &ctx.path[7..]),
+        _ if path.starts_with("/blob/") => |ctx| handle_blob_view_request(ctx, &ctx.path[6..]),
+        _ => return None,
+    })
+}
+
+fn post_route(path: &str) -> Option<Handler> {
+    Some(match path {
+        "/minify" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(text) => handle_minify_request(ctx, text),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/robots/check" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(text) => handle_robots_check_request(ctx, text),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/jsonld/validate" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(page) => handle_jsonld_validate_request(page),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/cache/analyze" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(head) => handle_cache_analyze_request(ctx, head),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/links/extract" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(page) => handle_links_extract_request(ctx, page),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/meta/extract" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(page) => handle_meta_extract_request(page),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/rewrite" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(page) => handle_rewrite_post_request(ctx, page),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/detect" => handle_detect_request,
+        "/upload/inspect" => handle_upload_inspect_request,
+        "/checksum" => handle_checksum_request,
+        "/proto/decode" => handle_proto_decode_request,
+        "/parse/bencode" => handle_parse_bencode_request,
+        "/image/resize" => handle_image_resize_request,
+        "/image/strip" => handle_image_strip_request,
+        "/zip/list" => handle_zip_list_request,
+        "/zip/extract" => handle_zip_extract_request,
+        "/tar/create" => handle_tar_create_request,
+        "/merkle" => handle_merkle_request,
+        "/search/index" => handle_search_index_request,
+        "/graph/shortest" => handle_graph_shortest_request,
+        "/life" => |ctx| match std::str::from_utf8(ctx.body) {
+            Ok(text) => handle_life_request(ctx, Some(text)),
+            Err(_) => invalid_utf8_body_response(),
+        },
+        "/graphql" => |ctx| match graphql_post_query(ctx) {
+            Ok(query) => handle_graphql_request(Some(&query)),
+            Err(reason) => graphql_errors_response(400, &[reason]),
+        },
+        "/fft" => handle_fft_request,
+        "/fit" => handle_fit_request,
+        "/cluster" => handle_cluster_request,
+        "/chart.svg" => handle_chart_svg_request,
+        "/table" => handle_table_request,
+        "/cipher/xor" => handle_cipher_xor_request,
+        "/shorten" => handle_shorten_request,
+        "/paste" => handle_paste_request,
+        "/blob" => handle_blob_request,
+        "/webhook/verify" => handle_webhook_verify_request,
+        _ if path.starts_with("/grpc/") => |ctx| handle_grpc_request(ctx, &ctx.path[6..]),
+        _ => return None,
+    })
+}
+
+// Picks the handler for the method and path. Paths that exist answer other
+// methods with 405 and OPTIONS with 204, both listing the methods they take
+// in Allow.
+fn dispatch(ctx: &mut RequestContext) -> String {
+    timing::mark(timing::Span::Route);
+
+    if ctx.config.allow_method_override && ctx.method == "POST" {
+        if let Err(reason) = apply_method_override(ctx) {
+            return create_error_response(400, &reason);
+        }
+    }
+
+    let (get, post) = (get_route(&ctx.path), post_route(&ctx.path));
+    match (ctx.method, get, post) {
+        (_, None, None) => not_found_response(&ctx.path),
+        ("GET", Some(handler), _) => ranged(ctx, handler(ctx)),
+        ("HEAD", Some(handler), _) => without_body(handler(ctx)),
+        ("POST", _, Some(handler)) => idempotent(ctx, handler),
+        // Form submissions reach the regular handlers, with form fields taking
+        // precedence over query parameters of the same name
+        ("POST", Some(handler), None) if params::is_form(ctx.headers.get("content-type")) => {
+            let form = match std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(QueryParams::parse_form) {
+                Ok(form) => form,
+                Err(reason) => return create_error_response(400, &reason),
+            };
+            ctx.params.merge(form);
+            handler(ctx)
+        }
+        ("OPTIONS", get, post) => format!("204|text/plain\nAllow: {}|", allowed_methods(get.is_some(), post.is_some())),
+        (_, get, post) => with_header(
+            create_error_response(405, "Method Not Allowed"),
+            "Allow",
+            &allowed_methods(get.is_some(), post.is_some()),
+        ),
+    }
+}
+
+// HEAD is left out: the host decides whether to send a body from the method
+// the request really used
+const OVERRIDE_METHODS: [&str; 6] = ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+// For clients that can only send GET and POST: a POST may name the method it
+// stands for in X-HTTP-Method-Override or, in a form body, a _method field.
+// A form that becomes a GET carries its fields as parameters, the
+// same way form submissions to GET endpoints do.
+fn apply_method_override(ctx: &mut RequestContext) -> Result<(), String> {
+    let is_form = params::is_form(ctx.headers.get("content-type"));
+    let form = if is_form {
+        Some(std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(QueryParams::parse_form)?)
+    } else {
+        None
+    };
+    let requested = match (ctx.headers.get("x-http-method-override"), form.as_ref().and_then(|form| form.get("_method"))) {
+        (Some(method), _) | (None, Some(method)) => method.trim().to_ascii_uppercase(),
+        (None, None) => return Ok(()),
+    };
+    let Some(&method) = OVERRIDE_METHODS.iter().find(|&&method| method == requested) else {
+        return Err(format!("Cannot override POST with '{}', expected one of: {}", requested, OVERRIDE_METHODS.join(", ")));
+    };
+    ctx.method = method;
+    if let (Some(form), "GET") = (form, method) {
+        ctx.params.merge(form);
+    }
+    Ok(())
+}
+
+// Runs a POST handler, or replays its earlier response when the request
+// carries an Idempotency-Key already seen on this route (see idempotency.rs)
+fn idempotent(ctx: &RequestContext, handler: Handler) -> String {
+    let Some(key) = ctx.headers.get("idempotency-key") else {
+        return handler(ctx);
+    };
+    if !idempotency::is_valid_key(key) {
+        return create_error_response(
+            400,
+            &format!("Idempotency-Key must be 1 to {} printable ASCII characters", idempotency::MAX_KEY_LENGTH),
+        );
+    }
+    let fingerprint = idempotency::fingerprint(&ctx.params.canonical(), ctx.body);
+    match idempotency::lookup(&ctx.path, key, &fingerprint) {
+        Ok(idempotency::Lookup::New) => {}
+        Ok(idempotency::Lookup::Replay(response)) => return with_header(response, "Idempotent-Replay", "true"),
+        Ok(idempotency::Lookup::Mismatch) => {
+            return create_error_response(422, "Idempotency-Key was already used for a different request")
+        }
+        Err(kv::Pending) => return kv_pending_response(),
+    }
+    let response = handler(ctx);
+    if !response.starts_with('5') && !budget::exhausted() {
+        idempotency::store(&ctx.path, key, &fingerprint, &response, ctx.config.idempotency_ttl_seconds);
+    }
+    response
+}
+
+fn allowed_methods(get: bool, post: bool) -> String {
+    let mut methods = Vec::new();
+    if get {
+        methods.extend(["GET", "HEAD"]);
+    }
+    if post {
+        methods.push("POST");
+    }
+    methods.push("OPTIONS");
+    methods.join(", ")
+}
+
+// Serves deterministic endpoints from the response cache. Only complete 200
+// responses are stored, so errors and requests that ran out of their compute
+// budget are always recomputed.
+fn cached(ctx: &RequestContext, handler: impl FnOnce() -> String) -> String {
+    let key = format!("{}?{}", ctx.path, ctx.params.canonical());
+    if let Some(response) = response_cache::get(&key, ctx.config) {
+        return with_header(response, "X-Wasm-Cache", "HIT");
+    }
+    let response = handler();
+    if response.starts_with("200|") && !budget::exhausted() {
+        response_cache::put(&key, &response, ctx.config);
+    }
+    with_header(response, "X-Wasm-Cache", "MISS")
+}
+
+fn handle_counter_request() -> String {
+    create_json_response(&format!(
+        r#"{{"operation":"counter","scope":"isolate","note":"Counts cover only the isolate that served this request; Cloudflare runs many isolates and may recycle them at any time","counters":{}}}"#,
+        counters::to_json()
+    ))
+}
+
+fn handle_metrics_request() -> String {
+    create_json_response(&format!(
+        r#"{{"operation":"metrics","scope":"isolate","spans":["parse","route","handler","serialize"],"timings":{},"breakers":{}}}"#,
+        timing::to_json(),
+        breaker::to_json()
+    ))
+}
+
+fn handle_security_txt_request(ctx: &RequestContext) -> String {
+    match assets::security_txt(&ctx.config.security_contacts, host::now_ms()) {
+        Some(text) => create_asset_response("text/plain; charset=utf-8", &text),
+        None => create_error_response(404, "No security contact is configured"),
+    }
+}
+
+// Unix seconds, see build.rs
+const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+
+// The home page and the endpoints marked public
+fn handle_sitemap_request(ctx: &RequestContext) -> String {
+    let origin = request_origin(&ctx.headers);
+    let public = endpoints::ENDPOINTS.iter().filter(|endpoint| endpoint.public).map(|endpoint| endpoint.path);
+    let urls: Vec<String> = std::iter::once("/").chain(public).map(|path| format!("{}{}", origin, path)).collect();
+    let built_ms = BUILD_TIMESTAMP.parse::<u64>().unwrap_or(0) * 1000;
+    create_asset_response("application/xml; charset=utf-8", &assets::sitemap(&urls, built_ms))
+}
+
+fn handle_add_request(ctx: &RequestContext) -> String {
+    let (a, b) = parse_two_numbers(&ctx.params, "a", "b");
+    let result = add(a, b);
+    create_json_response(&format!(
+        r#"{{"operation":"add","inputs":{{"a":{},"b":{}}},"result":{}}}"#,
+        a, b, result
+    ))
+}
+
+fn handle_factorial_request(ctx: &RequestContext) -> String {
+    let n = parse_number(&ctx.params, "n").unwrap_or(5);
+    if !(0..=20).contains(&n) {
+        return create_error_response(400, "Number must be between 0 and 20");
+    }
+    let result = factorial(n as u32);
+    create_json_response(&format!(
+        r#"{{"operation":"factorial","input":{},"result":"{}"}}"#,
+        n, result
+    ))
+}
+
+fn handle_prime_request(ctx: &RequestContext) -> String {
+    let n = parse_number(&ctx.params, "n").unwrap_or(17);
+    let result = is_prime(n as u32) != 0;
+    create_json_response(&format!(
+        r#"{{"operation":"is_prime","input":{},"result":{}}}"#,
+        n, result
+    ))
+}
+
+fn handle_fibonacci_request(ctx: &RequestContext) -> String {
+    let n = parse_number(&ctx.params, "n").unwrap_or(10);
+    if !(0..=40).contains(&n) {
+        return create_error_response(400, "Number must be between 0 and 40");
+    }
+    let result = fibonacci(n as u32);
+    create_json_response(&format!(
+        r#"{{"operation":"fibonacci","input":{},"result":"{}"}}"#,
+        n, result
+    ))
+}
+
+fn handle_hash_request(ctx: &RequestContext) -> String {
+    let input = parse_string(&ctx.params, "input").unwrap_or("cloudflare".to_string());
+    let hash = simd::djb2(5381, input.as_bytes());
+    create_json_response(&format!(
+        r#"{{"operation":"simple_hash","input":"{}","result":{}}}"#,
+        json::escape(&input),
+        hash
+    ))
+}
+
+fn handle_generate_request(ctx: &RequestContext) -> String {
+    let kind = parse_string(&ctx.params, "kind").unwrap_or("lorem".to_string());
+    let count = parse_number(&ctx.params, "count").unwrap_or(5);
+    let seed = parse_param(&ctx.params, "seed").unwrap_or(42);
+    if !(1..=100).contains(&count) {
+        return create_error_response(400, "Count must be between 1 and 100");
+    }
+    match generate::generate(&kind, count as usize, seed) {
+        Some(items) => create_json_response(&format!(
+            r#"{{"operation":"generate","kind":"{}","count":{},"seed":{},"result":[{}]}}"#,
+            kind, count, seed, items.join(",")
+        )),
+        None => create_error_response(
+            400,
+            &format!("Kind must be one of: {}", generate::KINDS.join(", ")),
+        ),
+    }
+}
+
+// For documents that need absolute links, taken from the Host header
+fn request_origin(headers: &headers::Headers) -> String {
+    match headers.get("host") {
+        Some(host) if !host.is_empty() && host.bytes().all(|b| b.is_ascii_alphanumeric() || b"-.:[]".contains(&b)) => {
+            format!("https://{}", host)
+        }
+        _ => "https://example.com".to_string(),
+    }
+}
+
+// A feed of seeded lorem ipsum posts, one a day up to the start of today
+fn handle_feed_request(ctx: &RequestContext) -> String {
+    let format = parse_string(&ctx.params, "format").unwrap_or("rss".to_string());
+    let count = parse_number(&ctx.params, "count").unwrap_or(10);
+    let seed = parse_param(&ctx.params, "seed").unwrap_or(42);
+    if !(1..=50).contains(&count) {
+        return create_error_response(400, "Count must be between 1 and 50");
+    }
+    let render: fn(&feeds::Feed) -> String = match format.as_str() {
+        "rss" => feeds::rss,
+        "atom" => feeds::atom,
+        _ => return create_error_response(400, "Format must be one of: rss, atom"),
+    };
+
+    let origin = request_origin(&ctx.headers);
+    let today_ms = host::now_ms() as u64 / dates::MS_PER_DAY * dates::MS_PER_DAY;
+    let mut rng = rand::Rng::new(seed);
+    let items = (0..count as u64)
+        .map(|index| feeds::Item {
+            title: generate::sentence(&mut rng).trim_end_matches('.').to_string(),
+            link: format!("{}/feed?seed={}#post-{}", origin, seed, index + 1),
+            summary: generate::sentence(&mut rng),
+            author: Some(generate::full_name(&mut rng)),
+            published_ms: today_ms.saturating_sub(index * dates::MS_PER_DAY),
+        })
+        .collect();
+    let feed = feeds::Feed {
+        title: "WASM-Handled Worker".to_string(),
+        link: format!("{}/", origin),
+        self_link: format!("{}/feed?format={}&count={}&seed={}", origin, format, count, seed),
+        description: "Demo posts generated in WebAssembly".to_string(),
+        author: "WASM-Handled Worker".to_string(),
+        items,
+    };
+    let content_type = if format == "rss" { "application/rss+xml; charset=utf-8" } else { "application/atom+xml; charset=utf-8" };
+    create_text_response(content_type, &render(&feed))
+}
+
+fn handle_random_request(ctx: &RequestContext) -> String {
+    let min: i64 = parse_param(&ctx.params, "min").unwrap_or(0);
+    let max: i64 = parse_param(&ctx.params, "max").unwrap_or(100);
+    let count = parse_number(&ctx.params, "count").unwrap_or(1);
+    let seed: Option<u64> = parse_param(&ctx.params, "seed");
+    if min > max {
+        return create_error_response(400, "min must not be greater than max");
+    }
+    if !(1..=1000).contains(&count) {
+        return create_error_response(400, "Count must be between 1 and 1000");
+    }
+
+    // An explicit seed gives a reproducible sequence, otherwise continue the module-wide one
+    let draw = |rng: &mut rand::Rng| -> Vec<String> {
+        (0..count).map(|_| rng.range(min, max).to_string()).collect()
+    };
+    let values = match seed {
+        Some(seed) => draw(&mut rand::Rng::new(seed)),
+        None => rand::with_global(draw),
+    };
+    let seed = seed.map_or("null".to_string(), |seed| seed.to_string());
+    create_json_response(&format!(
+        r#"{{"operation":"random","inputs":{{"min":{},"max":{},"count":{},"seed":{}}},"result":[{}]}}"#,
+        min, max, count, seed, values.join(",")
+    ))
+}
+
+fn handle_roll_request(ctx: &RequestContext) -> String {
+    let notation = parse_string(&ctx.params, "dice").unwrap_or("3d6".to_string());
+    let seed: Option<u64> = parse_param(&ctx.params, "seed");
+    let terms = match dice::parse(&notation) {
+        Ok(terms) => terms,
+        Err(message) => return create_error_response(400, &message),
+    };
+
+    let roll = match seed {
+        Some(seed) => dice::roll(&terms, &mut rand::Rng::new(seed)),
+        None => rand::with_global(|rng| dice::roll(&terms, rng)),
+    };
+    let stats = dice::stats(&terms);
+
+    let rolls: Vec<String> = roll.rolls.iter()
+        .map(|group| format!("[{}]", group.iter().map(|face| face.to_string()).collect::<Vec<_>>().join(",")))
+        .collect();
+    let (distribution, at_most) = match &stats.distribution {
+        Some(probabilities) => {
+            let entries: Vec<String> = probabilities.iter().enumerate()
+                .map(|(offset, p)| format!(r#"{{"total":{},"probability":{:.6}}}"#, stats.min + offset as i64, p))
+                .collect();
+            let rolled = (roll.total - stats.min) as usize;
+            let at_most: f64 = probabilities[..=rolled].iter().sum();
+            (format!("[{}]", entries.join(",")), format!("{:.6}", at_most))
+        }
+        None => ("null".to_string(), "null".to_string()),
+    };
+
+    create_json_response(&format!(
+        r#"{{"operation":"roll","input":"{}","rolls":[{}],"total":{},"stats":{{"min":{},"max":{},"mean":{:.4},"std_dev":{:.4},"probability_at_most_total":{},"distribution":{}}}}}"#,
+        json::escape(&notation), rolls.join(","), roll.total,
+        stats.min, stats.max, stats.mean, stats.std_dev, at_most, distribution
+    ))
+}
+
+fn handle_validate_card_request(ctx: &RequestContext) -> String {
+    let number = parse_string(&ctx.params, "number").unwrap_or_default();
+    match validators::card(&number) {
+        Ok(card) => create_json_response(&format!(
+            r#"{{"operation":"validate_card","number":"{}","length":{},"brand":{},"luhn_valid":{},"length_valid":{},"valid":{}}}"#,
+            card.masked,
+            card.length,
+            card.brand.map_or("null".to_string(), |brand| format!("\"{}\"", brand)),
+            card.luhn_valid,
+            card.length_valid.map_or("null".to_string(), |valid| valid.to_string()),
+            card.is_valid()
+        )),
+        Err(message) => create_error_response(400, &message),
+    }
+}
+
+fn handle_validate_iban_request(ctx: &RequestContext) -> String {
+    let input = parse_string(&ctx.params, "iban").unwrap_or_default();
+    match validators::iban(&input) {
+        Ok(iban) => create_json_response(&format!(
+            r#"{{"operation":"validate_iban","iban":"{}","country":"{}","check_digits":"{}","bban":"{}","length":{},"expected_length":{},"length_valid":{},"checksum_valid":{},"valid":{}}}"#,
+            iban.formatted,
+            iban.country,
+            iban.check_digits,
+            iban.bban,
+            iban.length,
+            iban.expected_length.map_or("null".to_string(), |length| length.to_string()),
+            iban.expected_length == Some(iban.length),
+            iban.checksum_valid,
+            iban.is_valid()
+        )),
+        Err(message) => create_error_response(400, &message),
+    }
+}
+
+fn handle_validate_isbn_request(ctx: &RequestContext) -> String {
+    let input = parse_string(&ctx.params, "isbn").unwrap_or_default();
+    let quoted = |value: &Option<String>| value.as_ref().map_or("null".to_string(), |value| format!("\"{}\"", value));
+    match validators::isbn(&input) {
+        Ok(isbn) => create_json_response(&format!(
+            r#"{{"operation":"validate_isbn","format":"{}","checksum_valid":{},"valid":{},"isbn10":{},"isbn13":{}}}"#,
+            isbn.format,
+            isbn.checksum_valid,
+            isbn.checksum_valid,
+            quoted(&isbn.isbn10),
+            quoted(&isbn.isbn13)
+        )),
+        Err(message) => create_error_response(400, &message),
+    }
+}
+
+fn handle_validate_email_request(ctx: &RequestContext) -> String {
+    let input = match parse_string(&ctx.params, "email") {
+        Some(input) => input,
+        None => return create_error_response(400, "Missing email parameter"),
+    };
+    let details = match validators::email(&input) {
+        Ok(email) => format!(
+            r#""valid":true,"reason":null,"local_part":"{}","domain":"{}""#,
+            json::escape(&email.local_part),
+            json::escape(&email.domain)
+        ),
+        Err(reason) => format!(r#""valid":false,"reason":"{}""#, json::escape(&reason)),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"validate_email","input":"{}",{}}}"#,
+        json::escape(&input),
+        details
+    ))
+}
+
+fn handle_validate_url_request(ctx: &RequestContext) -> String {
+    let input = match parse_string(&ctx.params, "url") {
+        Some(input) => input,
+        None => return create_error_response(400, "Missing url parameter"),
+    };
+    let optional = |value: &Option<String>| {
+        value.as_ref().map_or("null".to_string(), |value| format!("\"{}\"", json::escape(value)))
+    };
+    let details = match url::parse(&input) {
+        Ok(url) => format!(
+            r#""valid":true,"reason":null,"components":{{"scheme":"{}","userinfo":{},"host":"{}","port":{},"path":"{}","query":{},"fragment":{}}}"#,
+            json::escape(&url.scheme),
+            optional(&url.userinfo),
+            json::escape(&url.host),
+            url.port.map_or("null".to_string(), |port| port.to_string()),
+            json::escape(&url.path),
+            optional(&url.query),
+            optional(&url.fragment)
+        ),
+        Err(reason) => format!(r#""valid":false,"reason":"{}""#, json::escape(&reason)),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"validate_url","input":"{}",{}}}"#,
+        json::escape(&input),
+        details
+    ))
+}
+
+fn handle_validate_phone_request(ctx: &RequestContext) -> String {
+    let number = parse_string(&ctx.params, "number").unwrap_or_default();
+    let region = parse_string(&ctx.params, "region");
+    match validators::phone(&number, region.as_deref()) {
+        Ok(phone) => create_json_response(&format!(
+            r#"{{"operation":"validate_phone","e164":"{}","region":"{}","calling_code":"{}","national_number":"{}","valid":{},"reason":{}}}"#,
+            phone.e164,
+            phone.region,
+            phone.calling_code,
+            phone.national_number,
+            phone.valid,
+            phone.reason.map_or("null".to_string(), |reason| format!("\"{}\"", json::escape(&reason)))
+        )),
+        Err(message) => create_error_response(400, &message),
+    }
+}
+
+fn handle_semver_parse_request(ctx: &RequestContext) -> String {
+    let input = parse_string(&ctx.params, "version").unwrap_or_default();
+    match semver::parse(&input) {
+        Ok(version) => {
+            let prerelease: Vec<String> = version.prerelease.iter()
+                .map(|id| match id {
+                    semver::Identifier::Numeric(n) => n.to_string(),
+                    semver::Identifier::Alphanumeric(s) => format!("\"{}\"", s),
+                })
+                .collect();
+            let build: Vec<String> = version.build.iter().map(|id| format!("\"{}\"", id)).collect();
+            create_json_response(&format!(
+                r#"{{"operation":"semver_parse","version":"{}","major":{},"minor":{},"patch":{},"prerelease":[{}],"build":[{}]}}"#,
+                version, version.major, version.minor, version.patch, prerelease.join(","), build.join(",")
+            ))
+        }
+        Err(message) => create_error_response(400, &message),
+    }
+}
+
+fn handle_semver_satisfies_request(ctx: &RequestContext) -> String {
+    let input = parse_string(&ctx.params, "version").unwrap_or_default();
+    let range_input = parse_string(&ctx.params, "range").unwrap_or("*".to_string());
+    let version = match semver::parse(&input) {
+        Ok(version) => version,
+        Err(message) => return create_error_response(400, &message),
+    };
+    let range = match semver::parse_range(&range_input) {
+        Ok(range) => range,
+        Err(message) => return create_error_response(400, &message),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"semver_satisfies","version":"{}","range":"{}","normalized_range":"{}","satisfies":{}}}"#,
+        version,
+        json::escape(&range_input),
+        range,
+        range.satisfies(&version)
+    ))
+}
+
+fn handle_minify_request(ctx: &RequestContext, text: &str) -> String {
+    let kind = parse_string(&ctx.params, "type").unwrap_or("html".to_string());
+    match kind.as_str() {
+        "html" => create_text_response("text/html", &minify::html(text)),
+        "css" => create_text_response("text/css", &minify::css(text)),
+        _ => create_error_response(400, "Type must be html or css"),
+    }
+}
+
+fn handle_detect_request(ctx: &RequestContext) -> String {
+    let detected = mime::detect_bytes(ctx.body);
+    let filename = parse_string(&ctx.params, "filename");
+    let extension_type = filename.as_deref().and_then(mime::from_extension);
+    create_json_response(&format!(
+        r#"{{"operation":"detect","size":{},"detected":"{}","description":"{}","filename":{},"extension_type":{},"matches_extension":{}}}"#,
+        ctx.body.len(),
+        detected.mime,
+        detected.description,
+        filename.as_ref().map_or("null".to_string(), |name| format!("\"{}\"", json::escape(name))),
+        extension_type.map_or("null".to_string(), |mime| format!("\"{}\"", mime)),
+        extension_type.map_or("null".to_string(), |mime| mime::same_type(mime, detected.mime).to_string())
+    ))
+}
+
+fn handle_upload_inspect_request(ctx: &RequestContext) -> String {
+    let boundary = match multipart::boundary(ctx.headers.get("content-type").unwrap_or_default()) {
+        Ok(boundary) => boundary,
+        Err(message) => return create_error_response(400, &message),
+    };
+    let parts = match multipart::parse(ctx.body, &boundary) {
+        Ok(parts) => parts,
+        Err(message) => return create_error_response(400, &message),
+    };
+
+    let optional = |value: &Option<String>| {
+        value.as_ref().map_or("null".to_string(), |value| format!("\"{}\"", json::escape(value)))
+    };
+    let entries: Vec<String> = parts.iter()
+        .map(|part| format!(
+            r#"{{"name":{},"filename":{},"declared_type":{},"detected_type":"{}","size":{}}}"#,
+            optional(&part.name),
+            optional(&part.filename),
+            optional(&part.content_type),
+            mime::detect_bytes(part.data).mime,
+            part.data.len()
+        ))
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"upload_inspect","boundary":"{}","part_count":{},"total_size":{},"parts":[{}]}}"#,
+        json::escape(&boundary),
+        parts.len(),
+        ctx.body.len(),
+        entries.join(",")
+    ))
+}
+
+fn handle_autocomplete_request(ctx: &RequestContext) -> String {
+    let prefix = parse_string(&ctx.params, "prefix").unwrap_or_default().to_lowercase();
+    let limit = parse_param::<usize>(&ctx.params, "limit").unwrap_or(10);
+    if !(1..=50).contains(&limit) {
+        return create_error_response(400, "Limit must be between 1 and 50");
+    }
+
+    let (completions, dictionary_size) = trie::with_dictionary(|trie| (trie.complete(&prefix, limit), trie.word_count()));
+    let entries: Vec<String> = completions.iter()
+        .map(|(word, frequency)| format!(r#"{{"word":"{}","frequency":{}}}"#, json::escape(word), frequency))
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"autocomplete","prefix":"{}","limit":{},"dictionary_size":{},"completions":[{}]}}"#,
+        json::escape(&prefix),
+        limit,
+        dictionary_size,
+        entries.join(",")
+    ))
+}
+
+fn handle_search_query_request(ctx: &RequestContext) -> String {
+    let query = match parse_string(&ctx.params, "q") {
+        Some(query) => query,
+        None => return create_error_response(400, "Missing 'q' parameter"),
+    };
+    let limit = parse_param::<usize>(&ctx.params, "limit").unwrap_or(10);
+    if !(1..=50).contains(&limit) {
+        return create_error_response(400, "Limit must be between 1 and 50");
+    }
+    search::with_index(|index| {
+        let terms = index.terms(&query);
+        if terms.is_empty() {
+            return create_error_response(400, "Query must contain at least one word that is not a stop word");
+        }
+        let hits = index.search(&terms);
+        let results: Vec<String> = hits.iter()
+            .take(limit)
+            .map(|hit| {
+                let document = &index.documents[hit.document];
+                format!(
+                    r#"{{"id":{},"score":{:.6},"snippet":"{}"}}"#,
+                    document.id,
+                    hit.score,
+                    json::escape(&index.snippet(&document.text, &terms))
+                )
+            })
+            .collect();
+        let terms: Vec<String> = terms.iter().map(|term| format!("\"{}\"", json::escape(term))).collect();
+        create_json_response(&format!(
+            r#"{{"operation":"search_query","query":"{}","language":"{}","terms":[{}],"documents":{},"total_matches":{},"results":[{}]}}"#,
+            json::escape(&query),
+            index.language,
+            terms.join(","),
+            index.documents.len(),
+            hits.len(),
+            results.join(",")
+        ))
+    })
+}
+
+fn handle_search_index_request(ctx: &RequestContext) -> String {
+    let language = parse_string(&ctx.params, "lang").unwrap_or("en".to_string());
+    let Some(language) = stemming::LANGUAGES.iter().find(|code| **code == language) else {
+        return create_error_response(400, &format!("Unknown lang '{}', expected one of: {}", language, stemming::LANGUAGES.join(", ")));
+    };
+    let items = match std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(json::parse) {
+        Ok(json::Value::Array(items)) => items,
+        Ok(_) => return create_error_response(400, "Body must be a JSON array of documents"),
+        Err(message) => return create_error_response(400, &message),
+    };
+    if items.len() > 1000 {
+        return create_error_response(400, "At most 1000 documents can be indexed");
+    }
+
+    // Documents are plain strings or {"id": ..., "text": "..."} objects
+    let mut documents = Vec::new();
+    for (position, item) in items.into_iter().enumerate() {
+        match item {
+            json::Value::String(text) => documents.push((position.to_string(), text)),
+            json::Value::Object(members) => {
+                let mut id = position.to_string();
+                let mut text = None;
+                for (key, value) in members {
+                    match (key.as_str(), value) {
+                        ("id", value @ (json::Value::String(_) | json::Value::Number(_))) => id = value.to_json(),
+                        ("text", json::Value::String(value)) => text = Some(value),
+                        _ => {}
+                    }
+                }
+                match text {
+                    Some(text) => documents.push((id, text)),
+                    None => return create_error_response(400, &format!("Document {} has no string 'text' field", position)),
+                }
+            }
+            _ => return create_error_response(400, &format!("Document {} must be a string or an object", position)),
+        }
+    }
+
+    search::with_index(|index| {
+        *index = search::Index::new(language);
+        for (id, text) in documents {
+            index.add(&id, &text);
+        }
+        create_json_response(&format!(
+            r#"{{"operation":"search_index","language":"{}","documents":{},"terms":{}}}"#,
+            index.language,
+            index.documents.len(),
+            index.term_count()
+        ))
+    })
+}
+
+fn handle_sort_request(ctx: &RequestContext) -> String {
+    let algorithm = parse_string(&ctx.params, "algo").unwrap_or("pdq".to_string());
+    let n: usize = parse_param(&ctx.params, "n").unwrap_or(100_000);
+    let order = parse_string(&ctx.params, "order").unwrap_or("random".to_string());
+    let seed: u64 = parse_param(&ctx.params, "seed").unwrap_or(42);
+    if !sorting::ALGORITHMS.contains(&algorithm.as_str()) {
+        return create_error_response(400, &format!("Unknown algo '{}', expected one of: {}", algorithm, sorting::ALGORITHMS.join(", ")));
+    }
+    if !(1..=1_000_000).contains(&n) {
+        return create_error_response(400, "n must be between 1 and 1000000");
+    }
+
+    let mut rng = rand::Rng::new(seed);
+    let mut data: Vec<u32> = match order.as_str() {
+        "random" => (0..n).map(|_| rng.next_u64() as u32).collect(),
+        "sorted" => (0..n as u32).collect(),
+        "reversed" => (0..n as u32).rev().collect(),
+        "duplicates" => (0..n).map(|_| rng.below(10) as u32).collect(),
+        _ => return create_error_response(400, "Order must be one of: random, sorted, reversed, duplicates"),
+    };
+    let checksum = |data: &[u32]| data.iter().fold(0u64, |sum, &value| sum.wrapping_add(value as u64));
+    let input_checksum = checksum(&data);
+
+    let mut comparisons: u64 = 0;
+    let started = host::now_ms();
+    sorting::sort(&algorithm, &mut data, &mut |a: &u32, b: &u32| {
+        comparisons += 1;
+        a.cmp(b)
+    });
+    let elapsed = host::now_ms() - started;
+
+    let sample: Vec<String> = data.iter().take(10).map(|value| value.to_string()).collect();
+    // Workers only advance the clock on I/O, so elapsed_ms can read 0 in
+    // production; the comparison count is the portable measure of work
+    create_json_response(&format!(
+        r#"{{"operation":"sort","algorithm":"{}","n":{},"order":"{}","seed":{},"comparisons":{},"elapsed_ms":{},"sorted":{},"permutation_checksum_match":{},"sample":[{}]}}"#,
+        algorithm,
+        n,
+        order,
+        seed,
+        comparisons,
+        elapsed,
+        data.is_sorted(),
+        checksum(&data) == input_checksum,
+        sample.join(",")
+    ))
+}
+
+const BENCH_OPS: [&str; 3] = ["hash", "hex", "scan"];
+
+// Runs every compiled-in byte kernel (scalar, and simd128 with that feature)
+// over the same query-string-like data
+fn handle_bench_request(ctx: &RequestContext) -> String {
+    let op = parse_string(&ctx.params, "op").unwrap_or("hash".to_string());
+    let kib: usize = parse_param(&ctx.params, "kib").unwrap_or(64);
+    let iterations: usize = parse_param(&ctx.params, "iterations").unwrap_or(100);
+    let seed: u64 = parse_param(&ctx.params, "seed").unwrap_or(42);
+    if !BENCH_OPS.contains(&op.as_str()) {
+        return create_error_response(400, &format!("Unknown op '{}', expected one of: {}", op, BENCH_OPS.join(", ")));
+    }
+    if !(1..=1024).contains(&kib) {
+        return create_error_response(400, "kib must be between 1 and 1024");
+    }
+    if !(1..=1000).contains(&iterations) {
+        return create_error_response(400, "iterations must be between 1 and 1000");
+    }
+    if kib * iterations > 64 * 1024 {
+        return create_error_response(400, "kib * iterations must be at most 65536 (64 MiB of input)");
+    }
+
+    // Letters and digits with an '&' or '=' roughly every sixth byte
+    let mut rng = rand::Rng::new(seed);
+    let alphabet = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let data: Vec<u8> = (0..kib * 1024)
+        .map(|_| match rng.below(12) {
+            0 => b'&',
+            1 => b'=',
+            _ => alphabet[rng.below(alphabet.len() as u64) as usize],
+        })
+        .collect();
+
+    let runs: Vec<(simd::Kernels, f64, u64)> = simd::AVAILABLE
+        .iter()
+        .map(|&kernels| {
+            let mut hex = Vec::with_capacity(data.len() * 2);
+            let mut result = 0;
+            let started = host::now_ms();
+            for _ in 0..iterations {
+                let data = std::hint::black_box(&data);
+                result = match op.as_str() {
+                    "hash" => kernels.djb2(5381, data) as u64,
+                    "hex" => {
+                        hex.clear();
+                        kernels.hex(data, &mut hex);
+                        hex.len() as u64
+                    }
+                    _ => {
+                        let (mut delimiters, mut offset) = (0, 0);
+                        while let Some(index) = kernels.find_either(&data[offset..], b'&', b'=') {
+                            delimiters += 1;
+                            offset += index + 1;
+                        }
+                        delimiters
+                    }
+                };
+            }
+            let elapsed = host::now_ms() - started;
+            // Compare the encoded text itself, not just its length
+            if op == "hex" {
+                result = simd::djb2(5381, &hex) as u64;
+            }
+            (kernels, elapsed, result)
+        })
+        .collect();
+
+    let mib = (data.len() * iterations) as f64 / (1024.0 * 1024.0);
+    let results: Vec<String> = runs
+        .iter()
+        .map(|(kernels, elapsed, result)| {
+            let throughput = if *elapsed > 0.0 { format!("{:.1}", mib / (elapsed / 1000.0)) } else { "null".to_string() };
+            format!(
+                r#"{{"kernels":"{}","elapsed_ms":{},"mib_per_second":{},"result":{}}}"#,
+                kernels.name(),
+                elapsed,
+                throughput,
+                result
+            )
+        })
+        .collect();
+    // As with /sort, elapsed_ms can read 0 in production where the clock only
+    // advances on I/O
+    create_json_response(&format!(
+        r#"{{"operation":"bench","op":"{}","bytes":{},"iterations":{},"simd128":{},"results":[{}],"results_match":{}}}"#,
+        op,
+        data.len(),
+        iterations,
+        simd::AVAILABLE.len() > 1,
+        results.join(","),
+        runs.iter().all(|(_, _, result)| *result == runs[0].2)
+    ))
+}
+
+// Resolves a field to its JSON value; arguments the field does not take are
+// refused before any resolver runs
+type GraphqlResolver = fn(&graphql::Field) -> Result<String, String>;
+
+// What /graphql can select: the name, the arguments and the resolver
+const GRAPHQL_FIELDS: [(&str, &[&str], GraphqlResolver); 4] = [
+    ("add", &["a", "b"], |field| {
+        let (a, b) = (graphql_int(field, "a")?, graphql_int(field, "b")?);
+        match a.checked_add(b) {
+            Some(_) => Ok(add(a, b).to_string()),
+            None => Err("The sum does not fit in a 32-bit integer".to_string()),
+        }
+    }),
+    // A string, as in /factorial, since 20! is beyond what JSON numbers hold exactly
+    ("factorial", &["n"], |field| match graphql_int(field, "n")? {
+        n @ 0..=20 => Ok(format!("\"{}\"", factorial(n as u32))),
+        _ => Err("Argument 'n' must be between 0 and 20".to_string()),
+    }),
+    ("status", &[], |_| Ok(get_status_json())),
+    ("__typename", &[], |_| Ok("\"Query\"".to_string())),
+];
+
+// GraphQL's Int is 32 bits
+fn graphql_int(field: &graphql::Field, name: &str) -> Result<i32, String> {
+    match field.argument(name) {
+        Some(graphql::Argument::Int(value)) => i32::try_from(*value).map_err(|_| format!("Argument '{}' must be a 32-bit integer", name)),
+        Some(_) => Err(format!("Argument '{}' must be an Int", name)),
+        None => Err(format!("Argument '{}' is required", name)),
+    }
+}
+
+// A JSON body {"query": "..."} or, with any other content type, the query
+// itself as the body
+fn graphql_post_query(ctx: &RequestContext) -> Result<String, String> {
+    let text = std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string())?;
+    let content_type = ctx.headers.get("content-type").unwrap_or("");
+    if params::is_form(Some(content_type)) {
+        let form = QueryParams::parse_form(text)?;
+        return form.get("query").map(str::to_string).ok_or_else(|| "Form must have a query field".to_string());
+    }
+    if !content_type.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case("application/json") {
+        return Ok(text.to_string());
+    }
+    let json::Value::Object(members) = json::parse(text)? else {
+        return Err("Body must be a JSON object with a query".to_string());
+    };
+    let mut query = None;
+    for (key, value) in members {
+        match (key.as_str(), value) {
+            ("query", json::Value::String(text)) => query = Some(text),
+            ("query", _) => return Err("query must be a string".to_string()),
+            ("variables", json::Value::Object(variables)) if !variables.is_empty() => {
+                return Err("Variables are not supported".to_string());
+            }
+            ("variables", json::Value::Null | json::Value::Object(_)) | ("operationName", _) | ("extensions", _) => {}
+            ("variables", _) => return Err("variables must be an object".to_string()),
+            (other, _) => return Err(format!("Unknown request member '{}'", other)),
+        }
+    }
+    query.ok_or_else(|| "Body must have a query".to_string())
+}
+
+fn graphql_errors_response(status: u16, messages: &[String]) -> String {
+    let errors: Vec<String> = messages.iter().map(|message| format!(r#"{{"message":"{}"}}"#, json::escape(message))).collect();
+    format!("{}|application/json|{{\"errors\":[{}]}}", status, errors.join(","))
+}
+
+// Queries that do not parse or select what GRAPHQL_FIELDS lacks are refused
+// whole with 400. A field that fails to resolve comes back null, with an
+// error naming its path, and the others still resolve.
+fn handle_graphql_request(query: Option<&str>) -> String {
+    let Some(query) = query.filter(|query| !query.trim().is_empty()) else {
+        return graphql_errors_response(400, &["A query is required".to_string()]);
+    };
+    let fields = match graphql::parse(query) {
+        Ok(fields) => fields,
+        Err(reason) => return graphql_errors_response(400, &[reason]),
+    };
+
+    let mut problems = Vec::new();
+    let mut resolvers = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        if fields[..index].iter().any(|earlier| earlier.key() == field.key()) {
+            problems.push(format!("'{}' is selected more than once; give the others an alias", field.key()));
+        }
+        match GRAPHQL_FIELDS.iter().find(|(name, _, _)| *name == field.name) {
+            Some(&(name, arguments, resolver)) => {
+                for (argument, _) in &field.arguments {
+                    if !arguments.contains(&argument.as_str()) {
+                        problems.push(format!("Unknown argument '{}' on field '{}'", argument, name));
+                    }
+                }
+                resolvers.push(resolver);
+            }
+            None => problems.push(format!("Cannot query field '{}' on type 'Query'", field.name)),
+        }
+    }
+    if !problems.is_empty() {
+        return graphql_errors_response(400, &problems);
+    }
+
+    let mut data = Vec::new();
+    let mut errors = Vec::new();
+    for (field, resolver) in fields.iter().zip(resolvers) {
+        let key = json::escape(field.key());
+        match resolver(field) {
+            Ok(value) => data.push(format!("\"{}\":{}", key, value)),
+            Err(message) => {
+                data.push(format!("\"{}\":null", key));
+                errors.push(format!(r#"{{"message":"{}","path":["{}"]}}"#, json::escape(&message), key));
+            }
+        }
+    }
+    let errors = if errors.is_empty() { String::new() } else { format!(",\"errors\":[{}]", errors.join(",")) };
+    create_json_response(&format!("{{\"data\":{{{}}}{}}}", data.join(","), errors))
+}
+
+// gRPC-Web in its binary and base64 text encodings, protobuf messages only
+fn handle_grpc_request(ctx: &RequestContext, path: &str) -> String {
+    let content_type = ctx.headers.get("content-type").unwrap_or("").split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    let text = match content_type.as_str() {
+        "application/grpc-web" | "application/grpc-web+proto" => false,
+        "application/grpc-web-text" | "application/grpc-web-text+proto" => true,
+        _ => return create_error_response(415, "Content-Type must be application/grpc-web(-text)[+proto]"),
+    };
+    let result = grpc::method(path).and_then(|method| {
+        let body = if text {
+            let encoded = std::str::from_utf8(ctx.body).unwrap_or("").trim_ascii();
+            hashing::from_base64(encoded).map_err(|reason| grpc::Status::new(grpc::INVALID_ARGUMENT, reason))?
+        } else {
+            ctx.body.to_vec()
+        };
+        grpc::call(method, grpc::unframe(&body)?)
+    });
+
+    let content_type = if text { "application/grpc-web-text+proto" } else { "application/grpc-web+proto" };
+    let response = match result {
+        Ok(message) if text => create_text_response(content_type, &hashing::to_base64(&grpc::reply(&message))),
+        Ok(message) => create_binary_response(content_type, &grpc::reply(&message)),
+        // Trailers-only: a failed call carries its status in the headers
+        Err(status) => {
+            let response = with_header(format!("200|{}|", content_type), "grpc-status", &status.code.to_string());
+            with_header(response, "grpc-message", &grpc::encode_message(&status.message))
+        }
+    };
+    // Browser clients read the status from these
+    with_header(response, "Access-Control-Expose-Headers", "grpc-status, grpc-message")
+}
+
+fn handle_graph_shortest_request(ctx: &RequestContext) -> String {
+    let (source, target) = match (parse_string(&ctx.params, "source"), parse_string(&ctx.params, "target")) {
+        (Some(source), Some(target)) => (source, target),
+        _ => return create_error_response(400, "Missing 'source' or 'target' parameter"),
+    };
+    let graph = match std::str::from_utf8(ctx.body)
+        .map_err(|_| "Body is not valid UTF-8".to_string())
+        .and_then(json::parse)
+        .and_then(|value| graph::Graph::from_json(&value))
+    {
+        Ok(graph) => graph,
+        Err(message) => return create_error_response(400, &message),
+    };
+    let (from, to) = match (graph.find(&source), graph.find(&target)) {
+        (Some(from), Some(to)) => (from, to),
+        (None, _) => return create_error_response(400, &format!("Unknown source node '{}'", source)),
+        (_, None) => return create_error_response(400, &format!("Unknown target node '{}'", target)),
+    };
+
+    let path = graph.shortest_path(from, to);
+    let nodes: Vec<String> = path.iter()
+        .flat_map(|path| &path.nodes)
+        .map(|&node| format!("\"{}\"", json::escape(&graph.names[node])))
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"graph_shortest","algorithm":"{}","source":"{}","target":"{}","nodes":{},"edges":{},"reachable":{},"distance":{},"path":[{}]}}"#,
+        if graph.weighted { "dijkstra" } else { "bfs" },
+        json::escape(&source),
+        json::escape(&target),
+        graph.names.len(),
+        graph.edge_count(),
+        path.is_some(),
+        path.as_ref().map_or("null".to_string(), |path| path.distance.to_string()),
+        nodes.join(",")
+    ))
+}
+
+fn handle_sudoku_solve_request(ctx: &RequestContext) -> String {
+    let puzzle = match parse_string(&ctx.params, "puzzle") {
+        Some(puzzle) => puzzle,
+        None => return create_error_response(400, "Missing 'puzzle' parameter"),
+    };
+    let grid = match sudoku::parse(&puzzle) {
+        Ok(grid) => grid,
+        Err(message) => return create_error_response(400, &message),
+    };
+
+    let result = sudoku::solve(&grid);
+    create_json_response(&format!(
+        r#"{{"operation":"sudoku_solve","puzzle":"{}","clues":{},"solvable":{},"unique":{},"solution":{}}}"#,
+        sudoku::format(&grid),
+        grid.iter().filter(|&&digit| digit != 0).count(),
+        result.is_some(),
+        result.is_some_and(|(_, unique)| unique),
+        result.map_or("null".to_string(), |(solution, _)| format!("\"{}\"", sudoku::format(&solution)))
+    ))
+}
+
+fn handle_sudoku_generate_request(ctx: &RequestContext) -> String {
+    let difficulty = parse_string(&ctx.params, "difficulty").unwrap_or("medium".to_string());
+    let seed: Option<u64> = parse_param(&ctx.params, "seed");
+    let target_clues = match sudoku::DIFFICULTIES.iter().find(|(name, _)| *name == difficulty) {
+        Some(&(_, clues)) => clues,
+        None => return create_error_response(400, "Difficulty must be one of: easy, medium, hard"),
+    };
+
+    let (puzzle, solution) = match seed {
+        Some(seed) => sudoku::generate(target_clues, &mut rand::Rng::new(seed)),
+        None => rand::with_global(|rng| sudoku::generate(target_clues, rng)),
+    };
+    let seed = seed.map_or("null".to_string(), |seed| seed.to_string());
+    create_json_response(&format!(
+        r#"{{"operation":"sudoku_generate","difficulty":"{}","seed":{},"clues":{},"puzzle":"{}","solution":"{}"}}"#,
+        difficulty,
+        seed,
+        puzzle.iter().filter(|&&digit| digit != 0).count(),
+        sudoku::format(&puzzle),
+        sudoku::format(&solution)
+    ))
+}
+
+// Evolves a posted grid when one is given, otherwise a seeded random one
+fn handle_life_request(ctx: &RequestContext, grid: Option<&str>) -> String {
+    let steps: usize = parse_param(&ctx.params, "steps").unwrap_or(10);
+    let format = parse_string(&ctx.params, "format").unwrap_or("json".to_string());
+    let seed: Option<u64> = parse_param(&ctx.params, "seed");
+    if steps > 1000 {
+        return create_error_response(400, "Steps must be between 0 and 1000");
+    }
+    if format != "json" && format != "ascii" {
+        return create_error_response(400, "Format must be json or ascii");
+    }
+
+    let mut life = match grid {
+        Some(grid) => match life::Life::parse(grid) {
+            Ok(life) => life,
+            Err(message) => return create_error_response(400, &message),
+        },
+        None => {
+            let width: usize = parse_param(&ctx.params, "width").unwrap_or(32);
+            let height: usize = parse_param(&ctx.params, "height").unwrap_or(16);
+            let density: f64 = parse_param(&ctx.params, "density").unwrap_or(0.35);
+            if !(1..=life::MAX_SIDE).contains(&width) || !(1..=life::MAX_SIDE).contains(&height) {
+                return create_error_response(400, &format!("Width and height must be between 1 and {}", life::MAX_SIDE));
+            }
+            match seed {
+                Some(seed) => life::Life::random(width, height, density, &mut rand::Rng::new(seed)),
+                None => rand::with_global(|rng| life::Life::random(width, height, density, rng)),
+            }
+        }
+    };
+    life.wrap = parse_string(&ctx.params, "wrap").is_none_or(|wrap| wrap != "false");
+
+    let initial_population = life.population();
+    let mut stable_at = None;
+    for generation in 1..=steps {
+        if !life.step() {
+            stable_at = Some(generation - 1);
+            break;
+        }
+    }
+
+    if format == "ascii" {
+        return create_text_response("text/plain; charset=utf-8", &(life.rows().join("\n") + "\n"));
+    }
+    let rows: Vec<String> = life.rows().iter().map(|row| format!("\"{}\"", row)).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"life","width":{},"height":{},"wrap":{},"steps":{},"seed":{},"initial_population":{},"population":{},"stable_at":{},"grid":[{}]}}"#,
+        life.width,
+        life.height,
+        life.wrap,
+        steps,
+        seed.filter(|_| grid.is_none()).map_or("null".to_string(), |seed| seed.to_string()),
+        initial_population,
+        life.population(),
+        stable_at.map_or("null".to_string(), |generation: usize| generation.to_string()),
+        rows.join(",")
+    ))
+}
+
+fn handle_maze_request(ctx: &RequestContext) -> String {
+    let width: usize = parse_param(&ctx.params, "w").unwrap_or(10);
+    let height: usize = parse_param(&ctx.params, "h").unwrap_or(10);
+    let seed: Option<u64> = parse_param(&ctx.params, "seed");
+    let solve = parse_string(&ctx.params, "solve").is_some_and(|solve| solve == "true" || solve == "1");
+    let format = parse_string(&ctx.params, "format").unwrap_or("json".to_string());
+    if !(1..=maze::MAX_SIDE).contains(&width) || !(1..=maze::MAX_SIDE).contains(&height) {
+        return create_error_response(400, &format!("Width and height must be between 1 and {}", maze::MAX_SIDE));
+    }
+    if !["json", "ascii", "svg"].contains(&format.as_str()) {
+        return create_error_response(400, "Format must be json, ascii or svg");
+    }
+
+    let maze = match seed {
+        Some(seed) => maze::Maze::generate(width, height, &mut rand::Rng::new(seed)),
+        None => rand::with_global(|rng| maze::Maze::generate(width, height, rng)),
+    };
+    let path = if solve { maze.solve() } else { Vec::new() };
+
+    match format.as_str() {
+        "ascii" => create_text_response("text/plain; charset=utf-8", &maze.to_ascii(&path)),
+        "svg" => create_text_response("image/svg+xml", &maze.to_svg(&path)),
+        _ => {
+            let rows: Vec<String> = maze.walls.chunks(width)
+                .map(|row| format!("[{}]", row.iter().map(|walls| walls.to_string()).collect::<Vec<_>>().join(",")))
+                .collect();
+            let solution = if solve {
+                let cells: Vec<String> = path.iter().map(|&cell| format!("[{},{}]", cell % width, cell / width)).collect();
+                format!("[{}]", cells.join(","))
+            } else {
+                "null".to_string()
+            };
+            create_json_response(&format!(
+                r#"{{"operation":"maze","width":{},"height":{},"seed":{},"wall_bits":{{"north":{},"east":{},"south":{},"west":{}}},"walls":[{}],"solution":{}}}"#,
+                width,
+                height,
+                seed.map_or("null".to_string(), |seed| seed.to_string()),
+                maze::NORTH,
+                maze::EAST,
+                maze::SOUTH,
+                maze::WEST,
+                rows.join(","),
+                solution
+            ))
+        }
+    }
+}
+
+fn handle_chart_request(ctx: &RequestContext) -> String {
+    let Some(data) = ctx.params.get("data") else {
+        return create_error_response(400, "Missing data, e.g. ?data=1,5,3,8");
+    };
+    let values = match charts::parse_values(data) {
+        Ok(values) => values,
+        Err(reason) => return create_error_response(400, &reason),
+    };
+    let width: usize = parse_param(&ctx.params, "width").unwrap_or(40);
+    if !(1..=charts::MAX_WIDTH).contains(&width) {
+        return create_error_response(400, &format!("Width must be between 1 and {}", charts::MAX_WIDTH));
+    }
+    let labels: Vec<&str> = ctx.params.get("labels").map_or(Vec::new(), |labels| labels.split(',').map(str::trim).collect());
+    let ascii = parse_string(&ctx.params, "ascii").is_some_and(|ascii| ascii == "true" || ascii == "1");
+    let chart = match ctx.params.get("type").unwrap_or("bar") {
+        "bar" => charts::bar(&values, &labels, width, ascii),
+        "spark" => Ok(charts::sparkline(&values, ascii)),
+        _ => Err("Type must be bar or spark".to_string()),
+    };
+    match chart {
+        Ok(chart) => create_text_response("text/plain; charset=utf-8", &chart),
+        Err(reason) => create_error_response(400, &reason),
+    }
+}
+
+struct ChartData {
+    kind: charts::Kind,
+    values: Vec<f64>,
+    labels: Vec<String>,
+    title: Option<String>,
+}
+
+// GET takes ?data=1,5,3&labels=a,b,c&type=&title=; POST a JSON object with
+// "values", optional "labels", "type" and "title", where ?type also works
+fn chart_data(ctx: &RequestContext) -> Result<ChartData, String> {
+    let mut kind = ctx.params.get("type").map(str::to_string);
+    let mut title = ctx.params.get("title").map(str::to_string);
+    let (values, labels) = if ctx.method == "POST" {
+        let members = match std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(json::parse)? {
+            json::Value::Object(members) => members,
+            _ => return Err(r#"Body must be a JSON object like {"values":[1,5,3]}"#.to_string()),
+        };
+        let mut values = None;
+        let mut labels = Vec::new();
+        for (key, value) in &members {
+            match (key.as_str(), value) {
+                ("values", json::Value::Array(items)) => {
+                    let numbers: Option<Vec<f64>> = items.iter().map(|item| match item {
+                        json::Value::Number(number) => Some(*number),
+                        _ => None,
+                    }).collect();
+                    values = Some(numbers.ok_or("values must be an array of numbers")?);
+                }
+                ("labels", json::Value::Array(items)) => {
+                    labels = items.iter().map(|item| match item {
+                        json::Value::String(text) => text.clone(),
+                        other => other.to_json(),
+                    }).collect();
+                }
+                ("type", json::Value::String(name)) => kind = Some(name.clone()),
+                ("title", json::Value::String(text)) => title = Some(text.clone()),
+                ("values" | "labels" | "type" | "title", _) => return Err(format!("{} has the wrong type", key)),
+                _ => {}
+            }
+        }
+        let values = values.ok_or("Missing values")?;
+        if values.len() > charts::MAX_VALUES {
+            return Err(format!("At most {} values are supported", charts::MAX_VALUES));
+        }
+        (values, labels)
+    } else {
+        let data = ctx.params.get("data").ok_or("Missing data, e.g. ?data=1,5,3,8")?;
+        let labels = ctx.params.get("labels").map_or(Vec::new(), |labels| labels.split(',').map(|label| label.trim().to_string()).collect());
+        (charts::parse_values(data)?, labels)
+    };
+    let kind = match kind.as_deref() {
+        None => charts::Kind::Line,
+        Some(name) => charts::Kind::parse(name).ok_or("Type must be line, bar or pie")?,
+    };
+    Ok(ChartData { kind, values, labels, title })
+}
+
+// An SVG chart, or the text version for clients preferring text/plain such
+// as `curl -H 'Accept: text/plain'`
+fn handle_chart_svg_request(ctx: &RequestContext) -> String {
+    let chart = match chart_data(ctx) {
+        Ok(chart) => chart,
+        Err(reason) => return create_error_response(400, &reason),
+    };
+    let labels: Vec<&str> = chart.labels.iter().map(String::as_str).collect();
+    let wants_text = ctx.headers.get("accept").is_some_and(|accept| {
+        headers::accept_quality(accept, "text/plain") > headers::accept_quality(accept, "image/svg+xml")
+    });
+    let rendered = if wants_text {
+        let body = match chart.kind {
+            charts::Kind::Line => Ok(charts::sparkline(&chart.values, false)),
+            _ => charts::bar(&chart.values, &labels, 40, false),
+        };
+        let title = chart.title.map_or(String::new(), |title| title + "\n");
+        body.map(|body| create_text_response("text/plain; charset=utf-8", &(title + &body)))
+    } else {
+        charts::svg(chart.kind, &chart.values, &labels, chart.title.as_deref()).map(|svg| create_text_response("image/svg+xml", &svg))
+    };
+    match rendered {
+        Ok(response) => with_header(response, "Vary", "Accept"),
+        Err(reason) => create_error_response(400, &reason),
+    }
+}
+
+fn handle_banner_request(ctx: &RequestContext) -> String {
+    let text = ctx.params.get("text").unwrap_or("");
+    if text.is_empty() || text.chars().count() > banner::MAX_TEXT {
+        return create_error_response(400, &format!("Text must be between 1 and {} characters", banner::MAX_TEXT));
+    }
+    let name = ctx.params.get("font").unwrap_or("standard");
+    let Some((_, font, pixel)) = banner::FONTS.iter().find(|(font, _, _)| *font == name) else {
+        let names: Vec<&str> = banner::FONTS.iter().map(|(font, _, _)| *font).collect();
+        return create_error_response(400, &format!("Font must be one of: {}", names.join(", ")));
+    };
+    create_text_response("text/plain; charset=utf-8", &banner::render(text, font, *pixel))
+}
+
+fn handle_sentiment_request(ctx: &RequestContext) -> String {
+    let text = match parse_string(&ctx.params, "text") {
+        Some(text) => text,
+        None => return create_error_response(400, "Missing 'text' parameter"),
+    };
+    let analysis = sentiment::analyze(&text);
+    // Four decimals hide float noise such as 3.9000000000000004
+    let round = |value: f64| (value * 10_000.0).round() / 10_000.0;
+    let contributions: Vec<String> = analysis.contributions.iter()
+        .map(|contribution| format!(
+            r#"{{"token":"{}","weight":{},"negated":{},"multiplier":{},"score":{}}}"#,
+            json::escape(&contribution.token),
+            contribution.weight,
+            contribution.negated,
+            round(contribution.multiplier),
+            round(contribution.score)
+        ))
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"sentiment","text":"{}","tokens":{},"score":{},"comparative":{},"polarity":"{}","contributions":[{}]}}"#,
+        json::escape(&text),
+        analysis.tokens,
+        round(analysis.score),
+        round(analysis.comparative()),
+        analysis.polarity(),
+        contributions.join(",")
+    ))
+}
+
+fn handle_language_request(ctx: &RequestContext) -> String {
+    let text = match parse_string(&ctx.params, "text") {
+        Some(text) => text,
+        None => return create_error_response(400, "Missing 'text' parameter"),
+    };
+    let limit = parse_param::<usize>(&ctx.params, "limit").unwrap_or(5);
+    if !(1..=langdetect::language_count()).contains(&limit) {
+        return create_error_response(400, &format!("Limit must be between 1 and {}", langdetect::language_count()));
+    }
+    let trigrams = langdetect::trigrams(&text).len();
+    if trigrams == 0 {
+        return create_error_response(400, "Text must contain at least one letter");
+    }
+
+    let candidates = langdetect::detect(&text);
+    let ranked: Vec<String> = candidates.iter()
+        .take(limit)
+        .map(|candidate| format!(
+            r#"{{"code":"{}","name":"{}","confidence":{:.4}}}"#,
+            candidate.code, candidate.name, candidate.confidence
+        ))
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"language","text":"{}","trigrams":{},"language":"{}","name":"{}","confidence":{:.4},"candidates":[{}]}}"#,
+        json::escape(&text),
+        trigrams,
+        candidates[0].code,
+        candidates[0].name,
+        candidates[0].confidence,
+        ranked.join(",")
+    ))
+}
+
+fn handle_text_stem_request(ctx: &RequestContext) -> String {
+    let text = match parse_string(&ctx.params, "text") {
+        Some(text) => text,
+        None => return create_error_response(400, "Missing 'text' parameter"),
+    };
+    let language = parse_string(&ctx.params, "lang").unwrap_or("en".to_string());
+    if !stemming::LANGUAGES.contains(&language.as_str()) {
+        return create_error_response(400, &format!("Unknown lang '{}', expected one of: {}", language, stemming::LANGUAGES.join(", ")));
+    }
+
+    // Same word splitting as the search index
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.len() > 1000 {
+        return create_error_response(400, "At most 1000 words can be stemmed");
+    }
+    let mut terms = Vec::new();
+    let tokens: Vec<String> = words.iter()
+        .map(|word| match stemming::normalize(&language, word) {
+            Some(term) => {
+                let token = format!(r#"{{"word":"{}","stem":"{}","stop":false}}"#, json::escape(word), json::escape(&term));
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+                token
+            }
+            None => format!(r#"{{"word":"{}","stem":null,"stop":true}}"#, json::escape(word)),
+        })
+        .collect();
+    let terms: Vec<String> = terms.iter().map(|term| format!("\"{}\"", json::escape(term))).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"text_stem","language":"{}","stemmer":{},"tokens":[{}],"terms":[{}]}}"#,
+        language,
+        if language == "en" { "\"porter\"" } else { "null" },
+        tokens.join(","),
+        terms.join(",")
+    ))
+}
+
+fn handle_text_plural_request(ctx: &RequestContext) -> String {
+    let word = match parse_string(&ctx.params, "word") {
+        Some(word) if !word.trim().is_empty() => word.trim().to_string(),
+        _ => return create_error_response(400, "Missing 'word' parameter"),
+    };
+    if word.chars().count() > 100 {
+        return create_error_response(400, "Word must be at most 100 characters");
+    }
+    let count = match ctx.params.get("count") {
+        Some(count) => match count.parse::<i64>() {
+            Ok(count) => Some(count),
+            Err(_) => return create_error_response(400, "Count must be an integer"),
+        },
+        None => None,
+    };
+
+    let singular = text::singularize(&word);
+    let plural = text::pluralize(&singular);
+    let result = match count {
+        Some(1 | -1) => &singular,
+        _ => &plural,
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"plural","word":"{}","singular":"{}","plural":"{}","count":{},"result":"{}","phrase":{}}}"#,
+        json::escape(&word),
+        json::escape(&singular),
+        json::escape(&plural),
+        count.map_or("null".to_string(), |count| count.to_string()),
+        json::escape(result),
+        count.map_or("null".to_string(), |count| format!("\"{} {}\"", count, json::escape(result)))
+    ))
+}
+
+fn handle_text_translit_request(ctx: &RequestContext) -> String {
+    let text = match parse_string(&ctx.params, "text") {
+        Some(text) => text,
+        None => return create_error_response(400, "Missing 'text' parameter"),
+    };
+    if text.chars().count() > 10_000 {
+        return create_error_response(400, "Text must be at most 10000 characters");
+    }
+
+    let (ascii, unmapped) = text::transliterate(&text);
+    create_json_response(&format!(
+        r#"{{"operation":"translit","text":"{}","ascii":"{}","lossy":{},"unmapped":{},"slug":"{}"}}"#,
+        json::escape(&text),
+        json::escape(&ascii),
+        unmapped > 0,
+        unmapped,
+        text::slugify(&text)
+    ))
+}
+
+// check=anagram compares a and b, check=palindrome reads text both ways; case,
+// punctuation and, on request, accents are ignored
+fn handle_text_analyze_request(ctx: &RequestContext) -> String {
+    let flag = |name: &str, default: bool| match ctx.params.get(name) {
+        None => Ok(default),
+        Some("true" | "1") => Ok(true),
+        Some("false" | "0") => Ok(false),
+        Some(_) => Err(create_error_response(400, &format!("'{}' must be true or false", name))),
+    };
+    let folding = match (flag("ignore_case", true), flag("ignore_punctuation", true), flag("ignore_accents", false)) {
+        (Ok(case), Ok(punctuation), Ok(accents)) => text::Folding { case, punctuation, accents },
+        (Err(error), ..) | (_, Err(error), _) | (.., Err(error)) => return error,
+    };
+    let input = |name: &str| match parse_string(&ctx.params, name) {
+        Some(text) if text.chars().count() <= 10_000 => Ok(text),
+        Some(_) => Err(create_error_response(400, &format!("'{}' must be at most 10000 characters", name))),
+        None => Err(create_error_response(400, &format!("Missing '{}' parameter", name))),
+    };
+    let quoted = |clusters: &[String]| format!("\"{}\"", json::escape(&clusters.concat()));
+    let options = format!(
+        r#"{{"ignore_case":{},"ignore_punctuation":{},"ignore_accents":{}}}"#,
+        folding.case, folding.punctuation, folding.accents
+    );
+
+    let check = parse_string(&ctx.params, "check").unwrap_or_default();
+    match check.as_str() {
+        "anagram" => {
+            let (a, b) = match (input("a"), input("b")) {
+                (Ok(a), Ok(b)) => (a, b),
+                (Err(error), _) | (_, Err(error)) => return error,
+            };
+            let (folded_a, folded_b) = (text::fold(&a, &folding), text::fold(&b, &folding));
+            let (only_a, only_b) = text::anagram_difference(&folded_a, &folded_b);
+            create_json_response(&format!(
+                r#"{{"operation":"analyze","check":"anagram","a":"{}","b":"{}","normalized_a":{},"normalized_b":{},"anagram":{},"identical":{},"only_in_a":{},"only_in_b":{},"options":{}}}"#,
+                json::escape(&a),
+                json::escape(&b),
+                quoted(&folded_a),
+                quoted(&folded_b),
+                !folded_a.is_empty() && only_a.is_empty() && only_b.is_empty(),
+                folded_a == folded_b,
+                quoted(&only_a),
+                quoted(&only_b),
+                options
+            ))
+        }
+        "palindrome" => {
+            let text = match input("text") {
+                Ok(text) => text,
+                Err(error) => return error,
+            };
+            let folded = text::fold(&text, &folding);
+            let mismatch = text::palindrome_mismatch(&folded);
+            create_json_response(&format!(
+                r#"{{"operation":"analyze","check":"palindrome","text":"{}","normalized":{},"palindrome":{},"mismatch_at":{},"options":{}}}"#,
+                json::escape(&text),
+                quoted(&folded),
+                !folded.is_empty() && mismatch.is_none(),
+                mismatch.map_or("null".to_string(), |index| index.to_string()),
+                options
+            ))
+        }
+        _ => create_error_response(400, "Missing or unknown 'check' parameter, expected anagram or palindrome"),
+    }
+}
+
+// The phonetic code of a word, and with word2 whether the two sound alike
+fn handle_phonetic_request(ctx: &RequestContext) -> String {
+    let algorithm = parse_string(&ctx.params, "algo").unwrap_or("soundex".to_string());
+    let encode: fn(&str) -> Option<String> = match algorithm.as_str() {
+        "soundex" => text::soundex,
+        "metaphone" => text::metaphone,
+        _ => return create_error_response(400, &format!("Unknown algo '{}', expected soundex or metaphone", algorithm)),
+    };
+    let mut words = Vec::new();
+    for param in ["word", "word2"] {
+        let Some(word) = parse_string(&ctx.params, param) else {
+            continue;
+        };
+        if word.chars().count() > 100 {
+            return create_error_response(400, &format!("'{}' must be at most 100 characters", param));
+        }
+        match encode(&word) {
+            Some(code) => words.push((word, code)),
+            None => return create_error_response(400, &format!("'{}' has no letters to encode", param)),
+        }
+    }
+    let Some((word, code)) = words.first() else {
+        return create_error_response(400, "Missing 'word' parameter");
+    };
+    let comparison = match words.get(1) {
+        Some((word2, code2)) => format!(
+            r#","word2":"{}","code2":"{}","sounds_alike":{}"#,
+            json::escape(word2),
+            code2,
+            code == code2
+        ),
+        None => String::new(),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"phonetic","algo":"{}","word":"{}","code":"{}"{}}}"#,
+        algorithm,
+        json::escape(word),
+        code,
+        comparison
+    ))
+}
+
+fn handle_format_number_request(ctx: &RequestContext) -> String {
+    let value = match parse_string(&ctx.params, "value") {
+        Some(value) => value,
+        None => return create_error_response(400, "Missing 'value' parameter"),
+    };
+    let number = match formatting::parse(value.trim()) {
+        Ok(number) => number,
+        Err(message) => return create_error_response(400, &message),
+    };
+    let tag = parse_string(&ctx.params, "locale").unwrap_or("en-US".to_string());
+    let Some(locale) = formatting::find_locale(&tag) else {
+        let known: Vec<&str> = formatting::LOCALES.iter().map(|locale| locale.tag).collect();
+        return create_error_response(400, &format!("Unknown locale '{}', expected one of {}", tag, known.join(", ")));
+    };
+    let decimals = match ctx.params.get("decimals") {
+        Some(decimals) => match decimals.parse::<usize>() {
+            Ok(decimals) if decimals <= formatting::MAX_DECIMALS => Some(decimals),
+            _ => return create_error_response(400, &format!("Decimals must be between 0 and {}", formatting::MAX_DECIMALS)),
+        },
+        None => None,
+    };
+
+    let style = parse_string(&ctx.params, "style").unwrap_or("decimal".to_string());
+    let (formatted, currency) = match style.as_str() {
+        "decimal" => (formatting::decimal(&number, locale, decimals.unwrap_or(0), decimals.unwrap_or(3)), None),
+        "currency" => {
+            let code = parse_string(&ctx.params, "currency").unwrap_or(locale.currency.to_string());
+            let Some(currency) = formatting::find_currency(&code) else {
+                return create_error_response(400, &format!("'{}' is not a three-letter ISO 4217 currency code", code));
+            };
+            (formatting::currency(&number, locale, &currency, decimals), Some(currency.code))
+        }
+        _ => return create_error_response(400, &format!("Unknown style '{}', expected decimal or currency", style)),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"format_number","value":"{}","locale":"{}","style":"{}","currency":{},"formatted":"{}"}}"#,
+        json::escape(&value),
+        locale.tag,
+        style,
+        currency.map_or("null".to_string(), |code| format!("\"{}\"", code)),
+        json::escape(&formatted)
+    ))
+}
+
+fn handle_format_bytes_request(ctx: &RequestContext) -> String {
+    let bytes = match ctx.params.get("n").map(str::parse::<u64>) {
+        Some(Ok(bytes)) => bytes,
+        Some(Err(_)) => return create_error_response(400, "n must be a whole number of bytes"),
+        None => return create_error_response(400, "Missing 'n' parameter"),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"format_bytes","bytes":{},"si":"{}","iec":"{}"}}"#,
+        bytes,
+        humanize::bytes_si(bytes),
+        humanize::bytes_iec(bytes)
+    ))
+}
+
+fn handle_format_duration_request(ctx: &RequestContext) -> String {
+    let ms = match ctx.params.get("ms").map(str::parse::<u64>) {
+        Some(Ok(ms)) => ms,
+        Some(Err(_)) => return create_error_response(400, "ms must be a whole number of milliseconds"),
+        None => return create_error_response(400, "Missing 'ms' parameter"),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"format_duration","ms":{},"short":"{}","long":"{}","iso8601":"{}"}}"#,
+        ms,
+        humanize::duration(ms as f64),
+        humanize::duration_long(ms),
+        humanize::duration_iso8601(ms)
+    ))
+}
+
+fn handle_cipher_request(ctx: &RequestContext) -> String {
+    let text = match parse_string(&ctx.params, "text") {
+        Some(text) => text,
+        None => return create_error_response(400, "Missing 'text' parameter"),
+    };
+    if text.len() > 100_000 {
+        return create_error_response(400, "Text must be at most 100000 bytes");
+    }
+    let algorithm = parse_string(&ctx.params, "algo").unwrap_or("rot13".to_string());
+    let mode = parse_string(&ctx.params, "mode").unwrap_or("enc".to_string());
+    let decrypt = match mode.as_str() {
+        "enc" => false,
+        "dec" => true,
+        _ => return create_error_response(400, &format!("Unknown mode '{}', expected enc or dec", mode)),
+    };
+    let key = parse_string(&ctx.params, "key");
+
+    let (output, key) = match algorithm.as_str() {
+        // Its own inverse, so the mode makes no difference
+        "rot13" => (ciphers::rot13(&text), "null".to_string()),
+        "caesar" => {
+            let shift = match key.as_deref().map(str::parse::<i64>) {
+                None => 3,
+                Some(Ok(shift)) => shift,
+                Some(Err(_)) => return create_error_response(400, "Caesar key must be an integer shift"),
+            };
+            (ciphers::caesar(&text, if decrypt { -(shift % 26) } else { shift }), shift.to_string())
+        }
+        "vigenere" => {
+            let Some(key) = key else {
+                return create_error_response(400, "Missing 'key' parameter");
+            };
+            match ciphers::vigenere(&text, &key, decrypt) {
+                Ok(output) => (output, format!("\"{}\"", json::escape(&key))),
+                Err(message) => return create_error_response(400, &message),
+            }
+        }
+        _ => return create_error_response(400, &format!("Unknown algo '{}', expected one of: {}", algorithm, ciphers::ALGORITHMS.join(", "))),
+    };
+
+    create_json_response(&format!(
+        r#"{{"operation":"cipher","algo":"{}","mode":"{}","key":{},"input":"{}","output":"{}"}}"#,
+        algorithm,
+        mode,
+        key,
+        json::escape(&text),
+        json::escape(&output)
+    ))
+}
+
+// GET takes text= (or hex= to decrypt); POST XORs the raw request body
+fn handle_cipher_xor_request(ctx: &RequestContext) -> String {
+    let seed = match parse_param::<u64>(&ctx.params, "seed") {
+        Some(seed) => seed,
+        None => return create_error_response(400, "Missing or invalid 'seed' parameter"),
+    };
+    // POST transforms the body; GET takes text or hex from the query
+    let body = (ctx.method == "POST").then_some(ctx.body);
+    let mut bytes = match (body, parse_string(&ctx.params, "text"), parse_string(&ctx.params, "hex")) {
+        (Some(body), _, _) if !body.is_empty() => body.to_vec(),
+        (Some(_), _, _) => return create_error_response(400, "Request body is empty"),
+        (None, Some(text), _) => text.into_bytes(),
+        (None, None, Some(hex)) => match hashing::from_hex(&hex) {
+            Ok(bytes) => bytes,
+            Err(message) => return create_error_response(400, &message),
+        },
+        (None, None, None) => return create_error_response(400, "Missing 'text' or 'hex' parameter"),
+    };
+    if bytes.len() > 1_000_000 {
+        return create_error_response(400, "Input must be at most 1000000 bytes");
+    }
+
+    ciphers::xor_keystream(&mut bytes, seed);
+    create_json_response(&format!(
+        r#"{{"operation":"cipher_xor","seed":{},"secure":false,"bytes":{},"hex":"{}","text":{}}}"#,
+        seed,
+        bytes.len(),
+        hashing::to_hex(&bytes),
+        std::str::from_utf8(&bytes).map_or("null".to_string(), |text| format!("\"{}\"", json::escape(text)))
+    ))
+}
+
+// Encodes text or decodes dots and dashes, with the PARIS timing of the
+// result at `wpm` words per minute
+fn handle_morse_request(ctx: &RequestContext) -> String {
+    let text = match parse_string(&ctx.params, "text") {
+        Some(text) if !text.trim().is_empty() => text,
+        _ => return create_error_response(400, "Missing 'text' parameter"),
+    };
+    if text.chars().count() > 1000 {
+        return create_error_response(400, "Text must be at most 1000 characters");
+    }
+    let wpm = match ctx.params.get("wpm").map(str::parse::<u32>) {
+        None => 20,
+        Some(Ok(wpm)) if (5..=60).contains(&wpm) => wpm,
+        Some(_) => return create_error_response(400, "wpm must be between 5 and 60"),
+    };
+    let tone = match ctx.params.get("tone").map(str::parse::<u32>) {
+        None => 600,
+        Some(Ok(tone)) if (100..=4000).contains(&tone) => tone,
+        Some(_) => return create_error_response(400, "tone must be between 100 and 4000 Hz"),
+    };
+    let mode = parse_string(&ctx.params, "mode").unwrap_or("enc".to_string());
+    let words = match mode.as_str() {
+        "enc" => morse::encode(&text),
+        "dec" => morse::parse(&text),
+        _ => return create_error_response(400, &format!("Unknown mode '{}', expected enc or dec", mode)),
+    };
+    let (words, decoded) = match words.and_then(|words| morse::decode(&words).map(|decoded| (words, decoded))) {
+        Ok(result) => result,
+        Err(message) => return create_error_response(400, &message),
+    };
+
+    let durations = morse::durations(&words);
+    let units: u32 = durations.iter().sum();
+    // PARIS is 50 units, so a unit lasts 1200 / wpm milliseconds
+    let unit_ms = 1200.0 / f64::from(wpm);
+    let schedule: Vec<String> = durations.iter().map(|&units| format!("{:.1}", f64::from(units) * unit_ms)).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"morse","mode":"{}","text":"{}","morse":"{}","timing":"{}","units":{},"audio":{{"wpm":{},"unit_ms":{:.1},"tone_hz":{},"duration_ms":{:.1},"schedule_ms":[{}]}}}}"#,
+        mode,
+        json::escape(&decoded),
+        morse::to_text(&words),
+        morse::timing(&durations),
+        units,
+        wpm,
+        unit_ms,
+        tone,
+        f64::from(units) * unit_ms,
+        schedule.join(",")
+    ))
+}
+
+fn handle_rsa_demo_request(ctx: &RequestContext) -> String {
+    let bits: usize = parse_param(&ctx.params, "bits").unwrap_or(512);
+    if !(rsa::MIN_BITS..=rsa::MAX_BITS).contains(&bits) {
+        return create_error_response(400, &format!("Bits must be between {} and {}", rsa::MIN_BITS, rsa::MAX_BITS));
+    }
+    let message = parse_string(&ctx.params, "message").unwrap_or("hello from the edge".to_string());
+    let seed: Option<u64> = parse_param(&ctx.params, "seed");
+
+    // Workers only advance the clock on I/O, so these can read 0 in
+    // production; they are meaningful under wrangler dev and native builds
+    let started = host::now_ms();
+    let generation = match seed {
+        Some(seed) => rsa::generate(bits, &mut rand::Rng::new(seed)),
+        None => rand::with_global(|rng| rsa::generate(bits, rng)),
+    };
+    let Some(generation) = generation else {
+        return budget_exhausted_response();
+    };
+    let generated = host::now_ms();
+
+    let mut sha = hashing::Sha256::new();
+    sha.update(message.as_bytes());
+    let digest = sha.finalize();
+    let keys = &generation.keys;
+    let signature = rsa::sign(&bignum::BigUint::from_bytes_be(&digest), keys);
+    let signed = host::now_ms();
+
+    let valid = rsa::verify(&bignum::BigUint::from_bytes_be(&digest), &signature, &keys.n, &keys.e);
+    let verified = host::now_ms();
+
+    // The same signature must not verify for a different message
+    let mut sha = hashing::Sha256::new();
+    sha.update(message.as_bytes());
+    sha.update(b"!");
+    let tampered = rsa::verify(&bignum::BigUint::from_bytes_be(&sha.finalize()), &signature, &keys.n, &keys.e);
+
+    create_json_response(&format!(
+        r#"{{"operation":"rsa_demo","bits":{},"seed":{},"secure":false,"public_key":{{"n":"{}","e":"{}"}},"private_key":{{"d":"{}","p":"{}","q":"{}"}},"message":"{}","digest":"{}","signature":"{}","valid":{},"tampered_valid":{},"prime_candidates":{},"timing_ms":{{"keygen":{},"sign":{},"verify":{}}}}}"#,
+        bits,
+        seed.map_or("null".to_string(), |seed| seed.to_string()),
+        keys.n,
+        keys.e,
+        keys.d,
+        keys.p,
+        keys.q,
+        json::escape(&message),
+        hashing::to_hex(&digest),
+        signature,
+        valid,
+        tampered,
+        generation.candidates,
+        generated - started,
+        signed - generated,
+        verified - signed
+    ))
+}
+
+fn handle_ulid_request(ctx: &RequestContext) -> String {
+    let count: usize = parse_param(&ctx.params, "count").unwrap_or(1);
+    if !(1..=100).contains(&count) {
+        return create_error_response(400, "Count must be between 1 and 100");
+    }
+    let ids: Vec<String> = (0..count).map(|_| format!("\"{}\"", ids::ulid())).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"ulid","count":{},"timestamp_ms":{},"ids":[{}]}}"#,
+        count,
+        host::now_ms() as u64,
+        ids.join(",")
+    ))
+}
+
+fn handle_nanoid_request(ctx: &RequestContext) -> String {
+    let length: usize = parse_param(&ctx.params, "length").unwrap_or(ids::NANOID_LENGTH);
+    if !(1..=256).contains(&length) {
+        return create_error_response(400, "Length must be between 1 and 256");
+    }
+    let count: usize = parse_param(&ctx.params, "count").unwrap_or(1);
+    if !(1..=100).contains(&count) {
+        return create_error_response(400, "Count must be between 1 and 100");
+    }
+    let alphabet: Vec<char> = parse_string(&ctx.params, "alphabet").unwrap_or(ids::NANOID_ALPHABET.to_string()).chars().collect();
+    if !(2..=256).contains(&alphabet.len()) {
+        return create_error_response(400, "Alphabet must have between 2 and 256 characters");
+    }
+    if alphabet.iter().any(|c| c.is_control()) {
+        return create_error_response(400, "Alphabet must not contain control characters");
+    }
+    if alphabet.iter().enumerate().any(|(i, c)| alphabet[..i].contains(c)) {
+        return create_error_response(400, "Alphabet characters must be unique");
+    }
+
+    let ids: Vec<String> = (0..count).map(|_| format!("\"{}\"", json::escape(&ids::nanoid(&alphabet, length)))).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"nanoid","length":{},"alphabet_size":{},"count":{},"ids":[{}]}}"#,
+        length,
+        alphabet.len(),
+        count,
+        ids.join(",")
+    ))
+}
+
+fn handle_snowflake_request(ctx: &RequestContext) -> String {
+    let count: usize = parse_param(&ctx.params, "count").unwrap_or(1);
+    if !(1..=100).contains(&count) {
+        return create_error_response(400, "Count must be between 1 and 100");
+    }
+    let config = ctx.config;
+    let mut ids = Vec::with_capacity(count);
+    for _ in 0..count {
+        match snowflake::generate(config) {
+            Ok(id) => ids.push(snowflake::decode(id, config.snowflake_epoch_ms)),
+            Err(message) => return create_error_response(500, &message),
+        }
+    }
+
+    // IDs are strings because they exceed JavaScript's safe integer range
+    let ids: Vec<String> = ids.iter()
+        .map(|id| format!(
+            r#"{{"id":"{}","timestamp_ms":{},"node_id":{},"sequence":{}}}"#,
+            id.id, id.timestamp_ms, id.node_id, id.sequence
+        ))
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"snowflake","node_id":{},"epoch_ms":{},"count":{},"clock_regressions":{},"ids":[{}]}}"#,
+        config.snowflake_node_id,
+        config.snowflake_epoch_ms,
+        count,
+        snowflake::clock_regressions(),
+        ids.join(",")
+    ))
+}
+
+// The URL is the raw body, or the url field of a form submission
+fn handle_shorten_request(ctx: &RequestContext) -> String {
+    let Ok(body) = std::str::from_utf8(ctx.body) else {
+        return invalid_utf8_body_response();
+    };
+    let target = if params::is_form(ctx.headers.get("content-type")) {
+        match QueryParams::parse_form(body) {
+            Ok(form) => form.get("url").unwrap_or("").trim().to_string(),
+            Err(reason) => return create_error_response(400, &reason),
+        }
+    } else {
+        body.trim().to_string()
+    };
+    if target.is_empty() {
+        return create_error_response(400, "Body must be the URL to shorten");
+    }
+    if target.len() > 2048 {
+        return create_error_response(400, "URL must be at most 2048 bytes");
+    }
+    match url::parse(&target) {
+        Ok(parsed) if parsed.scheme == "http" || parsed.scheme == "https" => {}
+        Ok(parsed) => return create_error_response(400, &format!("Only http and https URLs can be shortened, not {}", parsed.scheme)),
+        Err(reason) => return create_error_response(400, &format!("Invalid URL: {}", reason)),
+    }
+
+    let link = match shortener::shorten(&target) {
+        Ok(Some(link)) => link,
+        Ok(None) => return create_error_response(409, "Every code for this URL is taken"),
+        Err(kv::Pending) => return kv_pending_response(),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"shorten","url":"{}","code":"{}","path":"/s/{}","created":{},"hits":{}}}"#,
+        json::escape(&link.url),
+        link.code,
+        link.code,
+        link.created,
+        link.hits
+    ))
+}
+
+// Redirects and counts a hit; ?stats returns the link details instead. HEAD
+// (link checkers, unfurlers) gets the same redirect without counting one.
+fn handle_short_link_request(ctx: &RequestContext, code: &str) -> String {
+    if !shortener::is_valid_code(code) {
+        return create_error_response(404, "Unknown short code");
+    }
+    let stats = ctx.params.get("stats").is_some();
+    match shortener::resolve(code, !stats && ctx.method != "HEAD") {
+        Ok(Some(link)) if stats => create_json_response(&format!(
+            r#"{{"operation":"short_link","code":"{}","url":"{}","hits":{}}}"#,
+            link.code,
+            json::escape(&link.url),
+            link.hits
+        )),
+        Ok(Some(link)) => create_redirect_response(302, &link.url),
+        Ok(None) => create_error_response(404, "Unknown short code"),
+        Err(kv::Pending) => kv_pending_response(),
+    }
+}
+
+fn handle_paste_request(ctx: &RequestContext) -> String {
+    let config = ctx.config;
+    if ctx.body.is_empty() {
+        return create_error_response(400, "Body must be the text to paste");
+    }
+    if ctx.body.len() > config.max_paste_bytes {
+        return create_error_response(413, &format!("Paste must be at most {} bytes", config.max_paste_bytes));
+    }
+    let Ok(text) = std::str::from_utf8(ctx.body) else {
+        return create_error_response(400, "Paste must be UTF-8 text");
+    };
+    let ttl: u32 = parse_param(&ctx.params, "ttl").unwrap_or(paste::DEFAULT_TTL_SECONDS.min(config.max_paste_ttl_seconds));
+    if !(paste::MIN_TTL_SECONDS..=config.max_paste_ttl_seconds).contains(&ttl) {
+        return create_error_response(
+            400,
+            &format!("TTL must be between {} and {} seconds", paste::MIN_TTL_SECONDS, config.max_paste_ttl_seconds),
+        );
+    }
+
+    let paste = paste::create(text, ttl);
+    create_json_response(&format!(
+        r#"{{"operation":"paste","id":"{}","path":"/paste/{}","bytes":{},"ttl_seconds":{},"created_ms":{},"expires_ms":{}}}"#,
+        paste.id,
+        paste.id,
+        paste.text.len(),
+        ttl,
+        paste.created_ms,
+        paste.expires_ms
+    ))
+}
+
+// Renders the paste as an escaped HTML page, or as plain text with ?raw
+fn handle_paste_view_request(ctx: &RequestContext, id: &str) -> String {
+    if !paste::is_valid_id(id) {
+        return create_error_response(404, "Paste not found or expired");
+    }
+    let paste = match paste::load(id) {
+        Ok(Some(paste)) => paste,
+        Ok(None) => return create_error_response(404, "Paste not found or expired"),
+        Err(kv::Pending) => return kv_pending_response(),
+    };
+    if ctx.params.get("raw").is_some() {
+        return create_text_response("text/plain; charset=utf-8", &paste.text);
+    }
+    let remaining = paste.expires_ms.saturating_sub(host::now_ms() as u64) / 1000;
+    create_html_response(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Paste {}</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; max-width: 800px; margin: 0 auto; padding: 20px; }}
+        pre {{ background: #f5f5f5; padding: 15px; border-radius: 5px; overflow-x: auto; }}
+    </style>
+</head>
+<body>
+    <h1>Paste {}</h1>
+    <p>{} bytes, expires in {} seconds. <a href="/paste/{}?raw">Raw</a></p>
+    <pre><code>{}</code></pre>
+</body>
+</html>"#,
+        paste.id,
+        paste.id,
+        paste.text.len(),
+        remaining,
+        paste.id,
+        html::escape(&paste.text)
+    ))
+}
+
+// Stores the body under its SHA-256, so identical uploads share one id
+fn handle_blob_request(ctx: &RequestContext) -> String {
+    let max_bytes = ctx.config.max_blob_bytes;
+    if ctx.body.is_empty() {
+        return create_error_response(400, "Body must be the content to store");
+    }
+    if ctx.body.len() > max_bytes {
+        return create_error_response(413, &format!("Blob must be at most {} bytes", max_bytes));
+    }
+    let content_type = match ctx.headers.get("content-type").map(str::trim) {
+        // A '|' would split the response string the blob is served in
+        Some(content_type) if !security::is_valid_value(content_type) => {
+            return create_error_response(400, "Content-Type must not contain '|' or line breaks")
+        }
+        Some(content_type) if !content_type.is_empty() && !content_type.bytes().any(|b| b.is_ascii_control()) => content_type,
+        _ => mime::detect_bytes(ctx.body).mime,
+    };
+    let (id, created) = match blob::store(ctx.body, content_type) {
+        Ok(stored) => stored,
+        Err(kv::Pending) => return kv_pending_response(),
+    };
+    let body = format!(
+        r#"{{"operation":"blob","id":"{}","path":"/blob/{}","bytes":{},"created":{}}}"#,
+        id,
+        id,
+        ctx.body.len(),
+        created
+    );
+    if created {
+        format!("201|application/json|{}", body)
+    } else {
+        create_json_response(&body)
+    }
+}
+
+// Blob contents never change, so they may be cached for good. The content
+// type is the uploader's: types a browser would run are sent as
+// application/octet-stream, and a sandbox CSP and nosniff keep the rest from
+// acting as pages or scripts on this origin.
+fn handle_blob_view_request(ctx: &RequestContext, id: &str) -> String {
+    if !blob::is_valid_id(id) {
+        return create_error_response(404, "Blob not found");
+    }
+    let etag = format!("\"{}\"", id);
+    let blob = match blob::load(id) {
+        Ok(Some(blob)) => blob,
+        Ok(None) => return create_error_response(404, "Blob not found"),
+        Err(kv::Pending) => return kv_pending_response(),
+    };
+    // Blobs stored before Content-Types were checked may still hold a '|'
+    let content_type = if mime::is_active(&blob.content_type) || !security::is_valid_value(&blob.content_type) {
+        "application/octet-stream"
+    } else {
+        &blob.content_type
+    };
+    let response = if ctx.headers.get("if-none-match").is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == etag)) {
+        format!("304|{}|", content_type)
+    } else {
+        create_binary_response(content_type, &blob.bytes)
+    };
+    let response = with_header(response, "Content-Security-Policy", "sandbox");
+    let response = with_header(response, "X-Content-Type-Options", "nosniff");
+    let response = with_header(response, "ETag", &etag);
+    let response = with_header(response, "Last-Modified", &dates::rfc822(blob.created_ms));
+    with_header(response, "Cache-Control", "public, max-age=31536000, immutable")
+}
+
+// Pinned assignments last 30 days
+const AB_COOKIE_MAX_AGE_SECONDS: u32 = 30 * 86_400;
+
+// Assigns the id a variant of the experiment, configured in CONFIG or given
+// as ?variants=. An ab_<experiment> cookie naming one of its variants wins
+// over the hash, and ?set_cookie=true pins the assignment with one.
+fn handle_ab_request(ctx: &RequestContext) -> String {
+    let Some(experiment) = parse_string(&ctx.params, "experiment") else {
+        return create_error_response(400, "Missing experiment");
+    };
+    if !experiments::is_valid_name(&experiment) {
+        return create_error_response(400, "Experiment must be 1 to 64 letters, digits, '-' or '_'");
+    }
+    let configured = ctx.config.experiments.iter().find(|(name, _)| *name == experiment).map(|(_, variants)| variants.clone());
+    let variants = match (configured, parse_string(&ctx.params, "variants")) {
+        (Some(variants), _) => variants,
+        (None, Some(spec)) => match experiments::parse_variants(&spec) {
+            Ok(variants) => variants,
+            Err(reason) => return create_error_response(400, &reason),
+        },
+        (None, None) => {
+            return create_error_response(404, &format!("Experiment {} is not configured; pass variants=a:50,b:50 to try one", experiment))
+        }
+    };
+    let cookie_name = format!("ab_{}", experiment);
+    let pinned = ctx
+        .headers
+        .get("cookie")
+        .and_then(|cookie| headers::cookie(cookie, &cookie_name))
+        .and_then(|value| variants.iter().find(|variant| variant.name == value && variant.weight > 0.0));
+    let id = parse_string(&ctx.params, "id");
+    let (variant, position, source) = match (pinned, &id) {
+        (Some(variant), _) => (Some(variant), None, "cookie"),
+        (None, Some(id)) => {
+            let position = experiments::position(&experiment, id);
+            (experiments::assign(&variants, position), Some(position), "hash")
+        }
+        (None, None) => return create_error_response(400, "Missing id"),
+    };
+    let Some(variant) = variant else {
+        return create_error_response(400, "At least one variant needs a positive weight");
+    };
+
+    let total: f64 = variants.iter().map(|variant| variant.weight).sum();
+    let split: Vec<String> = variants
+        .iter()
+        .map(|variant| format!(r#"{{"name":"{}","share":{}}}"#, variant.name, variant.weight / total))
+        .collect();
+    let response = create_json_response(&format!(
+        r#"{{"operation":"ab","experiment":"{}","id":{},"variant":"{}","source":"{}","position":{},"variants":[{}]}}"#,
+        experiment,
+        id.as_deref().map_or("null".to_string(), |id| format!("\"{}\"", json::escape(id))),
+        variant.name,
+        source,
+        position.map_or("null".to_string(), |position| position.to_string()),
+        split.join(",")
+    ));
+    let response = with_header(response, "Vary", "Cookie");
+    if parse_string(&ctx.params, "set_cookie").is_some_and(|set| set == "true" || set == "1") {
+        let cookie = format!("{}={}; Path=/; Max-Age={}; SameSite=Lax", cookie_name, variant.name, AB_COOKIE_MAX_AGE_SECONDS);
+        with_header(response, "Set-Cookie", &cookie)
+    } else {
+        response
+    }
+}
+
+// The request's flag attributes with its parameters other than `skip` put
+// right after the user, so that they override country and language
+fn flag_attributes(ctx: &RequestContext, user: &str, skip: &[&str]) -> Vec<(String, String)> {
+    let mut attributes = ctx.flag_attributes(user);
+    let params = ctx.params.pairs().iter().filter(|(name, _)| !skip.contains(&name.as_str())).cloned();
+    attributes.splice(1..1, params);
+    attributes
+}
+
+// Parameters besides flag and user are taken as attributes, e.g. plan=pro
+fn handle_flags_evaluate_request(ctx: &RequestContext) -> String {
+    let (Some(name), Some(user)) = (parse_string(&ctx.params, "flag"), parse_string(&ctx.params, "user")) else {
+        return create_error_response(400, "Missing 'flag' or 'user' parameter");
+    };
+    if !flags::is_valid_name(&name) {
+        return create_error_response(400, "Flag must be 1 to 64 letters, digits, '-' or '_'");
+    }
+    let flag = match flags::find(&ctx.config.flags, &name) {
+        Ok(Ok(Some(found))) => found,
+        Ok(Ok(None)) => return create_error_response(404, &format!("Flag {} is not defined in CONFIG or KV", name)),
+        Ok(Err(reason)) => return create_error_response(500, &reason),
+        Err(kv::Pending) => return kv_pending_response(),
+    };
+    let attributes = flag_attributes(ctx, &user, &["flag", "user"]);
+    let evaluation = flags::evaluate(&flag, &user, &attributes);
+    // As evaluated, the first of a repeated name
+    let mut listed: Vec<(&str, String)> = Vec::new();
+    for (name, value) in &attributes {
+        if !listed.iter().any(|(seen, _)| seen == name) {
+            listed.push((name, format!("\"{}\":\"{}\"", json::escape(name), json::escape(value))));
+        }
+    }
+    let attributes: Vec<String> = listed.into_iter().map(|(_, member)| member).collect();
+    let response = create_json_response(&format!(
+        r#"{{"operation":"flag_evaluate","flag":"{}","source":"{}","user":"{}","value":{},"on":{},"reason":"{}","rule":{},"bucket":{},"attributes":{{{}}}}}"#,
+        name,
+        flag.source,
+        json::escape(&user),
+        evaluation.value,
+        evaluation.on,
+        evaluation.reason,
+        evaluation.rule.map_or("null".to_string(), |rule| format!("\"{}\"", json::escape(&rule))),
+        evaluation.bucket,
+        attributes.join(",")
+    ));
+    with_header(response, "Vary", "CF-IPCountry, Accept-Language")
+}
+
+// Finds the node owning a key on a ring of ?nodes=, or on the isolate's ring
+// when none are given. ?replicas= also lists the next distinct nodes, where
+// copies of the key would go.
+fn handle_ring_locate_request(ctx: &RequestContext) -> String {
+    let Some(key) = parse_string(&ctx.params, "key") else {
+        return create_error_response(400, "Missing key");
+    };
+    let replicas = parse_number(&ctx.params, "replicas").unwrap_or(1);
+    if !(1..=10).contains(&replicas) {
+        return create_error_response(400, "Replicas must be between 1 and 10");
+    }
+    let locate = |ring: &hashring::Ring, source: &str| {
+        let preference = ring.preference(&key, replicas as usize);
+        let Some(node) = preference.first() else {
+            return create_error_response(409, "The ring has no nodes; pass nodes=a,b,c or set ring_nodes in CONFIG");
+        };
+        let preference: Vec<String> = preference.iter().map(|node| format!("\"{}\"", json::escape(node))).collect();
+        create_json_response(&format!(
+            r#"{{"operation":"ring_locate","key":"{}","hash":"{:016x}","node":"{}","replicas":[{}],"ring":"{}","nodes":{},"vnodes":{}}}"#,
+            json::escape(&key),
+            hashring::hash(&key),
+            json::escape(node),
+            preference.join(","),
+            source,
+            ring.nodes().len(),
+            ring.vnodes()
+        ))
+    };
+    let Some(nodes) = parse_string(&ctx.params, "nodes") else {
+        return hashring::with_isolate_ring(|ring| locate(ring, "isolate"));
+    };
+    let vnodes: u32 = parse_param(&ctx.params, "vnodes").unwrap_or(hashring::DEFAULT_VNODES);
+    if !(1..=hashring::MAX_VNODES).contains(&vnodes) {
+        return create_error_response(400, &format!("vnodes must be between 1 and {}", hashring::MAX_VNODES));
+    }
+    let nodes: Vec<&str> = nodes.split(',').map(str::trim).collect();
+    if nodes.len() > 100 {
+        return create_error_response(400, "At most 100 nodes can be passed in the query");
+    }
+    let mut ring = hashring::Ring::new(vnodes);
+    for node in nodes {
+        if !ring.add(node) {
+            return create_error_response(400, &format!("Node '{}' is empty, too long or listed twice", node));
+        }
+    }
+    locate(&ring, "query")
+}
+
+// Picks an origin for a visitor by the routing_rules in CONFIG. ?country= and
+// ?lang= stand in for the CF-IPCountry and Accept-Language headers, and
+// ?path= (default /) is the key that spreads visitors over a rule's origins.
+fn handle_route_decide_request(ctx: &RequestContext) -> String {
+    let country_param = parse_string(&ctx.params, "country");
+    let country = routing::country(country_param.as_deref().or(ctx.headers.get("cf-ipcountry")));
+    let lang_param = parse_string(&ctx.params, "lang");
+    let accept_language = lang_param.as_deref().or(ctx.headers.get("accept-language")).unwrap_or("");
+    let languages: Vec<String> = headers::accept_languages(accept_language).into_iter().map(|(tag, _)| tag).collect();
+    let key = parse_string(&ctx.params, "path").unwrap_or("/".to_string());
+
+    let config = ctx.config;
+    let decision = routing::decide(&config.routing_rules, config.routing_default_origin.as_deref(), country.as_deref(), &languages, &key);
+    let quoted = |value: Option<&str>| value.map_or("null".to_string(), |value| format!("\"{}\"", json::escape(value)));
+    let languages: Vec<String> = languages.iter().map(|language| format!("\"{}\"", json::escape(language))).collect();
+    let response = create_json_response(&format!(
+        r#"{{"operation":"route_decide","country":{},"languages":[{}],"path":"{}","rule":{},"matched_language":{},"origin":{},"cache_key":"{}"}}"#,
+        quoted(country.as_deref()),
+        languages.join(","),
+        json::escape(&key),
+        quoted(decision.rule.map(|rule| rule.name.as_str())),
+        quoted(decision.language.as_deref()),
+        quoted(decision.origin.as_deref()),
+        json::escape(&decision.cache_key)
+    ));
+    with_header(response, "Vary", "CF-IPCountry, Accept-Language")
+}
+
+// Relays a GET to one of the configured fetch_origins, with its status, content
+// type and body, through that origin's circuit breaker
+fn handle_fetch_request(ctx: &RequestContext) -> String {
+    let Some(target) = parse_string(&ctx.params, "url") else {
+        return create_error_response(400, "Missing url");
+    };
+    match fetch_from_origin(ctx, &target) {
+        Ok((origin, response)) => {
+            // A '|' (legal in media type parameters) or line break would split
+            // the response string
+            let content_type = if response.content_type.is_empty() || !security::is_valid_value(&response.content_type) {
+                "application/octet-stream"
+            } else {
+                &response.content_type
+            };
+            let relayed = format!("{}|{}\n{}: base64|{}", response.status, content_type, BINARY_BODY_HEADER, hashing::to_base64(&response.body));
+            with_header(relayed, "X-Fetch-Origin", &origin)
+        }
+        Err(response) => response,
+    }
+}
+
+// GETs a URL on one of the configured fetch_origins through that origin's
+// circuit breaker, with the origin; Err is the response to send instead
+fn fetch_from_origin(ctx: &RequestContext, target: &str) -> Result<(String, fetch::Response), String> {
+    let origin = match url::parse(target) {
+        Ok(parsed) if matches!(parsed.scheme.as_str(), "http" | "https") && parsed.userinfo.is_none() => parsed.origin(),
+        Ok(_) => return Err(create_error_response(400, "URL must be http or https, without credentials")),
+        Err(reason) => return Err(create_error_response(400, &format!("Invalid URL: {}", reason))),
+    };
+    if !ctx.config.fetch_origins.contains(&origin) {
+        return Err(create_error_response(403, &format!("{} is not one of the configured fetch_origins", origin)));
+    }
+    match breaker::fetch(&origin, target, ctx.config) {
+        Ok(response) => Ok((origin, response)),
+        Err(breaker::Error::Pending) => Err(kv_pending_response()),
+        Err(breaker::Error::Open { retry_after_ms }) => Err(with_header(
+            create_error_response(503, &format!("Circuit breaker for {} is open", origin)),
+            "Retry-After",
+            &retry_after_ms.div_ceil(1000).to_string(),
+        )),
+        Err(breaker::Error::Failed(reason)) => Err(create_error_response(502, &format!("Could not reach {}: {}", origin, reason))),
+    }
+}
+
+// Repeatable meta=name=content and prefix=from,to parameters, and a nonce
+// (nonce=random for a fresh one); Err is the response to send instead
+fn rewrite_rules(ctx: &RequestContext) -> Result<html_rewrite::Rules, String> {
+    let mut rules = html_rewrite::Rules::default();
+    for (name, value) in ctx.params.pairs() {
+        match name.as_str() {
+            "meta" => match value.split_once('=') {
+                Some((name, content)) if !name.is_empty() => rules.meta.push((name.to_string(), content.to_string())),
+                _ => return Err(create_error_response(400, "meta must be name=content")),
+            },
+            "prefix" => match value.split_once(',') {
+                Some((from, to)) if !from.is_empty() => rules.prefixes.push((from.to_string(), to.to_string())),
+                _ => return Err(create_error_response(400, "prefix must be from,to")),
+            },
+            _ => {}
+        }
+    }
+    rules.nonce = match parse_string(&ctx.params, "nonce").as_deref() {
+        None => None,
+        Some("random") => {
+            let mut random = [0u8; 16];
+            host::random_bytes(&mut random);
+            Some(hashing::to_base64(&random))
+        }
+        Some(nonce) if (1..=64).contains(&nonce.len()) && nonce.bytes().all(|b| b.is_ascii_alphanumeric() || b"+/=-_".contains(&b)) => {
+            Some(nonce.to_string())
+        }
+        Some(_) => return Err(create_error_response(400, "Nonce must be 1 to 64 base64 characters, or random")),
+    };
+    if rules.meta.is_empty() && rules.prefixes.is_empty() && rules.nonce.is_none() {
+        return Err(create_error_response(400, "Nothing to rewrite; pass meta, prefix or nonce"));
+    }
+    Ok(rules)
+}
+
+fn rewritten_response(page: &str, rules: &html_rewrite::Rules) -> String {
+    let (page, stats) = html_rewrite::rewrite(page, rules);
+    let summary = format!(
+        "meta_injected={}, meta_replaced={}, urls_rewritten={}, nonces_added={}",
+        stats.meta_injected, stats.meta_replaced, stats.urls_rewritten, stats.nonces_added
+    );
+    with_header(create_html_response(page), "X-Rewrite-Stats", &summary)
+}
+
+// Rewrites a page from one of the fetch_origins; with a nonce, the page is
+// sent with a Content-Security-Policy that lets exactly its nonced scripts
+// and styles run
+fn handle_rewrite_request(ctx: &RequestContext) -> String {
+    let Some(target) = parse_string(&ctx.params, "url") else {
+        return create_error_response(400, "Missing url");
+    };
+    let rules = match rewrite_rules(ctx) {
+        Ok(rules) => rules,
+        Err(response) => return response,
+    };
+    let (origin, page) = match fetch_from_origin(ctx, &target) {
+        Ok(fetched) => fetched,
+        Err(response) => return response,
+    };
+    if !page.content_type.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case("text/html") {
+        return create_error_response(502, &format!("{} answered with {}, not an HTML page", origin, page.content_type));
+    }
+    let response = with_header(rewritten_response(&String::from_utf8_lossy(&page.body), &rules), "X-Fetch-Origin", &origin);
+    match &rules.nonce {
+        Some(nonce) => with_header(
+            response,
+            "Content-Security-Policy",
+            &format!(
+                "default-src 'self'; script-src 'nonce-{0}'; style-src 'self' 'nonce-{0}'; img-src 'self' data:; base-uri 'none'; form-action 'self'; frame-ancestors 'none'",
+                nonce
+            ),
+        ),
+        None => response,
+    }
+}
+
+// The posted page, rewritten; it keeps the default Content-Security-Policy,
+// as the page is the client's own
+fn handle_rewrite_post_request(ctx: &RequestContext, page: &str) -> String {
+    match rewrite_rules(ctx) {
+        Ok(rules) => rewritten_response(page, &rules),
+        Err(response) => response,
+    }
+}
+
+// Title, description, canonical URL and language of a posted page, with its
+// og:* and twitter:* fields by name (the first of a repeated one); values
+// are unescaped and left as written otherwise, relative URLs included
+fn handle_meta_extract_request(page: &str) -> String {
+    let (mut title, mut description, mut canonical, mut language) = (None, None, None, None);
+    let mut open_graph: Vec<(String, String)> = Vec::new();
+    let mut twitter: Vec<(String, String)> = Vec::new();
+    for tag in html_rewrite::tags(page).filter(|tag| !tag.closing) {
+        match tag.name.as_str() {
+            "html" => language = language.or(tag.attribute("lang").map(html::unescape)),
+            // The first; an inline SVG's own <title> comes later
+            "title" if title.is_none() => title = tag.text.map(|text| html::unescape(text).split_whitespace().collect::<Vec<_>>().join(" ")),
+            "link" if canonical.is_none() && tag.has_rel("canonical") => canonical = tag.attribute("href").map(html::unescape),
+            "meta" => {
+                let (Some(name), Some(content)) = (tag.attribute("property").or(tag.attribute("name")), tag.attribute("content")) else {
+                    continue;
+                };
+                let name = name.to_ascii_lowercase();
+                let content = html::unescape(content);
+                if name == "description" {
+                    description = description.or(Some(content));
+                } else if let Some(field) = name.strip_prefix("og:") {
+                    if !open_graph.iter().any(|(seen, _)| seen == field) {
+                        open_graph.push((field.to_string(), content));
+                    }
+                } else if let Some(field) = name.strip_prefix("twitter:") {
+                    if !twitter.iter().any(|(seen, _)| seen == field) {
+                        twitter.push((field.to_string(), content));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let quoted = |value: &Option<String>| value.as_deref().map_or("null".to_string(), |value| format!("\"{}\"", json::escape(value)));
+    let fields = |fields: &[(String, String)]| {
+        fields.iter().map(|(name, value)| format!("\"{}\":\"{}\"", json::escape(name), json::escape(value))).collect::<Vec<_>>().join(",")
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"meta_extract","title":{},"description":{},"canonical":{},"language":{},"open_graph":{{{}}},"twitter":{{{}}}}}"#,
+        quoted(&title),
+        quoted(&description),
+        quoted(&canonical),
+        quoted(&language),
+        fields(&open_graph),
+        fields(&twitter)
+    ))
+}
+
+// `path` may be a full URL, whose path and query are checked
+fn handle_robots_check_request(ctx: &RequestContext, text: &str) -> String {
+    let agent = parse_string(&ctx.params, "agent").unwrap_or_else(|| "*".to_string());
+    let Some(target) = parse_string(&ctx.params, "path") else {
+        return create_error_response(400, "Missing path");
+    };
+    let path = match url::parse(&target) {
+        Ok(parsed) => match parsed.query {
+            Some(query) => format!("{}?{}", if parsed.path.is_empty() { "/" } else { &parsed.path }, query),
+            None if parsed.path.is_empty() => "/".to_string(),
+            None => parsed.path,
+        },
+        Err(_) if target.starts_with('/') => target.clone(),
+        Err(_) => return create_error_response(400, "Path must start with '/' or be a full URL"),
+    };
+
+    let robots = robots::parse(text);
+    let verdict = robots.check(&agent, &path);
+    let quoted = |value: &str| format!("\"{}\"", json::escape(value));
+    let rule = verdict.rule.map_or("null".to_string(), |rule| {
+        format!(
+            r#"{{"type":"{}","pattern":{},"line":{}}}"#,
+            if rule.allow { "allow" } else { "disallow" },
+            quoted(&rule.pattern),
+            rule.line
+        )
+    });
+    let sitemaps: Vec<String> = robots.sitemaps.iter().map(|sitemap| quoted(sitemap)).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"robots_check","agent":{},"product_token":{},"path":{},"allowed":{},"group":{},"rule":{},"crawl_delay":{},"sitemaps":[{}]}}"#,
+        quoted(&agent),
+        quoted(&robots::product_token(&agent)),
+        quoted(&path),
+        verdict.allowed,
+        verdict.group.as_deref().map_or("null".to_string(), quoted),
+        rule,
+        verdict.crawl_delay.map_or("null".to_string(), |delay| delay.to_string()),
+        sitemaps.join(",")
+    ))
+}
+
+// Every <script type="application/ld+json"> block of a posted page, parsed
+// and checked; the page is valid when no block has a parse error or an item
+// with an error, warnings aside
+fn handle_jsonld_validate_request(page: &str) -> String {
+    let (mut blocks, mut errors, mut warnings) = (Vec::new(), 0, 0);
+    let scripts = html_rewrite::tags(page).filter(|tag| {
+        tag.name == "script" && !tag.closing && tag.attribute("type").is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"))
+    });
+    for (index, tag) in scripts.enumerate() {
+        let line = page[..tag.range.start].matches('\n').count() + 1;
+        let (parse_error, items) = match json::parse(tag.text.unwrap_or("").trim()) {
+            Ok(block) => ("null".to_string(), jsonld::validate(&block)),
+            Err(reason) => {
+                errors += 1;
+                (format!("\"{}\"", json::escape(&reason)), Vec::new())
+            }
+        };
+        let items: Vec<String> = items
+            .iter()
+            .map(|item| {
+                let issues: Vec<String> = item
+                    .issues
+                    .iter()
+                    .map(|issue| {
+                        match issue.severity {
+                            jsonld::Severity::Error => errors += 1,
+                            jsonld::Severity::Warning => warnings += 1,
+                        }
+                        format!(
+                            r#"{{"severity":"{}","path":"{}","message":"{}"}}"#,
+                            issue.severity.name(),
+                            json::escape(&issue.path),
+                            json::escape(&issue.message)
+                        )
+                    })
+                    .collect();
+                let types: Vec<String> = item.types.iter().map(|name| format!("\"{}\"", json::escape(name))).collect();
+                format!(
+                    r#"{{"path":"{}","types":[{}],"checked":{},"issues":[{}]}}"#,
+                    json::escape(&item.path),
+                    types.join(","),
+                    item.checked,
+                    issues.join(",")
+                )
+            })
+            .collect();
+        blocks.push(format!(r#"{{"index":{},"line":{},"parse_error":{},"items":[{}]}}"#, index, line, parse_error, items.join(",")));
+    }
+    create_json_response(&format!(
+        r#"{{"operation":"jsonld_validate","valid":{},"errors":{},"warnings":{},"count":{},"blocks":[{}]}}"#,
+        errors == 0,
+        errors,
+        warnings,
+        blocks.len(),
+        blocks.join(",")
+    ))
+}
+
+// The body is a response head: an optional "HTTP/1.1 200 OK" status line,
+// then one header per line. The cache is shared (a CDN) unless shared=false,
+// and status= overrides the status line's code.
+fn handle_cache_analyze_request(ctx: &RequestContext, head: &str) -> String {
+    let (status_line, fields) = match head.trim_start().split_once('\n') {
+        Some((first, rest)) if first.starts_with("HTTP/") => (Some(first), rest),
+        None if head.trim_start().starts_with("HTTP/") => (Some(head.trim_start()), ""),
+        _ => (None, head),
+    };
+    let status = parse_string(&ctx.params, "status").or(status_line.and_then(|line| line.split_whitespace().nth(1)).map(str::to_string));
+    let status = match status.map(|status| status.parse::<u16>()) {
+        None => 200,
+        Some(Ok(status)) if (100..=599).contains(&status) => status,
+        Some(_) => return create_error_response(400, "Status must be a code from 100 to 599"),
+    };
+    let shared = !parse_string(&ctx.params, "shared").is_some_and(|shared| shared == "false" || shared == "0");
+
+    let analysis = cache_policy::analyze(&headers::Headers::parse(fields), status, shared, host::now_ms() as u64);
+    let quoted = |value: &str| format!("\"{}\"", json::escape(value));
+    let seconds = |value: Option<u64>| value.map_or("null".to_string(), |seconds| seconds.to_string());
+    let directives = &analysis.directives;
+    let flags = [
+        ("no-store", directives.no_store),
+        ("no-cache", directives.no_cache),
+        ("private", directives.private),
+        ("public", directives.public),
+        ("must-revalidate", directives.must_revalidate),
+        ("proxy-revalidate", directives.proxy_revalidate),
+        ("immutable", directives.immutable),
+    ];
+    let flags: Vec<String> = flags.iter().filter(|(_, set)| *set).map(|(name, _)| quoted(name)).collect();
+    let unknown: Vec<String> = directives.unknown.iter().map(|item| quoted(item)).collect();
+    let validators: Vec<String> = analysis.validators.iter().map(|name| quoted(name)).collect();
+    let vary: Vec<String> = analysis.vary.iter().map(|name| quoted(name)).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"cache_analyze","cache":"{}","status":{},"storable":{},"reason":{},"state":"{}","lifetime":{},"lifetime_source":"{}","age":{},"ttl":{},"stale_while_revalidate":{},"stale_if_error":{},"may_serve_stale":{},"validators":[{}],"vary":[{}],"directives":{{"max_age":{},"s_maxage":{},"flags":[{}],"unknown":[{}]}},"summary":{}}}"#,
+        if shared { "shared" } else { "private" },
+        status,
+        analysis.not_storable.is_none(),
+        analysis.not_storable.as_deref().map_or("null".to_string(), quoted),
+        analysis.state,
+        analysis.lifetime,
+        analysis.lifetime_source,
+        analysis.age,
+        analysis.ttl,
+        seconds(directives.stale_while_revalidate),
+        seconds(directives.stale_if_error),
+        analysis.may_serve_stale,
+        validators.join(","),
+        vary.join(","),
+        seconds(directives.max_age),
+        seconds(directives.s_maxage),
+        flags.join(","),
+        unknown.join(","),
+        quoted(&analysis.summary())
+    ))
+}
+
+struct ExtractedLink {
+    href: String,
+    rel: Option<String>,
+    // None for sitemap entries
+    text: Option<String>,
+}
+
+impl ExtractedLink {
+    // A sitemap <loc>'s text is its URL
+    fn finish(&mut self, text: String) {
+        match &mut self.text {
+            Some(own) => *own = text,
+            None => self.href = text,
+        }
+    }
+}
+
+// Links of a posted page, as document.links has them (<a> and <area> with an
+// href), or the <loc> entries of a posted sitemap. Each keeps its href as
+// written and gets an absolute url, resolved against the page's <base> and
+// the base parameter, or null when it cannot be resolved.
+fn handle_links_extract_request(ctx: &RequestContext, page: &str) -> String {
+    let mut base = match parse_string(&ctx.params, "base").map(|base| url::parse(&base)) {
+        None => None,
+        Some(Ok(base)) => Some(base),
+        Some(Err(reason)) => return create_error_response(400, &format!("Invalid base: {}", reason)),
+    };
+    let mut links: Vec<ExtractedLink> = Vec::new();
+    let mut sitemap = false;
+    let mut seen_base = false;
+    // The link whose text is being read, and where that text starts
+    let mut open: Option<(usize, usize)> = None;
+    for tag in html_rewrite::tags(page) {
+        match (tag.name.as_str(), tag.closing) {
+            ("urlset" | "sitemapindex", false) => sitemap = true,
+            // Only the first <base> counts, and only for links after it
+            ("base", false) if !seen_base => {
+                seen_base = true;
+                if let Some(href) = tag.attribute("href").map(|href| html::unescape(href.trim())) {
+                    let resolved = match &base {
+                        Some(base) => url::resolve(base, &href),
+                        None => url::parse(&href),
+                    };
+                    base = resolved.ok().or(base);
+                }
+            }
+            ("a" | "area", false) | ("a" | "loc", true) => {
+                if let Some((index, start)) = open.take() {
+                    links[index].finish(html_rewrite::text_content(&page[start..tag.range.start]));
+                }
+                if tag.closing {
+                    continue;
+                }
+                if let Some(href) = tag.attribute("href") {
+                    let rel = tag.attribute("rel").map(html::unescape);
+                    links.push(ExtractedLink { href: html::unescape(href.trim()), rel, text: Some(String::new()) });
+                    if tag.name == "a" {
+                        open = Some((links.len() - 1, tag.range.end));
+                    }
+                }
+            }
+            ("loc", false) => {
+                links.push(ExtractedLink { href: String::new(), rel: None, text: None });
+                open = Some((links.len() - 1, tag.range.end));
+            }
+            _ => {}
+        }
+    }
+    if let Some((index, start)) = open {
+        links[index].finish(html_rewrite::text_content(&page[start..]));
+    }
+
+    let quoted = |value: Option<&str>| value.map_or("null".to_string(), |value| format!("\"{}\"", json::escape(value)));
+    let entries: Vec<String> = links
+        .iter()
+        .map(|link| {
+            let absolute = match &base {
+                Some(base) => url::resolve(base, &link.href),
+                None => url::parse(&link.href),
+            };
+            format!(
+                r#"{{"href":"{}","url":{},"rel":{},"text":{}}}"#,
+                json::escape(&link.href),
+                quoted(absolute.ok().map(|url| url.to_string()).as_deref()),
+                quoted(link.rel.as_deref()),
+                quoted(link.text.as_deref())
+            )
+        })
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"links_extract","format":"{}","base":{},"count":{},"links":[{}]}}"#,
+        if sitemap { "sitemap" } else { "html" },
+        quoted(base.map(|base| base.to_string()).as_deref()),
+        links.len(),
+        entries.join(",")
+    ))
+}
+
+fn handle_webhook_verify_request(ctx: &RequestContext) -> String {
+    let Some(provider) = parse_string(&ctx.params, "provider") else {
+        return create_error_response(400, &format!("Missing provider (one of {})", webhook::PROVIDERS.join(", ")));
+    };
+    if !webhook::PROVIDERS.contains(&provider.as_str()) {
+        return create_error_response(400, &format!("Unknown provider (use one of {})", webhook::PROVIDERS.join(", ")));
+    }
+    let config = ctx.config;
+    let Some(secret) = config.webhook_secret(&provider) else {
+        return create_error_response(500, &format!("No secret configured for {} (set webhook_secrets in CONFIG)", provider));
+    };
+
+    let now_seconds = host::now_ms() as u64 / 1000;
+    let verification = match webhook::verify(&provider, secret, &ctx.headers, ctx.body, now_seconds) {
+        Ok(verification) => verification,
+        Err(reason) => return create_error_response(400, &reason),
+    };
+    let reason = if !verification.signature_matches {
+        "\"Signature does not match the body\"".to_string()
+    } else if !verification.within_tolerance() {
+        format!("\"Timestamp is more than {} seconds from the current time\"", webhook::TOLERANCE_SECONDS)
+    } else {
+        "null".to_string()
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"webhook_verify","provider":"{}","header":"{}","valid":{},"signature_matches":{},"timestamp":{},"age_seconds":{},"tolerance_seconds":{},"body_bytes":{},"reason":{}}}"#,
+        provider,
+        verification.header,
+        verification.valid(),
+        verification.signature_matches,
+        verification.timestamp.map_or("null".to_string(), |timestamp| timestamp.to_string()),
+        verification.age_seconds.map_or("null".to_string(), |age| age.to_string()),
+        webhook::TOLERANCE_SECONDS,
+        ctx.body.len(),
+        reason
+    ))
+}
+
+// Generates a verifier unless one is passed in to derive its challenge
+fn handle_pkce_request(ctx: &RequestContext) -> String {
+    let verifier = match parse_string(&ctx.params, "verifier") {
+        Some(verifier) => verifier,
+        None => {
+            let length: usize = parse_param(&ctx.params, "length").unwrap_or(oauth::DEFAULT_VERIFIER_LENGTH);
+            if !(oauth::MIN_VERIFIER_LENGTH..=oauth::MAX_VERIFIER_LENGTH).contains(&length) {
+                return create_error_response(
+                    400,
+                    &format!("Length must be between {} and {}", oauth::MIN_VERIFIER_LENGTH, oauth::MAX_VERIFIER_LENGTH),
+                );
+            }
+            oauth::code_verifier(length)
+        }
+    };
+    if let Err(reason) = oauth::validate_verifier(&verifier) {
+        return create_error_response(400, &reason);
+    }
+    create_json_response(&format!(
+        r#"{{"operation":"pkce","code_verifier":"{}","code_challenge":"{}","code_challenge_method":"S256"}}"#,
+        verifier,
+        oauth::s256_challenge(&verifier)
+    ))
+}
+
+// Issues a new state token, or checks the one passed as ?token=
+fn handle_oauth_state_request(ctx: &RequestContext) -> String {
+    let config = ctx.config;
+    let Some(secret) = config.oauth_state_secret.as_deref() else {
+        return create_error_response(500, "No state secret configured (set oauth_state_secret in CONFIG)");
+    };
+    let now_seconds = host::now_ms() as u64 / 1000;
+
+    let Some(token) = parse_string(&ctx.params, "token") else {
+        return create_json_response(&format!(
+            r#"{{"operation":"oauth_state","state":"{}","issued_at":{},"max_age_seconds":{}}}"#,
+            oauth::state_token(secret, now_seconds),
+            now_seconds,
+            oauth::MAX_STATE_AGE_SECONDS
+        ));
+    };
+    match oauth::check_state(secret, &token, now_seconds) {
+        Ok(check) => create_json_response(&format!(
+            r#"{{"operation":"oauth_state_check","valid":{},"signature_matches":{},"issued_at":{},"age_seconds":{},"max_age_seconds":{}}}"#,
+            check.valid(),
+            check.signature_matches,
+            check.issued_seconds,
+            check.age_seconds,
+            oauth::MAX_STATE_AGE_SECONDS
+        )),
+        Err(reason) => create_error_response(400, &reason),
+    }
+}
+
+// A math question, or with kind=text an SVG to read (format=svg serves the
+// image itself, its token in X-Captcha-Token)
+fn handle_captcha_new_request(ctx: &RequestContext) -> String {
+    let Some(secret) = ctx.config.captcha_secret.as_deref() else {
+        return create_error_response(500, "No captcha secret configured (set captcha_secret in CONFIG)");
+    };
+    let name = parse_string(&ctx.params, "kind").unwrap_or("math".to_string());
+    let Some(kind) = captcha::Kind::parse(&name) else {
+        return create_error_response(400, &format!("Unknown kind '{}', expected math or text", name));
+    };
+    let format = parse_string(&ctx.params, "format").unwrap_or("json".to_string());
+    if !matches!(format.as_str(), "json" | "svg") || format == "svg" && kind != captcha::Kind::Text {
+        return create_error_response(400, "Format must be json, or svg for text challenges");
+    }
+
+    let challenge = captcha::new(kind, secret, host::now_ms() as u64 / 1000);
+    let response = if format == "svg" {
+        with_header(create_text_response("image/svg+xml", &challenge.question), "X-Captcha-Token", &challenge.token)
+    } else {
+        let (question, image) = match kind {
+            captcha::Kind::Math => (format!("\"{}\"", json::escape(&challenge.question)), "null".to_string()),
+            captcha::Kind::Text => ("null".to_string(), format!("\"{}\"", json::escape(&challenge.question))),
+        };
+        create_json_response(&format!(
+            r#"{{"operation":"captcha","kind":"{}","question":{},"svg":{},"token":"{}","max_age_seconds":{}}}"#,
+            kind.name(),
+            question,
+            image,
+            challenge.token,
+            captcha::MAX_AGE_SECONDS
+        ))
+    };
+    with_header(response, "Cache-Control", "no-store")
+}
+
+fn handle_captcha_verify_request(ctx: &RequestContext) -> String {
+    let Some(secret) = ctx.config.captcha_secret.as_deref() else {
+        return create_error_response(500, "No captcha secret configured (set captcha_secret in CONFIG)");
+    };
+    let (Some(token), Some(answer)) = (parse_string(&ctx.params, "token"), parse_string(&ctx.params, "answer")) else {
+        return create_error_response(400, "Missing 'token' or 'answer' parameter");
+    };
+    match captcha::verify(secret, &token, &answer, host::now_ms() as u64 / 1000) {
+        Ok(Ok((kind, outcome))) => create_json_response(&format!(
+            r#"{{"operation":"captcha_verify","kind":"{}","valid":{},"reason":{}}}"#,
+            kind.name(),
+            outcome.reason().is_none(),
+            outcome.reason().map_or("null".to_string(), |reason| format!("\"{}\"", reason))
+        )),
+        Ok(Err(reason)) => create_error_response(400, &reason),
+        Err(kv::Pending) => kv_pending_response(),
+    }
+}
+
+// `difficulty` may raise the configured pow_difficulty but not lower it
+fn handle_pow_challenge_request(ctx: &RequestContext) -> String {
+    let Some(secret) = ctx.config.pow_secret.as_deref() else {
+        return create_error_response(500, "No proof-of-work secret configured (set pow_secret in CONFIG)");
+    };
+    let minimum = ctx.config.pow_difficulty;
+    let difficulty = match ctx.params.get("difficulty").map(str::parse::<u32>) {
+        None => minimum,
+        Some(Ok(difficulty)) if (minimum..=pow::MAX_DIFFICULTY).contains(&difficulty) => difficulty,
+        Some(_) => return create_error_response(400, &format!("Difficulty must be between {} and {}", minimum, pow::MAX_DIFFICULTY)),
+    };
+    let (challenge, prefix) = pow::challenge(secret, difficulty, host::now_ms() as u64 / 1000);
+    let response = create_json_response(&format!(
+        r#"{{"operation":"pow_challenge","challenge":"{}","prefix":"{}","difficulty":{},"algorithm":"sha256","expected_attempts":{},"max_age_seconds":{},"task":"Find a nonce of up to {} characters for which SHA-256(prefix + nonce) starts with {} zero bits"}}"#,
+        challenge,
+        prefix,
+        difficulty,
+        1u64 << difficulty,
+        pow::MAX_AGE_SECONDS,
+        pow::MAX_NONCE_LENGTH,
+        difficulty
+    ));
+    with_header(response, "Cache-Control", "no-store")
+}
+
+fn handle_pow_verify_request(ctx: &RequestContext) -> String {
+    let Some(secret) = ctx.config.pow_secret.as_deref() else {
+        return create_error_response(500, "No proof-of-work secret configured (set pow_secret in CONFIG)");
+    };
+    let (Some(challenge), Some(nonce)) = (parse_string(&ctx.params, "challenge"), parse_string(&ctx.params, "nonce")) else {
+        return create_error_response(400, "Missing 'challenge' or 'nonce' parameter");
+    };
+    if nonce.len() > pow::MAX_NONCE_LENGTH {
+        return create_error_response(400, &format!("Nonce must be at most {} bytes", pow::MAX_NONCE_LENGTH));
+    }
+    match pow::verify(secret, &challenge, &nonce, host::now_ms() as u64 / 1000) {
+        Ok(Ok(check)) => create_json_response(&format!(
+            r#"{{"operation":"pow_verify","valid":{},"reason":{},"difficulty":{},"leading_zero_bits":{},"hash":"{}"}}"#,
+            check.failure.is_none(),
+            check.failure.map_or("null".to_string(), |reason| format!("\"{}\"", reason)),
+            check.difficulty,
+            check.leading_zero_bits,
+            hashing::to_hex(&check.hash)
+        )),
+        Ok(Err(reason)) => create_error_response(400, &reason),
+        Err(kv::Pending) => kv_pending_response(),
+    }
+}
+
+// Each call counts as one request against `key`; denied ones get a 429 with
+// Retry-After, as a limited endpoint would send
+fn handle_ratelimit_test_request(ctx: &RequestContext) -> String {
+    let name = parse_string(&ctx.params, "algo").unwrap_or_else(|| "token_bucket".to_string());
+    let Some(algorithm) = ratelimit::Algorithm::parse(&name) else {
+        return create_error_response(400, &format!("Unknown algo (use one of {})", ratelimit::Algorithm::NAMES.join(", ")));
+    };
+    let key = parse_string(&ctx.params, "key").unwrap_or_else(|| "default".to_string());
+    if key.is_empty() || key.len() > 128 {
+        return create_error_response(400, "Key must be 1 to 128 bytes");
+    }
+    let limit = match ctx.params.get("limit").map(str::parse::<u32>) {
+        None => 10,
+        Some(Ok(limit)) if (1..=ratelimit::MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => return create_error_response(400, &format!("Limit must be between 1 and {}", ratelimit::MAX_LIMIT)),
+    };
+    let rate = match ctx.params.get("rate").map(str::parse::<f64>) {
+        None => 1.0,
+        Some(Ok(rate)) if rate > 0.0 && rate <= 1000.0 => rate,
+        Some(_) => return create_error_response(400, "Rate must be above 0 and at most 1000 per second"),
+    };
+    let window_ms = match ctx.params.get("window_ms").map(str::parse::<u64>) {
+        None => 60_000,
+        Some(Ok(window_ms)) if (1..=ratelimit::MAX_WINDOW_MS).contains(&window_ms) => window_ms,
+        Some(_) => return create_error_response(400, &format!("window_ms must be between 1 and {}", ratelimit::MAX_WINDOW_MS)),
+    };
+
+    let limits = ratelimit::Limits { limit, rate, window_ms };
+    let decision = match ratelimit::hit(algorithm, &key, &limits, host::now_ms()) {
+        Ok(decision) => decision,
+        Err(kv::Pending) => return kv_pending_response(),
+    };
+    let parameters = match algorithm {
+        ratelimit::Algorithm::SlidingWindow => format!("\"window_ms\":{}", window_ms),
+        _ => format!("\"rate\":{}", rate),
+    };
+    let members = format!(
+        r#""algo":"{}","key":"{}","limit":{},{},"allowed":{},"remaining":{},"retry_after_ms":{},"reset_ms":{}"#,
+        algorithm.name(),
+        json::escape(&key),
+        limit,
+        parameters,
+        decision.allowed,
+        decision.remaining,
+        decision.retry_after_ms,
+        decision.reset_ms
+    );
+    let response = if decision.allowed {
+        create_json_response(&format!(r#"{{"operation":"ratelimit_test",{}}}"#, members))
+    } else {
+        let denied = create_error_response_with(429, "Rate limit exceeded", &format!(",{}", members));
+        with_header(denied, "Retry-After", &decision.retry_after_ms.div_ceil(1000).to_string())
+    };
+    let response = with_header(response, "RateLimit-Limit", &limit.to_string());
+    let response = with_header(response, "RateLimit-Remaining", &decision.remaining.to_string());
+    with_header(response, "RateLimit-Reset", &decision.reset_ms.div_ceil(1000).to_string())
+}
+
+fn handle_nqueens_request(ctx: &RequestContext) -> String {
+    let n: u32 = parse_param(&ctx.params, "n").unwrap_or(8);
+    if !(1..=combinatorics::MAX_QUEENS).contains(&n) {
+        return create_error_response(400, &format!("n must be between 1 and {}", combinatorics::MAX_QUEENS));
+    }
+
+    let queens = combinatorics::n_queens(n);
+    let (columns, board) = match &queens.first {
+        Some(first) => {
+            let rows: Vec<String> = first.iter()
+                .map(|&column| format!("\"{}\"", (0..n as usize).map(|i| if i == column { 'Q' } else { '.' }).collect::<String>()))
+                .collect();
+            let columns: Vec<String> = first.iter().map(|column| column.to_string()).collect();
+            (format!("[{}]", columns.join(",")), format!("[{}]", rows.join(",")))
+        }
+        None => ("null".to_string(), "null".to_string()),
+    };
+    create_json_response(&format!(
+        r#"{{"operation":"nqueens","n":{},"solutions":{},"sample_columns":{},"sample_board":{}}}"#,
+        n, queens.solutions, columns, board
+    ))
+}
+
+fn handle_combinatorics_request(ctx: &RequestContext) -> String {
+    let op = parse_string(&ctx.params, "op").unwrap_or("nCr".to_string());
+    let n: Option<u32> = parse_param(&ctx.params, "n");
+    let k: Option<u32> = parse_param(&ctx.params, "k");
+    let n = match n {
+        Some(n) if n <= combinatorics::MAX_N => n,
+        _ => return create_error_response(400, &format!("n must be between 0 and {}", combinatorics::MAX_N)),
+    };
+
+    let result = match (op.as_str(), k) {
+        ("nCr", Some(k)) => combinatorics::n_choose_k(n, k),
+        ("nPr", Some(k)) => combinatorics::n_permute_k(n, k),
+        ("nCr" | "nPr", None) => return create_error_response(400, "Missing 'k' parameter"),
+        ("catalan", _) => combinatorics::catalan(n),
+        _ => return create_error_response(400, "Op must be one of: nCr, nPr, catalan"),
+    };
+    let result = result.to_string();
+    create_json_response(&format!(
+        r#"{{"operation":"combinatorics","op":"{}","n":{},"k":{},"digits":{},"result":"{}"}}"#,
+        op,
+        n,
+        k.filter(|_| op != "catalan").map_or("null".to_string(), |k| k.to_string()),
+        result.len(),
+        result
+    ))
+}
+
+fn handle_constant_request(ctx: &RequestContext, constant: &str) -> String {
+    let max_digits = ctx.config.max_constant_digits;
+    let digits: usize = parse_param(&ctx.params, "digits").unwrap_or(100);
+    if !(1..=max_digits).contains(&digits) {
+        return create_error_response(400, &format!("Digits must be between 1 and {}", max_digits));
+    }
+
+    let started = host::now_ms();
+    let Some(value) = constants::digits_of(constant, digits) else {
+        return budget_exhausted_response();
+    };
+    let elapsed = host::now_ms() - started;
+    create_json_response(&format!(
+        r#"{{"operation":"{}","digits":{},"elapsed_ms":{},"value":"{}"}}"#,
+        constant, digits, elapsed, value
+    ))
+}
+
+// Parse a POST body holding a JSON array of numbers
+fn parse_number_array(body: &[u8]) -> Result<Vec<f64>, String> {
+    let items = match std::str::from_utf8(body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(json::parse)? {
+        json::Value::Array(items) => items,
+        _ => return Err("Body must be a JSON array of numbers".to_string()),
+    };
+    items.into_iter()
+        .enumerate()
+        .map(|(index, item)| match item {
+            json::Value::Number(value) => Ok(value),
+            _ => Err(format!("Item {} is not a number", index)),
+        })
+        .collect()
+}
+
+fn handle_fft_request(ctx: &RequestContext) -> String {
+    let samples = match parse_number_array(ctx.body) {
+        Ok(samples) => samples,
+        Err(message) => return create_error_response(400, &message),
+    };
+    if samples.is_empty() || samples.len() > fft::MAX_SIZE {
+        return create_error_response(400, &format!("Provide between 1 and {} samples", fft::MAX_SIZE));
+    }
+    let window = parse_string(&ctx.params, "window").unwrap_or("none".to_string());
+    if window != "none" && window != "hann" {
+        return create_error_response(400, "Window must be none or hann");
+    }
+    let sample_rate: Option<f64> = parse_param(&ctx.params, "sample_rate");
+    if sample_rate.is_some_and(|rate| !(rate > 0.0 && rate.is_finite())) {
+        return create_error_response(400, "sample_rate must be a positive number");
+    }
+
+    let spectrum = fft::amplitudes(&samples, window == "hann");
+    let size = (spectrum.len() - 1) * 2;
+    let peak = (1..spectrum.len()).max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b])).unwrap_or(0);
+    let bin_frequency = |bin: usize| sample_rate.map_or("null".to_string(), |rate| format!("{:.6}", bin as f64 * rate / size as f64));
+    let amplitudes: Vec<String> = spectrum.iter().map(|amplitude| format!("{:.6}", amplitude)).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"fft","samples":{},"fft_size":{},"window":"{}","sample_rate":{},"bin_width":{},"peak":{{"bin":{},"frequency":{},"amplitude":{:.6}}},"amplitudes":[{}]}}"#,
+        samples.len(),
+        size,
+        window,
+        sample_rate.map_or("null".to_string(), |rate| rate.to_string()),
+        bin_frequency(1),
+        peak,
+        bin_frequency(peak),
+        spectrum[peak],
+        amplitudes.join(",")
+    ))
+}
+
+fn handle_fit_request(ctx: &RequestContext) -> String {
+    let model = parse_string(&ctx.params, "model").unwrap_or("linear".to_string());
+    let members = match std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(json::parse) {
+        Ok(json::Value::Object(members)) => members,
+        Ok(_) => return create_error_response(400, r#"Body must be a JSON object like {"x":[...],"y":[...]}"#),
+        Err(message) => return create_error_response(400, &message),
+    };
+    let series = |name: &str| -> Result<Vec<f64>, String> {
+        let Some((_, json::Value::Array(items))) = members.iter().find(|(key, _)| key == name) else {
+            return Err(format!("Missing '{}' array", name));
+        };
+        items.iter()
+            .map(|item| match item {
+                json::Value::Number(value) => Ok(*value),
+                _ => Err(format!("'{}' must contain only numbers", name)),
+            })
+            .collect()
+    };
+    let (x, y) = match (series("x"), series("y")) {
+        (Ok(x), Ok(y)) => (x, y),
+        (Err(message), _) | (_, Err(message)) => return create_error_response(400, &message),
+    };
+    if x.len() > 10_000 {
+        return create_error_response(400, "At most 10000 points are supported");
+    }
+
+    let fit = match regression::fit(&model, &x, &y) {
+        Ok(fit) => fit,
+        Err(message) => return create_error_response(400, &message),
+    };
+    // Six decimals, without printing tiny rounding noise as -0.000000
+    let decimal = |value: f64| format!("{:.6}", if value.abs() < 5e-7 { 0.0 } else { value });
+    let signed = |value: f64| {
+        let text = decimal(value);
+        match text.strip_prefix('-') {
+            Some(magnitude) => format!("- {}", magnitude),
+            None => format!("+ {}", text),
+        }
+    };
+    let c = &fit.coefficients;
+    let (coefficients, equation) = match model.as_str() {
+        "exp" => (
+            format!(r#"{{"a":{},"b":{}}}"#, decimal(c[0]), decimal(c[1])),
+            format!("y = {} * e^({} x)", decimal(c[0]), decimal(c[1])),
+        ),
+        "poly2" => (
+            format!(r#"{{"a":{},"b":{},"c":{}}}"#, decimal(c[0]), decimal(c[1]), decimal(c[2])),
+            format!("y = {} {} x {} x^2", decimal(c[0]), signed(c[1]), signed(c[2])),
+        ),
+        _ => (
+            format!(r#"{{"a":{},"b":{}}}"#, decimal(c[0]), decimal(c[1])),
+            format!("y = {} {} x", decimal(c[0]), signed(c[1])),
+        ),
+    };
+    let residuals: Vec<String> = y.iter().zip(&fit.predicted).map(|(y, p)| decimal(y - p)).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"fit","model":"{}","points":{},"equation":"{}","coefficients":{},"r_squared":{},"residuals":[{}]}}"#,
+        model,
+        x.len(),
+        equation,
+        coefficients,
+        decimal(fit.r_squared),
+        residuals.join(",")
+    ))
+}
+
+fn handle_cluster_request(ctx: &RequestContext) -> String {
+    let k: usize = parse_param(&ctx.params, "k").unwrap_or(3);
+    let iterations: usize = parse_param(&ctx.params, "iters").unwrap_or(100);
+    let seed: Option<u64> = parse_param(&ctx.params, "seed");
+    if !(1..=1000).contains(&iterations) {
+        return create_error_response(400, "iters must be between 1 and 1000");
+    }
+
+    let items = match std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(json::parse) {
+        Ok(json::Value::Array(items)) if !items.is_empty() => items,
+        Ok(_) => return create_error_response(400, "Body must be a non-empty JSON array of points like [[1,2],[3,4]]"),
+        Err(message) => return create_error_response(400, &message),
+    };
+    if items.len() > 10_000 {
+        return create_error_response(400, "At most 10000 points are supported");
+    }
+    let mut points = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        let coordinates: Option<Vec<f64>> = match item {
+            json::Value::Array(values) if !values.is_empty() => values.iter()
+                .map(|value| match value {
+                    json::Value::Number(number) => Some(*number),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        };
+        match coordinates {
+            Some(point) if points.first().is_none_or(|first: &Vec<f64>| first.len() == point.len()) => points.push(point),
+            Some(_) => return create_error_response(400, &format!("Point {} has a different dimension from point 0", index)),
+            None => return create_error_response(400, &format!("Point {} must be a non-empty array of numbers", index)),
+        }
+    }
+    if !(1..=points.len().min(100)).contains(&k) {
+        return create_error_response(400, &format!("k must be between 1 and {}", points.len().min(100)));
+    }
+
+    let clustering = match seed {
+        Some(seed) => kmeans::cluster(&points, k, iterations, &mut rand::Rng::new(seed)),
+        None => rand::with_global(|rng| kmeans::cluster(&points, k, iterations, rng)),
+    };
+    let centroids: Vec<String> = clustering.centroids.iter()
+        .map(|centroid| format!("[{}]", centroid.iter().map(|value| format!("{:.6}", value)).collect::<Vec<_>>().join(",")))
+        .collect();
+    let sizes: Vec<String> = (0..k)
+        .map(|cluster| clustering.assignments.iter().filter(|&&assigned| assigned == cluster).count().to_string())
+        .collect();
+    let assignments: Vec<String> = clustering.assignments.iter().map(|cluster| cluster.to_string()).collect();
+    create_json_response(&format!(
+        r#"{{"operation":"cluster","k":{},"points":{},"dimension":{},"seed":{},"iterations":{},"converged":{},"inertia":{:.6},"centroids":[{}],"sizes":[{}],"assignments":[{}]}}"#,
+        k,
+        points.len(),
+        points[0].len(),
+        seed.map_or("null".to_string(), |seed| seed.to_string()),
+        clustering.iterations,
+        clustering.converged,
+        clustering.inertia,
+        centroids.join(","),
+        sizes.join(","),
+        assignments.join(",")
+    ))
+}
+
+fn handle_table_request(ctx: &RequestContext) -> String {
+    let rows = match std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(json::parse) {
+        Ok(json::Value::Array(rows)) => rows,
+        Ok(_) => return create_error_response(400, "Body must be a JSON array of objects or of arrays"),
+        Err(message) => return create_error_response(400, &message),
+    };
+    match charts::table(&rows) {
+        Ok(table) => create_text_response("text/plain; charset=utf-8", &table),
+        Err(reason) => create_error_response(400, &reason),
+    }
+}
+
+fn handle_checksum_request(ctx: &RequestContext) -> String {
+    // Feed both hashers in fixed-size chunks, as a streamed body would arrive
+    let mut crc = hashing::Crc32::new();
+    let mut sha = hashing::Sha256::new();
+    for chunk in ctx.body.chunks(64 * 1024) {
+        crc.update(chunk);
+        sha.update(chunk);
+    }
+    let sha256 = hashing::to_hex(&sha.finalize());
+    let crc32 = crc.finalize();
+    create_json_response(&format!(
+        r#"{{"operation":"checksum","size":{},"crc32":"{:08x}","crc32_decimal":{},"sha256":"{}","etag":"\"{}\""}}"#,
+        ctx.body.len(),
+        crc32,
+        crc32,
+        sha256,
+        &sha256[..32]
+    ))
+}
+
+// Each nested message repeats its bytes in the breakdown, so payloads are
+// kept well under the body limit
+const MAX_PROTO_DECODE_BYTES: usize = 1024 * 1024;
+
+// Decodes a payload sent as is or, with ?encoding=hex or base64, as text
+fn handle_proto_decode_request(ctx: &RequestContext) -> String {
+    let encoding = ctx.params.get("encoding").unwrap_or("binary");
+    let text = || std::str::from_utf8(ctx.body).map(str::trim_ascii).map_err(|_| "Body is not valid UTF-8".to_string());
+    let payload = match encoding {
+        "binary" => Ok(ctx.body.to_vec()),
+        "hex" => text().and_then(hashing::from_hex),
+        "base64" => text().and_then(hashing::from_base64),
+        _ => return create_error_response(400, "Encoding must be one of: binary, hex, base64"),
+    };
+    let payload = match payload {
+        Ok(payload) => payload,
+        Err(reason) => return create_error_response(400, &reason),
+    };
+    if payload.len() > MAX_PROTO_DECODE_BYTES {
+        return create_error_response(413, &format!("Payload must be at most {} bytes", MAX_PROTO_DECODE_BYTES));
+    }
+    match protobuf::breakdown(&payload) {
+        Ok(fields) => create_json_response(&format!(
+            r#"{{"operation":"proto_decode","bytes":{},"fields":{}}}"#,
+            payload.len(),
+            fields
+        )),
+        Err(reason) => create_error_response(400, &format!("Invalid protobuf payload: {}", reason)),
+    }
+}
+
+// Decoding allocates four bytes per pixel, so this bounds memory at 16 MiB
+const MAX_IMAGE_PIXELS: usize = 2048 * 2048;
+const MAX_IMAGE_SIDE: usize = 2048;
+
+// Resizes a posted PNG to ?w= and/or ?h=; given one, the other follows the
+// aspect ratio
+fn handle_image_resize_request(ctx: &RequestContext) -> String {
+    if !ctx.body.starts_with(&png::SIGNATURE) {
+        return create_error_response(415, "Body must be a PNG image");
+    }
+    let side = |name: &str| match ctx.params.get(name) {
+        None => Ok(None),
+        Some(value) => match value.parse::<usize>() {
+            Ok(side) if (1..=MAX_IMAGE_SIDE).contains(&side) => Ok(Some(side)),
+            _ => Err(format!("{} must be between 1 and {}", name, MAX_IMAGE_SIDE)),
+        },
+    };
+    let (width, height) = match (side("w"), side("h")) {
+        (Err(reason), _) | (_, Err(reason)) => return create_error_response(400, &reason),
+        (Ok(None), Ok(None)) => return create_error_response(400, "Give w, h or both"),
+        (Ok(width), Ok(height)) => (width, height),
+    };
+    let image = match png::decode(ctx.body, MAX_IMAGE_PIXELS) {
+        Ok(image) => image,
+        Err(reason) => return create_error_response(400, &format!("Invalid PNG: {}", reason)),
+    };
+    let scaled = |side: usize, from: usize, to: usize| ((side * to) as f64 / from as f64).round().clamp(1.0, MAX_IMAGE_SIDE as f64) as usize;
+    let width = width.unwrap_or_else(|| scaled(image.width, image.height, height.unwrap_or(image.height)));
+    let height = height.unwrap_or_else(|| scaled(image.height, image.width, width));
+    let resized = image.resize(width, height);
+    let response = create_binary_response("image/png", &png::encode(&resized));
+    with_header(response, "X-Image-Source-Size", &format!("{}x{}", image.width, image.height))
+}
+
+// Removes metadata from a posted JPEG; ?keep=icc,... keeps those kinds
+fn handle_image_strip_request(ctx: &RequestContext) -> String {
+    if !ctx.body.starts_with(&jpeg::SOI) {
+        return create_error_response(415, "Body must be a JPEG image");
+    }
+    let keep: Vec<&str> = ctx.params.get("keep").map_or(Vec::new(), |keep| keep.split(',').map(str::trim).filter(|kind| !kind.is_empty()).collect());
+    if let Some(kind) = keep.iter().find(|kind| !jpeg::KINDS.contains(kind)) {
+        return create_error_response(400, &format!("Unknown metadata kind '{}'; expected some of: {}", kind, jpeg::KINDS.join(", ")));
+    }
+    let (cleaned, removed) = match jpeg::strip(ctx.body, &keep) {
+        Ok(result) => result,
+        Err(reason) => return create_error_response(400, &format!("Invalid JPEG: {}", reason)),
+    };
+    let mut kinds: Vec<&str> = Vec::new();
+    for kind in removed {
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    let kinds = if kinds.is_empty() { "none".to_string() } else { kinds.join(", ") };
+    let response = with_header(create_binary_response("image/jpeg", &cleaned), "X-Metadata-Removed", &kinds);
+    with_header(response, "X-Bytes-Removed", &(ctx.body.len() - cleaned.len()).to_string())
+}
+
+const MAX_ZIP_EXTRACT_BYTES: usize = 16 * 1024 * 1024;
+
+fn read_zip(body: &[u8]) -> Result<zip::Archive, String> {
+    zip::read(body).map_err(|reason| format!("Invalid ZIP archive: {}", reason))
+}
+
+fn handle_zip_list_request(ctx: &RequestContext) -> String {
+    let archive = match read_zip(ctx.body) {
+        Ok(archive) => archive,
+        Err(reason) => return create_error_response(400, &reason),
+    };
+    let entries: Vec<String> = archive
+        .entries
+        .iter()
+        .map(|entry| {
+            format!(
+                r#"{{"name":"{}","directory":{},"size":{},"compressed_size":{},"method":"{}","crc32":"{:08x}","modified":"{}","encrypted":{},"comment":"{}"}}"#,
+                json::escape(&entry.name),
+                entry.is_directory(),
+                entry.size,
+                entry.compressed_size,
+                entry.method_name(),
+                entry.crc32,
+                entry.modified,
+                entry.encrypted,
+                json::escape(&entry.comment)
+            )
+        })
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"zip_list","count":{},"size":{},"compressed_size":{},"comment":"{}","entries":[{}]}}"#,
+        entries.len(),
+        archive.entries.iter().map(|entry| entry.size).sum::<u64>(),
+        archive.entries.iter().map(|entry| entry.compressed_size).sum::<u64>(),
+        json::escape(&archive.comment),
+        entries.join(",")
+    ))
+}
+
+fn handle_zip_extract_request(ctx: &RequestContext) -> String {
+    let Some(name) = ctx.params.get("name") else {
+        return create_error_response(400, "Missing name, the entry's path as /zip/list shows it");
+    };
+    let archive = match read_zip(ctx.body) {
+        Ok(archive) => archive,
+        Err(reason) => return create_error_response(400, &reason),
+    };
+    let Some(entry) = archive.entries.iter().find(|entry| entry.name == name) else {
+        return create_error_response(404, &format!("No entry named '{}'", name));
+    };
+    if entry.is_directory() {
+        return create_error_response(400, &format!("{} is a directory", name));
+    }
+    let contents = match zip::extract(ctx.body, entry, MAX_ZIP_EXTRACT_BYTES) {
+        Ok(contents) => contents,
+        Err(reason) => return create_error_response(422, &reason),
+    };
+    // Only the base name, with anything that needs quoting replaced, and '|'
+    // too, which would end the header block of the response string
+    let file_name: String = name
+        .rsplit('/')
+        .next()
+        .unwrap_or(name)
+        .chars()
+        .map(|c| if (c.is_ascii_graphic() && !matches!(c, '"' | '\\' | '|')) || c == ' ' { c } else { '_' })
+        .collect();
+    let response = create_binary_response(mime::from_extension(name).unwrap_or("application/octet-stream"), &contents);
+    with_header(response, "Content-Disposition", &format!("attachment; filename=\"{}\"", file_name))
+}
+
+// Bundles the parts of a multipart/form-data body into a tar archive, each
+// under its filename (or field name); ?gzip=true compresses it
+fn handle_tar_create_request(ctx: &RequestContext) -> String {
+    let boundary = match multipart::boundary(ctx.headers.get("content-type").unwrap_or_default()) {
+        Ok(boundary) => boundary,
+        Err(message) => return create_error_response(400, &message),
+    };
+    let parts = match multipart::parse(ctx.body, &boundary) {
+        Ok(parts) if !parts.is_empty() => parts,
+        Ok(_) => return create_error_response(400, "Send at least one part"),
+        Err(message) => return create_error_response(400, &message),
+    };
+    let gzip = parse_string(&ctx.params, "gzip").is_some_and(|gzip| gzip == "true" || gzip == "1");
+
+    let mut archive = tar::Writer::new((host::now_ms() / 1000.0) as u64);
+    for (index, part) in parts.iter().enumerate() {
+        let Some(path) = part.filename.as_deref().or(part.name.as_deref()) else {
+            return create_error_response(400, &format!("Part {} has neither a filename nor a name", index));
+        };
+        if let Err(reason) = archive.append(path, part.data) {
+            return create_error_response(400, &format!("Part {}: {}", index, reason));
+        }
+    }
+    let archive = archive.finish();
+    let response = if gzip {
+        let response = create_binary_response("application/gzip", &compression::gzip_encode(&archive));
+        with_header(response, "Content-Disposition", "attachment; filename=\"archive.tar.gz\"")
+    } else {
+        with_header(create_binary_response("application/x-tar", &archive), "Content-Disposition", "attachment; filename=\"archive.tar\"")
+    };
+    with_header(response, "X-Tar-Entries", &parts.len().to_string())
+}
+
+// Bencode to JSON, or with ?to=bencode a JSON body back to bencode
+fn handle_parse_bencode_request(ctx: &RequestContext) -> String {
+    match ctx.params.get("to").unwrap_or("json") {
+        "json" => match bencode::decode(ctx.body).and_then(|value| value.to_json()) {
+            Ok(value) => create_json_response(&format!(r#"{{"operation":"parse_bencode","bytes":{},"value":{}}}"#, ctx.body.len(), value)),
+            Err(reason) => create_error_response(400, &format!("Invalid bencode: {}", reason)),
+        },
+        "bencode" => {
+            let encoded = std::str::from_utf8(ctx.body)
+                .map_err(|_| "Body is not valid UTF-8".to_string())
+                .and_then(json::parse)
+                .and_then(|value| bencode::from_json(&value));
+            match encoded {
+                Ok(bytes) => create_binary_response("application/octet-stream", &bytes),
+                Err(reason) => create_error_response(400, &reason),
+            }
+        }
+        _ => create_error_response(400, "to must be one of: json, bencode"),
+    }
+}
+
+fn handle_merkle_request(ctx: &RequestContext) -> String {
+    let items = match std::str::from_utf8(ctx.body).map_err(|_| "Body is not valid UTF-8".to_string()).and_then(json::parse) {
+        Ok(json::Value::Array(items)) if !items.is_empty() => items,
+        Ok(_) => return create_error_response(400, "Body must be a non-empty JSON array"),
+        Err(message) => return create_error_response(400, &message),
+    };
+    if items.len() > 10_000 {
+        return create_error_response(400, "At most 10000 items are supported");
+    }
+    let index = match ctx.params.get("index") {
+        None => 0,
+        Some(value) => match value.parse::<usize>() {
+            Ok(index) if index < items.len() => index,
+            _ => return create_error_response(400, &format!("Index must be between 0 and {}", items.len() - 1)),
+        },
+    };
+
+    // String items hash their raw text, anything else its compact JSON form
+    let leaves: Vec<merkle::Hash> = items.iter()
+        .map(|item| match item {
+            json::Value::String(text) => merkle::leaf_hash(text.as_bytes()),
+            other => merkle::leaf_hash(other.to_json().as_bytes()),
+        })
+        .collect();
+    let (root, proof) = merkle::root_and_proof(&leaves, index).unwrap();
+    let steps: Vec<String> = proof.iter()
+        .map(|step| format!(
+            r#"{{"position":"{}","hash":"{}"}}"#,
+            if step.sibling_on_left { "left" } else { "right" },
+            hashing::to_hex(&step.sibling)
+        ))
+        .collect();
+    create_json_response(&format!(
+        r#"{{"operation":"merkle","leaf_count":{},"root":"{}","index":{},"leaf":"{}","proof":[{}],"proof_valid":{}}}"#,
+        leaves.len(),
+        hashing::to_hex(&root),
+        index,
+        hashing::to_hex(&leaves[index]),
+        steps.join(","),
+        merkle::verify(&leaves[index], &proof, &root)
+    ))
+}
+
+fn create_json_response(body: &str) -> String {
+    format!("200|application/json|{}", body)
+}
+
+fn create_html_response(body: String) -> String {
+    format!("200|text/html|{}", body)
+}
+
+// Well-known files change only with CONFIG, so shared caches may keep them
+// for a day
+fn create_asset_response(content_type: &str, body: &str) -> String {
+    let response = with_header(create_text_response(content_type, body), "Cache-Control", "public, max-age=86400");
+    with_header(response, "Accept-Ranges", "bytes")
+}
+
+// The host decodes the body back into bytes and drops the marker header.
+// GET requests for the body may ask for byte ranges of it (see ranged).
+fn create_binary_response(content_type: &str, bytes: &[u8]) -> String {
+    format!("200|{}\n{}: base64\nAccept-Ranges: bytes|{}", content_type, BINARY_BODY_HEADER, hashing::to_base64(bytes))
+}
+
+const BINARY_BODY_HEADER: &str = "X-Wasm-Body-Encoding";
+
+fn create_text_response(content_type: &str, body: &str) -> String {
+    format!("200|{}|{}", content_type, body)
+}
+
+// Carries the request ID so a client reporting an error can quote it
+fn create_error_response(status: u16, message: &str) -> String {
+    create_error_response_with(status, message, "")
+}
+
+// `members` goes into the error object after the message, as
+// `,"name":value` pairs
+fn create_error_response_with(status: u16, message: &str, members: &str) -> String {
+    match context::request_id() {
+        Some(id) => format!(
+            "{}|application/json|{{\"error\":\"{}\"{},\"request_id\":\"{}\"}}",
+            status,
+            json::escape(message),
+            members,
+            id
+        ),
+        None => format!("{}|application/json|{{\"error\":\"{}\"{}}}", status, json::escape(message), members),
+    }
+}
+
+// Lists the routes a mistyped path most likely meant, so clients and the
+// error page can offer them
+fn not_found_response(path: &str) -> String {
+    let suggestions: Vec<String> = route_suggestions(path).iter().map(|route| format!("\"{}\"", json::escape(route))).collect();
+    create_error_response_with(404, "Not Found", &format!(",\"suggestions\":[{}]", suggestions.join(",")))
+}
+
+// Paths this long are not typos of any route
+const MAX_SUGGESTED_PATH_LEN: usize = 64;
+
+// Route names and aliases within a few edits of the path, allowing more for
+// longer paths; an alias is suggested as the route it stands for
+fn route_suggestions(path: &str) -> Vec<&'static str> {
+    if path.len() > MAX_SUGGESTED_PATH_LEN {
+        return Vec::new();
+    }
+    let path = path.to_ascii_lowercase();
+    let routes = counters::ROUTES.iter().copied().filter(|route| *route != "other" && !route.contains(':'));
+    let candidates = routes.chain(ROUTE_ALIASES.iter().map(|&(alias, _)| alias));
+    let mut suggestions = Vec::new();
+    for name in similarity::closest(&path, candidates, 1 + path.chars().count() / 5, 3) {
+        let route = ROUTE_ALIASES.iter().find(|(alias, _)| *alias == name).map_or(name, |&(_, target)| target);
+        if !suggestions.contains(&route) {
+            suggestions.push(route);
+        }
+    }
+    suggestions
+}
+
+// Browsers list text/html ahead of JSON; API clients and "*/*" get JSON
+fn prefers_html(headers: &headers::Headers) -> bool {
+    headers
+        .get("accept")
+        .is_some_and(|accept| headers::accept_quality(accept, "text/html") > headers::accept_quality(accept, "application/json"))
+}
+
+// Error responses (status 400 and up with a JSON error object) turn into an
+// HTML page, in the negotiated language, for clients preferring HTML. Either
+// way they vary on Accept and Accept-Language, and any extra headers such as
+// Allow are kept.
+fn negotiate_error(response: String, wants_html: bool, language: &i18n::Catalog) -> String {
+    let Some((status, (header_block, body))) =
+        response.split_once('|').and_then(|(status, rest)| Some((status, rest.split_once('|')?)))
+    else {
+        return response;
+    };
+    let (content_type, extra_headers) = header_block.split_once('\n').unwrap_or((header_block, ""));
+    let Some(status) = status.parse::<u16>().ok().filter(|&status| status >= 400 && content_type == "application/json") else {
+        return response;
+    };
+    let Ok(json::Value::Object(members)) = json::parse(body) else {
+        return response;
+    };
+    let member = |name: &str| members.iter().find(|(key, _)| key == name).map(|(_, value)| value);
+    let Some(json::Value::String(message)) = member("error") else {
+        return response;
+    };
+    if !wants_html {
+        return with_header(response, "Vary", "Accept, Accept-Language");
+    }
+    let suggestions: Vec<&str> = match member("suggestions") {
+        Some(json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                json::Value::String(route) => Some(route.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let request_id = match member("request_id") {
+        Some(json::Value::String(id)) => id.as_str(),
+        _ => "",
+    };
+    let page = error_page(status, message, &suggestions, request_id, language);
+    let header_block = if extra_headers.is_empty() { "text/html".to_string() } else { format!("text/html\n{}", extra_headers) };
+    let response = with_header(format!("{}|{}|{}", status, header_block, page), "Content-Language", language.code);
+    with_header(response, "Vary", "Accept, Accept-Language")
+}
+
+// JSON error messages are English, so this is what they are compared with
+fn status_reason(status: u16) -> &'static str {
+    i18n::ENGLISH.reason(status)
+}
+
+fn error_page(status: u16, message: &str, suggestions: &[&str], request_id: &str, language: &i18n::Catalog) -> String {
+    let reason = language.reason(status);
+    let detail = if message == status_reason(status) { String::new() } else { format!("\n    <p>{}</p>", html::escape(message)) };
+    let suggestion = match suggestions.split_last() {
+        None => String::new(),
+        Some((last, rest)) => {
+            let link = |route: &&str| format!(r#"<a href="{0}"><code>{0}</code></a>"#, html::escape(route));
+            let links = match rest {
+                [] => link(last),
+                _ => format!("{} {} {}", rest.iter().map(link).collect::<Vec<_>>().join(", "), language.or, link(last)),
+            };
+            format!("\n    <p>{}</p>", language.did_you_mean.replace("{}", &links))
+        }
+    };
+    let request = if request_id.is_empty() {
+        String::new()
+    } else {
+        format!("\n    <p class=\"request-id\">{}: <code>{}</code></p>", language.request_id, html::escape(request_id))
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="{5}">
+<head>
+    <title>{0} {1} - WASM-Handled Worker</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; max-width: 800px; margin: 0 auto; padding: 20px; }}
+        .request-id {{ color: #666; font-size: 0.9em; }}
+    </style>
+</head>
+<body>
+    <h1>🦀 {0} {1}</h1>{2}{3}
+    <p><a href="/">{6}</a></p>{4}
+</body>
+</html>"#,
+        status, reason, detail, suggestion, request, language.code, language.back_to_endpoints
+    )
+}
+
+// Extra headers follow the content type on their own lines, so values must
+// not contain '|' or line breaks
+fn create_redirect_response(status: u16, location: &str) -> String {
+    let location = location.replace('|', "%7C").replace(['\r', '\n'], "");
+    format!("{}|text/plain\nLocation: {}|Redirecting to {}", status, location, location)
+}
+
+// Adds a header line after the content type; the value must satisfy
+// security::is_valid_value
+fn with_header(response: String, name: &str, value: &str) -> String {
+    match response.split_once('|').and_then(|(status, rest)| Some((status, rest.split_once('|')?))) {
+        Some((status, (header_block, body))) => format!("{}|{}\n{}: {}|{}", status, header_block, name, value, body),
+        None => response,
+    }
+}
+
+// HEAD answers with the status and headers of GET, and the Content-Length of
+// the body GET would have sent
+fn without_body(response: String) -> String {
+    match response.split_once('|').and_then(|(status, rest)| Some((status, rest.split_once('|')?))) {
+        Some((status, (header_block, body))) => {
+            let length = if header_block.contains(&format!("\n{}: base64", BINARY_BODY_HEADER)) {
+                hashing::from_base64(body).map_or(0, |bytes| bytes.len())
+            } else {
+                body.len()
+            };
+            format!("{}|{}\nContent-Length: {}|", status, header_block, length)
+        }
+        None => response,
+    }
+}
+
+// Answers a Range header on a 200 response that sends Accept-Ranges: bytes
+// with 206 and the requested bytes, or 416 when none of them exist. If-Range
+// must match the response's ETag (strongly) or Last-Modified, or the full
+// response is sent, as it is for ranges ranges::evaluate will not serve.
+fn ranged(ctx: &RequestContext, response: String) -> String {
+    let Some(range) = ctx.headers.get("range") else {
+        return response;
+    };
+    let Some((status, (header_block, body))) = response.split_once('|').and_then(|(status, rest)| Some((status, rest.split_once('|')?))) else {
+        return response;
+    };
+    let header = |name: &str| {
+        header_block.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    };
+    if status != "200" || header("accept-ranges") != Some("bytes") {
+        return response;
+    }
+    let validator_matches = |validator: &str| match validator.strip_prefix("W/") {
+        Some(_) => false,
+        None if validator.starts_with('"') => header("etag") == Some(validator),
+        None => header("last-modified") == Some(validator),
+    };
+    if ctx.headers.get("if-range").is_some_and(|validator| !validator_matches(validator.trim())) {
+        return response;
+    }
+
+    let binary = header(BINARY_BODY_HEADER) == Some("base64");
+    let bytes = if binary { hashing::from_base64(body).unwrap_or_default() } else { body.as_bytes().to_vec() };
+    match ranges::evaluate(range, bytes.len()) {
+        ranges::Outcome::Full => response,
+        ranges::Outcome::Unsatisfiable => with_header(
+            with_header(create_error_response(416, "Range Not Satisfiable"), "Content-Range", &format!("bytes */{}", bytes.len())),
+            "Accept-Ranges",
+            "bytes",
+        ),
+        // A slice of a text body may split a character, so it goes as bytes
+        ranges::Outcome::Partial(part) => {
+            let header_block = if binary { header_block.to_string() } else { format!("{}\n{}: base64", header_block, BINARY_BODY_HEADER) };
+            let content_range = format!("bytes {}-{}/{}", part.start, part.end - 1, bytes.len());
+            with_header(format!("206|{}|{}", header_block, hashing::to_base64(&bytes[part])), "Content-Range", &content_range)
+        }
+    }
+}
+
+// Echoes the ID handle_request settled on, whether the client's or a new one
+fn invalid_utf8_body_response() -> String {
+    create_error_response(400, "Body is not valid UTF-8")
+}
+
+fn budget_exhausted_response() -> String {
+    create_error_response(503, "Computation too expensive: the request ran out of its compute budget")
+}
+
+// Discarded by the host, which replays the request once the values are loaded
+fn kv_pending_response() -> String {
+    create_error_response(503, "Waiting for KV values to load")
+}
+
+fn get_status_json() -> String {
+    let uptime = counters::uptime_ms();
+    let memory = allocator::memory_bytes();
+    format!(
+        r#"{{"status":"ok","implementation":"Pure WebAssembly","allocator":"{}","uptime_ms":{},"uptime":"{}","memory_bytes":{},"memory":{},"warmup":{},"response_cache":{},"timestamp":"{}","message":"Handled by WASM"}}"#,
+        allocator::NAME,
+        uptime,
+        humanize::duration(uptime as f64),
+        memory.map_or("null".to_string(), |bytes| bytes.to_string()),
+        memory.map_or("null".to_string(), |bytes| format!("\"{}\"", humanize::bytes_iec(bytes))),
+        warmup::status_json(),
+        response_cache::stats_json(),
+        "2024-01-01T00:00:00.000Z" // Would need JS to provide real timestamp
+    )
+}
+
+fn parse_number(params: &QueryParams, param: &str) -> Option<i32> {
+    parse_param(params, param)
+}
+
+fn parse_param<T: std::str::FromStr>(params: &QueryParams, param: &str) -> Option<T> {
+    params.get(param).and_then(|value| value.parse().ok())
+}
+
+fn parse_two_numbers(params: &QueryParams, param1: &str, param2: &str) -> (i32, i32) {
+    let a = parse_number(params, param1).unwrap_or(0);
+    let b = parse_number(params, param2).unwrap_or(0);
+    (a, b)
+}
+
+fn parse_string(params: &QueryParams, param: &str) -> Option<String> {
+    params.get(param).map(|value| value.to_string())
+}
+
+thread_local! {
+    // The page never changes, so each language's is minified once per instance
+    static HOME_PAGES: [OnceCell<String>; i18n::CATALOGS.len()] = const { [const { OnceCell::new() }; i18n::CATALOGS.len()] };
+}
+
+fn home_page(index: usize) -> String {
+    HOME_PAGES.with(|pages| pages[index].get_or_init(|| arena::outside(|| minify::html(&get_home_page(i18n::CATALOGS[index])))).clone())
+}
+
+// A string value of this flag is shown as a notice above the intro to the
+// visitors it is served to, who are bucketed by CF-Connecting-IP
+const HOME_NOTICE_FLAG: &str = "home-notice";
+
+fn handle_home_page(ctx: &RequestContext) -> String {
+    let notice = match ctx.flag(HOME_NOTICE_FLAG, ctx.headers.get("cf-connecting-ip").unwrap_or("")) {
+        Ok(evaluation) => evaluation,
+        Err(kv::Pending) => return kv_pending_response(),
+    };
+    let language = i18n::negotiate(ctx.params.get("lang"), ctx.headers.get("accept-language"));
+    let index = i18n::CATALOGS.iter().position(|catalog| catalog.code == language.code).unwrap_or(0);
+    let page = match json::parse(&notice.value) {
+        Ok(json::Value::String(text)) if !text.is_empty() => {
+            home_page(index).replacen("<p>", &format!(r#"<p class="notice">{}</p><p>"#, html::escape(&text)), 1)
+        }
+        _ => home_page(index),
+    };
+    let response = with_header(create_html_response(page), "Content-Language", language.code);
+    with_header(response, "Vary", "Accept-Language")
+}
+
+pub fn warm_home_page() {
+    for index in 0..i18n::CATALOGS.len() {
+        home_page(index);
+    }
+}
+
+pub fn home_page_is_warm() -> bool {
+    HOME_PAGES.with(|pages| pages.iter().all(|page| page.get().is_some()))
+}
+
+// Only the page's own text is translated; endpoint descriptions stay English
+fn get_home_page(language: &i18n::Catalog) -> String {
+    let links: Vec<String> = i18n::CATALOGS
+        .iter()
+        .map(|catalog| format!(r#"<a href="/?lang={}" hreflang="{0}" lang="{0}">{}</a>"#, catalog.code, catalog.name))
+        .collect();
+    HOME_PAGE_TEMPLATE
+        .replace("{lang}", language.code)
+        .replace("{intro}", language.home_intro)
+        .replace("{endpoints}", language.home_endpoints)
+        .replace("{languages}", &format!("{}: {}", language.language, links.join(" · ")))
+        .replace("{list}", &endpoint_list())
+}
+
+// Routes that do not answer GET show their method before the example
+fn endpoint_list() -> String {
+    let items: Vec<String> = endpoints::ENDPOINTS
+        .iter()
+        .map(|endpoint| {
+            let method = if endpoint.methods.contains(&"GET") { String::new() } else { format!("{} ", endpoint.methods.join(", ")) };
+            format!("            <li><code>{}{}</code> - {}</li>", method, endpoint.example, endpoint.description)
+        })
+        .collect();
+    items.join("\n")
+}
+
+const HOME_PAGE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <title>WASM-Handled Worker</title>
+    <style>
+        body { font-family: Arial, sans-serif; max-width: 800px; margin: 0 auto; padding: 20px; }
+        .endpoint { background: #f5f5f5; padding: 15px; margin: 10px 0; border-radius: 5px; }
+        .notice { background: #fff3cd; padding: 10px 15px; border-radius: 5px; }
+    </style>
+</head>
+<body>
+    <h1>🦀 WASM-Handled Worker</h1>
+    <p>{intro}</p>
+    <p>{languages}</p>
+    <div class="endpoint">
+        <h3>📊 {endpoints}</h3>
+        <ul>
+{list}
+        </ul>
+    </div>
+</body>
+</html>"#;
@@ -0,0 +1,385 @@
+// ZIP archives (PKWARE APPNOTE 6.3), read through the central directory
+// Listing never touches file data. Extraction handles stored and deflated
+// entries, checking each against its CRC-32; ZIP64 sizes and offsets are
+// understood, while encrypted and multi-disk archives are not.
+
+use crate::compression;
+use crate::hashing::Crc32;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+const END_OF_DIRECTORY: [u8; 4] = *b"PK\x05\x06";
+const ZIP64_END_OF_DIRECTORY: [u8; 4] = *b"PK\x06\x06";
+const ZIP64_LOCATOR: [u8; 4] = *b"PK\x06\x07";
+const DIRECTORY_ENTRY: [u8; 4] = *b"PK\x01\x02";
+const LOCAL_HEADER: [u8; 4] = *b"PK\x03\x04";
+
+pub const STORED: u16 = 0;
+pub const DEFLATED: u16 = 8;
+
+const ENCRYPTED_FLAG: u16 = 1;
+const UTF8_FLAG: u16 = 1 << 11;
+
+pub struct Entry {
+    pub name: String,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub size: u64,
+    // DOS time as "2024-01-31T12:00:00", in whatever zone the archiver used
+    pub modified: String,
+    pub encrypted: bool,
+    pub comment: String,
+    local_header: u64,
+}
+
+impl Entry {
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn method_name(&self) -> String {
+        match self.method {
+            STORED => "stored".to_string(),
+            DEFLATED => "deflate".to_string(),
+            9 => "deflate64".to_string(),
+            12 => "bzip2".to_string(),
+            14 => "lzma".to_string(),
+            93 => "zstd".to_string(),
+            other => format!("method {}", other),
+        }
+    }
+}
+
+pub struct Archive {
+    pub entries: Vec<Entry>,
+    pub comment: String,
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Result<u16, String> {
+    data.get(pos..pos + 2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]])).ok_or_else(|| format!("Archive ends inside a record at byte {}", pos))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Result<u32, String> {
+    data.get(pos..pos + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| format!("Archive ends inside a record at byte {}", pos))
+}
+
+fn u64_at(data: &[u8], pos: usize) -> Result<u64, String> {
+    Ok(u64::from(u32_at(data, pos)?) | u64::from(u32_at(data, pos + 4)?) << 32)
+}
+
+fn slice(data: &[u8], pos: u64, length: usize) -> Result<&[u8], String> {
+    usize::try_from(pos)
+        .ok()
+        .and_then(|pos| data.get(pos..pos.checked_add(length)?))
+        .ok_or_else(|| format!("Record at byte {} runs past the end of the archive", pos))
+}
+
+// Without the UTF-8 flag names are code page 437, which matches ASCII; other
+// bytes are shown as replacement characters
+fn text(bytes: &[u8], flags: u16) -> String {
+    if flags & UTF8_FLAG != 0 || bytes.is_ascii() {
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        bytes.iter().map(|&byte| if byte.is_ascii() { byte as char } else { char::REPLACEMENT_CHARACTER }).collect()
+    }
+}
+
+fn dos_time(date: u16, time: u16) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        1980 + (date >> 9),
+        (date >> 5) & 0x0F,
+        date & 0x1F,
+        time >> 11,
+        (time >> 5) & 0x3F,
+        (time & 0x1F) * 2
+    )
+}
+
+// The end of central directory record sits at the very end, unless the
+// archive has a trailing comment of up to 64 KiB
+fn find_end(data: &[u8]) -> Result<usize, String> {
+    let earliest = data.len().saturating_sub(22 + 0xFFFF);
+    (earliest..=data.len().saturating_sub(22))
+        .rev()
+        .find(|&pos| data[pos..].starts_with(&END_OF_DIRECTORY) && pos + 22 + usize::from(data[pos + 20]) + (usize::from(data[pos + 21]) << 8) == data.len())
+        .ok_or_else(|| "Not a ZIP archive (no end of central directory record)".to_string())
+}
+
+pub fn read(data: &[u8]) -> Result<Archive, String> {
+    let end = find_end(data)?;
+    if u16_at(data, end + 4)? != 0 || u16_at(data, end + 6)? != 0 {
+        return Err("Multi-disk archives are not supported".to_string());
+    }
+    let mut count = u64::from(u16_at(data, end + 10)?);
+    let mut directory = u64::from(u32_at(data, end + 16)?);
+    let comment = text(&data[end + 22..], UTF8_FLAG);
+    // ZIP64 archives mark overflowing fields with all ones and give the real
+    // values in a record found through the locator just before this one
+    if end >= 20 && data[end - 20..].starts_with(&ZIP64_LOCATOR) {
+        let record = u64_at(data, end - 20 + 8)?;
+        if slice(data, record, 4)? != ZIP64_END_OF_DIRECTORY {
+            return Err("ZIP64 end of central directory record is missing".to_string());
+        }
+        let record = record as usize;
+        count = u64_at(data, record + 32)?;
+        directory = u64_at(data, record + 48)?;
+    }
+
+    // Each directory entry takes at least 46 bytes, which bounds a count
+    // that would otherwise allocate without limit
+    if count > (data.len() / 46) as u64 {
+        return Err(format!("Directory claims {} entries, more than the archive can hold", count));
+    }
+    let mut entries = Vec::with_capacity(count as usize);
+    let mut pos = usize::try_from(directory).map_err(|_| "Central directory offset is out of range".to_string())?;
+    for _ in 0..count {
+        if slice(data, pos as u64, 4)? != DIRECTORY_ENTRY {
+            return Err(format!("Expected a central directory entry at byte {}", pos));
+        }
+        let flags = u16_at(data, pos + 8)?;
+        let name_length = usize::from(u16_at(data, pos + 28)?);
+        let extra_length = usize::from(u16_at(data, pos + 30)?);
+        let comment_length = usize::from(u16_at(data, pos + 32)?);
+        let name = slice(data, (pos + 46) as u64, name_length)?;
+        let extra = slice(data, (pos + 46 + name_length) as u64, extra_length)?;
+        let comment = slice(data, (pos + 46 + name_length + extra_length) as u64, comment_length)?;
+
+        let mut compressed_size = u64::from(u32_at(data, pos + 20)?);
+        let mut size = u64::from(u32_at(data, pos + 24)?);
+        let mut local_header = u64::from(u32_at(data, pos + 42)?);
+        // The ZIP64 extra field holds, in this order, only the fields that
+        // overflowed
+        let mut field = 0;
+        while field + 4 <= extra.len() {
+            let (id, length) = (u16_at(extra, field)?, usize::from(u16_at(extra, field + 2)?));
+            if id == 0x0001 {
+                let mut at = field + 4;
+                for value in [&mut size, &mut compressed_size, &mut local_header] {
+                    if *value == u64::from(u32::MAX) {
+                        *value = u64_at(extra, at)?;
+                        at += 8;
+                    }
+                }
+            }
+            field += 4 + length;
+        }
+
+        entries.push(Entry {
+            name: text(name, flags),
+            method: u16_at(data, pos + 10)?,
+            crc32: u32_at(data, pos + 16)?,
+            compressed_size,
+            size,
+            modified: dos_time(u16_at(data, pos + 14)?, u16_at(data, pos + 12)?),
+            encrypted: flags & ENCRYPTED_FLAG != 0,
+            comment: text(comment, flags),
+            local_header,
+        });
+        pos += 46 + name_length + extra_length + comment_length;
+    }
+    Ok(Archive { entries, comment })
+}
+
+// An entry's contents, refusing any larger than `max_size` bytes
+pub fn extract(data: &[u8], entry: &Entry, max_size: usize) -> Result<Vec<u8>, String> {
+    if entry.encrypted {
+        return Err(format!("{} is encrypted", entry.name));
+    }
+    if entry.size > max_size as u64 {
+        return Err(format!("{} is larger than {} bytes", entry.name, max_size));
+    }
+    if slice(data, entry.local_header, 4)? != LOCAL_HEADER {
+        return Err(format!("Local header of {} is missing", entry.name));
+    }
+    // The local header repeats the name and has its own extra field, whose
+    // length may differ from the central directory's
+    let header = entry.local_header as usize;
+    let start = header + 30 + usize::from(u16_at(data, header + 26)?) + usize::from(u16_at(data, header + 28)?);
+    let compressed_size = usize::try_from(entry.compressed_size).map_err(|_| "Entry is too large".to_string())?;
+    let compressed = slice(data, start as u64, compressed_size)?;
+    let contents = match entry.method {
+        STORED => compressed.to_vec(),
+        DEFLATED => compression::inflate(compressed, entry.size as usize)?.0,
+        _ => return Err(format!("{} uses {} compression, which is not supported", entry.name, entry.method_name())),
+    };
+    if contents.len() as u64 != entry.size {
+        return Err(format!("{} is {} bytes rather than the {} its directory entry says", entry.name, contents.len(), entry.size));
+    }
+    let mut crc = Crc32::new();
+    crc.update(&contents);
+    if crc.finalize() != entry.crc32 {
+        return Err(format!("CRC-32 mismatch in {}", entry.name));
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct File<'a> {
+        name: &'a str,
+        data: &'a [u8],
+        method: u16,
+        flags: u16,
+        // Sizes and offset only in a ZIP64 extra field
+        zip64: bool,
+    }
+
+    fn file<'a>(name: &'a str, data: &'a [u8], method: u16) -> File<'a> {
+        File { name, data, method, flags: 0, zip64: false }
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(data);
+        crc.finalize()
+    }
+
+    // 2024-01-31T12:00:00
+    const TIME: u16 = 12 << 11;
+    const DATE: u16 = 44 << 9 | 1 << 5 | 31;
+
+    fn archive(files: &[File], comment: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut directory = Vec::new();
+        for file in files {
+            let compressed = if file.method == DEFLATED { compression::deflate(file.data) } else { file.data.to_vec() };
+            let offset = out.len() as u32;
+            let (size, compressed_size, offset_field) =
+                if file.zip64 { (u32::MAX, u32::MAX, u32::MAX) } else { (file.data.len() as u32, compressed.len() as u32, offset) };
+            let mut extra = Vec::new();
+            if file.zip64 {
+                extra.extend_from_slice(&1u16.to_le_bytes());
+                extra.extend_from_slice(&24u16.to_le_bytes());
+                for value in [file.data.len() as u64, compressed.len() as u64, u64::from(offset)] {
+                    extra.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            let common = |record: &mut Vec<u8>| {
+                for value in [file.flags, file.method, TIME, DATE] {
+                    record.extend_from_slice(&value.to_le_bytes());
+                }
+                for value in [crc32(file.data), compressed_size, size] {
+                    record.extend_from_slice(&value.to_le_bytes());
+                }
+                record.extend_from_slice(&(file.name.len() as u16).to_le_bytes());
+            };
+            out.extend_from_slice(&LOCAL_HEADER);
+            out.extend_from_slice(&20u16.to_le_bytes());
+            common(&mut out);
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(file.name.as_bytes());
+            out.extend_from_slice(&compressed);
+
+            directory.extend_from_slice(&DIRECTORY_ENTRY);
+            directory.extend_from_slice(&[20, 0, 20, 0]);
+            common(&mut directory);
+            directory.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&offset_field.to_le_bytes());
+            directory.extend_from_slice(file.name.as_bytes());
+            directory.extend_from_slice(&extra);
+        }
+        let start = out.len() as u32;
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&END_OF_DIRECTORY);
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&start.to_le_bytes());
+        out.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+        out.extend_from_slice(comment.as_bytes());
+        out
+    }
+
+    fn read_error(data: &[u8]) -> String {
+        match read(data) {
+            Ok(_) => panic!("read a broken archive"),
+            Err(reason) => reason,
+        }
+    }
+
+    #[test]
+    fn lists_and_extracts_stored_and_deflated_entries() {
+        let text = b"hello hello hello hello hello".repeat(20);
+        let data = archive(&[file("a.txt", &text, DEFLATED), file("raw.bin", &[0, 1, 2, 255], STORED), file("dir/", b"", STORED)], "test archive");
+        let archive = read(&data).unwrap();
+        assert_eq!(archive.comment, "test archive");
+        assert_eq!(archive.entries.len(), 3);
+        let entry = &archive.entries[0];
+        assert_eq!((entry.name.as_str(), entry.method_name().as_str(), entry.size), ("a.txt", "deflate", text.len() as u64));
+        assert!(entry.compressed_size < entry.size);
+        assert_eq!(entry.modified, "2024-01-31T12:00:00");
+        assert_eq!(extract(&data, entry, text.len()).unwrap(), text);
+        assert_eq!(extract(&data, &archive.entries[1], 4).unwrap(), [0, 1, 2, 255]);
+        assert!(archive.entries[2].is_directory());
+    }
+
+    #[test]
+    fn reads_zip64_extra_fields() {
+        let mut big = file("big.txt", b"zip64 sized entry", STORED);
+        big.zip64 = true;
+        let data = archive(&[file("small.txt", b"first", STORED), big], "");
+        let archive = read(&data).unwrap();
+        let entry = &archive.entries[1];
+        assert_eq!(entry.size, 17);
+        assert_eq!(entry.compressed_size, 17);
+        assert_eq!(extract(&data, entry, 17).unwrap(), b"zip64 sized entry");
+    }
+
+    #[test]
+    fn names_follow_the_utf8_flag() {
+        let mut flagged = file("zażółć.json", b"{}", STORED);
+        flagged.flags = UTF8_FLAG;
+        let data = archive(&[flagged, file("zażółć.json", b"{}", STORED)], "");
+        let archive = read(&data).unwrap();
+        assert_eq!(archive.entries[0].name, "zażółć.json");
+        assert_eq!(archive.entries[1].name, "za\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}.json");
+    }
+
+    #[test]
+    fn refuses_what_it_cannot_extract_safely() {
+        let text = b"x".repeat(1000);
+        let mut locked = file("locked", b"secret", STORED);
+        locked.flags = ENCRYPTED_FLAG;
+        let mut data = archive(&[file("bomb", &text, DEFLATED), locked, file("sum", b"abcd", STORED)], "");
+        let archive = read(&data).unwrap();
+        assert!(extract(&data, &archive.entries[0], 999).unwrap_err().contains("larger than 999"));
+        assert!(extract(&data, &archive.entries[1], 100).unwrap_err().contains("encrypted"));
+        // Flip a byte of the stored contents
+        let pos = data.windows(4).position(|window| window == b"abcd").unwrap();
+        data[pos] = b'A';
+        assert!(extract(&data, &archive.entries[2], 100).unwrap_err().contains("CRC-32"));
+    }
+
+    #[test]
+    fn rejects_broken_archives() {
+        assert!(read_error(b"not a zip").contains("Not a ZIP archive"));
+        let data = archive(&[file("a", b"a", STORED)], "");
+        // Too many entries for the archive's size
+        let mut inflated = data.clone();
+        let end = inflated.len() - 22;
+        inflated[end + 10..end + 12].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(read_error(&inflated).contains("more than the archive can hold"));
+        // A directory offset past the end
+        let mut moved = data.clone();
+        moved[end + 16..end + 20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read(&moved).is_err());
+        // A comment length that overruns the data hides the end record
+        let mut commented = data.clone();
+        commented[end + 20] = 5;
+        assert!(read(&commented).is_err());
+        // Multi-disk
+        let mut split = data;
+        split[end + 4] = 1;
+        assert!(read_error(&split).contains("Multi-disk"));
+        assert!(read(&[0; 21]).is_err());
+    }
+}